@@ -2,4 +2,8 @@ pub mod services;
 pub mod types;
 
 pub use services::app::AppServices;
-pub use types::{BookmarkPage, BookmarkQuery, BookmarkStats, PaginatedResponse};
+pub use services::import::ImportHandle;
+pub use types::{
+    BookmarkPage, BookmarkQuery, BookmarkStats, BookmarkView, BookmarkViewPage, ImportEvent,
+    PaginatedResponse, Pagination, Period, SearchFilters, TagMatch,
+};
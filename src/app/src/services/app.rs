@@ -1,28 +1,58 @@
-use crate::types::{BookmarkPage, BookmarkQuery, BookmarkStats};
+use crate::services::import::{self, ImportHandle};
+use crate::types::{
+    BookmarkPage, BookmarkQuery, BookmarkStats, BookmarkView, BookmarkViewPage, Collection, Facets,
+    Pagination, Period, SavedSearch, SearchFilters, TagMapReport,
+};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use eterea_core::{Database, Ingester};
-use std::path::Path;
+use eterea_core::export::ExportFormat;
+use eterea_core::ingestion::FileReport;
+use eterea_core::models::Annotation;
+use eterea_core::preview::{
+    ArchiveReport, EnrichProgress, EnrichReport, ExpandedUrl, LinkPreview, LinkPreviewer,
+    PreviewOptions, PreviewSettings,
+};
+use eterea_core::storage::SearchField;
+use eterea_core::{Bookmark, Database, Ingester};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
 
 pub struct AppServices {
     db: Database,
+    /// Backing file, used to open extra connections for background work
+    db_path: Option<PathBuf>,
+    /// Idle connections for the `*_async` queries, opened on demand
+    readers: Arc<Mutex<Vec<Database>>>,
 }
 
 impl AppServices {
     pub fn open_default() -> Result<Self> {
-        let db = Database::open_default().context("failed to open default Eterea database")?;
-        Ok(Self { db })
+        let path = Database::default_path();
+        let db = Database::open(&path).context("failed to open default Eterea database")?;
+        Ok(Self {
+            db,
+            db_path: Some(path),
+            readers: Arc::default(),
+        })
     }
 
     pub fn open(path: &Path) -> Result<Self> {
         let db = Database::open(path)
             .with_context(|| format!("failed to open database at {}", path.display()))?;
-        Ok(Self { db })
+        Ok(Self {
+            db,
+            db_path: Some(path.to_path_buf()),
+            readers: Arc::default(),
+        })
     }
 
     pub fn open_memory() -> Result<Self> {
         let db = Database::open_memory().context("failed to open in-memory database")?;
-        Ok(Self { db })
+        Ok(Self {
+            db,
+            db_path: None,
+            readers: Arc::default(),
+        })
     }
 
     pub fn list_bookmarks(&self, offset: usize, limit: usize) -> Result<BookmarkPage> {
@@ -37,35 +67,376 @@ impl AppServices {
         Ok(BookmarkPage::new(items, total, offset, limit))
     }
 
+    /// `list_bookmarks` on a blocking worker thread (see `read_off_thread`)
+    pub async fn list_bookmarks_async(&self, offset: usize, limit: usize) -> Result<BookmarkPage> {
+        self.read_off_thread(move |services| services.list_bookmarks(offset, limit))
+            .await
+    }
+
+    /// `query_bookmarks` on a blocking worker thread (see `read_off_thread`)
+    pub async fn query_bookmarks_async(&self, query: &BookmarkQuery) -> Result<BookmarkPage> {
+        let query = query.clone();
+        self.read_off_thread(move |services| services.query_bookmarks(&query))
+            .await
+    }
+
+    /// `query` on a blocking worker thread (see `read_off_thread`)
+    pub async fn query_async(
+        &self,
+        filters: &SearchFilters,
+        pagination: Pagination,
+    ) -> Result<BookmarkPage> {
+        let filters = filters.clone();
+        self.read_off_thread(move |services| services.query(&filters, pagination))
+            .await
+    }
+
+    /// Run the read-only `work` on tokio's blocking pool with a connection of
+    /// its own, so a heavy query doesn't hold up the calling (IPC) thread and
+    /// several can run at once. Connections are kept for reuse afterwards.
+    ///
+    /// Pooled connections are plain `Database::open`s: they have no instant
+    /// index and no `SearchBackend`, so `work` must only use reads that SQL
+    /// answers (text through FTS5), as the `*_async` queries do. That also
+    /// keeps them correct after writes from other connections, which the
+    /// main connection's instant index doesn't see.
+    ///
+    /// An in-memory library has no file to open a second connection on, so
+    /// `work` runs inline on the main connection instead.
+    async fn read_off_thread<T, F>(&self, work: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&AppServices) -> Result<T> + Send + 'static,
+    {
+        let Some(db_path) = self.db_path.clone() else {
+            return work(self);
+        };
+        let readers = Arc::clone(&self.readers);
+
+        tokio::task::spawn_blocking(move || {
+            let idle = readers.lock().unwrap_or_else(PoisonError::into_inner).pop();
+            let db = match idle {
+                Some(db) => db,
+                None => Database::open(&db_path)
+                    .with_context(|| format!("failed to open database at {}", db_path.display()))?,
+            };
+            let reader = AppServices {
+                db,
+                db_path: None,
+                readers: Arc::default(),
+            };
+            let result = work(&reader);
+            readers
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(reader.db);
+            result
+        })
+        .await
+        .context("background query did not finish")?
+    }
+
+    pub fn get_favorites(&self, offset: usize, limit: usize) -> Result<BookmarkPage> {
+        self.db
+            .get_favorites_page(offset, limit)
+            .context("failed to list favorites")
+    }
+
+    pub fn get_bookmarks_by_tag(
+        &self,
+        tag: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<BookmarkPage> {
+        self.db
+            .get_bookmarks_by_tag_page(tag, offset, limit)
+            .with_context(|| format!("failed to list bookmarks tagged {tag:?}"))
+    }
+
+    pub fn get_bookmarks_by_author(
+        &self,
+        handle: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<BookmarkPage> {
+        self.db
+            .get_bookmarks_by_author_page(handle, offset, limit)
+            .with_context(|| format!("failed to list bookmarks by {handle:?}"))
+    }
+
+    pub fn create_collection(&self, name: &str) -> Result<Collection> {
+        self.db
+            .create_collection(name)
+            .with_context(|| format!("failed to create collection {name:?}"))
+    }
+
+    pub fn delete_collection(&self, collection_id: i64) -> Result<bool> {
+        self.db
+            .delete_collection(collection_id)
+            .with_context(|| format!("failed to delete collection {collection_id}"))
+    }
+
+    pub fn list_collections(&self) -> Result<Vec<Collection>> {
+        self.db
+            .list_collections()
+            .context("failed to list collections")
+    }
+
+    pub fn add_to_collection(&self, collection_id: i64, bookmark_id: &str) -> Result<bool> {
+        self.db
+            .add_to_collection(collection_id, bookmark_id)
+            .with_context(|| format!("failed to add {bookmark_id} to collection {collection_id}"))
+    }
+
+    pub fn remove_from_collection(&self, collection_id: i64, bookmark_id: &str) -> Result<bool> {
+        self.db
+            .remove_from_collection(collection_id, bookmark_id)
+            .with_context(|| {
+                format!("failed to remove {bookmark_id} from collection {collection_id}")
+            })
+    }
+
+    pub fn move_to_collection(&self, bookmark_id: &str, from: i64, to: i64) -> Result<bool> {
+        self.db
+            .move_to_collection(bookmark_id, from, to)
+            .with_context(|| format!("failed to move {bookmark_id} to collection {to}"))
+    }
+
+    pub fn get_collection_bookmarks(
+        &self,
+        collection_id: i64,
+        offset: usize,
+        limit: usize,
+    ) -> Result<BookmarkPage> {
+        self.db
+            .get_collection_bookmarks(collection_id, Pagination::new(offset, limit))
+            .with_context(|| format!("failed to list collection {collection_id}"))
+    }
+
     pub fn query_bookmarks(&self, query: &BookmarkQuery) -> Result<BookmarkPage> {
         if !query.is_filtered() {
             return self.list_bookmarks(query.offset, query.limit);
         }
 
-        let from = parse_rfc3339(query.from_date.as_deref())?;
-        let to = parse_rfc3339(query.to_date.as_deref())?;
-        let (items, total) = self
-            .db
-            .search_with_filters_page(
-                normalize_filter(query.query.as_deref()),
-                query.tag.as_deref(),
-                query.author.as_deref(),
-                from,
-                to,
-                query.favorites_only,
-                query.has_media,
-                query.offset,
-                query.limit,
-            )
-            .context("failed to query bookmarks")?;
+        let filters = SearchFilters {
+            query: normalize_filter(query.query.as_deref()).map(str::to_string),
+            tags: query.all_tags().into_iter().map(str::to_string).collect(),
+            tag_match: query.tag_match,
+            author: query.author.clone(),
+            from_date: parse_rfc3339(query.from_date.as_deref())?,
+            to_date: parse_rfc3339(query.to_date.as_deref())?,
+            favorites_only: query.favorites_only,
+            has_media: query.has_media,
+            media_type: query.media_type,
+            prefix_match: !query.exact_match,
+            ..SearchFilters::default()
+        };
+        self.query(&filters, Pagination::new(query.offset, query.limit))
+    }
 
-        Ok(BookmarkPage::new(items, total, query.offset, query.limit))
+    /// One page of bookmarks matching a typed filter object, as sent by the
+    /// frontend (multiple tags, date range, media, favorites)
+    pub fn query(&self, filters: &SearchFilters, pagination: Pagination) -> Result<BookmarkPage> {
+        self.db
+            .query_bookmarks(filters, pagination)
+            .context("failed to query bookmarks")
+    }
+
+    /// `query_bookmarks` with word counts and reading-time estimates attached
+    pub fn query_bookmark_views(&self, query: &BookmarkQuery) -> Result<BookmarkViewPage> {
+        Ok(self.query_bookmarks(query)?.map(BookmarkView::from))
+    }
+
+    /// Full-text search; with `rerank`, matches with the query words close
+    /// together in their content come first
+    pub fn search_with_proximity(
+        &self,
+        query: &str,
+        limit: usize,
+        rerank: bool,
+    ) -> Result<Vec<Bookmark>> {
+        self.db
+            .search_with_proximity(query, limit, rerank)
+            .with_context(|| format!("failed to search for {query:?}"))
+    }
+
+    /// Search only content, notes, authors or tags (`field` as sent by the UI)
+    pub fn search_in_field(&self, field: &str, query: &str, limit: usize) -> Result<Vec<Bookmark>> {
+        let field: SearchField = field.parse()?;
+        self.db
+            .search_in_field(field, query, limit)
+            .with_context(|| format!("failed to search {field:?} for {query:?}"))
+    }
+
+    /// Build the in-memory index behind `search_instant`; call at startup and
+    /// again after a background import (`start_import` writes through another
+    /// connection). Returns `false` when the library is too large for it.
+    pub fn enable_instant_search(&self) -> Result<bool> {
+        self.db
+            .enable_instant_search()
+            .context("failed to build the instant search index")
+    }
+
+    /// Search-as-you-type, answered from memory once `enable_instant_search`
+    /// has run and by FTS5 otherwise
+    pub fn search_instant(&self, query: &str, limit: usize) -> Result<Vec<Bookmark>> {
+        self.db
+            .search_instant(query, limit)
+            .with_context(|| format!("failed to search for {query:?}"))
+    }
+
+    /// Up to `n` random bookmarks, for the rediscovery widget
+    pub fn random_bookmarks(&self, n: usize) -> Result<Vec<Bookmark>> {
+        self.db
+            .random_bookmarks(n)
+            .context("failed to sample random bookmarks")
+    }
+
+    /// Bookmarks tweeted on this calendar day in earlier years
+    pub fn on_this_day(&self, month: u32, day: u32) -> Result<Vec<Bookmark>> {
+        self.db
+            .on_this_day(month, day)
+            .with_context(|| format!("failed to load bookmarks from {month:02}-{day:02}"))
+    }
+
+    /// "More like this" for the bookmark with `id`
+    pub fn find_related(&self, id: &str, limit: usize) -> Result<Vec<Bookmark>> {
+        self.db
+            .find_related(id, limit)
+            .with_context(|| format!("failed to find bookmarks related to {id}"))
+    }
+
+    /// Tag/author/media/favorite counts for `query`, for the filter sidebar
+    pub fn search_facets(&self, query: &str, filters: &SearchFilters) -> Result<Facets> {
+        self.db
+            .search_facets(query, filters)
+            .with_context(|| format!("failed to compute facets for {query:?}"))
+    }
+
+    /// Save `filters` as `name`, replacing a saved search of the same name
+    pub fn save_search(&self, name: &str, filters: &SearchFilters) -> Result<()> {
+        self.db
+            .save_search(name, filters)
+            .with_context(|| format!("failed to save search {name:?}"))
+    }
+
+    pub fn list_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        self.db
+            .list_saved_searches()
+            .context("failed to list saved searches")
+    }
+
+    pub fn run_saved_search(&self, name: &str, limit: usize) -> Result<Vec<Bookmark>> {
+        self.db
+            .run_saved_search(name, limit)
+            .with_context(|| format!("failed to run saved search {name:?}"))?
+            .with_context(|| format!("no saved search named {name:?}"))
+    }
+
+    pub fn delete_saved_search(&self, name: &str) -> Result<bool> {
+        self.db
+            .delete_saved_search(name)
+            .with_context(|| format!("failed to delete saved search {name:?}"))
+    }
+
+    pub fn get_bookmarks(&self, ids: &[&str]) -> Result<Vec<Bookmark>> {
+        self.db
+            .get_bookmarks_by_ids(ids)
+            .context("failed to load bookmarks by id")
     }
 
     pub fn stats(&self) -> Result<BookmarkStats> {
         self.db.get_stats().context("failed to load bookmark stats")
     }
 
+    /// Bookmark counts per day/week/month/year, for drawing an activity chart
+    pub fn bookmark_counts_by_period(&self, period: Period) -> Result<Vec<(String, i64)>> {
+        self.db
+            .bookmark_counts_by_period(period)
+            .context("failed to count bookmarks by period")
+    }
+
+    /// Tags often used together with `tag`, for "also tag this with" hints
+    pub fn tag_cooccurrence(&self, tag: &str, limit: usize) -> Result<Vec<(String, i64)>> {
+        self.db
+            .tag_cooccurrence(tag, limit)
+            .with_context(|| format!("failed to find tags used with {tag}"))
+    }
+
+    pub fn suggest_tags(&self, prefix: &str, limit: usize) -> Result<Vec<(String, i64)>> {
+        self.db
+            .suggest_tags(prefix, limit)
+            .context("failed to suggest tags")
+    }
+
+    pub fn suggest_authors(
+        &self,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, String, i64)>> {
+        self.db
+            .suggest_authors(prefix, limit)
+            .context("failed to suggest authors")
+    }
+
+    pub fn recent_tags(&self, limit: usize) -> Result<Vec<String>> {
+        self.db
+            .recent_tags(limit)
+            .context("failed to load recent tags")
+    }
+
+    pub fn recent_authors(&self, limit: usize) -> Result<Vec<(String, String)>> {
+        self.db
+            .recent_authors(limit)
+            .context("failed to load recent authors")
+    }
+
+    /// Rename a tag everywhere, merging it into `to` if that tag exists
+    pub fn rename_tag(&self, from: &str, to: &str) -> Result<usize> {
+        self.db
+            .rename_tag(from, to)
+            .with_context(|| format!("failed to rename tag {from} to {to}"))
+    }
+
+    pub fn merge_tags(&self, sources: &[&str], target: &str) -> Result<usize> {
+        self.db
+            .merge_tags(sources, target)
+            .with_context(|| format!("failed to merge tags into {target}"))
+    }
+
+    /// Write every bookmark the view lists for `filters` to `dest` as
+    /// `format`; returns how many were written
+    pub fn export_query(
+        &self,
+        filters: &SearchFilters,
+        format: ExportFormat,
+        dest: &Path,
+    ) -> Result<usize> {
+        let file = std::fs::File::create(dest)
+            .with_context(|| format!("failed to create {}", dest.display()))?;
+        self.db
+            .export_query(filters, format, std::io::BufWriter::new(file))
+            .with_context(|| format!("failed to export results to {}", dest.display()))
+    }
+
+    /// Save the tag taxonomy (tags, counts and former names) to `dest`
+    pub fn export_tag_map(&self, dest: &Path) -> Result<usize> {
+        let file = std::fs::File::create(dest)
+            .with_context(|| format!("failed to create {}", dest.display()))?;
+        self.db
+            .export_tag_map(std::io::BufWriter::new(file))
+            .with_context(|| format!("failed to export tags to {}", dest.display()))
+    }
+
+    /// Apply the renames and merges from a tag map saved by `export_tag_map`
+    pub fn import_tag_map(&self, src: &Path) -> Result<TagMapReport> {
+        let file = std::fs::File::open(src)
+            .with_context(|| format!("failed to open {}", src.display()))?;
+        self.db
+            .import_tag_map(std::io::BufReader::new(file))
+            .with_context(|| format!("failed to import tags from {}", src.display()))
+    }
+
     pub fn import_file(&self, path: &Path) -> Result<usize> {
         let ingester = Ingester::new();
         ingester
@@ -73,6 +444,30 @@ impl AppServices {
             .with_context(|| format!("failed to import file at {}", path.display()))
     }
 
+    /// Detect a file's format and preview its rows without importing anything
+    pub fn inspect_file(&self, path: &Path) -> Result<FileReport> {
+        Ingester::new()
+            .inspect(path)
+            .with_context(|| format!("failed to inspect file at {}", path.display()))
+    }
+
+    /// Import a file on a background thread, reporting progress through the
+    /// returned handle. Needs a file-backed database.
+    pub fn start_import(&self, path: &Path) -> Result<ImportHandle> {
+        let db_path = self
+            .db_path
+            .clone()
+            .context("background imports need a file-backed database")?;
+        import::spawn(db_path, path)
+    }
+
+    pub async fn import_url(&self, url: &str) -> Result<usize> {
+        Ingester::new()
+            .ingest_url(url, &self.db)
+            .await
+            .with_context(|| format!("failed to import from {url}"))
+    }
+
     pub fn import_content(&self, filename: &str, content: &str) -> Result<usize> {
         let extension = Path::new(filename)
             .extension()
@@ -87,17 +482,177 @@ impl AppServices {
             .with_context(|| format!("failed to store imported bookmarks for {filename}"))
     }
 
+    pub async fn link_preview(&self, url: &str, force_refresh: bool) -> Result<LinkPreview> {
+        self.previewer()?
+            .fetch_cached(url, &self.db, force_refresh)
+            .await
+            .with_context(|| format!("failed to load preview for {url}"))
+    }
+
+    /// Outbound links of a bookmark with shortened ones (`t.co`, ...) resolved
+    pub async fn expand_links(&self, bookmark_id: &str) -> Result<Vec<ExpandedUrl>> {
+        self.previewer()?
+            .expand_links(bookmark_id, &self.db)
+            .await
+            .with_context(|| format!("failed to expand links for bookmark {bookmark_id}"))
+    }
+
+    /// Backfill missing author profile URLs (and avatars, with
+    /// `fetch_avatars`), reporting progress after each author
+    pub async fn enrich_authors(
+        &self,
+        fetch_avatars: bool,
+        on_progress: impl FnMut(EnrichProgress),
+    ) -> Result<EnrichReport> {
+        self.previewer()?
+            .enrich_authors(&self.db, fetch_avatars, on_progress)
+            .await
+            .context("failed to enrich author profiles")
+    }
+
+    /// Download a bookmark's media into `media_dir` so the UI can show the
+    /// local copy once the remote URL stops working
+    pub async fn archive_bookmark_media(
+        &self,
+        bookmark_id: &str,
+        media_dir: &Path,
+    ) -> Result<ArchiveReport> {
+        self.previewer()?
+            .archive_bookmark_media(&self.db, bookmark_id, media_dir)
+            .await
+            .with_context(|| format!("failed to archive media of bookmark {bookmark_id}"))
+    }
+
+    /// `archive_bookmark_media` for every bookmark with media not archived yet
+    pub async fn archive_all_media(&self, media_dir: &Path) -> Result<ArchiveReport> {
+        self.previewer()?
+            .archive_all_media(&self.db, media_dir)
+            .await
+            .context("failed to archive media")
+    }
+
+    pub fn preview_settings(&self) -> Result<PreviewSettings> {
+        PreviewSettings::load(&self.db).context("failed to load preview settings")
+    }
+
+    /// Change the preview user-agent and proxy; takes effect on the next fetch
+    pub fn set_preview_settings(&self, settings: &PreviewSettings) -> Result<()> {
+        settings
+            .save(&self.db)
+            .context("failed to save preview settings")
+    }
+
+    /// A previewer built from the current saved settings
+    fn previewer(&self) -> Result<LinkPreviewer> {
+        let options = PreviewOptions::default().with_settings(&self.preview_settings()?);
+        LinkPreviewer::new(options).context("failed to configure link previews")
+    }
+
+    /// Snapshot the library to `dest`; safe while the app is using it
+    pub fn backup_to(&self, dest: &Path) -> Result<()> {
+        self.db
+            .backup_to(dest)
+            .with_context(|| format!("failed to back up to {}", dest.display()))
+    }
+
+    /// Replace the whole library with the backup at `src`, then reopen it so
+    /// per-connection settings match the restored file
+    pub fn restore_from(&mut self, src: &Path) -> Result<()> {
+        self.db
+            .restore_from(src)
+            .with_context(|| format!("failed to restore from {}", src.display()))?;
+        if let Some(path) = &self.db_path {
+            let instant_search = self.db.instant_search_enabled();
+            self.db = Database::open(path).context("failed to reopen the restored database")?;
+            if instant_search {
+                self.enable_instant_search()?;
+            }
+        }
+        self.readers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        Ok(())
+    }
+
+    pub fn purge_link_previews(&self) -> Result<usize> {
+        self.db
+            .purge_stale_previews(PreviewOptions::default().cache_ttl)
+            .context("failed to purge stale link previews")
+    }
+
     pub fn toggle_favorite(&self, id: &str) -> Result<bool> {
         self.db
             .toggle_favorite(id)
             .with_context(|| format!("failed to toggle favorite for bookmark {id}"))
     }
 
+    /// Favorite or unfavorite a multi-selection in one call; returns how many
+    /// bookmarks changed
+    pub fn set_favorites(&self, ids: &[String], favorite: bool) -> Result<usize> {
+        let ids = ids.iter().map(String::as_str).collect::<Vec<_>>();
+        self.db
+            .set_favorites(&ids, favorite)
+            .with_context(|| format!("failed to update favorites for {} bookmarks", ids.len()))
+    }
+
+    pub fn toggle_archived(&self, id: &str) -> Result<bool> {
+        self.db
+            .toggle_archived(id)
+            .with_context(|| format!("failed to toggle archive for bookmark {id}"))
+    }
+
+    /// Add a timestamped note; errors if the bookmark doesn't exist
+    pub fn add_annotation(&self, id: &str, body: &str) -> Result<Annotation> {
+        self.db
+            .add_annotation(id, body)
+            .with_context(|| format!("failed to annotate bookmark {id}"))?
+            .with_context(|| format!("bookmark {id} not found"))
+    }
+
+    pub fn get_annotations(&self, id: &str) -> Result<Vec<Annotation>> {
+        self.db
+            .get_annotations(id)
+            .with_context(|| format!("failed to load annotations for bookmark {id}"))
+    }
+
+    pub fn list_archived(&self, offset: usize, limit: usize) -> Result<Vec<Bookmark>> {
+        self.db
+            .get_archived(offset, limit)
+            .context("failed to list archived bookmarks")
+    }
+
     pub fn delete_bookmark(&self, id: &str) -> Result<bool> {
         self.db
             .delete_bookmark(id)
             .with_context(|| format!("failed to delete bookmark {id}"))
     }
+
+    pub fn delete_bookmark_permanently(&self, id: &str) -> Result<bool> {
+        self.db
+            .delete_bookmark_permanently(id)
+            .with_context(|| format!("failed to permanently delete bookmark {id}"))
+    }
+
+    pub fn restore_bookmark(&self, id: &str) -> Result<bool> {
+        self.db
+            .restore_bookmark(id)
+            .with_context(|| format!("failed to restore bookmark {id}"))
+    }
+
+    /// Bookmarks no import has listed since `since`, e.g. ones removed from
+    /// the account after the last full export
+    pub fn bookmarks_not_seen_since(&self, since: DateTime<Utc>) -> Result<Vec<Bookmark>> {
+        self.db
+            .bookmarks_not_seen_since(since)
+            .context("failed to find bookmarks missing from recent imports")
+    }
+
+    pub fn list_trash(&self, offset: usize, limit: usize) -> Result<Vec<Bookmark>> {
+        self.db
+            .get_trash(offset, limit)
+            .context("failed to list trashed bookmarks")
+    }
 }
 
 fn parse_rfc3339(value: Option<&str>) -> Result<Option<DateTime<Utc>>> {
@@ -125,12 +680,149 @@ fn normalize_filter(value: Option<&str>) -> Option<&str> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::{ImportEvent, TagMatch};
     use tempfile::NamedTempFile;
 
     fn sample_json() -> &'static str {
         include_str!("../../../legacy/new_bookmarks.json")
     }
 
+    #[test]
+    fn background_import_reports_progress_then_completion() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let services =
+            AppServices::open(&dir.path().join("bookmarks.db")).expect("services should open");
+        let export = dir.path().join("export.json");
+        std::fs::write(&export, sample_json()).expect("export should be written");
+
+        let events = services
+            .start_import(&export)
+            .expect("import should start")
+            .wait();
+
+        let Some(ImportEvent::Complete { imported, skipped }) = events.last().cloned() else {
+            panic!("expected a completion event, got {events:?}");
+        };
+        assert!(imported > 0);
+        assert_eq!(skipped, 0);
+        assert!(events[..events.len() - 1]
+            .iter()
+            .all(|event| matches!(event, ImportEvent::Progress { .. })));
+        assert_eq!(
+            services.stats().expect("stats should load").total_bookmarks,
+            imported as i64
+        );
+
+        let again = services
+            .start_import(&export)
+            .expect("import should start")
+            .wait();
+        assert_eq!(
+            again.last(),
+            Some(&ImportEvent::Complete {
+                imported: 0,
+                skipped: imported
+            })
+        );
+        assert!(AppServices::open_memory()
+            .expect("in-memory services should open")
+            .start_import(&export)
+            .is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn async_queries_match_sync_ones_and_reuse_connections() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let services =
+            AppServices::open(&dir.path().join("bookmarks.db")).expect("services should open");
+        services
+            .import_content("sample.json", sample_json())
+            .expect("json import should succeed");
+        let query = BookmarkQuery {
+            query: Some("rust".to_string()),
+            limit: 20,
+            ..BookmarkQuery::default()
+        };
+
+        let (listed, searched) = tokio::join!(
+            services.list_bookmarks_async(0, 10),
+            services.query_bookmarks_async(&query)
+        );
+        let listed = listed.expect("async listing should succeed");
+        let searched = searched.expect("async query should succeed");
+        assert_eq!(
+            listed,
+            services
+                .list_bookmarks(0, 10)
+                .expect("listing should succeed")
+        );
+        assert_eq!(
+            searched,
+            services
+                .query_bookmarks(&query)
+                .expect("query should succeed")
+        );
+        let idle = services.readers.lock().unwrap().len();
+        assert!((1..=2).contains(&idle));
+
+        services
+            .query_async(&SearchFilters::new(), Pagination::new(0, 5))
+            .await
+            .expect("typed async query should succeed");
+        assert_eq!(services.readers.lock().unwrap().len(), idle);
+
+        let in_memory = AppServices::open_memory().expect("in-memory services should open");
+        assert_eq!(
+            in_memory
+                .list_bookmarks_async(0, 10)
+                .await
+                .expect("in-memory listing runs inline")
+                .total,
+            0
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn pooled_reads_search_through_fts5() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let path = dir.path().join("bookmarks.db");
+        let services = AppServices::open(&path).expect("services should open");
+        services
+            .import_content("sample.json", sample_json())
+            .expect("json import should succeed");
+        assert!(services
+            .enable_instant_search()
+            .expect("instant search should build"));
+        // Written through another connection, so only FTS5 knows about it
+        AppServices::open(&path)
+            .expect("second services should open")
+            .import_content(
+                "zig.json",
+                r#"[{"screen_name":"ziglang","name":"Zig","full_text":"Comptime tricks #zig","tweeted_at":"2025-08-26T10:52:35.000Z","tweet_url":"https://x.com/ziglang/status/3"}]"#,
+            )
+            .expect("second import should succeed");
+
+        let query = BookmarkQuery {
+            query: Some("comptime".to_string()),
+            limit: 20,
+            ..BookmarkQuery::default()
+        };
+        let page = services
+            .query_bookmarks_async(&query)
+            .await
+            .expect("async query should succeed");
+        assert_eq!(page.total, 1);
+        assert_eq!(
+            page,
+            services
+                .query_bookmarks(&query)
+                .expect("query should succeed")
+        );
+        let readers = services.readers.lock().unwrap();
+        assert!(!readers.is_empty());
+        assert!(readers.iter().all(|db| !db.instant_search_enabled()));
+    }
+
     #[test]
     fn imports_content_and_surfaces_stats() {
         let services = AppServices::open_memory().expect("in-memory services should open");
@@ -145,6 +837,48 @@ mod tests {
         assert!(stats.unique_authors > 0);
     }
 
+    #[test]
+    fn listing_total_counts_every_imported_row() {
+        let services = AppServices::open_memory().expect("in-memory services should open");
+        let imported = services
+            .import_content("sample.json", sample_json())
+            .expect("json import should succeed");
+
+        let page = services
+            .list_bookmarks(0, 1)
+            .expect("listing should succeed");
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.total, imported as i64);
+    }
+
+    #[test]
+    fn typed_filters_query_multiple_tags() {
+        let services = AppServices::open_memory().expect("in-memory services should open");
+        services
+            .import_content("sample.json", sample_json())
+            .expect("json import should succeed");
+
+        let filters: SearchFilters =
+            serde_json::from_str(r#"{"tags":["rust","svelte"],"tag_match":"Any"}"#)
+                .expect("filters should deserialize from frontend JSON");
+        let pagination: Pagination =
+            serde_json::from_str(r#"{"offset":0}"#).expect("pagination should deserialize");
+        let page = services
+            .query(&filters, pagination)
+            .expect("typed query should succeed");
+
+        let legacy = services
+            .query_bookmarks(&BookmarkQuery {
+                tags: vec!["rust".to_string(), "svelte".to_string()],
+                tag_match: TagMatch::Any,
+                limit: pagination.limit,
+                ..BookmarkQuery::default()
+            })
+            .expect("query should succeed");
+        assert_eq!(page.total, legacy.total);
+        assert_eq!(page.limit, eterea_core::storage::DEFAULT_PAGE_LIMIT);
+    }
+
     #[test]
     fn filters_bookmarks_by_query_and_tag() {
         let services = AppServices::open_memory().expect("in-memory services should open");
@@ -172,6 +906,36 @@ mod tests {
             })
             .expect("tag query should succeed");
         assert!(!tagged.items.is_empty(), "expected tagged results");
+
+        let any_of = services
+            .query_bookmarks(&BookmarkQuery {
+                tags: vec!["rust".to_string(), "svelte".to_string()],
+                tag_match: TagMatch::Any,
+                limit: 20,
+                ..BookmarkQuery::default()
+            })
+            .expect("multi-tag query should succeed");
+        let all_of = services
+            .query_bookmarks(&BookmarkQuery {
+                tags: vec!["rust".to_string(), "svelte".to_string()],
+                tag_match: TagMatch::All,
+                limit: 20,
+                ..BookmarkQuery::default()
+            })
+            .expect("multi-tag query should succeed");
+        assert!(any_of.total >= all_of.total);
+
+        let views = services
+            .query_bookmark_views(&BookmarkQuery {
+                query: Some("rust".to_string()),
+                limit: 20,
+                ..BookmarkQuery::default()
+            })
+            .expect("view query should succeed");
+        assert_eq!(views.total, first_page.total);
+        let json = serde_json::to_value(&views.items[0]).expect("view should serialize");
+        assert_eq!(json["id"], first_page.items[0].id.as_str());
+        assert_eq!(json["word_count"], views.items[0].bookmark.word_count());
     }
 
     #[test]
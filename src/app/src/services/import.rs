@@ -0,0 +1,100 @@
+use crate::types::ImportEvent;
+use anyhow::{Context, Result};
+use eterea_core::ingestion::ImportProgress;
+use eterea_core::{Database, Ingester};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// A running background import. Events arrive in order and end with either
+/// `ImportEvent::Complete`, `ImportEvent::Cancelled` or `ImportEvent::Error`.
+pub struct ImportHandle {
+    events: Receiver<ImportEvent>,
+    worker: JoinHandle<()>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl ImportHandle {
+    /// Ask the worker to stop before its next batch. Batches already committed
+    /// stay in the database; the import ends with `ImportEvent::Cancelled`.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// Next event if one is ready, without blocking (for UI polling loops)
+    pub fn try_next(&self) -> Option<ImportEvent> {
+        self.events.try_recv().ok()
+    }
+
+    /// Wait up to `timeout` for the next event
+    pub fn next_timeout(&self, timeout: Duration) -> Option<ImportEvent> {
+        self.events.recv_timeout(timeout).ok()
+    }
+
+    /// Block until the import ends, returning every remaining event
+    pub fn wait(self) -> Vec<ImportEvent> {
+        let events = self.events.iter().collect();
+        let _ = self.worker.join();
+        events
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.worker.is_finished()
+    }
+}
+
+/// Import `source` into the database at `db_path` on a worker thread with its
+/// own connection, so the caller's connection stays free for reads.
+pub(crate) fn spawn(db_path: PathBuf, source: &Path) -> Result<ImportHandle> {
+    let source = source.to_path_buf();
+    let (sender, events) = mpsc::channel();
+    let cancel = Arc::new(AtomicBool::new(false));
+    let token = Arc::clone(&cancel);
+
+    let worker = std::thread::Builder::new()
+        .name("eterea-import".to_string())
+        .spawn(move || {
+            let mut last = ImportProgress::default();
+            let result = Database::open(&db_path)
+                .with_context(|| format!("failed to open database at {}", db_path.display()))
+                .and_then(|db| {
+                    Ingester::new()
+                        .with_cancel_token(Arc::clone(&token))
+                        .ingest_file_with_progress(&source, &db, |progress| {
+                            last = progress;
+                            let _ = sender.send(ImportEvent::Progress {
+                                done: progress.done,
+                                total: progress.total,
+                            });
+                        })
+                        .with_context(|| format!("failed to import file at {}", source.display()))
+                });
+
+            let skipped = last.done - last.inserted;
+            let event = match result {
+                Ok(imported)
+                    if token.load(Ordering::Relaxed)
+                        && (last.total == 0 || last.done < last.total) =>
+                {
+                    ImportEvent::Cancelled { imported, skipped }
+                }
+                Ok(imported) => ImportEvent::Complete { imported, skipped },
+                Err(error) => ImportEvent::Error {
+                    message: format!("{error:#}"),
+                    imported: last.inserted,
+                    skipped,
+                },
+            };
+            let _ = sender.send(event);
+        })
+        .context("failed to start import worker")?;
+
+    Ok(ImportHandle {
+        events,
+        worker,
+        cancel,
+    })
+}
@@ -1,39 +1,32 @@
+use eterea_core::models::{MediaType, DEFAULT_WORDS_PER_MINUTE};
 use eterea_core::Bookmark;
 use serde::{Deserialize, Serialize};
 
-pub use eterea_core::storage::BookmarkStats;
-
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub struct PaginatedResponse<T> {
-    pub items: Vec<T>,
-    pub total: i64,
-    pub offset: usize,
-    pub limit: usize,
-    pub has_more: bool,
-}
-
-impl<T> PaginatedResponse<T> {
-    pub fn new(items: Vec<T>, total: i64, offset: usize, limit: usize) -> Self {
-        let has_more = offset + items.len() < total as usize;
-        Self {
-            items,
-            total,
-            offset,
-            limit,
-            has_more,
-        }
-    }
-}
+pub use eterea_core::storage::{
+    BookmarkStats, Collection, Facets, PaginatedResponse, Pagination, Period, SavedSearch,
+    SearchFilters, SortBy, TagMapReport, TagMatch,
+};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct BookmarkQuery {
     pub query: Option<String>,
     pub tag: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub tag_match: TagMatch,
     pub author: Option<String>,
     pub from_date: Option<String>,
     pub to_date: Option<String>,
     pub favorites_only: bool,
     pub has_media: Option<bool>,
+    /// Only bookmarks with this kind of media (implies `has_media`)
+    #[serde(default)]
+    pub media_type: Option<MediaType>,
+    /// The "exact match" toggle: query terms only match whole words
+    /// (`SearchFilters::prefix_match` off)
+    #[serde(default)]
+    pub exact_match: bool,
     pub offset: usize,
     pub limit: usize,
 }
@@ -44,12 +37,69 @@ impl BookmarkQuery {
             .as_deref()
             .is_some_and(|value| !value.trim().is_empty())
             || self.tag.is_some()
+            || !self.tags.is_empty()
             || self.author.is_some()
             || self.from_date.is_some()
             || self.to_date.is_some()
             || self.favorites_only
             || self.has_media.is_some()
+            || self.media_type.is_some()
+    }
+
+    /// Every requested tag, merging the single-tag shortcut with the tag list.
+    pub fn all_tags(&self) -> Vec<&str> {
+        self.tag
+            .iter()
+            .chain(self.tags.iter())
+            .map(String::as_str)
+            .collect()
     }
 }
 
 pub type BookmarkPage = PaginatedResponse<Bookmark>;
+
+/// A bookmark plus fields computed for display. Serializes flat, so the
+/// frontend sees the usual bookmark keys alongside `word_count` and
+/// `reading_seconds`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BookmarkView {
+    #[serde(flatten)]
+    pub bookmark: Bookmark,
+    pub word_count: usize,
+    pub reading_seconds: u32,
+}
+
+impl From<Bookmark> for BookmarkView {
+    fn from(bookmark: Bookmark) -> Self {
+        Self {
+            word_count: bookmark.word_count(),
+            reading_seconds: bookmark.estimated_reading_seconds(DEFAULT_WORDS_PER_MINUTE),
+            bookmark,
+        }
+    }
+}
+
+pub type BookmarkViewPage = PaginatedResponse<BookmarkView>;
+
+/// Events emitted by a background import (see `AppServices::start_import`).
+///
+/// Serializes as `{ "event": "import-progress", "payload": { ... } }` so a UI
+/// shell can forward it as-is.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "event", content = "payload")]
+pub enum ImportEvent {
+    #[serde(rename = "import-progress")]
+    Progress { done: usize, total: usize },
+    #[serde(rename = "import-complete")]
+    Complete { imported: usize, skipped: usize },
+    /// The import stopped early; counts cover the batches committed before the failure
+    #[serde(rename = "import-error")]
+    Error {
+        message: String,
+        imported: usize,
+        skipped: usize,
+    },
+    /// Cancelled via `ImportHandle::cancel`; batches committed before that are kept
+    #[serde(rename = "import-cancelled")]
+    Cancelled { imported: usize, skipped: usize },
+}
@@ -7,6 +7,12 @@ pub enum Error {
     #[error("Database error: {0}")]
     Database(#[from] rusqlite::Error),
 
+    #[error("Database {0} is locked by another process; close other Eterea windows or CLI commands and try again")]
+    DatabaseLocked(std::path::PathBuf),
+
+    #[error("Database {0} is corrupt or not an Eterea database; run `PRAGMA integrity_check` on it, or restore a backup (Database::restore_from)")]
+    DatabaseCorrupt(std::path::PathBuf),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -19,12 +25,34 @@ pub enum Error {
     #[error("Date parsing error: {0}")]
     DateParse(#[from] chrono::ParseError),
 
+    #[error("Database is open read-only; reopen it with Database::open to make changes")]
+    ReadOnly,
+
+    #[error("Invalid database options: {0}")]
+    InvalidOptions(String),
+
     #[error("Invalid file format: {0}")]
     InvalidFormat(String),
 
     #[error("Unsupported file type: {0}")]
     UnsupportedFileType(String),
 
+    #[error("Missing required field: {0}")]
+    MissingField(&'static str),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Download exceeds the {0} byte limit")]
+    DownloadTooLarge(u64),
+
+    #[error("Link preview error: {0}")]
+    Preview(#[from] crate::preview::PreviewError),
+
+    #[cfg(feature = "tantivy")]
+    #[error("Search index error: {0}")]
+    SearchIndex(#[from] tantivy::TantivyError),
+
     #[error("{0}")]
     Other(String),
 }
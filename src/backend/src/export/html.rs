@@ -0,0 +1,229 @@
+//! Standalone HTML export
+
+use crate::models::{Bookmark, MediaType};
+use crate::search::{extract_snippets, highlight_matches};
+use crate::Result;
+use std::io::Write;
+
+/// Characters of context on each side of each match in a snippet
+const SNIPPET_CONTEXT: usize = 80;
+/// Most match regions shown in a snippet
+const SNIPPET_MATCHES: usize = 3;
+
+/// Stand-ins for `<mark>`/`</mark>` while highlighting, so markup can be added
+/// after the text is escaped (private-use code points, stripped from input)
+const MARK_START: char = '\u{E000}';
+const MARK_END: char = '\u{E001}';
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; max-width: 42rem; margin: 2rem auto; padding: 0 1rem; color: #1f2328; background: #fff; }
+h1 { font-size: 1.5rem; }
+article { border-bottom: 1px solid #d0d7de; padding: 1rem 0; }
+header { display: flex; gap: .5rem; align-items: baseline; flex-wrap: wrap; }
+.name { font-weight: 600; }
+.handle, time { color: #59636e; font-size: .9rem; }
+.text, .snippet { white-space: pre-wrap; overflow-wrap: anywhere; }
+.snippet { color: #59636e; }
+.media { display: grid; grid-template-columns: repeat(auto-fill, minmax(12rem, 1fr)); gap: .5rem; }
+.media img, .media video { width: 100%; border-radius: .5rem; }
+.tags span { display: inline-block; background: #ddf4ff; border-radius: 1rem; padding: 0 .5rem; margin-right: .25rem; font-size: .85rem; }
+mark { background: #fff8c5; }
+a { color: #0969da; }
+@media (prefers-color-scheme: dark) {
+  body { color: #e6edf3; background: #0d1117; }
+  article { border-color: #30363d; }
+  .handle, time, .snippet { color: #9198a1; }
+  .tags span { background: #12263b; }
+  mark { background: #5c4a00; color: inherit; }
+  a { color: #4493f8; }
+}
+"#;
+
+/// Write `bookmarks` as a single self-contained HTML page (inline CSS, no
+/// scripts). With a `query`, matches are highlighted and long bookmarks lead
+/// with a snippet around their first match.
+pub fn write_html(
+    bookmarks: &[Bookmark],
+    query: Option<&str>,
+    mut writer: impl Write,
+) -> Result<()> {
+    let query = query.map(str::trim).filter(|query| !query.is_empty());
+    let title = match query {
+        Some(query) => format!("Bookmarks matching “{}”", escape_html(query)),
+        None => "Bookmarks".to_string(),
+    };
+
+    writeln!(writer, "<!DOCTYPE html>")?;
+    writeln!(writer, "<html lang=\"en\">")?;
+    writeln!(writer, "<head>")?;
+    writeln!(writer, "<meta charset=\"utf-8\">")?;
+    writeln!(
+        writer,
+        "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+    )?;
+    writeln!(writer, "<title>{title}</title>")?;
+    writeln!(writer, "<style>{STYLE}</style>")?;
+    writeln!(writer, "</head>")?;
+    writeln!(writer, "<body>")?;
+    writeln!(writer, "<h1>{title}</h1>")?;
+    writeln!(writer, "<p>{} bookmarks</p>", bookmarks.len())?;
+
+    for bookmark in bookmarks {
+        write_bookmark(&mut writer, bookmark, query)?;
+    }
+
+    writeln!(writer, "</body>")?;
+    writeln!(writer, "</html>")?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_bookmark(writer: &mut impl Write, bookmark: &Bookmark, query: Option<&str>) -> Result<()> {
+    writeln!(writer, "<article>")?;
+    writeln!(
+        writer,
+        "<header><span class=\"name\">{}</span> <span class=\"handle\">@{}</span> <time datetime=\"{}\">{}</time></header>",
+        escape_html(&bookmark.author_name),
+        escape_html(&bookmark.author_handle),
+        bookmark.tweeted_at.to_rfc3339(),
+        bookmark.tweeted_at.format("%Y-%m-%d")
+    )?;
+
+    // Note tweets carry the full text; `content` is the truncated version
+    let text = bookmark.note_text.as_deref().unwrap_or(&bookmark.content);
+    let snippet = query.and_then(|query| {
+        let text_lower = text.to_lowercase();
+        query
+            .split_whitespace()
+            .find(|term| text_lower.contains(&term.to_lowercase()))
+            .map(|term| extract_snippets(text, term, SNIPPET_CONTEXT, SNIPPET_MATCHES))
+    });
+    match (query, snippet) {
+        // Long text: lead with the match in context, full text on demand
+        (Some(query), Some(snippet)) if snippet != text => {
+            writeln!(
+                writer,
+                "<p class=\"snippet\">{}</p>",
+                highlighted_html(&snippet, query)
+            )?;
+            writeln!(
+                writer,
+                "<details><summary>Full text</summary><p class=\"text\">{}</p></details>",
+                highlighted_html(text, query)
+            )?;
+        }
+        (Some(query), _) => writeln!(
+            writer,
+            "<p class=\"text\">{}</p>",
+            highlighted_html(text, query)
+        )?,
+        (None, _) => writeln!(writer, "<p class=\"text\">{}</p>", escape_html(text))?,
+    }
+
+    let media = bookmark
+        .media
+        .iter()
+        .filter(|media| is_web_url(&media.url))
+        .collect::<Vec<_>>();
+    if !media.is_empty() {
+        writeln!(writer, "<div class=\"media\">")?;
+        for media in media {
+            let url = escape_html(&media.url);
+            match media.media_type {
+                MediaType::Image => {
+                    writeln!(writer, "<img src=\"{url}\" loading=\"lazy\" alt=\"\">")?
+                }
+                MediaType::Video | MediaType::Gif => writeln!(
+                    writer,
+                    "<video src=\"{url}\" preload=\"none\" controls muted playsinline></video>"
+                )?,
+                MediaType::Unknown => writeln!(writer, "<a href=\"{url}\">{url}</a>")?,
+            }
+        }
+        writeln!(writer, "</div>")?;
+    }
+
+    if !bookmark.tags.is_empty() {
+        let tags = bookmark
+            .tags
+            .iter()
+            .map(|tag| format!("<span>{}</span>", escape_html(tag)))
+            .collect::<String>();
+        writeln!(writer, "<p class=\"tags\">{tags}</p>")?;
+    }
+
+    if is_web_url(&bookmark.tweet_url) {
+        writeln!(
+            writer,
+            "<p><a href=\"{}\" rel=\"noopener noreferrer\">View on X</a></p>",
+            escape_html(&bookmark.tweet_url)
+        )?;
+    }
+    writeln!(writer, "</article>")?;
+    Ok(())
+}
+
+/// Escape text for HTML element content and quoted attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Escape `text`, wrapping matches of `query` in `<mark>`
+fn highlighted_html(text: &str, query: &str) -> String {
+    let text = text.replace([MARK_START, MARK_END], "");
+    let highlighted =
+        highlight_matches(&text, query, &MARK_START.to_string(), &MARK_END.to_string());
+    escape_html(&highlighted)
+        .replace(MARK_START, "<mark>")
+        .replace(MARK_END, "</mark>")
+}
+
+/// Only link plain web URLs, never `javascript:` or other schemes
+fn is_web_url(url: &str) -> bool {
+    let lower = url.trim_start().to_ascii_lowercase();
+    lower.starts_with("https://") || lower.starts_with("http://")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::BookmarkBuilder;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn escapes_content_and_highlights_matches() {
+        let bookmark = BookmarkBuilder::new()
+            .tweet_url("https://x.com/alice/status/1")
+            .content("<script>alert('hi')</script> Rust & friends")
+            .tweeted_at(Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap())
+            .author_handle("alice")
+            .author_name("Alice \"A\"")
+            .add_media_with_type("https://pbs.twimg.com/media/a.jpg", MediaType::Image)
+            .add_media_with_type("javascript:alert(1)", MediaType::Image)
+            .build()
+            .unwrap();
+
+        let mut out = Vec::new();
+        write_html(std::slice::from_ref(&bookmark), Some("rust"), &mut out).unwrap();
+        let html = String::from_utf8(out).unwrap();
+
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(&#39;hi&#39;)&lt;/script&gt;"));
+        assert!(html.contains("<mark>Rust</mark> &amp; friends"));
+        assert!(html.contains("Alice &quot;A&quot;"));
+        assert!(html
+            .contains("<img src=\"https://pbs.twimg.com/media/a.jpg\" loading=\"lazy\" alt=\"\">"));
+        assert!(!html.contains("javascript:"));
+    }
+}
@@ -0,0 +1,141 @@
+//! Markdown digest export
+
+use crate::models::Bookmark;
+use crate::Result;
+use std::io::Write;
+
+/// Write `bookmarks` as a Markdown digest: one section per bookmark with the
+/// author, date, text, link and tags, separated by horizontal rules.
+pub fn write_markdown(bookmarks: &[Bookmark], mut writer: impl Write) -> Result<()> {
+    writeln!(writer, "# Bookmarks")?;
+    for bookmark in bookmarks {
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "## {} (@{}) · {}",
+            escape_markdown(&bookmark.author_name),
+            escape_markdown(&bookmark.author_handle),
+            bookmark.tweeted_at.format("%Y-%m-%d")
+        )?;
+        writeln!(writer)?;
+
+        // Note tweets carry the full text; `content` is the truncated version
+        let text = bookmark.note_text.as_deref().unwrap_or(&bookmark.content);
+        let text = text_with_line_breaks(text);
+        if !text.is_empty() {
+            writeln!(writer, "{text}")?;
+            writeln!(writer)?;
+        }
+
+        writeln!(
+            writer,
+            "[View on X]({})",
+            bookmark.tweet_url.replace(' ', "%20").replace(')', "%29")
+        )?;
+        if !bookmark.tags.is_empty() {
+            let tags = bookmark
+                .tags
+                .iter()
+                .map(|tag| escape_markdown(tag))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(writer)?;
+            writeln!(writer, "Tags: {tags}")?;
+        }
+        writeln!(writer)?;
+        writeln!(writer, "---")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Escape `text` so it renders literally: inline markup characters anywhere,
+/// and list / heading / quote markers at the start of a line.
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            escaped.push('\n');
+        }
+        let indent = line.len() - line.trim_start().len();
+        escaped.push_str(&line[..indent]);
+        let line = &line[indent..];
+
+        // "1. item" / "1) item" would start an ordered list
+        let digits = line.chars().take_while(char::is_ascii_digit).count();
+        let mut chars = line.chars().peekable();
+        if digits > 0 && matches!(line[digits..].chars().next(), Some('.' | ')')) {
+            escaped.push_str(&line[..digits]);
+            escaped.push('\\');
+            chars = line[digits..].chars().peekable();
+        } else if matches!(line.chars().next(), Some('-' | '+' | '=')) {
+            escaped.push('\\');
+        }
+
+        for ch in chars {
+            if matches!(
+                ch,
+                '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~'
+            ) {
+                escaped.push('\\');
+            }
+            escaped.push(ch);
+        }
+    }
+    escaped
+}
+
+/// Escape `text` and keep its line breaks: single newlines become hard breaks,
+/// blank lines stay paragraph breaks.
+fn text_with_line_breaks(text: &str) -> String {
+    let lines = text.trim().lines().map(str::trim_end).collect::<Vec<_>>();
+    let mut rendered = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        if index > 0 {
+            rendered.push('\n');
+        }
+        rendered.push_str(&escape_markdown(line));
+        let next_is_text = lines
+            .get(index + 1)
+            .is_some_and(|next| !next.trim().is_empty());
+        if !line.is_empty() && next_is_text {
+            rendered.push('\\');
+        }
+    }
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::BookmarkBuilder;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn escapes_markup_and_keeps_line_breaks() {
+        assert_eq!(
+            escape_markdown("snake_case *bold*"),
+            r"snake\_case \*bold\*"
+        );
+        assert_eq!(escape_markdown("1. first\n- item"), "1\\. first\n\\- item");
+        assert_eq!(escape_markdown("v1.2 costs 5-10"), "v1.2 costs 5-10");
+
+        let bookmark = BookmarkBuilder::new()
+            .tweet_url("https://x.com/some_one/status/1")
+            .content("Line one\nLine two\n\n# Not a heading")
+            .tweeted_at(Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap())
+            .author_handle("some_one")
+            .author_name("Some One")
+            .add_tag("rust")
+            .build()
+            .unwrap();
+        let mut out = Vec::new();
+        write_markdown(std::slice::from_ref(&bookmark), &mut out).unwrap();
+        let markdown = String::from_utf8(out).unwrap();
+
+        assert!(markdown.contains("## Some One (@some\\_one) · 2024-05-01\n"));
+        assert!(markdown.contains("Line one\\\nLine two\n\n\\# Not a heading\n"));
+        assert!(markdown.contains("[View on X](https://x.com/some_one/status/1)"));
+        assert!(markdown.contains("Tags: rust"));
+    }
+}
@@ -0,0 +1,30 @@
+//! Export module
+//!
+//! Renders bookmarks into shareable documents. The entry points live on
+//! `Database` (`export_query`, `export_markdown`, `export_html`), which select
+//! bookmarks with `SearchFilters`.
+
+mod csv;
+mod html;
+mod markdown;
+
+use serde::{Deserialize, Serialize};
+
+pub use self::csv::write_csv;
+pub use html::{escape_html, write_html};
+pub use markdown::{escape_markdown, write_markdown};
+
+/// Document types `Database::export_query` can write
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// A JSON array of bookmarks
+    #[default]
+    Json,
+    /// Dewey-style CSV the importer reads back (`write_csv`)
+    Csv,
+    /// A Markdown digest (`write_markdown`)
+    Markdown,
+    /// A standalone HTML page (`write_html`)
+    Html,
+}
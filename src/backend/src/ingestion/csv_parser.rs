@@ -4,15 +4,51 @@
 //! 1. Legacy (Dewey): Tweet Date, Posted By, Profile Pic, Profile URL, Handle, Tweet URL, Content, Tags, Comments, Media
 //! 2. New (Twitter/X): profile_image_url_https, screen_name, name, full_text, note_tweet_text, tweeted_at, tweet_url
 
+use super::{date_or_estimate, SourceRow};
 use crate::models::{Bookmark, BookmarkBuilder};
 use crate::{Error, Result};
-use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
 use csv::ReaderBuilder;
 use std::fs::File;
-use std::io::{BufReader, Cursor, Read};
+use std::io::{BufRead, BufReader, Cursor};
 use std::path::Path;
 use tracing::{debug, warn};
 
+/// Date-and-time layouts seen in Dewey exports, most common first. Day-first
+/// numeric dates are tried before month-first ones, so an ambiguous
+/// `03/04/2024` reads as 3 April.
+const LEGACY_DATE_FORMATS: &[&str] = &[
+    "%I:%M %p, %b %d, %Y",
+    "%b %d, %Y %I:%M %p",
+    "%I:%M %p, %B %d, %Y",
+    "%B %d, %Y %I:%M %p",
+    "%H:%M, %b %d, %Y",
+    "%b %d, %Y %H:%M",
+    "%H:%M, %d %b %Y",
+    "%d %b %Y %H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%d/%m/%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M",
+    "%m/%d/%Y %I:%M %p",
+    "%m/%d/%Y %H:%M:%S",
+    "%m/%d/%Y %H:%M",
+    "%d.%m.%Y %H:%M:%S",
+    "%d.%m.%Y %H:%M",
+];
+
+/// Date-only layouts, tried after `LEGACY_DATE_FORMATS` (midnight UTC)
+const LEGACY_DAY_FORMATS: &[&str] = &[
+    "%b %d, %Y",
+    "%B %d, %Y",
+    "%d %b %Y",
+    "%Y-%m-%d",
+    "%d/%m/%Y",
+    "%m/%d/%Y",
+    "%d.%m.%Y",
+];
+
 /// Detected CSV format
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CsvFormat {
@@ -26,9 +62,7 @@ impl CsvFormat {
     /// Detect the CSV format by examining the header row
     pub fn detect(path: &Path) -> Result<Self> {
         let file = File::open(path)?;
-        let mut reader = ReaderBuilder::new()
-            .has_headers(true)
-            .from_reader(BufReader::new(file));
+        let mut reader = open_csv(BufReader::new(file), None)?;
         
         let headers = reader.headers()?;
         let header_str = headers.iter().collect::<Vec<_>>().join(",").to_lowercase();
@@ -36,16 +70,14 @@ impl CsvFormat {
     }
 
     pub fn detect_from_content(content: &str) -> Result<Self> {
-        let mut reader = ReaderBuilder::new()
-            .has_headers(true)
-            .from_reader(Cursor::new(content.as_bytes()));
+        let mut reader = open_csv(Cursor::new(content.as_bytes()), None)?;
 
         let headers = reader.headers()?;
         let header_str = headers.iter().collect::<Vec<_>>().join(",").to_lowercase();
         Self::detect_from_header_str(&header_str)
     }
 
-    fn detect_from_header_str(header_str: &str) -> Result<Self> {
+    pub(super) fn detect_from_header_str(header_str: &str) -> Result<Self> {
         
         if header_str.contains("tweet date") || header_str.contains("posted by") {
             Ok(CsvFormat::Legacy)
@@ -60,12 +92,159 @@ impl CsvFormat {
     }
 }
 
+/// Guess the delimiter of a CSV file from its header line: whichever of comma,
+/// semicolon (European Excel) or tab appears most often outside quotes.
+/// Defaults to a comma.
+pub fn sniff_delimiter(first_line: &str) -> u8 {
+    let mut counts = [(b',', 0usize), (b';', 0), (b'\t', 0)];
+    let mut in_quotes = false;
+    for byte in first_line.bytes() {
+        match byte {
+            b'"' => in_quotes = !in_quotes,
+            b'\n' if !in_quotes => break,
+            _ if !in_quotes => {
+                if let Some((_, count)) = counts.iter_mut().find(|(d, _)| *d == byte) {
+                    *count += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Ties (including no delimiter at all) keep the comma, which comes first
+    let mut best = counts[0];
+    for candidate in counts {
+        if candidate.1 > best.1 {
+            best = candidate;
+        }
+    }
+    best.0
+}
+
+/// Build a CSV reader over `reader`, skipping a UTF-8 byte order mark (Excel
+/// adds one) and using `delimiter`, or one sniffed from the header line.
+pub(super) fn open_csv<R: BufRead>(
+    mut reader: R,
+    delimiter: Option<u8>,
+) -> Result<csv::Reader<R>> {
+    if reader.fill_buf()?.starts_with(b"\xEF\xBB\xBF") {
+        reader.consume(3);
+    }
+    let delimiter = match delimiter {
+        Some(delimiter) => delimiter,
+        None => sniff_delimiter(&String::from_utf8_lossy(reader.fill_buf()?)),
+    };
+
+    Ok(ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(reader))
+}
+
+/// Whether a legacy Tweet URL cell plausibly holds a tweet link
+fn looks_like_tweet_url(value: &str) -> bool {
+    let value = value.trim();
+    value.starts_with("https://") || value.starts_with("http://") || value.contains("/status/")
+}
+
+/// Split a legacy Tags cell into tags. Tags are comma-separated, so one that
+/// contains a comma is wrapped in double quotes inside the cell
+/// (`"machine, learning", rust`), with `""` for a literal quote; outside
+/// quotes `\,` is a literal comma. Tags are trimmed and empty ones dropped.
+/// An unterminated quote is taken literally.
+pub fn split_tags(cell: &str) -> Vec<String> {
+    fn push(tags: &mut Vec<String>, tag: &str) {
+        let tag = tag.trim();
+        if !tag.is_empty() {
+            tags.push(tag.to_string());
+        }
+    }
+
+    let mut tags = Vec::new();
+    let mut current = String::new();
+    // Where the open quote started, to fall back if it never closes
+    let mut quote_start: Option<usize> = None;
+    let mut chars = cell.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' if quote_start.is_some() => {
+                if chars.peek().is_some_and(|&(_, next)| next == '"') {
+                    chars.next();
+                    current.push('"');
+                } else {
+                    quote_start = None;
+                }
+            }
+            '"' if current.trim().is_empty() => {
+                current.clear();
+                quote_start = Some(i);
+            }
+            '\\' if quote_start.is_none() && chars.peek().is_some_and(|&(_, next)| next == ',') => {
+                chars.next();
+                current.push(',');
+            }
+            ',' if quote_start.is_none() => {
+                push(&mut tags, &current);
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    match quote_start {
+        Some(start) => {
+            for tag in cell[start..].split(',') {
+                push(&mut tags, tag);
+            }
+        }
+        None => push(&mut tags, &current),
+    }
+    tags
+}
+
+/// Inverse of `split_tags`: join tags into one cell, quoting those that
+/// contain a comma, quote or backslash (a trailing `\` would escape the
+/// separator after it)
+pub fn join_tags(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| {
+            if tag.contains([',', '"', '\\']) {
+                format!("\"{}\"", tag.replace('"', "\"\""))
+            } else {
+                tag.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Glue `tail` onto `head` where a newline split one row: the last cell of
+/// `head` and the first of `tail` are one cell
+fn join_split_row(head: &csv::StringRecord, tail: &csv::StringRecord) -> csv::StringRecord {
+    let mut joined: Vec<String> = head.iter().map(str::to_string).collect();
+    let mut rest = tail.iter();
+    if let (Some(last), Some(first)) = (joined.last_mut(), rest.next()) {
+        last.push('\n');
+        last.push_str(first);
+    }
+    joined.extend(rest.map(str::to_string));
+    csv::StringRecord::from(joined)
+}
+
 /// Parser for legacy Dewey CSV exports
-pub struct LegacyCsvParser;
+pub struct LegacyCsvParser {
+    delimiter: Option<u8>,
+}
 
 impl LegacyCsvParser {
     pub fn new() -> Self {
-        Self
+        Self { delimiter: None }
+    }
+
+    /// Use `delimiter` instead of sniffing it from the header line
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = Some(delimiter);
+        self
     }
     
     pub fn parse(&self, path: &Path) -> Result<Vec<Bookmark>> {
@@ -77,29 +256,70 @@ impl LegacyCsvParser {
         self.parse_reader(Cursor::new(content.as_bytes()))
     }
 
-    fn parse_reader<R: Read>(&self, reader: R) -> Result<Vec<Bookmark>> {
-        let mut reader = ReaderBuilder::new()
-            .has_headers(true)
-            .flexible(true)
-            .from_reader(reader);
+    pub(super) fn parse_reader<R: BufRead>(&self, reader: R) -> Result<Vec<Bookmark>> {
+        self.parse_reader_keeping_failures(reader, &mut Vec::new())
+    }
+
+    /// `parse_reader`, adding the rows it skips to `failed`
+    pub(super) fn parse_reader_keeping_failures<R: BufRead>(
+        &self,
+        reader: R,
+        failed: &mut Vec<SourceRow>,
+    ) -> Result<Vec<Bookmark>> {
+        let mut reader = open_csv(reader, self.delimiter)?;
+        let header = reader.headers()?.clone();
+        let columns = header.len();
         
         let mut bookmarks = Vec::new();
+        let mut push = |row: usize, record: &csv::StringRecord| -> Result<()> {
+            match self.parse_record(record) {
+                Ok(bookmark) => bookmarks.push(bookmark),
+                Err(e) => {
+                    warn!("Skipping row {}: {}", row, e);
+                    failed.push(SourceRow::from_record(row, &header, record, &e)?);
+                }
+            }
+            Ok(())
+        };
+        // A short row with a real tweet URL, possibly cut off by a newline in
+        // its unquoted Content cell; the following record may continue it
+        let mut pending: Option<(usize, csv::StringRecord)> = None;
         
         for (idx, result) in reader.records().enumerate() {
-            match result {
-                Ok(record) => {
-                    match self.parse_record(&record) {
-                        Ok(bookmark) => bookmarks.push(bookmark),
-                        Err(e) => {
-                            warn!("Skipping row {}: {}", idx + 2, e);
-                        }
-                    }
-                }
+            let record = match result {
+                Ok(record) => record,
                 Err(e) => {
                     warn!("CSV error at row {}: {}", idx + 2, e);
+                    continue;
+                }
+            };
+
+            let record = match pending.take() {
+                Some((row, head)) if !record.get(5).is_some_and(looks_like_tweet_url) => {
+                    warn!(
+                        row,
+                        continued_at = idx + 2,
+                        cause = "unquoted newline in Content",
+                        "Rejoining legacy CSV row split across lines"
+                    );
+                    (row, join_split_row(&head, &record))
+                }
+                Some((row, head)) => {
+                    push(row, &head)?;
+                    (idx + 2, record)
                 }
+                None => (idx + 2, record),
+            };
+
+            if record.1.len() < columns && record.1.get(5).is_some_and(looks_like_tweet_url) {
+                pending = Some(record);
+            } else {
+                push(record.0, &record.1)?;
             }
         }
+        if let Some((row, head)) = pending {
+            push(row, &head)?;
+        }
         
         debug!("Parsed {} bookmarks from legacy CSV", bookmarks.len());
         Ok(bookmarks)
@@ -120,8 +340,18 @@ impl LegacyCsvParser {
         let tags = record.get(7).unwrap_or("");
         let comments = record.get(8).unwrap_or("");
         let media = record.get(9).unwrap_or("");
+
+        if !looks_like_tweet_url(tweet_url) {
+            return Err(Error::InvalidFormat(format!(
+                "Tweet URL column holds {tweet_url:?}; columns look shifted \
+                 (unquoted newline or delimiter in Content?)"
+            )));
+        }
         
-        let tweeted_at = self.parse_legacy_date(tweet_date)?;
+        // Rows with an unreadable date are kept and flagged; the ingester's
+        // `DatePolicy` decides what happens to them
+        let (tweeted_at, date_estimated) =
+            date_or_estimate(self.parse_legacy_date(tweet_date), tweet_url);
         
         let mut builder = BookmarkBuilder::new()
             .tweet_url(tweet_url)
@@ -133,14 +363,9 @@ impl LegacyCsvParser {
             .author_profile_image(profile_pic)
             .comments(comments);
         
-        // Parse tags (comma-separated in legacy format)
-        if !tags.is_empty() {
-            for tag in tags.split(',') {
-                let tag = tag.trim();
-                if !tag.is_empty() {
-                    builder = builder.add_tag(tag);
-                }
-            }
+        // Parse tags (comma-separated in legacy format, see `split_tags`)
+        for tag in split_tags(tags) {
+            builder = builder.add_tag(tag);
         }
         
         // Parse media URLs (semicolon-separated in legacy format)
@@ -153,27 +378,31 @@ impl LegacyCsvParser {
             }
         }
         
-        builder.build().map_err(|e| Error::Other(e.to_string()))
+        let mut bookmark = builder.build()?;
+        bookmark.date_estimated = date_estimated;
+        Ok(bookmark)
     }
     
-    /// Parse legacy date format: "02:51 PM, May 01, 2024"
+    /// Parse a legacy date ("02:51 PM, May 01, 2024"), trying
+    /// `LEGACY_DATE_FORMATS` and then `LEGACY_DAY_FORMATS` in order
     fn parse_legacy_date(&self, s: &str) -> Result<DateTime<Utc>> {
-        let s = s.trim().trim_matches('"');
-        
-        // Try format: "02:51 PM, May 01, 2024"
-        if let Ok(dt) = NaiveDateTime::parse_from_str(s, "%I:%M %p, %b %d, %Y") {
-            return Ok(Utc.from_utc_datetime(&dt));
-        }
-        
-        // Try format: "May 01, 2024 02:51 PM"
-        if let Ok(dt) = NaiveDateTime::parse_from_str(s, "%b %d, %Y %I:%M %p") {
-            return Ok(Utc.from_utc_datetime(&dt));
-        }
+        let s = s.trim().trim_matches('"').trim();
         
-        // Try ISO format as fallback
         if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
             return Ok(dt.with_timezone(&Utc));
         }
+
+        for format in LEGACY_DATE_FORMATS {
+            if let Ok(dt) = NaiveDateTime::parse_from_str(s, format) {
+                return Ok(Utc.from_utc_datetime(&dt));
+            }
+        }
+
+        for format in LEGACY_DAY_FORMATS {
+            if let Ok(day) = NaiveDate::parse_from_str(s, format) {
+                return Ok(Utc.from_utc_datetime(&day.and_time(NaiveTime::MIN)));
+            }
+        }
         
         Err(Error::Other(format!("Could not parse date: {}", s)))
     }
@@ -186,11 +415,19 @@ impl Default for LegacyCsvParser {
 }
 
 /// Parser for new Twitter/X CSV exports
-pub struct NewCsvParser;
+pub struct NewCsvParser {
+    delimiter: Option<u8>,
+}
 
 impl NewCsvParser {
     pub fn new() -> Self {
-        Self
+        Self { delimiter: None }
+    }
+
+    /// Use `delimiter` instead of sniffing it from the header line
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = Some(delimiter);
+        self
     }
     
     pub fn parse(&self, path: &Path) -> Result<Vec<Bookmark>> {
@@ -202,11 +439,18 @@ impl NewCsvParser {
         self.parse_reader(Cursor::new(content.as_bytes()))
     }
 
-    fn parse_reader<R: Read>(&self, reader: R) -> Result<Vec<Bookmark>> {
-        let mut reader = ReaderBuilder::new()
-            .has_headers(true)
-            .flexible(true)
-            .from_reader(reader);
+    pub(super) fn parse_reader<R: BufRead>(&self, reader: R) -> Result<Vec<Bookmark>> {
+        self.parse_reader_keeping_failures(reader, &mut Vec::new())
+    }
+
+    /// `parse_reader`, adding the rows it skips to `failed`
+    pub(super) fn parse_reader_keeping_failures<R: BufRead>(
+        &self,
+        reader: R,
+        failed: &mut Vec<SourceRow>,
+    ) -> Result<Vec<Bookmark>> {
+        let mut reader = open_csv(reader, self.delimiter)?;
+        let header = reader.headers()?.clone();
         
         let mut bookmarks = Vec::new();
         
@@ -217,6 +461,7 @@ impl NewCsvParser {
                         Ok(bookmark) => bookmarks.push(bookmark),
                         Err(e) => {
                             warn!("Skipping row {}: {}", idx + 2, e);
+                            failed.push(SourceRow::from_record(idx + 2, &header, &record, &e)?);
                         }
                     }
                 }
@@ -243,7 +488,8 @@ impl NewCsvParser {
         let tweeted_at = record.get(5).unwrap_or("");
         let tweet_url = record.get(6).unwrap_or("");
         
-        let parsed_date = self.parse_new_date(tweeted_at)?;
+        let (parsed_date, date_estimated) =
+            date_or_estimate(self.parse_new_date(tweeted_at), tweet_url);
         
         let builder = BookmarkBuilder::new()
             .tweet_url(tweet_url)
@@ -254,7 +500,9 @@ impl NewCsvParser {
             .author_name(name)
             .author_profile_image(profile_image);
         
-        builder.build().map_err(|e| Error::Other(e.to_string()))
+        let mut bookmark = builder.build()?;
+        bookmark.date_estimated = date_estimated;
+        Ok(bookmark)
     }
     
     /// Parse new date format: "2025-08-25T10:52:35.000Z"
@@ -300,6 +548,129 @@ mod tests {
         assert_eq!(dt.format("%Y-%m-%d").to_string(), "2024-05-01");
     }
     
+    #[test]
+    fn test_legacy_date_formats() {
+        let parser = LegacyCsvParser::new();
+        let cases = [
+            ("May 01, 2024 02:51 PM", "2024-05-01 14:51"),
+            ("14:51, May 01, 2024", "2024-05-01 14:51"),
+            ("02:51 PM, September 01, 2024", "2024-09-01 14:51"),
+            ("2024-05-01 14:51:00", "2024-05-01 14:51"),
+            ("2024-05-01T14:51:00.000Z", "2024-05-01 14:51"),
+            ("25/12/2023 09:30", "2023-12-25 09:30"),
+            ("12/25/2023 09:30 AM", "2023-12-25 09:30"),
+            ("03/04/2024 10:00", "2024-04-03 10:00"),
+            ("01.05.2024 08:15", "2024-05-01 08:15"),
+            ("1 May 2024", "2024-05-01 00:00"),
+        ];
+        for (input, expected) in cases {
+            let parsed = parser
+                .parse_legacy_date(input)
+                .unwrap_or_else(|e| panic!("{input}: {e}"));
+            assert_eq!(parsed.format("%Y-%m-%d %H:%M").to_string(), expected, "{input}");
+        }
+        assert!(parser.parse_legacy_date("sometime last spring").is_err());
+    }
+
+    #[test]
+    fn test_unparseable_legacy_date_is_kept_and_flagged() {
+        let content = "Tweet Date,Posted By,Profile Pic,Profile URL,Twitter Handle,Tweet URL,Content,Tags,Comments,Media\n\
+            hier,Alice,,,alice,https://x.com/alice/status/1,Hello,,,\n\
+            \"02:51 PM, May 01, 2024\",Bob,,,bob,https://x.com/bob/status/2,Hi,,,\n";
+
+        let bookmarks = LegacyCsvParser::new().parse_str(content).unwrap();
+        assert_eq!(bookmarks.len(), 2);
+        assert!(bookmarks[0].date_estimated);
+        assert_eq!(bookmarks[0].tweeted_at, DateTime::UNIX_EPOCH);
+        assert!(!bookmarks[1].date_estimated);
+    }
+
+    #[test]
+    fn test_legacy_row_split_by_unquoted_newline_is_rejoined() {
+        let content = "Tweet Date,Posted By,Profile Pic,Profile URL,Twitter Handle,Tweet URL,Content,Tags,Comments,Media\n\
+            2024-05-01 14:51,Alice,,,alice,https://x.com/alice/status/1,First line\n\
+            second line\n\
+            third line,rust,note,https://pbs.twimg.com/media/a.jpg\n\
+            2024-05-02 09:00,Bob,,,bob,https://x.com/bob/status/2,Short row\n\
+            2024-05-03 09:00,Carol,,,carol,https://x.com/carol/status/3,Plain,svelte,,\n";
+
+        let bookmarks = LegacyCsvParser::new().parse_str(content).unwrap();
+        assert_eq!(bookmarks.len(), 3);
+        assert_eq!(bookmarks[0].content, "First line\nsecond line\nthird line");
+        assert_eq!(bookmarks[0].tags, vec!["rust"]);
+        assert_eq!(bookmarks[0].comments.as_deref(), Some("note"));
+        assert_eq!(bookmarks[0].media.len(), 1);
+        assert_eq!(bookmarks[1].content, "Short row");
+        assert_eq!(bookmarks[2].tags, vec!["svelte"]);
+
+        // A stray continuation with no row to attach to is rejected, not misread
+        let orphan = "Tweet Date,Posted By,Profile Pic,Profile URL,Twitter Handle,Tweet URL,Content,Tags,Comments,Media\n\
+            just text,rust,note,media\n";
+        assert!(LegacyCsvParser::new().parse_str(orphan).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_bom_prefixed_legacy_csv() {
+        let content = "\u{feff}Tweet Date,Posted By,Profile Pic,Profile URL,Twitter Handle,Tweet URL,Content,Tags,Comments,Media\n\
+            \"02:51 PM, May 01, 2024\",Alice,,,alice,https://x.com/alice/status/1,Hello,\"rust, news\",,\n";
+
+        assert_eq!(CsvFormat::detect_from_content(content).unwrap(), CsvFormat::Legacy);
+        let bookmarks = LegacyCsvParser::new().parse_str(content).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].tags, vec!["rust", "news"]);
+    }
+
+    #[test]
+    fn test_split_tags_keeps_quoted_commas() {
+        assert_eq!(split_tags("rust, news"), vec!["rust", "news"]);
+        assert_eq!(split_tags(" , rust,, "), vec!["rust"]);
+        assert!(split_tags("").is_empty());
+        assert_eq!(
+            split_tags("\"machine, learning\", rust"),
+            vec!["machine, learning", "rust"]
+        );
+        assert_eq!(
+            split_tags("rust, \"say \"\"hi\"\", then go\""),
+            vec!["rust", "say \"hi\", then go"]
+        );
+        assert_eq!(split_tags("c\\, c++, go"), vec!["c, c++", "go"]);
+        // Only a quote opening a tag is special; an unterminated one is literal
+        assert_eq!(split_tags("6\" screen, rust"), vec!["6\" screen", "rust"]);
+        assert_eq!(split_tags("rust, \"ml, ai"), vec!["rust", "\"ml", "ai"]);
+
+        let tags = split_tags("\"machine, learning\", \"say \"\"hi\"\"\", rust");
+        assert_eq!(split_tags(&join_tags(&tags)), tags);
+        let tags = vec!["a\\".to_string(), "b".to_string()];
+        assert_eq!(split_tags(&join_tags(&tags)), tags);
+
+        // In a file the whole cell is quoted again, doubling the inner quotes
+        let content = "Tweet Date,Posted By,Profile Pic,Profile URL,Twitter Handle,Tweet URL,Content,Tags,Comments,Media\n\
+            2024-05-01 14:51,Alice,,,alice,https://x.com/alice/status/1,Hello,\"\"\"machine, learning\"\", rust\",,\n";
+        let bookmarks = LegacyCsvParser::new().parse_str(content).unwrap();
+        assert_eq!(bookmarks[0].tags, vec!["machine, learning", "rust"]);
+    }
+
+    #[test]
+    fn test_semicolon_delimited_new_csv() {
+        let content = "profile_image_url_https;screen_name;name;full_text;note_tweet_text;tweeted_at;tweet_url\n\
+            ;bob;Bob;\"Hi; there\";;2025-08-25T10:52:35.000Z;https://x.com/bob/status/2\n";
+
+        assert_eq!(sniff_delimiter(content), b';');
+        assert_eq!(CsvFormat::detect_from_content(content).unwrap(), CsvFormat::New);
+        let bookmarks = NewCsvParser::new().parse_str(content).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].content, "Hi; there");
+        assert_eq!(bookmarks[0].author_handle, "bob");
+    }
+
+    #[test]
+    fn test_sniff_delimiter() {
+        assert_eq!(sniff_delimiter("a,b,c"), b',');
+        assert_eq!(sniff_delimiter("a\tb\tc"), b'\t');
+        assert_eq!(sniff_delimiter("\"a,b\";c;d"), b';');
+        assert_eq!(sniff_delimiter("single"), b',');
+    }
+
     #[test]
     fn test_new_date_parsing() {
         let parser = NewCsvParser::new();
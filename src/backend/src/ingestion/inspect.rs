@@ -0,0 +1,216 @@
+//! Dry-run inspection of export files
+
+use super::csv_parser::{open_csv, CsvFormat};
+use super::json_parser::{JsonParser, ENTRY_KEYS};
+use super::{detect_input_format, Ingester, InputFormat};
+use crate::{Error, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// How many rows `FileReport::sample_rows` holds at most
+const SAMPLE_ROWS: usize = 3;
+
+/// Format `Ingester::inspect` recognised
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum DetectedFormat {
+    /// Dewey CSV export
+    LegacyCsv,
+    /// Twitter/X CSV export
+    NewCsv,
+    /// A CSV file whose headers match neither export; importing it fails
+    UnknownCsv,
+    /// A JSON (or archive `.js`) export. `entries_key` names the wrapper key
+    /// (`"bookmarks"`, ...) when the top level is an object.
+    Json {
+        shape: JsonShape,
+        entries_key: Option<String>,
+        archive_js: bool,
+    },
+}
+
+/// Top-level structure of a JSON export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonShape {
+    Array,
+    Object,
+}
+
+/// What an import of a file would see, without writing anything
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileReport {
+    pub format: DetectedFormat,
+    /// CSV records or JSON entries in the file. Rows the parser later rejects
+    /// (missing URL, bad date) still count.
+    pub row_count_estimate: usize,
+    /// CSV headers, or the keys of the first JSON entry
+    pub header_columns: Vec<String>,
+    /// The first few rows, one value per `header_columns` entry
+    pub sample_rows: Vec<Vec<String>>,
+}
+
+impl Ingester {
+    /// Detect a file's format and summarise its contents without importing it,
+    /// so a UI can confirm ("Legacy CSV, 4,210 rows") before committing.
+    pub fn inspect(&self, path: &Path) -> Result<FileReport> {
+        match detect_input_format(path)? {
+            InputFormat::Csv => inspect_csv(&std::fs::read_to_string(path)?),
+            InputFormat::Json => inspect_json(&std::fs::read_to_string(path)?),
+        }
+    }
+}
+
+fn inspect_csv(content: &str) -> Result<FileReport> {
+    let mut reader = open_csv(content.as_bytes(), None)?;
+    let header_columns = reader
+        .headers()?
+        .iter()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    let header_str = header_columns.join(",").to_lowercase();
+    let format = match CsvFormat::detect_from_header_str(&header_str) {
+        Ok(CsvFormat::Legacy) => DetectedFormat::LegacyCsv,
+        Ok(CsvFormat::New) => DetectedFormat::NewCsv,
+        Err(_) => DetectedFormat::UnknownCsv,
+    };
+
+    let mut row_count_estimate = 0;
+    let mut sample_rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        if sample_rows.len() < SAMPLE_ROWS {
+            sample_rows.push(record.iter().map(str::to_string).collect());
+        }
+        row_count_estimate += 1;
+    }
+
+    Ok(FileReport {
+        format,
+        row_count_estimate,
+        header_columns,
+        sample_rows,
+    })
+}
+
+fn inspect_json(content: &str) -> Result<FileReport> {
+    let archive_js = content
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .starts_with("window.YTD");
+    let root: Value = serde_json::from_str(JsonParser::new().extract_payload(content)?)?;
+
+    let (shape, entries_key, entries) = match &root {
+        Value::Array(items) => (JsonShape::Array, None, items.as_slice()),
+        Value::Object(map) => {
+            match ENTRY_KEYS.iter().find_map(|key| match map.get(*key) {
+                Some(Value::Array(items)) => Some((key, items)),
+                _ => None,
+            }) {
+                Some((key, items)) => (JsonShape::Object, Some(key.to_string()), items.as_slice()),
+                // A lone bookmark object
+                None => (JsonShape::Object, None, std::slice::from_ref(&root)),
+            }
+        }
+        _ => {
+            return Err(Error::InvalidFormat(
+                "JSON export must be an array or an object".into(),
+            ))
+        }
+    };
+
+    let header_columns = match entries.first() {
+        Some(Value::Object(first)) => first.keys().cloned().collect(),
+        _ => Vec::new(),
+    };
+    let sample_rows = entries
+        .iter()
+        .take(SAMPLE_ROWS)
+        .map(|entry| {
+            header_columns
+                .iter()
+                .map(|key| match entry.get(key) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(value)) => value.clone(),
+                    Some(value) => value.to_string(),
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(FileReport {
+        format: DetectedFormat::Json {
+            shape,
+            entries_key,
+            archive_js,
+        },
+        row_count_estimate: entries.len(),
+        header_columns,
+        sample_rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn file(suffix: &str, content: &str) -> tempfile::NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+        write!(file, "{content}").unwrap();
+        file
+    }
+
+    #[test]
+    fn reports_csv_format_rows_and_samples() {
+        let csv = file(
+            ".csv",
+            "screen_name,full_text,tweeted_at,tweet_url\n\
+             alice,\"Hello,\nworld\",2024-05-01 10:00:00,https://x.com/alice/status/1\n\
+             bob,Hi,2024-05-02 10:00:00,https://x.com/bob/status/2\n",
+        );
+        let report = Ingester::new().inspect(csv.path()).unwrap();
+        assert_eq!(report.format, DetectedFormat::NewCsv);
+        assert_eq!(report.row_count_estimate, 2);
+        assert_eq!(report.header_columns[0], "screen_name");
+        assert_eq!(report.sample_rows[0][1], "Hello,\nworld");
+
+        let unknown = file(".csv", "a,b\n1,2\n");
+        let report = Ingester::new().inspect(unknown.path()).unwrap();
+        assert_eq!(report.format, DetectedFormat::UnknownCsv);
+        assert_eq!(report.header_columns, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn reports_json_shape_and_entry_count() {
+        let wrapped = file(
+            ".json",
+            r#"{"bookmarks":[{"tweet_url":"u1","likes":3},{"tweet_url":"u2"}]}"#,
+        );
+        let report = Ingester::new().inspect(wrapped.path()).unwrap();
+        assert_eq!(
+            report.format,
+            DetectedFormat::Json {
+                shape: JsonShape::Object,
+                entries_key: Some("bookmarks".into()),
+                archive_js: false,
+            }
+        );
+        assert_eq!(report.row_count_estimate, 2);
+        assert_eq!(report.sample_rows[1], vec!["", "u2"]);
+
+        let archive = file(".js", r#"window.YTD.bookmark.part0 = [{"bookmark":{}}]"#);
+        let report = Ingester::new().inspect(archive.path()).unwrap();
+        assert_eq!(
+            report.format,
+            DetectedFormat::Json {
+                shape: JsonShape::Array,
+                entries_key: None,
+                archive_js: true,
+            }
+        );
+        assert_eq!(report.row_count_estimate, 1);
+    }
+}
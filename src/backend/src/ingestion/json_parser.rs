@@ -1,15 +1,19 @@
 //! JSON parsing for Twitter bookmark exports
 
-use crate::models::{Bookmark, BookmarkBuilder};
+use super::{date_or_estimate, SourceRow};
+use crate::models::{Bookmark, BookmarkBuilder, MediaType};
 use crate::{Error, Result};
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use serde_json::Value;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufRead, BufReader};
 use std::path::Path;
 use tracing::{debug, warn};
 
+/// Keys under which an object-shaped export keeps its array of bookmarks
+pub(super) const ENTRY_KEYS: [&str; 4] = ["bookmarks", "data", "tweets", "items"];
+
 /// Parser for JSON bookmark exports
 pub struct JsonParser;
 
@@ -18,27 +22,45 @@ impl JsonParser {
         Self
     }
 
+    /// Parse a JSON or archive-JS export. Arrays are streamed entry by entry
+    /// (see `stream`); other shapes are read whole.
     pub fn parse(&self, path: &Path) -> Result<Vec<Bookmark>> {
-        let file = File::open(path)?;
-        match serde_json::from_reader::<_, Vec<FlatJsonBookmark>>(BufReader::new(file)) {
-            Ok(bookmarks) => match Self::try_map_flat_bookmarks(bookmarks) {
-                Some(bookmarks) => {
-                    debug!("Parsed {} bookmarks from JSON fast path", bookmarks.len());
-                    Ok(bookmarks)
-                }
-                None => {
-                    let raw = std::fs::read_to_string(path)?;
-                    self.parse_str(&raw)
-                }
-            },
-            Err(_) => {
-                let raw = std::fs::read_to_string(path)?;
-                self.parse_str(&raw)
-            }
+        let mut reader = BufReader::new(File::open(path)?);
+        if !is_streamable(&mut reader)? {
+            let raw = std::fs::read_to_string(path)?;
+            return self.parse_str(&raw);
+        }
+
+        let bookmarks = self.stream(reader).collect::<Result<Vec<_>>>()?;
+        debug!("Parsed {} bookmarks from JSON stream", bookmarks.len());
+        Ok(bookmarks)
+    }
+
+    /// Iterate the bookmarks of a JSON array (or archive-JS) export, decoding
+    /// one entry at a time so memory stays flat however large the file is.
+    ///
+    /// Entries that can't be converted are logged and skipped; malformed JSON
+    /// ends the stream with an error.
+    pub fn stream<R: BufRead>(&self, reader: R) -> JsonStream<R> {
+        JsonStream {
+            reader,
+            index: 0,
+            started: false,
+            done: false,
+            failed: Vec::new(),
         }
     }
 
     pub fn parse_str(&self, raw: &str) -> Result<Vec<Bookmark>> {
+        self.parse_str_keeping_failures(raw, &mut Vec::new())
+    }
+
+    /// `parse_str`, adding the entries it skips to `failed`
+    pub(super) fn parse_str_keeping_failures(
+        &self,
+        raw: &str,
+        failed: &mut Vec<SourceRow>,
+    ) -> Result<Vec<Bookmark>> {
         let payload = self.extract_payload(raw)?;
 
         if let Ok(bookmarks) = serde_json::from_str::<Vec<FlatJsonBookmark>>(payload) {
@@ -49,13 +71,16 @@ impl JsonParser {
         }
 
         let root: Value = serde_json::from_str(payload)?;
-        let raw_bookmarks = self.collect_entries(&root);
+        let raw_bookmarks = self.collect_entries(&root)?;
 
         let mut bookmarks = Vec::new();
         for (idx, raw) in raw_bookmarks.into_iter().enumerate() {
             match self.convert_raw(raw) {
                 Ok(bookmark) => bookmarks.push(bookmark),
-                Err(e) => warn!("Skipping JSON entry {}: {}", idx, e),
+                Err(e) => {
+                    warn!("Skipping JSON entry {}: {}", idx, e);
+                    failed.push(SourceRow::from_entry(idx, raw, &e));
+                }
             }
         }
 
@@ -72,14 +97,17 @@ impl JsonParser {
                     .zip(raw.id_str.as_ref())
                     .map(|(screen_name, id)| format!("https://x.com/{screen_name}/status/{id}"))
             })
-            .ok_or_else(|| Error::Other("Missing tweet URL".into()))?;
+            .ok_or(Error::MissingField("tweet_url"))?;
         let author_handle = raw.screen_name.unwrap_or_default();
         let author_name = raw.name.unwrap_or_else(|| author_handle.clone());
-        let tweeted_at = parse_date_candidates([
-            raw.tweeted_at.as_deref(),
-            raw.bookmark_date.as_deref(),
-            raw.created_at.as_deref(),
-        ])?;
+        let (tweeted_at, date_estimated) = date_or_estimate(
+            parse_date_candidates([
+                raw.tweeted_at.as_deref(),
+                raw.bookmark_date.as_deref(),
+                raw.created_at.as_deref(),
+            ]),
+            &tweet_url,
+        );
 
         let mut builder = BookmarkBuilder::new()
             .tweet_url(tweet_url)
@@ -90,15 +118,26 @@ impl JsonParser {
             .author_name(author_name)
             .author_profile_image(raw.profile_image_url_https.unwrap_or_default());
 
+        for tag in raw.tags.unwrap_or_default() {
+            let tag = tag.trim().trim_start_matches('#').to_lowercase();
+            if !tag.is_empty() {
+                builder = builder.add_tag(tag);
+            }
+        }
+
         if let Some(media) = raw.extended_media.or(raw.media) {
             for item in media {
                 if let Some(url) = item.media_url_https.or(item.url) {
-                    builder = builder.add_media(url);
+                    builder = match item.kind.as_deref().and_then(MediaType::from_name) {
+                        Some(media_type) => builder.add_media_with_type(url, media_type),
+                        None => builder.add_media(url),
+                    };
                 }
             }
         }
 
-        let mut bookmark = builder.build().map_err(|e| Error::Other(e.to_string()))?;
+        let mut bookmark = builder.build()?;
+        bookmark.date_estimated = date_estimated;
         if bookmark.tags.is_empty() {
             for tag in bookmark.extract_hashtags() {
                 if !bookmark.tags.contains(&tag) {
@@ -111,6 +150,14 @@ impl JsonParser {
         Ok(bookmark)
     }
 
+    /// Convert one array entry, preferring the flat export shape
+    fn convert_entry(&self, value: &Value) -> Result<Bookmark> {
+        FlatJsonBookmark::deserialize(value)
+            .map_err(Error::from)
+            .and_then(Self::from_flat_bookmark)
+            .or_else(|_| self.convert_raw(value))
+    }
+
     fn try_map_flat_bookmarks(bookmarks: Vec<FlatJsonBookmark>) -> Option<Vec<Bookmark>> {
         let mut mapped = Vec::with_capacity(bookmarks.len());
         for bookmark in bookmarks {
@@ -122,7 +169,7 @@ impl JsonParser {
         Some(mapped)
     }
 
-    fn extract_payload<'a>(&self, raw: &'a str) -> Result<&'a str> {
+    pub(super) fn extract_payload<'a>(&self, raw: &'a str) -> Result<&'a str> {
         let trimmed = raw.trim_start_matches('\u{feff}').trim();
 
         if trimmed.starts_with("window.YTD") {
@@ -139,18 +186,28 @@ impl JsonParser {
         Ok(trimmed)
     }
 
-    fn collect_entries<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+    /// Entries of a bare array, of an array under one of `ENTRY_KEYS`, or a
+    /// lone bookmark object
+    fn collect_entries<'a>(&self, root: &'a Value) -> Result<Vec<&'a Value>> {
         match root {
-            Value::Array(items) => items.iter().collect(),
+            Value::Array(items) => Ok(items.iter().collect()),
             Value::Object(map) => {
-                for key in ["bookmarks", "items", "data"] {
+                for key in ENTRY_KEYS {
                     if let Some(Value::Array(items)) = map.get(key) {
-                        return items.iter().collect();
+                        return Ok(items.iter().collect());
                     }
                 }
-                vec![root]
+                if self.convert_raw(root).is_ok() {
+                    return Ok(vec![root]);
+                }
+                let keys = map.keys().map(String::as_str).collect::<Vec<_>>();
+                Err(Error::InvalidFormat(format!(
+                    "JSON object has no bookmark array under any of {}; top-level keys: {}",
+                    ENTRY_KEYS.join(", "),
+                    keys.join(", ")
+                )))
             }
-            _ => Vec::new(),
+            _ => Ok(Vec::new()),
         }
     }
 
@@ -213,7 +270,7 @@ impl JsonParser {
                     }
                 })
             })
-            .ok_or_else(|| Error::Other("Missing tweet URL".into()))?;
+            .ok_or(Error::MissingField("tweet_url"))?;
 
         let content = self
             .extract_string(
@@ -228,8 +285,11 @@ impl JsonParser {
             )
             .unwrap_or_default();
 
-        let note_text = self.extract_string(raw, &[&["note_tweet_text"], &["noteTweetText"]]);
-        let tweeted_at = self.parse_date(raw)?;
+        let note_text = self.extract_string(
+            raw,
+            &[&["note_tweet_text"], &["noteTweetText"], &["note_text"]],
+        );
+        let (tweeted_at, date_estimated) = date_or_estimate(self.parse_date(raw), &tweet_url);
 
         let mut builder = BookmarkBuilder::new()
             .tweet_url(tweet_url)
@@ -269,11 +329,15 @@ impl JsonParser {
             }
         }
 
-        for url in self.extract_media_urls(raw) {
-            builder = builder.add_media(url);
+        for (url, media_type) in self.extract_media(raw) {
+            builder = match media_type {
+                Some(media_type) => builder.add_media_with_type(url, media_type),
+                None => builder.add_media(url),
+            };
         }
 
-        let mut bookmark = builder.build().map_err(|e| Error::Other(e.to_string()))?;
+        let mut bookmark = builder.build()?;
+        bookmark.date_estimated = date_estimated;
 
         if bookmark.tags.is_empty() {
             for tag in bookmark.extract_hashtags() {
@@ -343,8 +407,9 @@ impl JsonParser {
         Some(result)
     }
 
-    fn extract_media_urls(&self, raw: &Value) -> Vec<String> {
-        let mut urls = Vec::new();
+    /// Media URLs with their declared type (`type` / `media_type`), if any
+    fn extract_media(&self, raw: &Value) -> Vec<(String, Option<MediaType>)> {
+        let mut media: Vec<(String, Option<MediaType>)> = Vec::new();
 
         for path in [
             &["media"][..],
@@ -362,15 +427,20 @@ impl JsonParser {
                     .or_else(|| Self::value_at_path(item, &["media_url_https"]))
                     .and_then(Self::value_to_string);
 
+                let media_type = Self::value_at_path(item, &["media_type"])
+                    .or_else(|| Self::value_at_path(item, &["type"]))
+                    .and_then(Value::as_str)
+                    .and_then(MediaType::from_name);
+
                 if let Some(url) = url {
-                    if !urls.contains(&url) {
-                        urls.push(url);
+                    if !media.iter().any(|(seen, _)| *seen == url) {
+                        media.push((url, media_type));
                     }
                 }
             }
         }
 
-        urls
+        media
     }
 
     fn extract_handle_from_url(&self, url: &str) -> Option<String> {
@@ -399,7 +469,7 @@ impl JsonParser {
         );
 
         let Some(s) = date_str else {
-            return Err(Error::Other("Missing date".into()));
+            return Err(Error::MissingField("tweeted_at"));
         };
 
         parse_date_candidates([Some(s.as_str()), None, None])
@@ -440,6 +510,123 @@ fn parse_date_candidates(candidates: [Option<&str>; 3]) -> Result<DateTime<Utc>>
     Err(Error::Other("Could not parse date".into()))
 }
 
+/// Streaming iterator returned by `JsonParser::stream`
+pub struct JsonStream<R> {
+    reader: R,
+    /// Position of the next array entry, for log messages
+    index: usize,
+    started: bool,
+    done: bool,
+    /// Entries skipped so far
+    failed: Vec<SourceRow>,
+}
+
+impl<R: BufRead> JsonStream<R> {
+    /// Take the entries skipped so far (see `Ingester::ingest_rows`)
+    pub fn take_failed(&mut self) -> Vec<SourceRow> {
+        std::mem::take(&mut self.failed)
+    }
+
+    /// Decode the next array entry, or `None` after the closing `]`
+    fn next_entry(&mut self) -> Result<Option<Value>> {
+        if !self.started {
+            self.started = true;
+            match peek_document_start(&mut self.reader)? {
+                Some(b'[') => self.reader.consume(1),
+                // Archive JS: `window.YTD.bookmark.part0 = [...]`
+                Some(b'w') => {
+                    let mut prefix = Vec::new();
+                    self.reader.read_until(b'[', &mut prefix)?;
+                    if prefix.last() != Some(&b'[') {
+                        return Err(Error::InvalidFormat(
+                            "Could not locate JSON payload in archive JS".into(),
+                        ));
+                    }
+                }
+                _ => {
+                    return Err(Error::InvalidFormat(
+                        "Expected a JSON array of bookmarks".into(),
+                    ))
+                }
+            }
+            if peek_token(&mut self.reader)? == Some(b']') {
+                self.reader.consume(1);
+                return Ok(None);
+            }
+        } else {
+            match peek_token(&mut self.reader)? {
+                Some(b',') => self.reader.consume(1),
+                Some(b']') => return Ok(None),
+                _ => {
+                    return Err(Error::InvalidFormat(format!(
+                        "Expected ',' or ']' after JSON entry {}",
+                        self.index
+                    )))
+                }
+            }
+        }
+
+        // Deserializing an object or string stops right after its closing byte,
+        // so the reader is left positioned at the separator.
+        let mut deserializer = serde_json::Deserializer::from_reader(&mut self.reader);
+        Ok(Some(Value::deserialize(&mut deserializer)?))
+    }
+}
+
+impl<R: BufRead> Iterator for JsonStream<R> {
+    type Item = Result<Bookmark>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.next_entry() {
+                Ok(Some(value)) => {
+                    let index = self.index;
+                    self.index += 1;
+                    match JsonParser::new().convert_entry(&value) {
+                        Ok(bookmark) => return Some(Ok(bookmark)),
+                        Err(e) => {
+                            warn!("Skipping JSON entry {}: {}", index, e);
+                            self.failed.push(SourceRow::from_entry(index, &value, &e));
+                        }
+                    }
+                }
+                Ok(None) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Whether the document is an array (or archive JS) that `JsonParser::stream` can read
+pub(super) fn is_streamable<R: BufRead>(reader: &mut R) -> Result<bool> {
+    Ok(matches!(peek_document_start(reader)?, Some(b'[' | b'w')))
+}
+
+/// First significant byte of a document, after skipping a BOM and whitespace
+fn peek_document_start<R: BufRead>(reader: &mut R) -> Result<Option<u8>> {
+    if reader.fill_buf()?.starts_with(b"\xEF\xBB\xBF") {
+        reader.consume(3);
+    }
+    peek_token(reader)
+}
+
+/// Next non-whitespace byte, left unconsumed
+fn peek_token<R: BufRead>(reader: &mut R) -> Result<Option<u8>> {
+    loop {
+        let Some(&byte) = reader.fill_buf()?.first() else {
+            return Ok(None);
+        };
+        if !byte.is_ascii_whitespace() {
+            return Ok(Some(byte));
+        }
+        reader.consume(1);
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct FlatJsonBookmark {
     #[serde(default)]
@@ -448,13 +635,13 @@ struct FlatJsonBookmark {
     bookmark_date: Option<String>,
     #[serde(default)]
     profile_image_url_https: Option<String>,
-    #[serde(default)]
+    #[serde(default, alias = "author_handle")]
     screen_name: Option<String>,
-    #[serde(default)]
+    #[serde(default, alias = "author_name")]
     name: Option<String>,
-    #[serde(default)]
+    #[serde(default, alias = "content")]
     full_text: Option<String>,
-    #[serde(default)]
+    #[serde(default, alias = "note_text")]
     note_tweet_text: Option<String>,
     #[serde(default)]
     tweeted_at: Option<String>,
@@ -466,6 +653,8 @@ struct FlatJsonBookmark {
     extended_media: Option<Vec<FlatMedia>>,
     #[serde(default)]
     media: Option<Vec<FlatMedia>>,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -474,6 +663,8 @@ struct FlatMedia {
     media_url_https: Option<String>,
     #[serde(default)]
     url: Option<String>,
+    #[serde(default, rename = "type", alias = "media_type")]
+    kind: Option<String>,
 }
 
 impl Default for JsonParser {
@@ -501,6 +692,47 @@ mod tests {
         assert_eq!(bookmarks[0].media.len(), 1);
     }
 
+    #[test]
+    fn exported_media_types_survive_reimport() {
+        let mut builder = BookmarkBuilder::new()
+            .tweet_url("https://x.com/rustlang/status/123")
+            .content("Release assets")
+            .note_text("Full notes")
+            .tweeted_at(DateTime::parse_from_rfc3339("2024-05-01T14:30:00Z").unwrap().into())
+            .author_handle("rustlang")
+            .author_name("Rust Language")
+            .add_tag("release");
+        // URLs that would be guessed as a different type, so the declared type must win
+        for (url, media_type) in [
+            ("https://example.com/a", MediaType::Image),
+            ("https://example.com/b", MediaType::Video),
+            ("https://example.com/c", MediaType::Gif),
+            ("https://example.com/d.jpg", MediaType::Unknown),
+        ] {
+            builder = builder.add_media_with_type(url, media_type);
+        }
+        let original = builder.build().unwrap();
+
+        let exported = serde_json::to_string(&[&original]).unwrap();
+        assert!(exported.contains(r#""media_type":"gif""#));
+
+        let reimported = JsonParser::new().parse_str(&exported).unwrap();
+        assert_eq!(reimported.len(), 1);
+        assert_eq!(reimported[0].media, original.media);
+        assert_eq!(reimported[0].note_text, original.note_text);
+        assert_eq!(reimported[0].author_handle, "rustlang");
+        assert_eq!(reimported[0].tags, vec!["release"]);
+
+        let db = crate::Database::open_memory().unwrap();
+        db.insert_bookmarks(&reimported).unwrap();
+        let stored = db.get_bookmark(&reimported[0].id).unwrap().unwrap();
+        assert_eq!(stored.media, original.media);
+
+        // Exports written before the lowercase rename still read
+        let legacy: MediaType = serde_json::from_str(r#""Video""#).unwrap();
+        assert_eq!(legacy, MediaType::Video);
+    }
+
     #[test]
     fn parses_array_json_export() {
         let dir = tempdir().unwrap();
@@ -518,6 +750,111 @@ mod tests {
         assert_eq!(bookmarks[0].media.len(), 1);
     }
 
+    /// Serves `[entry, entry, ...]` on demand, counting the bytes handed out
+    struct SyntheticExport {
+        next: usize,
+        total: usize,
+        pending: Vec<u8>,
+        served: std::rc::Rc<std::cell::Cell<usize>>,
+    }
+
+    impl std::io::Read for SyntheticExport {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.pending.is_empty() && self.next <= self.total {
+                let id = self.next;
+                self.pending = match id {
+                    0 => b"[".to_vec(),
+                    _ if id == self.total => b"]".to_vec(),
+                    _ => format!(
+                        r#"{}{{"tweet_url":"https://x.com/rustlang/status/{id}","full_text":"Tweet {id}","tweeted_at":"2024-05-01T14:30:00Z","screen_name":"rustlang"}}"#,
+                        if id == 1 { "" } else { "," }
+                    )
+                    .into_bytes(),
+                };
+                self.next += 1;
+            }
+            let n = buf.len().min(self.pending.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            self.served.set(self.served.get() + n);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn streams_large_arrays_without_reading_ahead() {
+        let served = std::rc::Rc::new(std::cell::Cell::new(0));
+        // Roughly 15 MB if it were read in full
+        let export = SyntheticExport {
+            next: 0,
+            total: 100_000,
+            pending: Vec::new(),
+            served: served.clone(),
+        };
+
+        let mut stream = JsonParser::new().stream(BufReader::new(export));
+        let first: Vec<Bookmark> = stream.by_ref().take(3).map(|b| b.unwrap()).collect();
+        assert_eq!(first[2].tweet_url, "https://x.com/rustlang/status/3");
+        assert!(served.get() < 64 * 1024, "read {} bytes", served.get());
+
+        assert_eq!(stream.count(), 100_000 - 4);
+    }
+
+    #[test]
+    fn streaming_skips_bad_entries_and_reports_syntax_errors() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bookmarks.json");
+        fs::write(
+            &path,
+            "\u{feff} [ {\"tweet_url\":\"https://x.com/a/status/1\",\"tweeted_at\":\"2024-05-01T14:30:00Z\"},\n\
+             {\"full_text\":\"no url\"},\n\
+             {\"bookmark\":{\"tweetId\":\"3\",\"createdAt\":\"2024-08-25T10:52:35.000Z\",\"screen_name\":\"b\"}} ]",
+        )
+        .unwrap();
+        let bookmarks = JsonParser::new().parse(&path).unwrap();
+        let urls: Vec<&str> = bookmarks.iter().map(|b| b.tweet_url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec!["https://x.com/a/status/1", "https://x.com/b/status/3"]
+        );
+
+        let truncated =
+            r#"[{"tweet_url":"https://x.com/a/status/1","tweeted_at":"2024-05-01T14:30:00Z"} {"#;
+        let results: Vec<Result<Bookmark>> =
+            JsonParser::new().stream(truncated.as_bytes()).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn parses_arrays_wrapped_under_a_top_level_key() {
+        for key in ENTRY_KEYS {
+            let dir = tempdir().unwrap();
+            let path = dir.path().join("bookmarks.json");
+            fs::write(
+                &path,
+                format!(
+                    r#"{{"version":2,"{key}":[{{"tweet_url":"https://x.com/rustlang/status/123","full_text":"Wrapped","tweeted_at":"2024-05-01T14:30:00Z","screen_name":"rustlang"}}]}}"#
+                ),
+            )
+            .unwrap();
+
+            let bookmarks = JsonParser::new().parse(&path).unwrap();
+            assert_eq!(bookmarks.len(), 1, "wrapper key {key}");
+            assert_eq!(bookmarks[0].content, "Wrapped");
+        }
+
+        let err = JsonParser::new()
+            .parse_str(r#"{"version":2,"results":[]}"#)
+            .unwrap_err();
+        let Error::InvalidFormat(message) = err else {
+            panic!("expected InvalidFormat, got {err:?}");
+        };
+        assert!(message.contains("results") && message.contains("version"));
+        assert!(message.contains("tweets"));
+    }
+
     #[test]
     fn parses_archive_js_wrapper() {
         let dir = tempdir().unwrap();
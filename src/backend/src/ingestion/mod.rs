@@ -5,22 +5,164 @@
 //! - New CSV format (Twitter/X export)
 //! - JSON format
 //! - Archive JS format (`window.YTD... = [...]`)
+//!
+//! `Ingester::inspect` reports what an import would see without writing.
+//!
+//! With the `watch` feature, `Ingester::watch_directory` imports exports as
+//! they are dropped into a folder.
 
 mod csv_parser;
+mod inspect;
 mod json_parser;
+mod remote;
+mod source_row;
+#[cfg(feature = "watch")]
+mod watch;
 
-pub use csv_parser::{CsvFormat, LegacyCsvParser, NewCsvParser};
-pub use json_parser::JsonParser;
+pub use csv_parser::{
+    join_tags, sniff_delimiter, split_tags, CsvFormat, LegacyCsvParser, NewCsvParser,
+};
+pub use inspect::{DetectedFormat, FileReport, JsonShape};
+pub use json_parser::{JsonParser, JsonStream};
+pub use source_row::{write_source_rows, SourceRow};
+#[cfg(feature = "watch")]
+pub use watch::WatchEvent;
 
 use crate::models::Bookmark;
-use crate::storage::Database;
+use crate::storage::{Database, MergeReport};
 use crate::{Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Read};
 use std::path::Path;
-use tracing::info;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
 
 /// Main ingestion engine that auto-detects format and imports bookmarks
 pub struct Ingester {
     batch_size: usize,
+    checkpoint_every: usize,
+    max_download_bytes: u64,
+    incremental_overlap: Duration,
+    cancel: Option<Arc<AtomicBool>>,
+    date_policy: DatePolicy,
+    max_content_len: Option<usize>,
+    transform: Option<Box<Transform>>,
+}
+
+/// Per-bookmark hook set with `Ingester::with_transform`
+type Transform = dyn Fn(&mut Bookmark) -> Keep + Send + Sync;
+
+/// Whether a `with_transform` hook keeps the bookmark it was given
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keep {
+    Keep,
+    Drop,
+}
+
+/// What to do with rows whose date could not be read. Parsers keep such rows
+/// with `Bookmark::date_estimated` set; the ingester then applies the policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatePolicy {
+    /// Drop the row
+    #[default]
+    Skip,
+    /// Keep it, dated when it was imported
+    UseImportTime,
+    /// Keep it, dated 1970-01-01 so it sorts after everything else
+    UseEpoch,
+}
+
+/// Export format, given explicitly when there is no file extension to go by
+/// (`Ingester::ingest_reader`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputFormat {
+    /// Legacy (Dewey) or new (Twitter/X) CSV; the variant is read from the header
+    Csv,
+    /// JSON or archive JS (`window.YTD... = [...]`)
+    Json,
+}
+
+impl InputFormat {
+    /// Format for a file extension (`csv`, `json`, `js`), ignoring case
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "csv" => Some(InputFormat::Csv),
+            "json" | "js" => Some(InputFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Guess an export's format from its first bytes: `[`, `{` or `window.YTD`
+/// mean JSON, a header line naming known export columns means CSV. `None`
+/// when the content doesn't say; callers then go by the file extension.
+pub fn sniff_format(head: &[u8]) -> Option<InputFormat> {
+    let head = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(head);
+    let start = head.iter().position(|byte| !byte.is_ascii_whitespace())?;
+    let head = &head[start..];
+    if head.starts_with(b"[") || head.starts_with(b"{") || head.starts_with(b"window.YTD") {
+        return Some(InputFormat::Json);
+    }
+
+    let first_line = head.split(|&byte| byte == b'\n').next().unwrap_or(head);
+    let first_line = String::from_utf8_lossy(first_line).to_lowercase();
+    CsvFormat::detect_from_header_str(&first_line)
+        .is_ok()
+        .then_some(InputFormat::Csv)
+}
+
+/// Format of the file at `path`: sniffed from its content, else its extension
+fn detect_input_format(path: &Path) -> Result<InputFormat> {
+    let mut head = Vec::with_capacity(4096);
+    File::open(path)?.take(4096).read_to_end(&mut head)?;
+    let extension = extension_of(path);
+    sniff_format(&head)
+        .or_else(|| InputFormat::from_extension(&extension))
+        .ok_or(Error::UnsupportedFileType(extension))
+}
+
+impl std::str::FromStr for InputFormat {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Self::from_extension(name.trim().trim_start_matches('.'))
+            .ok_or_else(|| Error::UnsupportedFileType(name.to_string()))
+    }
+}
+
+/// Running totals reported while an import is being written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ImportProgress {
+    /// Parsed bookmarks written so far (inserted or skipped as duplicates)
+    pub done: usize,
+    /// Parsed bookmarks in the file
+    pub total: usize,
+    /// Bookmarks actually inserted so far
+    pub inserted: usize,
+}
+
+/// Outcome of `Ingester::ingest_file_keeping_failures` and `Ingester::ingest_rows`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IngestReport {
+    /// Bookmarks newly inserted
+    pub inserted: usize,
+    /// Rows the parser rejected, verbatim, to be fixed and retried
+    pub failed_rows: Vec<SourceRow>,
+}
+
+/// Outcome of `Ingester::ingest_file_incremental`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IncrementalReport {
+    /// Bookmarks newly inserted
+    pub inserted: usize,
+    /// Bookmarks skipped because they are older than what is already stored
+    pub already_imported: usize,
 }
 
 impl Default for Ingester {
@@ -31,7 +173,16 @@ impl Default for Ingester {
 
 impl Ingester {
     pub fn new() -> Self {
-        Self { batch_size: 1000 }
+        Self {
+            batch_size: 1000,
+            checkpoint_every: 0,
+            max_download_bytes: remote::DEFAULT_MAX_DOWNLOAD_BYTES,
+            incremental_overlap: Duration::from_secs(24 * 60 * 60),
+            cancel: None,
+            date_policy: DatePolicy::default(),
+            max_content_len: None,
+            transform: None,
+        }
     }
 
     pub fn with_batch_size(mut self, size: usize) -> Self {
@@ -39,50 +190,396 @@ impl Ingester {
         self
     }
 
-    /// Ingest bookmarks from a file, auto-detecting the format
+    /// Checkpoint the WAL every `batches` committed batches (`0` leaves it to SQLite)
+    pub fn with_checkpoint_every(mut self, batches: usize) -> Self {
+        self.checkpoint_every = batches;
+        self
+    }
+
+    /// Cap how many bytes `ingest_url` will download before giving up
+    pub fn with_max_download_bytes(mut self, bytes: u64) -> Self {
+        self.max_download_bytes = bytes;
+        self
+    }
+
+    /// How far before the newest stored bookmark `ingest_file_incremental` still
+    /// looks, to pick up rows that were added to the export out of order
+    pub fn with_incremental_overlap(mut self, overlap: Duration) -> Self {
+        self.incremental_overlap = overlap;
+        self
+    }
+
+    /// Stop inserting once `token` is set. The flag is checked between batches;
+    /// batches committed before that are kept, not rolled back, and the ingest
+    /// call returns the count inserted so far.
+    pub fn with_cancel_token(mut self, token: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// How to treat rows with an unreadable date (dropped by default)
+    pub fn with_date_policy(mut self, policy: DatePolicy) -> Self {
+        self.date_policy = policy;
+        self
+    }
+
+    /// Truncate content longer than `bytes` (on a char boundary), keeping the
+    /// original length in `Bookmark::content_original_len`. Unlimited by
+    /// default; a cap keeps pasted-in documents from bloating the index.
+    pub fn with_max_content_len(mut self, bytes: usize) -> Self {
+        self.max_content_len = Some(bytes);
+        self
+    }
+
+    /// Run `transform` on every parsed bookmark before it is inserted, to
+    /// apply custom rules: add tags, rewrite fields, or return `Keep::Drop`
+    /// to leave it out. It sees rows that passed the date policy, and the
+    /// content cap applies to what it returns.
+    ///
+    /// It is called once per row, inside the import loop, so keep it cheap:
+    /// no I/O or database lookups.
+    pub fn with_transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(&mut Bookmark) -> Keep + Send + Sync + 'static,
+    {
+        self.transform = Some(Box::new(transform));
+        self
+    }
+
+    /// Ingest bookmarks from a file, auto-detecting the format from its content
+    /// (or its extension when the content is ambiguous)
     pub fn ingest_file(&self, path: &Path, db: &Database) -> Result<usize> {
+        let format = detect_input_format(path)?;
+        self.ingest_reader(File::open(path)?, format, db)
+    }
+
+    /// Ingest an export from any reader (stdin, an HTTP body, a buffer). With
+    /// no extension to go by, the caller names the format.
+    pub fn ingest_reader(
+        &self,
+        reader: impl Read,
+        format: InputFormat,
+        db: &Database,
+    ) -> Result<usize> {
+        let mut reader = BufReader::new(reader);
+        let bookmarks = match format {
+            InputFormat::Json => {
+                if json_parser::is_streamable(&mut reader)? {
+                    return self.ingest_json_stream(reader, db);
+                }
+                let mut content = String::new();
+                reader.read_to_string(&mut content)?;
+                self.parse_json_content(&content)?
+            }
+            InputFormat::Csv => self.parse_csv_reader(reader, &mut Vec::new())?,
+        };
+        self.insert_bookmarks(self.prepare_rows(bookmarks), db)
+    }
+
+    /// Like `ingest_file`, also returning the rows that failed to parse so they
+    /// can be corrected and retried with `ingest_rows` (or written out with
+    /// `write_source_rows`). The file is read whole rather than streamed.
+    pub fn ingest_file_keeping_failures(&self, path: &Path, db: &Database) -> Result<IngestReport> {
+        let format = detect_input_format(path)?;
+        self.ingest_reader_keeping_failures(File::open(path)?, format, db)
+    }
+
+    /// `ingest_reader`, also returning the rows that failed to parse
+    pub fn ingest_reader_keeping_failures(
+        &self,
+        reader: impl Read,
+        format: InputFormat,
+        db: &Database,
+    ) -> Result<IngestReport> {
+        let mut reader = BufReader::new(reader);
+        let mut failed_rows = Vec::new();
+        let bookmarks = match format {
+            InputFormat::Json if json_parser::is_streamable(&mut reader)? => {
+                let mut stream = JsonParser::new().stream(reader);
+                let bookmarks = stream.by_ref().collect::<Result<Vec<_>>>()?;
+                failed_rows.extend(stream.take_failed());
+                bookmarks
+            }
+            InputFormat::Json => {
+                let mut content = String::new();
+                reader.read_to_string(&mut content)?;
+                JsonParser::new().parse_str_keeping_failures(&content, &mut failed_rows)?
+            }
+            InputFormat::Csv => self.parse_csv_reader(reader, &mut failed_rows)?,
+        };
+
+        let inserted = self.insert_bookmarks(self.prepare_rows(bookmarks), db)?;
+        if !failed_rows.is_empty() {
+            info!(
+                "{} rows failed to parse and can be retried",
+                failed_rows.len()
+            );
+        }
+        Ok(IngestReport {
+            inserted,
+            failed_rows,
+        })
+    }
+
+    /// Re-process a subset of rows, typically the `failed_rows` of an earlier
+    /// import after the user corrected them, without reading the whole file
+    /// again. Rows that still fail come back with their original row numbers.
+    pub fn ingest_rows(
+        &self,
+        rows: Vec<SourceRow>,
+        format: InputFormat,
+        db: &Database,
+    ) -> Result<IngestReport> {
+        if rows.is_empty() {
+            return Ok(IngestReport::default());
+        }
+        let document = source_row::to_document(&rows, format)?;
+        let mut report = self.ingest_reader_keeping_failures(document.as_bytes(), format, db)?;
+
+        // Row numbers point into the rebuilt document; map them back
+        for failed in &mut report.failed_rows {
+            let index = match format {
+                InputFormat::Csv => failed.row.checked_sub(2),
+                InputFormat::Json => Some(failed.row),
+            };
+            if let Some(source) = index.and_then(|index| rows.get(index)) {
+                failed.row = source.row;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Ingest a JSON array (or archive-JS) export, inserting batches as entries
+    /// are decoded so the whole file is never held in memory.
+    ///
+    /// A syntax error part-way through keeps the batches already committed and
+    /// returns the error.
+    pub fn ingest_json_stream<R: BufRead>(&self, reader: R, db: &Database) -> Result<usize> {
+        let mut stream_error = None;
+        let bookmarks = JsonParser::new()
+            .stream(reader)
+            .map_while(|result| result.map_err(|e| stream_error = Some(e)).ok())
+            .filter_map(|bookmark| self.prepare_row(bookmark));
+
+        let never_cancelled = AtomicBool::new(false);
+        let cancel = self.cancel.as_deref().unwrap_or(&never_cancelled);
+        let inserted = db.insert_bookmarks_streaming_cancellable(
+            bookmarks,
+            self.batch_size,
+            self.checkpoint_every,
+            cancel,
+            |_, _| {},
+        )?;
+
+        match stream_error {
+            Some(e) => Err(e),
+            None => {
+                info!("Successfully inserted {} bookmarks", inserted);
+                Ok(inserted)
+            }
+        }
+    }
+
+    /// Like `ingest_file`, calling `on_progress` after each committed batch
+    pub fn ingest_file_with_progress<F>(
+        &self,
+        path: &Path,
+        db: &Database,
+        on_progress: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(ImportProgress),
+    {
         let bookmarks = self.parse_file(path)?;
-        self.insert_bookmarks(bookmarks, db)
+        self.insert_bookmarks_with_progress(bookmarks, db, on_progress)
     }
 
-    /// Parse bookmarks from a file without inserting them.
-    pub fn parse_file(&self, path: &Path) -> Result<Vec<Bookmark>> {
-        let extension = path
-            .extension()
-            .and_then(|e| e.to_str())
-            .map(|e| e.to_lowercase())
-            .unwrap_or_default();
+    /// Ingest only bookmarks newer than the newest one already stored (minus the
+    /// overlap window), so re-syncing a growing export skips the bulk of it.
+    ///
+    /// Rows inside the overlap window are still deduplicated by URL on insert.
+    pub fn ingest_file_incremental(&self, path: &Path, db: &Database) -> Result<IncrementalReport> {
+        let mut bookmarks = self.parse_file(path)?;
+        let parsed = bookmarks.len();
+
+        if let Some(latest) = db.latest_tweeted_at()? {
+            let overlap = chrono::Duration::from_std(self.incremental_overlap)
+                .unwrap_or(chrono::Duration::MAX);
+            let cutoff = latest.checked_sub_signed(overlap).unwrap_or(latest);
+            bookmarks.retain(|bookmark| bookmark.tweeted_at > cutoff);
+        }
+
+        let already_imported = parsed - bookmarks.len();
+        info!(
+            "Incremental import: {} of {} bookmarks already imported",
+            already_imported, parsed
+        );
+        let inserted = self.insert_bookmarks(bookmarks, db)?;
+
+        Ok(IncrementalReport {
+            inserted,
+            already_imported,
+        })
+    }
 
-        match extension.as_str() {
-            "csv" => self.parse_csv(path),
-            "json" | "js" => self.parse_json(path),
-            _ => Err(Error::UnsupportedFileType(extension)),
+    /// Re-import an export into an existing library, merging bookmarks that
+    /// are already stored instead of skipping them (see `Database::merge_import`):
+    /// empty fields are filled in, while user-added tags, favorites and
+    /// comments are kept.
+    pub fn ingest_file_merging(&self, path: &Path, db: &Database) -> Result<MergeReport> {
+        let bookmarks = self.parse_file(path)?;
+        let mut report = MergeReport::default();
+        for batch in bookmarks.chunks(self.batch_size.max(1)) {
+            let batch_report = db.merge_import(batch)?;
+            report.inserted += batch_report.inserted;
+            report.merged += batch_report.merged;
+            report.unchanged += batch_report.unchanged;
+            report.failed += batch_report.failed;
         }
+        info!(
+            "Merge import: {} inserted, {} merged, {} unchanged, {} failed",
+            report.inserted, report.merged, report.unchanged, report.failed
+        );
+        Ok(report)
+    }
+
+    /// Parse bookmarks from a file without inserting them. The format is
+    /// sniffed from the content, falling back to the extension.
+    pub fn parse_file(&self, path: &Path) -> Result<Vec<Bookmark>> {
+        let bookmarks = match detect_input_format(path)? {
+            InputFormat::Csv => self.parse_csv(path)?,
+            InputFormat::Json => self.parse_json(path)?,
+        };
+        Ok(self.prepare_rows(bookmarks))
     }
 
     /// Parse bookmarks from raw content using the file extension.
     pub fn parse_content(&self, extension: &str, content: &str) -> Result<Vec<Bookmark>> {
-        match extension.to_lowercase().as_str() {
-            "csv" => self.parse_csv_content(content),
-            "json" | "js" => self.parse_json_content(content),
-            other => Err(Error::UnsupportedFileType(other.to_string())),
+        let bookmarks = match extension.to_lowercase().as_str() {
+            "csv" => self.parse_csv_content(content)?,
+            "json" | "js" => self.parse_json_content(content)?,
+            other => return Err(Error::UnsupportedFileType(other.to_string())),
+        };
+        Ok(self.prepare_rows(bookmarks))
+    }
+
+    /// `prepare_rows` for a single streamed bookmark (`None` = dropped)
+    fn prepare_row(&self, bookmark: Bookmark) -> Option<Bookmark> {
+        let mut bookmark = self.apply_date_policy_to(bookmark)?;
+        bookmark.normalize_unicode();
+        if !self.apply_transform(&mut bookmark) {
+            return None;
         }
+        self.limit_content(&mut bookmark);
+        Some(bookmark)
+    }
+
+    /// Apply the date policy, Unicode normalization, transform hook and
+    /// content cap to freshly parsed rows
+    fn prepare_rows(&self, bookmarks: Vec<Bookmark>) -> Vec<Bookmark> {
+        let mut bookmarks = self.apply_date_policy(bookmarks);
+        let parsed = bookmarks.len();
+        bookmarks.retain_mut(|bookmark| {
+            bookmark.normalize_unicode();
+            self.apply_transform(bookmark)
+        });
+        if bookmarks.len() < parsed {
+            info!("Transform dropped {} bookmarks", parsed - bookmarks.len());
+        }
+        for bookmark in &mut bookmarks {
+            self.limit_content(bookmark);
+        }
+        bookmarks
+    }
+
+    /// Run the `with_transform` hook, if any; returns whether to keep the row
+    fn apply_transform(&self, bookmark: &mut Bookmark) -> bool {
+        let Some(transform) = &self.transform else {
+            return true;
+        };
+        let keep = transform(bookmark) == Keep::Keep;
+        if keep {
+            // The hook may have changed searchable fields
+            bookmark.compute_search_text();
+        }
+        keep
+    }
+
+    /// Truncate `bookmark`'s content to `max_content_len`, if one is set
+    fn limit_content(&self, bookmark: &mut Bookmark) {
+        if let Some(max) = self.max_content_len {
+            if bookmark.truncate_content(max) {
+                warn!(
+                    "Truncated content of {} from {} to {} bytes",
+                    bookmark.tweet_url,
+                    bookmark.content_original_len.unwrap_or_default(),
+                    bookmark.content.len()
+                );
+            }
+        }
+    }
+
+    /// `apply_date_policy` for a single streamed bookmark (`None` = dropped)
+    fn apply_date_policy_to(&self, mut bookmark: Bookmark) -> Option<Bookmark> {
+        if bookmark.date_estimated {
+            match self.date_policy {
+                DatePolicy::Skip => {
+                    warn!("Skipping {} with an unreadable date", bookmark.tweet_url);
+                    return None;
+                }
+                DatePolicy::UseImportTime => bookmark.tweeted_at = bookmark.imported_at,
+                DatePolicy::UseEpoch => bookmark.tweeted_at = DateTime::UNIX_EPOCH,
+            }
+        }
+        Some(bookmark)
+    }
+
+    fn apply_date_policy(&self, mut bookmarks: Vec<Bookmark>) -> Vec<Bookmark> {
+        let estimated = bookmarks.iter().filter(|b| b.date_estimated).count();
+        if estimated == 0 {
+            return bookmarks;
+        }
+
+        match self.date_policy {
+            DatePolicy::Skip => {
+                warn!("Skipping {} bookmarks with unreadable dates", estimated);
+                bookmarks.retain(|bookmark| !bookmark.date_estimated);
+            }
+            DatePolicy::UseImportTime | DatePolicy::UseEpoch => {
+                info!("Keeping {} bookmarks with estimated dates", estimated);
+                for bookmark in bookmarks.iter_mut().filter(|b| b.date_estimated) {
+                    bookmark.tweeted_at = match self.date_policy {
+                        DatePolicy::UseImportTime => bookmark.imported_at,
+                        _ => DateTime::UNIX_EPOCH,
+                    };
+                }
+            }
+        }
+        bookmarks
     }
 
     /// Parse from CSV, auto-detecting the format variant
     fn parse_csv(&self, path: &Path) -> Result<Vec<Bookmark>> {
-        let format = CsvFormat::detect(path)?;
+        self.parse_csv_reader(BufReader::new(File::open(path)?), &mut Vec::new())
+    }
+
+    /// Parse CSV from a reader, detecting the variant from the header line
+    fn parse_csv_reader<R: BufRead>(
+        &self,
+        mut reader: R,
+        failed: &mut Vec<SourceRow>,
+    ) -> Result<Vec<Bookmark>> {
+        let mut header = Vec::new();
+        reader.read_until(b'\n', &mut header)?;
+        let format = CsvFormat::detect_from_content(&String::from_utf8_lossy(&header))?;
         info!("Detected CSV format: {:?}", format);
 
+        let reader = Cursor::new(header).chain(reader);
         match format {
             CsvFormat::Legacy => {
-                let parser = LegacyCsvParser::new();
-                parser.parse(path)
-            }
-            CsvFormat::New => {
-                let parser = NewCsvParser::new();
-                parser.parse(path)
+                LegacyCsvParser::new().parse_reader_keeping_failures(reader, failed)
             }
+            CsvFormat::New => NewCsvParser::new().parse_reader_keeping_failures(reader, failed),
         }
     }
 
@@ -105,18 +602,409 @@ impl Ingester {
 
     /// Insert bookmarks in batches for optimal performance
     fn insert_bookmarks(&self, bookmarks: Vec<Bookmark>, db: &Database) -> Result<usize> {
+        self.insert_bookmarks_with_progress(bookmarks, db, |_| {})
+    }
+
+    fn insert_bookmarks_with_progress<F>(
+        &self,
+        bookmarks: Vec<Bookmark>,
+        db: &Database,
+        mut on_progress: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(ImportProgress),
+    {
         let total = bookmarks.len();
         info!(
             "Inserting {} bookmarks in batches of {}",
             total, self.batch_size
         );
 
-        let mut inserted = 0;
-        for chunk in bookmarks.chunks(self.batch_size) {
-            inserted += db.insert_bookmarks(chunk)?;
-        }
+        let never_cancelled = AtomicBool::new(false);
+        let cancel = self.cancel.as_deref().unwrap_or(&never_cancelled);
+        let inserted = db.insert_bookmarks_streaming_cancellable(
+            bookmarks,
+            self.batch_size,
+            self.checkpoint_every,
+            cancel,
+            |done, inserted| {
+                on_progress(ImportProgress {
+                    done,
+                    total,
+                    inserted,
+                })
+            },
+        )?;
 
         info!("Successfully inserted {} bookmarks", inserted);
         Ok(inserted)
     }
 }
+
+/// Lowercased file extension, or an empty string
+fn extension_of(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// A parsed date, or the epoch plus `true` (`Bookmark::date_estimated`) when
+/// the row's date is missing or unreadable
+fn date_or_estimate(parsed: Result<DateTime<Utc>>, row: &str) -> (DateTime<Utc>, bool) {
+    match parsed {
+        Ok(date) => (date, false),
+        Err(e) => {
+            warn!("No usable date for {}: {}", row, e);
+            (DateTime::UNIX_EPOCH, true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn export(rows: &[(&str, &str)]) -> tempfile::NamedTempFile {
+        let entries = rows
+            .iter()
+            .map(|(id, date)| {
+                format!(
+                    r#"{{"tweet_url":"https://x.com/rustlang/status/{id}","full_text":"Tweet {id}","tweeted_at":"{date}","screen_name":"rustlang"}}"#
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut file = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        write!(file, "[{entries}]").unwrap();
+        file
+    }
+
+    #[test]
+    fn incremental_ingest_skips_rows_older_than_the_overlap_window() {
+        let db = Database::open_memory().unwrap();
+        let ingester = Ingester::new().with_incremental_overlap(Duration::from_secs(3600));
+
+        let first = export(&[("1", "2024-05-01T10:00:00Z"), ("2", "2024-05-02T10:00:00Z")]);
+        let report = ingester.ingest_file_incremental(first.path(), &db).unwrap();
+        assert_eq!(
+            report,
+            IncrementalReport {
+                inserted: 2,
+                already_imported: 0
+            }
+        );
+
+        // "3" is a late addition inside the overlap window; "0" is old history
+        let second = export(&[
+            ("0", "2024-04-01T10:00:00Z"),
+            ("1", "2024-05-01T10:00:00Z"),
+            ("2", "2024-05-02T10:00:00Z"),
+            ("3", "2024-05-02T09:30:00Z"),
+            ("4", "2024-05-03T10:00:00Z"),
+        ]);
+        let report = ingester
+            .ingest_file_incremental(second.path(), &db)
+            .unwrap();
+        assert_eq!(
+            report,
+            IncrementalReport {
+                inserted: 2,
+                already_imported: 2
+            }
+        );
+        assert_eq!(db.count_bookmarks().unwrap(), 4);
+    }
+
+    #[test]
+    fn progress_is_reported_after_each_batch() {
+        let db = Database::open_memory().unwrap();
+        let file = export(&[
+            ("1", "2024-05-01T10:00:00Z"),
+            ("2", "2024-05-02T10:00:00Z"),
+            ("3", "2024-05-03T10:00:00Z"),
+        ]);
+
+        let mut updates = Vec::new();
+        let inserted = Ingester::new()
+            .with_batch_size(2)
+            .ingest_file_with_progress(file.path(), &db, |progress| updates.push(progress))
+            .unwrap();
+
+        assert_eq!(inserted, 3);
+        assert_eq!(
+            updates,
+            vec![
+                ImportProgress {
+                    done: 2,
+                    total: 3,
+                    inserted: 2
+                },
+                ImportProgress {
+                    done: 3,
+                    total: 3,
+                    inserted: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn date_policy_decides_what_happens_to_unreadable_dates() {
+        let file = export(&[("1", "2024-05-01T10:00:00Z"), ("2", "last tuesday")]);
+
+        let parse = |policy| {
+            Ingester::new()
+                .with_date_policy(policy)
+                .parse_file(file.path())
+                .unwrap()
+        };
+
+        let skipped = parse(DatePolicy::Skip);
+        assert_eq!(skipped.len(), 1);
+        assert!(!skipped[0].date_estimated);
+
+        let import_time = parse(DatePolicy::UseImportTime);
+        assert_eq!(import_time.len(), 2);
+        assert!(import_time[1].date_estimated);
+        assert_eq!(import_time[1].tweeted_at, import_time[1].imported_at);
+
+        let epoch = parse(DatePolicy::UseEpoch);
+        assert_eq!(epoch.len(), 2);
+        assert!(epoch[1].date_estimated);
+        assert_eq!(epoch[1].tweeted_at, DateTime::UNIX_EPOCH);
+
+        let db = Database::open_memory().unwrap();
+        let inserted = Ingester::new()
+            .with_date_policy(DatePolicy::UseEpoch)
+            .ingest_file(file.path(), &db)
+            .unwrap();
+        assert_eq!(inserted, 2);
+    }
+
+    #[test]
+    fn ingests_from_a_reader_with_an_explicit_format() {
+        let db = Database::open_memory().unwrap();
+        let csv = "profile_image_url_https,screen_name,name,full_text,note_tweet_text,tweeted_at,tweet_url\n\
+            ,bob,Bob,Hello from stdin,,2025-08-25T10:52:35.000Z,https://x.com/bob/status/2\n";
+        let inserted = Ingester::new()
+            .ingest_reader(Cursor::new(csv.as_bytes()), InputFormat::Csv, &db)
+            .unwrap();
+        assert_eq!(inserted, 1);
+
+        let json = br#"{"bookmarks":[{"tweet_url":"https://x.com/a/status/1","tweeted_at":"2024-05-01T10:00:00Z"}]}"#;
+        let inserted = Ingester::new()
+            .ingest_reader(Cursor::new(&json[..]), "json".parse().unwrap(), &db)
+            .unwrap();
+        assert_eq!(inserted, 1);
+        assert_eq!(db.count_bookmarks().unwrap(), 2);
+        assert!("txt".parse::<InputFormat>().is_err());
+    }
+
+    #[test]
+    fn overlong_content_is_truncated_and_its_length_recorded() {
+        let db = Database::open_memory().unwrap();
+        // 1 MB of two-byte characters, so the cap lands mid-character
+        let huge = "é".repeat(512 * 1024);
+        let csv = format!(
+            "profile_image_url_https,screen_name,name,full_text,note_tweet_text,tweeted_at,tweet_url\n\
+             ,bob,Bob,{huge},,2025-08-25T10:52:35.000Z,https://x.com/bob/status/2\n\
+             ,bob,Bob,Short one,,2025-08-25T10:52:35.000Z,https://x.com/bob/status/3\n"
+        );
+        let inserted = Ingester::new()
+            .with_max_content_len(1001)
+            .ingest_reader(Cursor::new(csv.as_bytes()), InputFormat::Csv, &db)
+            .unwrap();
+        assert_eq!(inserted, 2);
+
+        let stored = db.get_bookmark_by_tweet_id("2").unwrap().unwrap();
+        assert_eq!(stored.content, "é".repeat(500));
+        assert_eq!(stored.content_original_len, Some(1024 * 1024));
+        let short = db.get_bookmark_by_tweet_id("3").unwrap().unwrap();
+        assert_eq!(short.content, "Short one");
+        assert_eq!(short.content_original_len, None);
+    }
+
+    #[test]
+    fn transform_can_drop_and_retag_bookmarks() {
+        let db = Database::open_memory().unwrap();
+        let csv = "profile_image_url_https,screen_name,name,full_text,note_tweet_text,tweeted_at,tweet_url\n\
+                   ,spammer,Spam,Buy now,,2025-08-25T10:52:35.000Z,https://x.com/spammer/status/1\n\
+                   ,rustlang,Rust,Rust 1.80 is out,,2025-08-25T10:52:35.000Z,https://x.com/rustlang/status/2\n\
+                   ,SPAMMER,Spam,Buy again,,2025-08-25T10:52:35.000Z,https://x.com/spammer/status/3\n";
+        let blocked = ["spammer"];
+        let ingester = Ingester::new().with_transform(move |bookmark| {
+            if blocked.contains(&bookmark.author_handle.to_lowercase().as_str()) {
+                return Keep::Drop;
+            }
+            if bookmark.author_handle == "rustlang" {
+                bookmark.tags.push("rust".into());
+            }
+            Keep::Keep
+        });
+
+        let inserted = ingester
+            .ingest_reader(Cursor::new(csv.as_bytes()), InputFormat::Csv, &db)
+            .unwrap();
+        assert_eq!(inserted, 1);
+        assert!(db.get_bookmark_by_tweet_id("1").unwrap().is_none());
+        let kept = db.get_bookmark_by_tweet_id("2").unwrap().unwrap();
+        assert_eq!(kept.tags, vec!["rust"]);
+
+        // Parsing without inserting goes through the same hook
+        let parsed = ingester.parse_content("csv", csv).unwrap();
+        assert_eq!(parsed.len(), 1);
+    }
+
+    #[test]
+    fn decomposed_accents_are_found_by_composed_queries() {
+        let db = Database::open_memory().unwrap();
+        // "Café" and "Zoë" with combining marks (NFD)
+        let csv = "profile_image_url_https,screen_name,name,full_text,note_tweet_text,tweeted_at,tweet_url\n\
+                   ,zoe,Zoe\u{0308},Best cafe\u{0301} in town,,2025-08-25T10:52:35.000Z,https://x.com/zoe/status/1\n";
+        let inserted = Ingester::new()
+            .ingest_reader(Cursor::new(csv.as_bytes()), InputFormat::Csv, &db)
+            .unwrap();
+        assert_eq!(inserted, 1);
+
+        let stored = db.get_bookmark_by_tweet_id("1").unwrap().unwrap();
+        assert_eq!(stored.content, "Best caf\u{e9} in town");
+        assert_eq!(stored.author_name, "Zo\u{eb}");
+        assert_eq!(db.search("caf\u{e9}", 10).unwrap().len(), 1);
+        // Queries are normalized the same way
+        assert_eq!(db.search("cafe\u{0301}", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn failed_rows_can_be_fixed_and_retried_on_their_own() {
+        let csv = "Tweet Date,Posted By,Profile Pic,Profile URL,Twitter Handle,Tweet URL,Content,Tags,Comments,Media\n\
+                   2024-05-01 09:00,Alice,,,alice,https://x.com/alice/status/1,Fine,,,\n\
+                   2024-05-02 09:00,Bob,,,bob,x.com/bob,\"Bad link, fix me\",,,\n\
+                   2024-05-03 09:00,Carol,,,carol,https://x.com/carol/status/3,Fine too,,,\n";
+        let db = Database::open_memory().unwrap();
+        let ingester = Ingester::new();
+
+        let report = ingester
+            .ingest_reader_keeping_failures(csv.as_bytes(), InputFormat::Csv, &db)
+            .unwrap();
+        assert_eq!(report.inserted, 2);
+        assert_eq!(report.failed_rows.len(), 1);
+        let failed = &report.failed_rows[0];
+        assert_eq!(failed.row, 3);
+        assert_eq!(
+            failed.raw,
+            "2024-05-02 09:00,Bob,,,bob,x.com/bob,\"Bad link, fix me\",,,"
+        );
+
+        let mut errors = Vec::new();
+        write_source_rows(&report.failed_rows, &mut errors).unwrap();
+        assert!(String::from_utf8(errors)
+            .unwrap()
+            .starts_with("Tweet Date,Posted By,"));
+
+        // Retrying unchanged rows reports them again, under their source row
+        let again = ingester
+            .ingest_rows(report.failed_rows.clone(), InputFormat::Csv, &db)
+            .unwrap();
+        assert_eq!(again.inserted, 0);
+        assert_eq!(again.failed_rows, report.failed_rows);
+
+        let mut fixed = report.failed_rows.clone();
+        fixed[0].raw = fixed[0]
+            .raw
+            .replace("x.com/bob", "https://x.com/bob/status/2");
+        let retried = ingester.ingest_rows(fixed, InputFormat::Csv, &db).unwrap();
+        assert_eq!(retried.inserted, 1);
+        assert!(retried.failed_rows.is_empty());
+        assert_eq!(db.get_stats().unwrap().total_bookmarks, 3);
+
+        let json = r#"[{"tweet_url":"https://x.com/dan/status/4","tweeted_at":"2024-05-01T10:00:00Z"},
+                       {"content":"no url"}]"#;
+        let report = ingester
+            .ingest_reader_keeping_failures(json.as_bytes(), InputFormat::Json, &db)
+            .unwrap();
+        assert_eq!(report.inserted, 1);
+        assert_eq!(report.failed_rows.len(), 1);
+        assert_eq!(report.failed_rows[0].row, 1);
+        assert_eq!(report.failed_rows[0].raw, r#"{"content":"no url"}"#);
+        assert_eq!(report.failed_rows[0].header, None);
+    }
+
+    #[test]
+    fn sniffs_the_format_of_files_without_a_useful_extension() {
+        assert_eq!(
+            sniff_format(b"\xEF\xBB\xBF\n  [{}]"),
+            Some(InputFormat::Json)
+        );
+        assert_eq!(
+            sniff_format(b"window.YTD.bookmark.part0 = ["),
+            Some(InputFormat::Json)
+        );
+        assert_eq!(
+            sniff_format(b"Tweet Date;Posted By;Tweet URL\n..."),
+            Some(InputFormat::Csv)
+        );
+        assert_eq!(sniff_format(b"hello world"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("download");
+        std::fs::write(
+            &json,
+            r#"[{"tweet_url":"https://x.com/a/status/1","tweeted_at":"2024-05-01T10:00:00Z"}]"#,
+        )
+        .unwrap();
+        let csv = dir.path().join("bookmarks.txt");
+        std::fs::write(
+            &csv,
+            "profile_image_url_https,screen_name,name,full_text,note_tweet_text,tweeted_at,tweet_url\n\
+             ,bob,Bob,Hi,,2025-08-25T10:52:35.000Z,https://x.com/bob/status/2\n",
+        )
+        .unwrap();
+
+        let db = Database::open_memory().unwrap();
+        assert_eq!(Ingester::new().ingest_file(&json, &db).unwrap(), 1);
+        assert_eq!(Ingester::new().ingest_file(&csv, &db).unwrap(), 1);
+        assert_eq!(
+            Ingester::new().inspect(&csv).unwrap().format,
+            DetectedFormat::NewCsv
+        );
+    }
+
+    #[test]
+    fn json_stream_ingest_keeps_batches_before_a_syntax_error() {
+        let db = Database::open_memory().unwrap();
+        let truncated =
+            br#"[{"tweet_url":"https://x.com/a/status/1","tweeted_at":"2024-05-01T10:00:00Z"},
+            {"tweet_url":"https://x.com/a/status/2","tweeted_at":"2024-05-02T10:00:00Z"},
+            {"tweet_url":"#;
+
+        let result = Ingester::new()
+            .with_batch_size(1)
+            .ingest_json_stream(&truncated[..], &db);
+        assert!(result.is_err());
+        assert_eq!(db.count_bookmarks().unwrap(), 2);
+    }
+
+    #[test]
+    fn cancel_token_stops_between_batches() {
+        let db = Database::open_memory().unwrap();
+        let file = export(&[
+            ("1", "2024-05-01T10:00:00Z"),
+            ("2", "2024-05-02T10:00:00Z"),
+            ("3", "2024-05-03T10:00:00Z"),
+        ]);
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let inserted = Ingester::new()
+            .with_batch_size(2)
+            .with_cancel_token(Arc::clone(&cancel))
+            .ingest_file_with_progress(file.path(), &db, |_| {
+                cancel.store(true, std::sync::atomic::Ordering::Relaxed)
+            })
+            .unwrap();
+
+        assert_eq!(inserted, 2);
+        assert_eq!(db.count_bookmarks().unwrap(), 2);
+    }
+}
@@ -0,0 +1,162 @@
+//! Remote export downloads
+//!
+//! Fetches an export over HTTP into a temp file, then hands it to the regular
+//! file ingestion path so format detection stays in one place.
+
+use super::Ingester;
+use crate::storage::Database;
+use crate::{Error, Result};
+use std::io::Write;
+use tracing::info;
+
+/// Default download cap (256 MiB) — large enough for multi-year archives
+pub(super) const DEFAULT_MAX_DOWNLOAD_BYTES: u64 = 256 * 1024 * 1024;
+
+impl Ingester {
+    /// Download an export from `url` and ingest it.
+    ///
+    /// The format comes from the response `Content-Type`, falling back to the
+    /// URL's file extension. The temp file is removed once ingestion finishes.
+    pub async fn ingest_url(&self, url: &str, db: &Database) -> Result<usize> {
+        info!("Downloading bookmarks from: {}", url);
+        let mut response = reqwest::get(url).await?.error_for_status()?;
+
+        if let Some(length) = response.content_length() {
+            if length > self.max_download_bytes {
+                return Err(Error::DownloadTooLarge(self.max_download_bytes));
+            }
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let extension = infer_extension(content_type.as_deref(), response.url().path())
+            .or_else(|| infer_extension(None, url))
+            .ok_or_else(|| {
+                Error::UnsupportedFileType(content_type.clone().unwrap_or_else(|| url.to_string()))
+            })?;
+
+        let mut file = tempfile::Builder::new()
+            .prefix("eterea-download-")
+            .suffix(&format!(".{extension}"))
+            .tempfile()?;
+        let mut downloaded = 0u64;
+        while let Some(chunk) = response.chunk().await? {
+            downloaded += chunk.len() as u64;
+            if downloaded > self.max_download_bytes {
+                return Err(Error::DownloadTooLarge(self.max_download_bytes));
+            }
+            file.write_all(&chunk)?;
+        }
+        file.flush()?;
+
+        info!("Downloaded {} bytes as .{}", downloaded, extension);
+        // `file` is deleted on drop, after ingestion has read it.
+        self.ingest_file(file.path(), db)
+    }
+}
+
+/// Map a `Content-Type` (preferred) or URL path to a supported file extension
+fn infer_extension(content_type: Option<&str>, url_path: &str) -> Option<&'static str> {
+    if let Some(content_type) = content_type {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+        match mime.as_str() {
+            "text/csv" | "application/csv" => return Some("csv"),
+            "application/json" | "text/json" => return Some("json"),
+            "application/javascript" | "text/javascript" => return Some("js"),
+            _ => {}
+        }
+    }
+
+    let path = url_path.split(['?', '#']).next().unwrap_or_default();
+    let file_name = path.rsplit('/').next().unwrap_or_default();
+    let (_, extension) = file_name.rsplit_once('.')?;
+    match extension.to_lowercase().as_str() {
+        "csv" => Some("csv"),
+        "json" => Some("json"),
+        "js" => Some("js"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    /// Serve a single HTTP response on a local port and return its base URL.
+    fn serve_once(content_type: &'static str, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                line.clear();
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        });
+        format!("http://{address}")
+    }
+
+    const SAMPLE_JSON: &str = r#"[{"tweet_url":"https://x.com/rustlang/status/1","full_text":"Hello #rust","tweeted_at":"2024-05-01T14:30:00Z","screen_name":"rustlang"}]"#;
+
+    #[test]
+    fn infers_extension_from_content_type_then_url() {
+        assert_eq!(
+            infer_extension(Some("text/csv; charset=utf-8"), "/download"),
+            Some("csv")
+        );
+        assert_eq!(
+            infer_extension(
+                Some("application/octet-stream"),
+                "/exports/bookmarks.JSON?dl=1"
+            ),
+            Some("json")
+        );
+        assert_eq!(infer_extension(None, "/archive/bookmark.js"), Some("js"));
+        assert_eq!(infer_extension(None, "/download"), None);
+    }
+
+    #[tokio::test]
+    async fn ingests_a_downloaded_export() {
+        let base = serve_once("application/json", SAMPLE_JSON);
+        let db = Database::open_memory().unwrap();
+
+        let count = Ingester::new()
+            .ingest_url(&format!("{base}/export"), &db)
+            .await
+            .unwrap();
+
+        assert_eq!(count, 1);
+        assert_eq!(db.count_bookmarks().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_downloads_over_the_size_limit() {
+        let base = serve_once("application/json", SAMPLE_JSON);
+        let db = Database::open_memory().unwrap();
+
+        let error = Ingester::new()
+            .with_max_download_bytes(16)
+            .ingest_url(&format!("{base}/export.json"), &db)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, Error::DownloadTooLarge(16)));
+        assert_eq!(db.count_bookmarks().unwrap(), 0);
+    }
+}
@@ -0,0 +1,94 @@
+//! Source rows the parsers could not turn into bookmarks, kept verbatim so
+//! they can be fixed and retried (`Ingester::ingest_rows`)
+
+use super::InputFormat;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// One row of an export that failed to parse
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceRow {
+    /// Where the row was: the CSV record number counting the header as 1, or
+    /// the 0-based index of the JSON entry
+    pub row: usize,
+    /// The row itself: one CSV record (comma-delimited) or one JSON entry
+    pub raw: String,
+    /// Header record of the CSV file the row came from; `None` for JSON
+    pub header: Option<String>,
+    /// Why the row was rejected
+    pub error: String,
+}
+
+impl SourceRow {
+    pub(super) fn from_record(
+        row: usize,
+        header: &csv::StringRecord,
+        record: &csv::StringRecord,
+        error: &Error,
+    ) -> Result<Self> {
+        Ok(Self {
+            row,
+            raw: encode_record(record)?,
+            header: Some(encode_record(header)?),
+            error: error.to_string(),
+        })
+    }
+
+    pub(super) fn from_entry(row: usize, entry: &serde_json::Value, error: &Error) -> Self {
+        Self {
+            row,
+            raw: entry.to_string(),
+            header: None,
+            error: error.to_string(),
+        }
+    }
+}
+
+/// One CSV line (without the line break) holding `record`
+fn encode_record(record: &csv::StringRecord) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(Vec::new());
+    writer.write_record(record)?;
+    let mut line = writer
+        .into_inner()
+        .map_err(|e| Error::Other(e.to_string()))?;
+    line.pop();
+    String::from_utf8(line).map_err(|e| Error::Other(e.to_string()))
+}
+
+/// Rebuild a document of `format` from `rows`, as the parsers expect it: a CSV
+/// file under the first row's header, or a JSON array
+pub(super) fn to_document(rows: &[SourceRow], format: InputFormat) -> Result<String> {
+    let raws = rows.iter().map(|row| row.raw.as_str());
+    match format {
+        InputFormat::Csv => {
+            let header = rows
+                .iter()
+                .find_map(|row| row.header.as_deref())
+                .ok_or_else(|| Error::InvalidFormat("CSV rows without a header".into()))?;
+            Ok(std::iter::once(header)
+                .chain(raws)
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        InputFormat::Json => Ok(format!("[{}]", raws.collect::<Vec<_>>().join(","))),
+    }
+}
+
+/// Write `rows` as a file the user can fix and import again: CSV under the
+/// original header, or a JSON array of the entries
+pub fn write_source_rows<W: Write>(rows: &[SourceRow], mut writer: W) -> Result<()> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let format = if rows[0].header.is_some() {
+        InputFormat::Csv
+    } else {
+        InputFormat::Json
+    };
+    writer.write_all(to_document(rows, format)?.as_bytes())?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
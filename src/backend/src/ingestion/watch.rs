@@ -0,0 +1,187 @@
+//! Folder watching (`watch` feature)
+//!
+//! Imports `.csv` / `.json` / `.js` exports as they are dropped into a folder.
+//! Filesystem events are debounced per file, and a file is only ingested once
+//! its size has stopped changing, so exports still being copied in are not
+//! read half-written.
+
+use super::Ingester;
+use crate::storage::Database;
+use crate::{Error, Result};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// How long a file's size must stay unchanged before it is ingested
+const SETTLE_TIME: Duration = Duration::from_secs(1);
+
+/// How often pending files and the cancel token are checked
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Result of ingesting one file picked up by `Ingester::watch_directory`
+#[derive(Debug)]
+pub enum WatchEvent {
+    Imported { path: PathBuf, inserted: usize },
+    Failed { path: PathBuf, error: Error },
+}
+
+/// A file seen by the watcher that hasn't settled yet
+struct Pending {
+    size: u64,
+    changed_at: Instant,
+}
+
+impl Ingester {
+    /// Watch `dir` (not recursively) and ingest new export files as they appear,
+    /// calling `on_event` with the outcome of each.
+    ///
+    /// Runs until the cancel token (`with_cancel_token`) is set; without one it
+    /// runs for the life of the process. Files already in `dir` when watching
+    /// starts are left alone.
+    pub fn watch_directory<F>(&self, dir: &Path, db: &Database, mut on_event: F) -> Result<()>
+    where
+        F: FnMut(WatchEvent),
+    {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(watch_error)?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+        info!("Watching {} for new exports", dir.display());
+
+        let mut pending = HashMap::<PathBuf, Pending>::new();
+        loop {
+            if self
+                .cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+            {
+                info!("Stopped watching {}", dir.display());
+                return Ok(());
+            }
+
+            match events.recv_timeout(POLL_INTERVAL) {
+                Ok(Ok(event)) => {
+                    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                        for path in event.paths.into_iter().filter(|path| is_export(path)) {
+                            debug!("Saw change to {}", path.display());
+                            pending.insert(
+                                path,
+                                Pending {
+                                    size: u64::MAX,
+                                    changed_at: Instant::now(),
+                                },
+                            );
+                        }
+                    }
+                }
+                Ok(Err(error)) => return Err(watch_error(error)),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    return Err(Error::Other("file watcher stopped unexpectedly".into()))
+                }
+            }
+
+            let settled = settled_files(&mut pending);
+            for path in settled {
+                let event = match self.ingest_file(&path, db) {
+                    Ok(inserted) => {
+                        info!("Imported {} bookmarks from {}", inserted, path.display());
+                        WatchEvent::Imported { path, inserted }
+                    }
+                    Err(error) => WatchEvent::Failed { path, error },
+                };
+                on_event(event);
+            }
+        }
+    }
+}
+
+/// Remove and return pending files whose size hasn't changed for `SETTLE_TIME`.
+/// Files that disappeared are dropped.
+fn settled_files(pending: &mut HashMap<PathBuf, Pending>) -> Vec<PathBuf> {
+    let now = Instant::now();
+    let mut settled = Vec::new();
+    pending.retain(|path, state| {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return false;
+        };
+        if metadata.len() != state.size {
+            state.size = metadata.len();
+            state.changed_at = now;
+            return true;
+        }
+        if now.duration_since(state.changed_at) < SETTLE_TIME {
+            return true;
+        }
+        settled.push(path.clone());
+        false
+    });
+    settled
+}
+
+fn is_export(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| matches!(extension.to_lowercase().as_str(), "csv" | "json" | "js"))
+}
+
+fn watch_error(error: notify::Error) -> Error {
+    Error::Other(format!("file watcher error: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    #[test]
+    fn imports_exports_dropped_into_the_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open_memory().unwrap();
+        let cancel = Arc::new(AtomicBool::new(false));
+        std::fs::write(dir.path().join("existing.json"), "not even json").unwrap();
+
+        let export = dir.path().join("export.json");
+        let writer = {
+            let export = export.clone();
+            let cancel = Arc::clone(&cancel);
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(300));
+                std::fs::write(export.with_file_name("notes.txt"), "ignored").unwrap();
+                std::fs::write(
+                    &export,
+                    r#"[{"tweet_url":"https://x.com/rustlang/status/1","full_text":"Hi","tweeted_at":"2024-05-01T10:00:00Z","screen_name":"rustlang"}]"#,
+                )
+                .unwrap();
+                // Give up rather than hang if the watcher never reports back
+                std::thread::sleep(Duration::from_secs(10));
+                cancel.store(true, Ordering::Relaxed);
+            })
+        };
+
+        let mut events = Vec::new();
+        Ingester::new()
+            .with_cancel_token(Arc::clone(&cancel))
+            .watch_directory(dir.path(), &db, |event| {
+                events.push(event);
+                cancel.store(true, Ordering::Relaxed);
+            })
+            .unwrap();
+        drop(writer);
+
+        assert!(
+            matches!(
+                events.as_slice(),
+                [WatchEvent::Imported { path, inserted: 1 }] if *path == export
+            ),
+            "unexpected events: {events:?}"
+        );
+        assert_eq!(db.count_bookmarks().unwrap(), 1);
+    }
+}
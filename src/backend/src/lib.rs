@@ -7,10 +7,12 @@ pub mod models;
 pub mod ingestion;
 pub mod storage;
 pub mod search;
+pub mod preview;
+pub mod export;
 pub mod error;
 
 pub use error::{Error, Result};
 pub use models::Bookmark;
 pub use storage::Database;
-pub use ingestion::Ingester;
+pub use ingestion::{Ingester, Keep};
 
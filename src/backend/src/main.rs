@@ -1,18 +1,40 @@
 //! Eterea CLI - Command-line interface for bookmark management
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use eterea_core::ingestion::{write_source_rows, DetectedFormat, InputFormat};
+use eterea_core::models::{Bookmark, DEFAULT_WORDS_PER_MINUTE};
+use eterea_core::preview::{LinkPreviewer, PreviewOptions, PreviewSettings};
+use eterea_core::storage::{DatabaseOptions, Period, SearchFilters, TagMatch};
 use eterea_core::{Database, Ingester};
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use std::sync::OnceLock;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 fn main() -> Result<()> {
-    // Initialize logging
+    let mut args: Vec<String> = std::env::args().collect();
+
+    // Initialize logging; -q/-v flags override $RUST_LOG. Logs go to stderr so
+    // they never mix into `export -` output
+    let filter = match take_verbosity(&mut args) {
+        Some(level) => EnvFilter::new(level),
+        None => EnvFilter::from_default_env(),
+    };
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .with(tracing_subscriber::EnvFilter::from_default_env())
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(filter)
         .init();
 
-    let args: Vec<String> = std::env::args().collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--db") {
+        if index + 1 >= args.len() {
+            eprintln!("--db needs a path");
+            return Ok(());
+        }
+        let path = args.remove(index + 1);
+        args.remove(index);
+        DB_PATH.set(PathBuf::from(path)).ok();
+    }
 
     if args.len() < 2 {
         print_usage();
@@ -21,23 +43,149 @@ fn main() -> Result<()> {
 
     match args[1].as_str() {
         "ingest" => {
+            let usage = "Usage: eterea-cli ingest [--merge] [--skip-content-duplicates] [--save-failed <file>] [--format csv|json] <file_path|->";
+            let mut merge = false;
+            let mut skip_content_duplicates = false;
+            let mut format = None;
+            let mut save_failed = None;
+            let mut file_path = None;
+            let mut rest = args[2..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--merge" => merge = true,
+                    "--skip-content-duplicates" => skip_content_duplicates = true,
+                    "--save-failed" => match rest.next() {
+                        Some(path) => save_failed = Some(PathBuf::from(path)),
+                        None => {
+                            eprintln!("--save-failed needs a file to write failed rows to");
+                            return Ok(());
+                        }
+                    },
+                    "--format" => match rest.next().map(|name| name.parse::<InputFormat>()) {
+                        Some(Ok(parsed)) => format = Some(parsed),
+                        _ => {
+                            eprintln!("--format must be csv or json");
+                            return Ok(());
+                        }
+                    },
+                    _ => file_path = Some(PathBuf::from(arg)),
+                }
+            }
+            let Some(file_path) = file_path else {
+                eprintln!("{usage}");
+                return Ok(());
+            };
+            let from_stdin = file_path == Path::new("-");
+            if from_stdin && (merge || format.is_none()) {
+                eprintln!("Reading standard input needs --format csv|json and no --merge");
+            } else if merge {
+                merge_file(&file_path)?;
+            } else {
+                ingest_file(
+                    &file_path,
+                    format,
+                    skip_content_duplicates,
+                    save_failed.as_deref(),
+                )?;
+            }
+        }
+        "inspect" => {
             if args.len() < 3 {
-                eprintln!("Usage: eterea-cli ingest <file_path>");
+                eprintln!("Usage: eterea-cli inspect <file_path>");
                 return Ok(());
             }
-            let file_path = PathBuf::from(&args[2]);
-            ingest_file(&file_path)?;
+            inspect_file(Path::new(&args[2]))?;
+        }
+        "watch" => {
+            if args.len() < 3 {
+                eprintln!("Usage: eterea-cli watch <directory>");
+                return Ok(());
+            }
+            watch_directory(Path::new(&args[2]))?;
         }
         "search" => {
+            let search = SearchArgs::parse(&args[2..])?;
+            if search.query.is_empty()
+                && search.tags.is_empty()
+                && search.author.is_none()
+                && search.from.is_none()
+                && search.to.is_none()
+            {
+                eprintln!(
+                    "Usage: eterea-cli search [--tag TAG]... [--author HANDLE] [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--limit N] [--offset N] [--reading-time] [--proximity] [query]"
+                );
+                return Ok(());
+            }
+            search_bookmarks(&search)?;
+        }
+        "export" => {
+            let export = match ExportArgs::parse(&args[2..]) {
+                Ok(export) => export,
+                Err(error) => {
+                    eprintln!("{error:#}");
+                    eprintln!(
+                        "Usage: eterea-cli export [--format md|html|csv] [--tag TAG]... [--author HANDLE] [--from YYYY-MM-DD] [--to YYYY-MM-DD] [query] <output_file|->"
+                    );
+                    eprintln!("       eterea-cli export --since <rfc3339> <output_file|->");
+                    return Ok(());
+                }
+            };
+            export_bookmarks(&export)?;
+        }
+        "stats" => match args.get(2).map(String::as_str) {
+            None => show_stats()?,
+            Some("--by-day") => show_histogram(Period::Day)?,
+            Some("--by-week") => show_histogram(Period::Week)?,
+            Some("--by-month") => show_histogram(Period::Month)?,
+            Some("--by-year") => show_histogram(Period::Year)?,
+            Some(_) => {
+                eprintln!("Usage: eterea-cli stats [--by-day|--by-week|--by-month|--by-year]")
+            }
+        },
+        "tag" => {
+            if args.len() < 5 || !matches!(args[2].as_str(), "add" | "remove") {
+                eprintln!("Usage: eterea-cli tag <add|remove> <bookmark_id> <tag>");
+                return Ok(());
+            }
+            edit_tag(args[2] == "add", &args[3], &args[4])?;
+        }
+        "doctor" => doctor()?,
+        "backup" => {
             if args.len() < 3 {
-                eprintln!("Usage: eterea-cli search <query>");
+                eprintln!("Usage: eterea-cli backup <path>");
                 return Ok(());
             }
-            let query = args[2..].join(" ");
-            search_bookmarks(&query)?;
+            backup(Path::new(&args[2]))?;
         }
-        "stats" => {
-            show_stats()?;
+        "dedupe" => match args.get(2).map(String::as_str) {
+            None | Some("--report") => dedupe(false)?,
+            Some("--merge") => dedupe(true)?,
+            Some(_) => eprintln!("Usage: eterea-cli dedupe [--report|--merge]"),
+        },
+        "archive-media" => {
+            let usage = "Usage: eterea-cli archive-media [--dir <directory>] [bookmark_id]";
+            let mut media_dir = None;
+            let mut bookmark_id = None;
+            let mut rest = args[2..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--dir" => match rest.next() {
+                        Some(dir) => media_dir = Some(PathBuf::from(dir)),
+                        None => {
+                            eprintln!("{usage}");
+                            return Ok(());
+                        }
+                    },
+                    id if bookmark_id.is_none() && !id.starts_with("--") => {
+                        bookmark_id = Some(id.to_string())
+                    }
+                    _ => {
+                        eprintln!("{usage}");
+                        return Ok(());
+                    }
+                }
+            }
+            archive_media(media_dir, bookmark_id.as_deref())?;
         }
         _ => {
             print_usage();
@@ -47,23 +195,163 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// `--db` flag, if given
+static DB_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+/// Database file to use: `--db`, then `$ETEREA_DB_PATH`, then the platform default
+fn db_path() -> PathBuf {
+    Database::resolve_path(DB_PATH.get().map(PathBuf::as_path))
+}
+
+/// Remove `-q`/`--quiet` and `-v`/`-vv`/`--verbose` flags from `args`,
+/// returning the log level they add up to, if any were given
+fn take_verbosity(args: &mut Vec<String>) -> Option<&'static str> {
+    let mut verbosity: Option<i32> = None;
+    args.retain(|arg| {
+        let delta = match arg.as_str() {
+            "-q" | "--quiet" => -1,
+            "--verbose" => 1,
+            flag if flag.len() > 1
+                && flag.starts_with('-')
+                && flag[1..].bytes().all(|b| b == b'v') =>
+            {
+                flag.len() as i32 - 1
+            }
+            _ => return true,
+        };
+        *verbosity.get_or_insert(0) += delta;
+        false
+    });
+
+    verbosity.map(|level| match level {
+        i32::MIN..=-1 => "off",
+        0 => "error",
+        1 => "warn",
+        2 => "info",
+        3 => "debug",
+        _ => "trace",
+    })
+}
+
+fn open_db() -> Result<Database> {
+    let path = db_path();
+    Database::open(&path).with_context(|| format!("failed to open {}", path.display()))
+}
+
+/// For commands that only read, so they can run while the app has the
+/// database open without contending for write locks
+fn open_db_read_only() -> Result<Database> {
+    let path = db_path();
+    if !path.exists() {
+        anyhow::bail!(
+            "no database at {}; import bookmarks with `eterea-cli ingest` first",
+            path.display()
+        );
+    }
+    Database::open_read_only(&path).with_context(|| format!("failed to open {}", path.display()))
+}
+
 fn print_usage() {
     println!("Eterea CLI - Lightning-fast Twitter bookmarks manager");
     println!();
-    println!("Usage:");
+    println!("Usage: eterea-cli [--db <path>] [-q|-v...] <command>");
+    println!("  --db <path> overrides $ETEREA_DB_PATH, which overrides the platform default");
+    println!("  -v shows warnings such as skipped rows; -vv, -vvv, -vvvv add info, debug, trace");
+    println!("  -q silences logging; without either, $RUST_LOG decides");
+    println!();
+    println!("Commands:");
     println!("  eterea-cli ingest <file_path>  - Import bookmarks from CSV/JSON");
+    println!(
+        "      --format csv|json          - Format to read (required for `-`, standard input)"
+    );
+    println!(
+        "      --merge                    - Fill in missing fields on bookmarks already stored"
+    );
+    println!(
+        "      --skip-content-duplicates  - Skip reposts: same text and author under another URL"
+    );
+    println!(
+        "      --save-failed <file>       - Write rows that fail to parse to <file> for fixing"
+    );
+    println!("  eterea-cli inspect <file_path> - Show the detected format and first rows, without importing");
+    println!("  eterea-cli watch <directory>   - Import exports dropped into a folder");
     println!("  eterea-cli search <query>      - Search bookmarks");
+    println!("      --tag TAG                  - Only bookmarks tagged TAG (repeatable)");
+    println!("      --author HANDLE            - Only bookmarks by @HANDLE");
+    println!(
+        "      --from / --to YYYY-MM-DD   - Only bookmarks tweeted in this range (UTC, inclusive)"
+    );
+    println!("      --limit N / --offset N     - Page through results (default 20 from 0)");
+    println!("      --reading-time             - Show word count and reading time");
+    println!(
+        "      --proximity                - Rank matches with the query words close together first"
+    );
+    println!("  eterea-cli export --format md <file> - Export bookmarks as a Markdown digest");
+    println!(
+        "  eterea-cli export --format html <file> - Export bookmarks as a standalone web page"
+    );
+    println!("  eterea-cli export --format csv <file> - Export bookmarks as CSV that can be imported again");
+    println!("      (takes the same --tag/--author/--from/--to filters and query as search)");
+    println!(
+        "  eterea-cli export --since <rfc3339> <file> - Export JSON of what changed or was deleted since then"
+    );
     println!("  eterea-cli stats               - Show database statistics");
+    println!("  eterea-cli stats --by-month    - Bookmarks per month (also --by-day/week/year)");
+    println!("  eterea-cli tag add <id> <tag>  - Tag a bookmark");
+    println!("  eterea-cli tag remove <id> <tag> - Remove a tag from a bookmark");
+    println!("  eterea-cli doctor              - Check the database for corruption");
+    println!("  eterea-cli backup <path>       - Copy the database to <path> (safe while in use)");
+    println!("  eterea-cli dedupe [--report]   - List groups of duplicate bookmarks");
+    println!("  eterea-cli dedupe --merge      - Keep the earliest import of each group");
+    println!("  eterea-cli archive-media [id]  - Download bookmark media so it survives link rot");
+    println!(
+        "      --dir <directory>          - Where to store files (default: `media` beside the database)"
+    );
 }
 
-fn ingest_file(path: &Path) -> Result<()> {
-    println!("📥 Ingesting bookmarks from: {}", path.display());
+/// Import `path`, or standard input when it is `-` (which needs `format`).
+/// With `save_failed`, rows that fail to parse are written there for fixing.
+fn ingest_file(
+    path: &Path,
+    format: Option<InputFormat>,
+    skip_content_duplicates: bool,
+    save_failed: Option<&Path>,
+) -> Result<()> {
+    let from_stdin = path == Path::new("-");
+    if from_stdin {
+        println!("📥 Ingesting bookmarks from standard input");
+    } else {
+        println!("📥 Ingesting bookmarks from: {}", path.display());
+    }
 
-    let db = Database::open_default()?;
+    let path_to_db = db_path();
+    let options = DatabaseOptions::new().with_skip_content_duplicates(skip_content_duplicates);
+    let db = Database::open_with_options(&path_to_db, options)
+        .with_context(|| format!("failed to open {}", path_to_db.display()))?;
     let ingester = Ingester::new();
 
     let start = std::time::Instant::now();
-    let count = ingester.ingest_file(path, &db)?;
+    let (count, failed_rows) = if save_failed.is_some() {
+        let report = match format {
+            Some(format) if from_stdin => {
+                ingester.ingest_reader_keeping_failures(std::io::stdin().lock(), format, &db)?
+            }
+            Some(format) => {
+                ingester.ingest_reader_keeping_failures(std::fs::File::open(path)?, format, &db)?
+            }
+            None => ingester.ingest_file_keeping_failures(path, &db)?,
+        };
+        (report.inserted, report.failed_rows)
+    } else {
+        let count = match format {
+            Some(format) if from_stdin => {
+                ingester.ingest_reader(std::io::stdin().lock(), format, &db)?
+            }
+            Some(format) => ingester.ingest_reader(std::fs::File::open(path)?, format, &db)?,
+            None => ingester.ingest_file(path, &db)?,
+        };
+        (count, Vec::new())
+    };
     let elapsed = start.elapsed();
 
     println!(
@@ -75,40 +363,465 @@ fn ingest_file(path: &Path) -> Result<()> {
         "⚡ Rate: {:.0} bookmarks/second",
         count as f64 / elapsed.as_secs_f64()
     );
+    if let Some(errors) = save_failed {
+        if failed_rows.is_empty() {
+            println!(
+                "✅ Every row parsed; nothing written to {}",
+                errors.display()
+            );
+        } else {
+            write_source_rows(&failed_rows, std::fs::File::create(errors)?)?;
+            println!(
+                "⚠️  {} rows failed to parse; fix them in {} and ingest that file",
+                failed_rows.len(),
+                errors.display()
+            );
+        }
+    }
 
     Ok(())
 }
 
-fn search_bookmarks(query: &str) -> Result<()> {
-    let db = Database::open_default()?;
+fn merge_file(path: &Path) -> Result<()> {
+    println!("📥 Merging bookmarks from: {}", path.display());
 
-    let start = std::time::Instant::now();
-    let results = db.search(query, 20)?;
-    let elapsed = start.elapsed();
+    let db = open_db()?;
+    let report = Ingester::new().ingest_file_merging(path, &db)?;
 
     println!(
-        "🔍 Found {} results in {:.2}ms\n",
-        results.len(),
-        elapsed.as_secs_f64() * 1000.0
+        "✅ {} new, {} updated, {} unchanged",
+        report.inserted, report.merged, report.unchanged
     );
+    if report.failed > 0 {
+        println!("⚠️  {} bookmarks could not be imported", report.failed);
+    }
 
-    for bookmark in results {
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        println!("👤 @{} ({})", bookmark.author_handle, bookmark.author_name);
-        println!("📅 {}", bookmark.tweeted_at.format("%Y-%m-%d %H:%M"));
-        println!("📝 {}", bookmark.content);
-        if !bookmark.tags.is_empty() {
-            println!("🏷️  {}", bookmark.tags.join(", "));
+    Ok(())
+}
+
+fn inspect_file(path: &Path) -> Result<()> {
+    let report = Ingester::new().inspect(path)?;
+
+    let format = match &report.format {
+        DetectedFormat::LegacyCsv => "Legacy CSV (Dewey)".to_string(),
+        DetectedFormat::NewCsv => "Twitter/X CSV".to_string(),
+        DetectedFormat::UnknownCsv => {
+            "CSV with unrecognised headers (import will fail)".to_string()
+        }
+        DetectedFormat::Json {
+            shape,
+            entries_key,
+            archive_js,
+        } => {
+            let source = if *archive_js { "Archive JS" } else { "JSON" };
+            match entries_key {
+                Some(key) => format!("{source} object with a \"{key}\" array"),
+                None => format!("{source} {}", format!("{shape:?}").to_lowercase()),
+            }
+        }
+    };
+    println!("🔎 {}", path.display());
+    println!("Format:  {}", format);
+    println!("Rows:    {}", report.row_count_estimate);
+    println!("Columns: {}", report.header_columns.join(", "));
+    for (index, row) in report.sample_rows.iter().enumerate() {
+        println!("\nRow {}:", index + 1);
+        for (column, value) in report.header_columns.iter().zip(row) {
+            println!("  {}: {}", column, value.replace('\n', " "));
+        }
+    }
+
+    Ok(())
+}
+
+/// Options for `eterea-cli search`
+struct SearchArgs {
+    query: String,
+    tags: Vec<String>,
+    author: Option<String>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    limit: usize,
+    offset: usize,
+    reading_time: bool,
+    /// Re-rank by how close together the query words are
+    /// (`Database::search_with_proximity`); only for a plain query
+    proximity: bool,
+}
+
+impl SearchArgs {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut search = SearchArgs {
+            query: String::new(),
+            tags: Vec::new(),
+            author: None,
+            from: None,
+            to: None,
+            limit: 20,
+            offset: 0,
+            reading_time: false,
+            proximity: false,
+        };
+        let mut terms = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--limit" => search.limit = parse_flag_value(arg, args.next())?,
+                "--offset" => search.offset = parse_flag_value(arg, args.next())?,
+                "--tag" => search.tags.push(flag_value(arg, args.next())?.to_string()),
+                "--author" => {
+                    let handle = flag_value(arg, args.next())?;
+                    search.author = Some(handle.trim_start_matches('@').to_string());
+                }
+                "--from" => {
+                    let day = parse_day(arg, args.next())?;
+                    search.from = Some(day.and_time(NaiveTime::MIN).and_utc());
+                }
+                "--to" => {
+                    // Inclusive: the whole of the given day
+                    let day = parse_day(arg, args.next())?;
+                    search.to = Some(
+                        day.and_hms_opt(23, 59, 59)
+                            .expect("23:59:59 is a valid time")
+                            .and_utc(),
+                    );
+                }
+                "--reading-time" => search.reading_time = true,
+                "--proximity" => search.proximity = true,
+                _ => terms.push(arg.as_str()),
+            }
+        }
+        search.query = terms.join(" ");
+        if search.proximity {
+            anyhow::ensure!(!search.query.is_empty(), "--proximity needs a query");
+            anyhow::ensure!(
+                search.tags.is_empty()
+                    && search.author.is_none()
+                    && search.from.is_none()
+                    && search.to.is_none(),
+                "--proximity can't be combined with --tag, --author, --from or --to"
+            );
+        }
+        if let (Some(from), Some(to)) = (search.from, search.to) {
+            anyhow::ensure!(
+                from <= to,
+                "--from ({}) is after --to ({})",
+                from.format("%Y-%m-%d"),
+                to.format("%Y-%m-%d")
+            );
+        }
+        Ok(search)
+    }
+
+    /// Flags whose next argument is their value rather than a query term
+    fn takes_value(flag: &str) -> bool {
+        matches!(
+            flag,
+            "--limit" | "--offset" | "--tag" | "--author" | "--from" | "--to"
+        )
+    }
+
+    fn filters(&self) -> SearchFilters {
+        let mut filters = SearchFilters::new().with_date_range(self.from, self.to);
+        if !self.query.is_empty() {
+            filters = filters.with_query(self.query.as_str());
+        }
+        if let Some(author) = &self.author {
+            filters = filters.with_author(author.as_str());
+        }
+        for tag in &self.tags {
+            filters = filters.with_tag(tag.as_str());
+        }
+        filters
+    }
+}
+
+/// Output formats for `eterea-cli export`
+#[derive(Debug, Clone, Copy)]
+enum ExportFormat {
+    Markdown,
+    Html,
+    Csv,
+    /// `Database::export_json_since`, for incremental sync
+    Json,
+}
+
+impl ExportFormat {
+    fn from_name(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "md" | "markdown" => Ok(ExportFormat::Markdown),
+            "html" | "htm" => Ok(ExportFormat::Html),
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            other => {
+                anyhow::bail!("unknown export format {other:?} (expected md, html, csv or json)")
+            }
+        }
+    }
+}
+
+/// Options for `eterea-cli export`: search filters plus the format and output,
+/// or `--since` for a JSON export of what changed
+struct ExportArgs {
+    format: ExportFormat,
+    /// `None` writes to stdout (`-`)
+    output: Option<PathBuf>,
+    search: SearchArgs,
+    /// Start of a JSON sync export (the `until` of the previous one);
+    /// everything when exporting JSON without it
+    since: Option<DateTime<Utc>>,
+}
+
+impl ExportArgs {
+    /// The last bare argument is the output file; earlier ones form the query
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut format = None;
+        let mut since = None;
+        let mut rest = Vec::new();
+        let mut output_index = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--format" => {
+                    format = Some(ExportFormat::from_name(flag_value(arg, args.next())?)?)
+                }
+                "--since" => {
+                    let value = flag_value(arg, args.next())?;
+                    let timestamp = DateTime::parse_from_rfc3339(value).with_context(|| {
+                        format!("--since expects a time like 2024-05-31T12:00:00Z, got {value:?}")
+                    })?;
+                    since = Some(timestamp.with_timezone(&Utc));
+                }
+                "--limit" | "--offset" | "--reading-time" | "--proximity" => {
+                    anyhow::bail!("{arg} only applies to search")
+                }
+                flag if SearchArgs::takes_value(flag) => {
+                    rest.push(arg.clone());
+                    rest.push(flag_value(arg, args.next())?.to_string());
+                }
+                flag if flag.starts_with("--") => rest.push(arg.clone()),
+                _ => {
+                    output_index = Some(rest.len());
+                    rest.push(arg.clone());
+                }
+            }
+        }
+
+        let output = output_index
+            .map(|index| rest.remove(index))
+            .context("missing output file")?;
+        let format = match format {
+            Some(format) => format,
+            None if since.is_some() => ExportFormat::Json,
+            None => {
+                let extension = Path::new(&output)
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .context("--format is required when the output file has no extension")?;
+                ExportFormat::from_name(extension)?
+            }
+        };
+
+        let search = SearchArgs::parse(&rest)?;
+        match format {
+            ExportFormat::Json => anyhow::ensure!(
+                search.query.is_empty()
+                    && search.tags.is_empty()
+                    && search.author.is_none()
+                    && search.from.is_none()
+                    && search.to.is_none(),
+                "JSON exports take --since, not search filters"
+            ),
+            _ => anyhow::ensure!(since.is_none(), "--since only applies to JSON exports"),
+        }
+
+        Ok(ExportArgs {
+            format,
+            output: Some(PathBuf::from(&output)).filter(|_| output != "-"),
+            search,
+            since,
+        })
+    }
+}
+
+fn flag_value<'a>(flag: &str, value: Option<&'a String>) -> Result<&'a str> {
+    value
+        .map(String::as_str)
+        .with_context(|| format!("{flag} needs a value"))
+}
+
+fn parse_day(flag: &str, value: Option<&String>) -> Result<NaiveDate> {
+    let value = flag_value(flag, value)?;
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .with_context(|| format!("{flag} expects a date like 2024-05-31, got {value:?}"))
+}
+
+fn parse_flag_value(flag: &str, value: Option<&String>) -> Result<usize> {
+    let value = flag_value(flag, value)?;
+    value
+        .parse()
+        .with_context(|| format!("{flag} expects a number, got {value:?}"))
+}
+
+fn search_bookmarks(search: &SearchArgs) -> Result<()> {
+    let db = open_db_read_only()?;
+
+    if search.proximity {
+        return search_by_proximity(&db, search);
+    }
+
+    // Text, tag and author filters all go through the same paginated query so
+    // any combination of them works and the total is always known
+    let tags = search.tags.iter().map(String::as_str).collect::<Vec<_>>();
+    let start = std::time::Instant::now();
+    let page = db.search_with_filters_page(
+        Some(search.query.as_str()).filter(|query| !query.is_empty()),
+        &tags,
+        TagMatch::All,
+        search.author.as_deref(),
+        search.from,
+        search.to,
+        false,
+        None,
+        None,
+        search.offset,
+        search.limit,
+    )?;
+    let elapsed = start.elapsed();
+
+    if page.items.is_empty() {
+        println!(
+            "🔍 No results on this page ({} total) in {:.2}ms\n",
+            page.total,
+            elapsed.as_secs_f64() * 1000.0
+        );
+    } else {
+        println!(
+            "🔍 Showing {}-{} of {} results in {:.2}ms\n",
+            page.offset + 1,
+            page.offset + page.items.len(),
+            page.total,
+            elapsed.as_secs_f64() * 1000.0
+        );
+    }
+
+    for bookmark in &page.items {
+        print_search_result(bookmark, search.reading_time);
+    }
+
+    if page.has_more {
+        println!(
+            "➡️  More results: --offset {}",
+            page.offset + page.items.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// `search --proximity`: the query's matches re-ranked so that ones with the
+/// words close together come first. There is no total, so fetch one extra
+/// result to tell whether another page follows.
+fn search_by_proximity(db: &Database, search: &SearchArgs) -> Result<()> {
+    let start = std::time::Instant::now();
+    let mut items =
+        db.search_with_proximity(&search.query, search.offset + search.limit + 1, true)?;
+    let elapsed = start.elapsed();
+    let has_more = items.len() > search.offset + search.limit;
+    items.truncate(search.offset + search.limit);
+    let items = items.get(search.offset..).unwrap_or_default();
+
+    if items.is_empty() {
+        println!(
+            "🔍 No results on this page in {:.2}ms\n",
+            elapsed.as_secs_f64() * 1000.0
+        );
+    } else {
+        println!(
+            "🔍 Showing {}-{} by proximity in {:.2}ms\n",
+            search.offset + 1,
+            search.offset + items.len(),
+            elapsed.as_secs_f64() * 1000.0
+        );
+    }
+
+    for bookmark in items {
+        print_search_result(bookmark, search.reading_time);
+    }
+
+    if has_more {
+        println!("➡️  More results: --offset {}", search.offset + items.len());
+    }
+
+    Ok(())
+}
+
+fn print_search_result(bookmark: &Bookmark, reading_time: bool) {
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("👤 @{} ({})", bookmark.author_handle, bookmark.author_name);
+    println!("📅 {}", bookmark.tweeted_at.format("%Y-%m-%d %H:%M"));
+    println!("📝 {}", bookmark.content);
+    if reading_time {
+        println!(
+            "⏱️  {} words, ~{}s read",
+            bookmark.word_count(),
+            bookmark.estimated_reading_seconds(DEFAULT_WORDS_PER_MINUTE)
+        );
+    }
+    if !bookmark.tags.is_empty() {
+        println!("🏷️  {}", bookmark.tags.join(", "));
+    }
+    println!("🔗 {}", bookmark.tweet_url);
+    println!();
+}
+
+fn export_bookmarks(export: &ExportArgs) -> Result<()> {
+    let db = open_db()?;
+    let filters = export.search.filters();
+
+    let mut writer: Box<dyn Write> = match &export.output {
+        Some(path) => Box::new(std::io::BufWriter::new(
+            std::fs::File::create(path)
+                .with_context(|| format!("failed to create {}", path.display()))?,
+        )),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let (written, deleted) = match export.format {
+        ExportFormat::Markdown => (db.export_markdown(&filters, &mut writer)?, None),
+        ExportFormat::Html => (db.export_html(&filters, &mut writer)?, None),
+        ExportFormat::Csv => (
+            db.export_query(
+                &filters,
+                eterea_core::export::ExportFormat::Csv,
+                &mut writer,
+            )?,
+            None,
+        ),
+        ExportFormat::Json => {
+            let since = export.since.unwrap_or(DateTime::UNIX_EPOCH);
+            let (written, deleted) = db.export_json_since(since, &mut writer)?;
+            (written, Some(deleted))
+        }
+    };
+    writer.flush()?;
+
+    if let Some(path) = &export.output {
+        match deleted {
+            Some(deleted) => println!(
+                "📤 Exported {} bookmarks and {} deletions to {}",
+                written,
+                deleted,
+                path.display()
+            ),
+            None => println!("📤 Exported {} bookmarks to {}", written, path.display()),
         }
-        println!("🔗 {}", bookmark.tweet_url);
-        println!();
     }
 
     Ok(())
 }
 
 fn show_stats() -> Result<()> {
-    let db = Database::open_default()?;
+    let db = open_db_read_only()?;
     let stats = db.get_stats()?;
 
     println!("📊 Database Statistics");
@@ -135,3 +848,181 @@ fn show_stats() -> Result<()> {
 
     Ok(())
 }
+
+fn show_histogram(period: Period) -> Result<()> {
+    const BAR_WIDTH: i64 = 40;
+
+    let db = open_db_read_only()?;
+    let counts = db.bookmark_counts_by_period(period)?;
+    let max = counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+
+    println!("📊 Bookmarks per {}", format!("{period:?}").to_lowercase());
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    for (bucket, count) in &counts {
+        // Round up so every non-empty bucket shows at least one block
+        let width = (count * BAR_WIDTH + max - 1) / max;
+        println!("{:<10} {:>6} {}", bucket, count, "█".repeat(width as usize));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "watch")]
+fn watch_directory(dir: &Path) -> Result<()> {
+    use eterea_core::ingestion::WatchEvent;
+
+    let db = open_db()?;
+    println!(
+        "👀 Watching {} for new exports (Ctrl+C to stop)",
+        dir.display()
+    );
+    Ingester::new().watch_directory(dir, &db, |event| match event {
+        WatchEvent::Imported { path, inserted } => {
+            println!("✅ Imported {} bookmarks from {}", inserted, path.display());
+        }
+        WatchEvent::Failed { path, error } => {
+            eprintln!("❌ Could not import {}: {}", path.display(), error);
+        }
+    })?;
+    Ok(())
+}
+
+#[cfg(not(feature = "watch"))]
+fn watch_directory(_dir: &Path) -> Result<()> {
+    anyhow::bail!("this build has no watch support; rebuild with `--features watch`")
+}
+
+fn edit_tag(add: bool, id: &str, tag: &str) -> Result<()> {
+    let db = open_db()?;
+
+    let changed = if add {
+        db.add_tag_to_bookmark(id, tag)?
+    } else {
+        db.remove_tag_from_bookmark(id, tag)?
+    };
+
+    match (add, changed) {
+        (true, true) => println!("🏷️  Tagged {} with '{}'", id, tag),
+        (false, true) => println!("🏷️  Removed '{}' from {}", tag, id),
+        (true, false) => println!("No change: {} is missing or already tagged '{}'", id, tag),
+        (false, false) => println!("No change: {} is not tagged '{}'", id, tag),
+    }
+
+    Ok(())
+}
+
+fn doctor() -> Result<()> {
+    let db = open_db()?;
+    println!("🩺 Checking {}", db_path().display());
+
+    let checks = [
+        ("File integrity", db.integrity_check()?),
+        ("Foreign keys", db.foreign_key_check()?),
+        ("Full-text index", db.fts_integrity_check()?),
+    ];
+    let mut problems = 0;
+    for (name, found) in &checks {
+        if found.is_empty() {
+            println!("  ✅ {name}: ok");
+        } else {
+            println!("  ❌ {name}: {} problem(s)", found.len());
+            for problem in found {
+                println!("     - {problem}");
+            }
+        }
+        problems += found.len();
+    }
+
+    println!();
+    if problems == 0 {
+        println!(
+            "✅ Database looks healthy ({} bookmarks)",
+            db.count_bookmarks()?
+        );
+    } else {
+        println!(
+            "⚠️  Found {problems} problem(s). Restore a backup, or attach this output when filing an issue."
+        );
+    }
+    Ok(())
+}
+
+fn backup(dest: &Path) -> Result<()> {
+    let db = open_db()?;
+    db.backup_to(dest)
+        .with_context(|| format!("failed to back up to {}", dest.display()))?;
+    println!("💾 Backed up database to {}", dest.display());
+    Ok(())
+}
+
+fn dedupe(merge: bool) -> Result<()> {
+    let db = open_db()?;
+    let groups = db.find_duplicates()?;
+
+    if groups.is_empty() {
+        println!("✅ No duplicate bookmarks found");
+        return Ok(());
+    }
+
+    println!("🔁 Found {} duplicate groups\n", groups.len());
+    let mut removed = 0;
+    for group in &groups {
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        for (index, id) in group.iter().enumerate() {
+            let Some(bookmark) = db.get_bookmark(id)? else {
+                continue;
+            };
+            let marker = if index == 0 { "keep" } else { "dupe" };
+            println!(
+                "[{}] {} ({})",
+                marker,
+                bookmark.tweet_url,
+                bookmark.imported_at.format("%Y-%m-%d %H:%M")
+            );
+        }
+
+        if merge {
+            for id in &group[1..] {
+                if db.delete_bookmark(id)? {
+                    removed += 1;
+                }
+            }
+        }
+    }
+
+    if merge {
+        println!("\n🧹 Moved {} duplicate bookmarks to the trash", removed);
+    } else {
+        println!("\nRun `eterea-cli dedupe --merge` to keep the earliest import of each group.");
+    }
+
+    Ok(())
+}
+
+/// Download media of one bookmark, or of every bookmark not archived yet
+fn archive_media(media_dir: Option<PathBuf>, bookmark_id: Option<&str>) -> Result<()> {
+    let db = open_db()?;
+    let media_dir =
+        media_dir.unwrap_or_else(|| db_path().parent().unwrap_or(Path::new(".")).join("media"));
+    let options = PreviewOptions::default().with_settings(&PreviewSettings::load(&db)?);
+    let previewer = LinkPreviewer::new(options)?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let report = runtime
+        .block_on(async {
+            match bookmark_id {
+                Some(id) => previewer.archive_bookmark_media(&db, id, &media_dir).await,
+                None => previewer.archive_all_media(&db, &media_dir).await,
+            }
+        })
+        .with_context(|| format!("failed to archive media into {}", media_dir.display()))?;
+
+    println!(
+        "🖼️  Archived {} media file(s) into {} ({} already archived, {} failed)",
+        report.archived,
+        media_dir.display(),
+        report.skipped,
+        report.failed
+    );
+    Ok(())
+}
@@ -2,9 +2,12 @@
 //!
 //! This unified model supports data from multiple Twitter export formats.
 
+use crate::{Error, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::sync::OnceLock;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
 use uuid::Uuid;
 
 /// Represents a single Twitter/X bookmark with all associated metadata.
@@ -16,6 +19,11 @@ pub struct Bookmark {
     /// Tweet URL (serves as natural unique key)
     pub tweet_url: String,
 
+    /// Numeric status id from `tweet_url` (`/status/<id>`), if it has one.
+    /// Snowflake ids sort chronologically and are the same on twitter.com and x.com.
+    #[serde(default)]
+    pub tweet_id: Option<String>,
+
     /// Tweet content/text
     pub content: String,
 
@@ -46,6 +54,24 @@ pub struct Bookmark {
     /// Whether this bookmark is marked as favorite
     pub is_favorite: bool,
 
+    /// Whether this bookmark has been archived (read / done)
+    #[serde(default)]
+    pub is_archived: bool,
+
+    /// `tweeted_at` is a stand-in because the export's date could not be parsed
+    #[serde(default)]
+    pub date_estimated: bool,
+
+    /// Length in bytes of `content` before ingest truncated it
+    /// (`Ingester::with_max_content_len`); `None` if it wasn't
+    #[serde(default)]
+    pub content_original_len: Option<usize>,
+
+    /// When an import last contained this bookmark, whether it was stored
+    /// then or skipped as already stored; `None` until it is stored
+    #[serde(default)]
+    pub last_seen_at: Option<DateTime<Utc>>,
+
     /// Full-text search content (precomputed for FTS5)
     #[serde(skip)]
     pub search_text: String,
@@ -56,17 +82,52 @@ pub struct Bookmark {
 pub struct Media {
     pub url: String,
     pub media_type: MediaType,
+    /// Downloaded copy on disk (`LinkPreviewer::archive_bookmark_media`), which
+    /// outlives the remote URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_path: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Kind of media attachment. Serializes lowercase (`"image"`), matching the
+/// database and the JSON import format; the old `"Image"` spelling still reads.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
 pub enum MediaType {
+    #[serde(alias = "Image")]
     Image,
+    #[serde(alias = "Video")]
     Video,
+    #[serde(alias = "Gif")]
     Gif,
+    #[serde(alias = "Unknown")]
     Unknown,
 }
 
-/// Author information (denormalized for speed)
+impl MediaType {
+    /// Lowercase name used in the database and in JSON
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaType::Image => "image",
+            MediaType::Video => "video",
+            MediaType::Gif => "gif",
+            MediaType::Unknown => "unknown",
+        }
+    }
+
+    /// Parse a media type name, case-insensitively. Accepts Twitter's own
+    /// names (`photo`, `animated_gif`) as well as ours.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "image" | "photo" => Some(MediaType::Image),
+            "video" => Some(MediaType::Video),
+            "gif" | "animated_gif" => Some(MediaType::Gif),
+            "unknown" => Some(MediaType::Unknown),
+            _ => None,
+        }
+    }
+}
+
+/// Author record (one row per handle in the `authors` table)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Author {
     pub handle: String,
@@ -75,6 +136,16 @@ pub struct Author {
     pub profile_image: Option<String>,
 }
 
+/// A timestamped note on a bookmark (`annotations` table). The latest one is
+/// mirrored into `Bookmark::comments`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Annotation {
+    pub id: i64,
+    pub bookmark_id: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
 impl Bookmark {
     /// Create a new bookmark with generated ID and current import timestamp
     pub fn new(
@@ -86,6 +157,7 @@ impl Bookmark {
     ) -> Self {
         let mut bookmark = Self {
             id: Uuid::new_v4().to_string(),
+            tweet_id: tweet_id_from_url(&tweet_url),
             tweet_url,
             content,
             note_text: None,
@@ -99,6 +171,10 @@ impl Bookmark {
             comments: None,
             media: Vec::new(),
             is_favorite: false,
+            is_archived: false,
+            date_estimated: false,
+            content_original_len: None,
+            last_seen_at: None,
             search_text: String::new(),
         };
         bookmark.compute_search_text();
@@ -145,6 +221,270 @@ impl Bookmark {
             .filter_map(|cap| cap.get(1).map(|m| m.as_str().to_lowercase()))
             .collect()
     }
+
+    /// Extract outbound links from content and note text
+    pub fn extract_urls(&self) -> Vec<String> {
+        extract_urls(&self.content, self.note_text.as_deref())
+    }
+
+    /// Content with links removed (trailing `t.co` links included) and
+    /// leftover spacing tidied, for compact listings
+    pub fn content_without_urls(&self) -> String {
+        strip_urls(&self.content)
+    }
+
+    /// `content_without_urls`, cut to at most `max_chars` characters with a
+    /// trailing `…` when anything was dropped. The `…` counts towards
+    /// `max_chars`, so a budget of 0 gives an empty string.
+    pub fn content_truncated(&self, max_chars: usize) -> String {
+        let text = self.content_without_urls();
+        if text.chars().count() <= max_chars {
+            return text;
+        }
+        if max_chars == 0 {
+            return String::new();
+        }
+        let mut truncated = text
+            .chars()
+            .take(max_chars.saturating_sub(1))
+            .collect::<String>()
+            .trim_end()
+            .to_string();
+        truncated.push('…');
+        truncated
+    }
+
+    /// Bring the text fields (content, note, comments, author and tags) to
+    /// Unicode NFC, so composed and decomposed accents store and search alike.
+    /// Tags that become identical are merged. Returns whether anything changed.
+    pub fn normalize_unicode(&mut self) -> bool {
+        fn nfc(text: &mut String) -> bool {
+            if is_nfc(text) {
+                return false;
+            }
+            *text = text.nfc().collect();
+            true
+        }
+
+        let mut changed = false;
+        for text in [
+            &mut self.content,
+            &mut self.author_handle,
+            &mut self.author_name,
+        ]
+        .into_iter()
+        .chain(self.note_text.as_mut())
+        .chain(self.comments.as_mut())
+        .chain(self.tags.iter_mut())
+        {
+            changed |= nfc(text);
+        }
+        if changed {
+            let mut seen = std::collections::HashSet::new();
+            self.tags.retain(|tag| seen.insert(tag.clone()));
+            self.compute_search_text();
+        }
+        changed
+    }
+
+    /// Cut `content` to at most `max_bytes`, on a char boundary, recording the
+    /// original length in `content_original_len`. Returns whether it was cut.
+    pub fn truncate_content(&mut self, max_bytes: usize) -> bool {
+        if self.content.len() <= max_bytes {
+            return false;
+        }
+        let mut end = max_bytes;
+        while !self.content.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.content_original_len = Some(self.content.len());
+        self.content.truncate(end);
+        self.compute_search_text();
+        true
+    }
+
+    /// Whether `other` bookmarks the same tweet: the same status id, or the
+    /// same URL once normalized, whichever host either uses
+    pub fn same_tweet(&self, other: &Bookmark) -> bool {
+        normalize_tweet_url(&self.tweet_url) == normalize_tweet_url(&other.tweet_url)
+    }
+
+    /// Merge another copy of this bookmark (say, from a later export) into it.
+    ///
+    /// Empty fields (note, comments, author profile URL and image) are filled
+    /// from `other`, and its tags (ignoring case) and media (by URL) are added
+    /// when missing. Nothing already set is overwritten or removed; the id,
+    /// URL, content, dates and favorite/archive state are left alone. Returns
+    /// whether anything changed.
+    pub fn merge_from(&mut self, other: &Bookmark) -> bool {
+        let mut changed = false;
+        for (own, theirs) in [
+            (&mut self.note_text, &other.note_text),
+            (&mut self.comments, &other.comments),
+            (&mut self.author_profile_url, &other.author_profile_url),
+            (&mut self.author_profile_image, &other.author_profile_image),
+        ] {
+            if is_blank(own) && !is_blank(theirs) {
+                own.clone_from(theirs);
+                changed = true;
+            }
+        }
+
+        for tag in &other.tags {
+            let tag = tag.trim();
+            if !tag.is_empty() && !self.tags.iter().any(|own| own.eq_ignore_ascii_case(tag)) {
+                self.tags.push(tag.to_string());
+                changed = true;
+            }
+        }
+
+        for media in &other.media {
+            match self.media.iter_mut().find(|own| own.url == media.url) {
+                Some(own) => {
+                    if own.local_path.is_none() && media.local_path.is_some() {
+                        own.local_path.clone_from(&media.local_path);
+                        changed = true;
+                    }
+                }
+                None => {
+                    self.media.push(media.clone());
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            self.compute_search_text();
+        }
+        changed
+    }
+
+    /// Hash of the normalized content, used to spot reposted duplicates
+    pub fn content_hash(&self) -> Option<String> {
+        content_hash(&self.content)
+    }
+
+    /// Whitespace-separated words in the content plus the note text
+    pub fn word_count(&self) -> usize {
+        self.content.split_whitespace().count()
+            + self
+                .note_text
+                .as_deref()
+                .map_or(0, |note| note.split_whitespace().count())
+    }
+
+    /// Rough time to read this bookmark at `wpm` words per minute, rounded up
+    pub fn estimated_reading_seconds(&self, wpm: u32) -> u32 {
+        let words = u32::try_from(self.word_count()).unwrap_or(u32::MAX);
+        words.saturating_mul(60).div_ceil(wpm.max(1))
+    }
+}
+
+fn is_blank(value: &Option<String>) -> bool {
+    value.as_deref().is_none_or(|text| text.trim().is_empty())
+}
+
+/// Reading speed used for reading-time estimates when the caller has no preference
+pub const DEFAULT_WORDS_PER_MINUTE: u32 = 200;
+
+/// Numeric status id in a tweet URL (`.../status/1234567890`), if any
+pub fn tweet_id_from_url(url: &str) -> Option<String> {
+    static STATUS_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let re = STATUS_RE.get_or_init(|| regex::Regex::new(r"/status(?:es)?/(\d+)").unwrap());
+    re.captures(url).map(|cap| cap[1].to_string())
+}
+
+/// Profile page for `handle` (`https://twitter.com/<handle>`, with any leading
+/// `@` dropped), or `None` if it can't be a handle: 1-15 letters, digits or `_`
+pub fn profile_url_for_handle(handle: &str) -> Option<String> {
+    let handle = handle.trim().trim_start_matches('@');
+    let valid = (1..=15).contains(&handle.len())
+        && handle
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+    valid.then(|| format!("https://twitter.com/{handle}"))
+}
+
+/// Normalize a tweet URL so twitter.com / x.com / mobile variants compare equal.
+///
+/// Status URLs collapse to `status/<id>`; anything else is lowercased with the
+/// scheme, `www.`, query, fragment and trailing slash removed.
+pub fn normalize_tweet_url(url: &str) -> String {
+    if let Some(id) = tweet_id_from_url(url) {
+        return format!("status/{id}");
+    }
+
+    let lower = url.trim().to_lowercase();
+    let without_scheme = lower
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("www.");
+    let end = without_scheme
+        .find(['?', '#'])
+        .unwrap_or(without_scheme.len());
+    without_scheme[..end].trim_end_matches('/').to_string()
+}
+
+/// Matches a link in free text: `http(s)://` up to whitespace, `<`, `>` or `"`
+pub(crate) fn url_regex() -> &'static regex::Regex {
+    static URL_RE: OnceLock<regex::Regex> = OnceLock::new();
+    URL_RE.get_or_init(|| regex::Regex::new(r#"https?://[^\s<>"]+"#).unwrap())
+}
+
+/// Links in `content` then `note_text`, in order and without duplicates.
+/// Shortened links (`t.co`) are kept as written.
+pub fn extract_urls(content: &str, note_text: Option<&str>) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for text in std::iter::once(content).chain(note_text) {
+        for m in url_regex().find_iter(text) {
+            let url = m
+                .as_str()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '\'']);
+            if !urls.iter().any(|seen| seen == url) {
+                urls.push(url.to_string());
+            }
+        }
+    }
+    urls
+}
+
+/// `text` with links removed, spaces collapsed within each line and blank
+/// lines dropped
+fn strip_urls(text: &str) -> String {
+    let stripped = url_regex().replace_all(text, "");
+    stripped
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Lowercased host of `url` without a leading `www.`, if it parses
+pub fn url_domain(url: &str) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    let host = parsed.host_str()?.to_lowercase();
+    Some(
+        host.strip_prefix("www.")
+            .map(str::to_string)
+            .unwrap_or(host),
+    )
+}
+
+/// SHA-256 of `content` with URLs removed and whitespace collapsed.
+///
+/// Returns `None` when nothing is left to compare (e.g. link-only tweets).
+pub fn content_hash(content: &str) -> Option<String> {
+    let stripped = url_regex().replace_all(content, " ");
+    let normalized = stripped
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    if normalized.is_empty() {
+        return None;
+    }
+    Some(format!("{:x}", Sha256::digest(normalized.as_bytes())))
 }
 
 /// Builder pattern for constructing bookmarks from various sources
@@ -247,7 +587,24 @@ impl BookmarkBuilder {
         let url = url.into();
         if !url.is_empty() {
             let media_type = Self::detect_media_type(&url);
-            self.media.push(Media { url, media_type });
+            self.media.push(Media {
+                url,
+                media_type,
+                local_path: None,
+            });
+        }
+        self
+    }
+
+    /// Add media with a known type instead of guessing it from the URL
+    pub fn add_media_with_type(mut self, url: impl Into<String>, media_type: MediaType) -> Self {
+        let url = url.into();
+        if !url.is_empty() {
+            self.media.push(Media {
+                url,
+                media_type,
+                local_path: None,
+            });
         }
         self
     }
@@ -270,11 +627,15 @@ impl BookmarkBuilder {
         }
     }
 
-    pub fn build(self) -> Result<Bookmark, &'static str> {
-        let tweet_url = self.tweet_url.ok_or("tweet_url is required")?;
+    /// Build the bookmark, failing with `Error::MissingField` naming the first
+    /// required field (`tweet_url`, `tweeted_at`, `author_handle`) that was not set
+    pub fn build(self) -> Result<Bookmark> {
+        let tweet_url = self.tweet_url.ok_or(Error::MissingField("tweet_url"))?;
         let content = self.content.unwrap_or_default();
-        let tweeted_at = self.tweeted_at.ok_or("tweeted_at is required")?;
-        let author_handle = self.author_handle.ok_or("author_handle is required")?;
+        let tweeted_at = self.tweeted_at.ok_or(Error::MissingField("tweeted_at"))?;
+        let author_handle = self
+            .author_handle
+            .ok_or(Error::MissingField("author_handle"))?;
         let author_name = self.author_name.unwrap_or_else(|| author_handle.clone());
 
         let mut bookmark =
@@ -287,6 +648,7 @@ impl BookmarkBuilder {
         bookmark.comments = self.comments;
         bookmark.media = self.media;
         bookmark.is_favorite = false;
+        bookmark.is_archived = false;
         bookmark.compute_search_text();
 
         Ok(bookmark)
@@ -318,4 +680,224 @@ mod tests {
         let mentions = bookmark.extract_mentions();
         assert_eq!(mentions, vec!["rustlang"]);
     }
+
+    #[test]
+    fn extracts_urls_from_content_and_note() {
+        let mut bookmark = Bookmark::new(
+            "https://x.com/user/status/1".to_string(),
+            "Read https://t.co/abc123, then (https://github.com/rust-lang/rust).".to_string(),
+            Utc::now(),
+            "user".to_string(),
+            "User".to_string(),
+        );
+        bookmark.note_text =
+            Some("again https://t.co/abc123 and http://www.Example.com/a?b=1".to_string());
+
+        assert_eq!(
+            bookmark.extract_urls(),
+            vec![
+                "https://t.co/abc123",
+                "https://github.com/rust-lang/rust",
+                "http://www.Example.com/a?b=1",
+            ]
+        );
+        assert_eq!(
+            url_domain("http://www.Example.com/a?b=1").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(url_domain("https://t.co/abc123").as_deref(), Some("t.co"));
+        assert_eq!(url_domain("not a url"), None);
+    }
+
+    #[test]
+    fn derives_profile_urls_from_handles() {
+        assert_eq!(
+            profile_url_for_handle("rustlang").as_deref(),
+            Some("https://twitter.com/rustlang")
+        );
+        assert_eq!(
+            profile_url_for_handle(" @Jane_Doe99 ").as_deref(),
+            Some("https://twitter.com/Jane_Doe99")
+        );
+        assert_eq!(profile_url_for_handle(""), None);
+        assert_eq!(profile_url_for_handle("@"), None);
+        assert_eq!(profile_url_for_handle("sixteen_chars_xx"), None);
+        assert_eq!(profile_url_for_handle("no spaces"), None);
+        assert_eq!(profile_url_for_handle("../admin"), None);
+    }
+
+    #[test]
+    fn extracts_tweet_ids_from_status_urls() {
+        assert_eq!(
+            tweet_id_from_url("https://twitter.com/user/status/1234567890?s=20").as_deref(),
+            Some("1234567890")
+        );
+        assert_eq!(
+            tweet_id_from_url("https://mobile.x.com/i/web/statuses/42").as_deref(),
+            Some("42")
+        );
+        assert_eq!(tweet_id_from_url("https://x.com/user"), None);
+
+        let bookmark = BookmarkBuilder::new()
+            .tweet_url("https://x.com/user/status/987")
+            .tweeted_at(Utc::now())
+            .author_handle("user")
+            .build()
+            .unwrap();
+        assert_eq!(bookmark.tweet_id.as_deref(), Some("987"));
+    }
+
+    #[test]
+    fn strips_urls_and_truncates_for_display() {
+        let mut bookmark = Bookmark::new(
+            "https://x.com/user/status/1".to_string(),
+            "https://t.co/abc123".to_string(),
+            Utc::now(),
+            "user".to_string(),
+            "User".to_string(),
+        );
+        assert_eq!(bookmark.content_without_urls(), "");
+        assert_eq!(bookmark.content_truncated(10), "");
+
+        bookmark.content =
+            "New release https://github.com/rust-lang/rust is out\n\nhttps://t.co/xyz".to_string();
+        assert_eq!(bookmark.content_without_urls(), "New release is out");
+        assert_eq!(bookmark.content_truncated(18), "New release is out");
+        assert_eq!(bookmark.content_truncated(12), "New release…");
+
+        bookmark.content = "Café ☕ time".to_string();
+        assert_eq!(bookmark.content_truncated(6), "Café…");
+        assert_eq!(bookmark.content_truncated(7), "Café ☕…");
+        assert_eq!(bookmark.content_truncated(0), "");
+        assert_eq!(bookmark.content_truncated(1), "…");
+        for max_chars in 0..12 {
+            assert!(bookmark.content_truncated(max_chars).chars().count() <= max_chars);
+        }
+    }
+
+    #[test]
+    fn word_count_covers_content_and_note_text() {
+        let mut bookmark = Bookmark::new(
+            "https://x.com/user/status/1".to_string(),
+            String::new(),
+            Utc::now(),
+            "user".to_string(),
+            "User".to_string(),
+        );
+        assert_eq!(bookmark.word_count(), 0);
+        assert_eq!(bookmark.estimated_reading_seconds(200), 0);
+
+        bookmark.note_text = Some("a longer  note\nspanning lines".to_string());
+        assert_eq!(bookmark.word_count(), 5);
+        assert_eq!(bookmark.estimated_reading_seconds(200), 2);
+
+        bookmark.content = "three short words".to_string();
+        assert_eq!(bookmark.word_count(), 8);
+        assert_eq!(bookmark.estimated_reading_seconds(60), 8);
+        assert_eq!(bookmark.estimated_reading_seconds(0), 480);
+    }
+
+    #[test]
+    fn same_tweet_ignores_host_and_tracking_parameters() {
+        let stored = BookmarkBuilder::new()
+            .tweet_url("https://twitter.com/User/status/123?s=20")
+            .tweeted_at(Utc::now())
+            .author_handle("user")
+            .build()
+            .unwrap();
+        let mut other = stored.clone();
+        other.id = "another".to_string();
+        other.tweet_url = "https://x.com/user/status/123".to_string();
+        assert!(stored.same_tweet(&other));
+        other.tweet_url = "https://x.com/user/status/124".to_string();
+        assert!(!stored.same_tweet(&other));
+    }
+
+    #[test]
+    fn merge_from_fills_gaps_and_unions_tags_and_media() {
+        let mut stored = BookmarkBuilder::new()
+            .tweet_url("https://x.com/user/status/1")
+            .content("Short text")
+            .tweeted_at(Utc::now())
+            .author_handle("user")
+            .author_profile_url("https://x.com/user")
+            .add_tag("Rust")
+            .add_media("https://pbs.twimg.com/media/a.jpg")
+            .build()
+            .unwrap();
+        stored.note_text = Some("  ".to_string());
+        stored.is_favorite = true;
+        let export = BookmarkBuilder::new()
+            .tweet_url("https://twitter.com/user/status/1")
+            .content("Different text")
+            .note_text("Short text, in full")
+            .tweeted_at(Utc::now())
+            .author_handle("user")
+            .author_profile_url("https://twitter.com/user")
+            .author_profile_image("https://pbs.twimg.com/user.jpg")
+            .add_tag("rust")
+            .add_tag("async")
+            .add_media("https://pbs.twimg.com/media/a.jpg")
+            .add_media("https://pbs.twimg.com/media/b.jpg")
+            .build()
+            .unwrap();
+
+        let before = stored.clone();
+        assert!(stored.merge_from(&export));
+        assert_eq!(stored.note_text.as_deref(), Some("Short text, in full"));
+        assert_eq!(
+            stored.author_profile_image.as_deref(),
+            Some("https://pbs.twimg.com/user.jpg")
+        );
+        assert_eq!(stored.tags, vec!["Rust", "async"]);
+        assert_eq!(stored.media.len(), 2);
+        // What was already there stays
+        assert_eq!(stored.id, before.id);
+        assert_eq!(stored.content, "Short text");
+        assert_eq!(
+            stored.author_profile_url.as_deref(),
+            Some("https://x.com/user")
+        );
+        assert!(stored.is_favorite);
+        assert!(stored.search_text.contains("full"));
+
+        assert!(!stored.merge_from(&export));
+    }
+
+    #[test]
+    fn builder_reports_which_field_is_missing() {
+        let missing_date = BookmarkBuilder::new()
+            .tweet_url("https://x.com/user/status/1")
+            .author_handle("user")
+            .build();
+        assert!(matches!(
+            missing_date,
+            Err(Error::MissingField("tweeted_at"))
+        ));
+
+        let missing_url = BookmarkBuilder::new()
+            .tweeted_at(Utc::now())
+            .author_handle("user")
+            .build();
+        assert!(matches!(missing_url, Err(Error::MissingField("tweet_url"))));
+    }
+
+    #[test]
+    fn duplicate_keys_ignore_host_whitespace_and_links() {
+        assert_eq!(
+            normalize_tweet_url("https://twitter.com/User/status/123?s=20"),
+            normalize_tweet_url("https://x.com/user/status/123"),
+        );
+        assert_eq!(
+            normalize_tweet_url("https://www.Example.com/post/?utm=1"),
+            "example.com/post"
+        );
+
+        assert_eq!(
+            content_hash("Hello   world\n https://t.co/abc"),
+            content_hash("hello world https://t.co/xyz")
+        );
+        assert_ne!(content_hash("hello world"), content_hash("hello there"));
+        assert_eq!(content_hash("https://t.co/only"), None);
+    }
 }
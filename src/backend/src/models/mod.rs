@@ -5,5 +5,10 @@
 
 mod bookmark;
 
-pub use bookmark::{Bookmark, BookmarkBuilder, Media, MediaType, Author};
+pub use bookmark::{
+    content_hash, extract_urls, normalize_tweet_url, profile_url_for_handle, tweet_id_from_url,
+    url_domain, Annotation, Author, Bookmark, BookmarkBuilder, Media, MediaType,
+    DEFAULT_WORDS_PER_MINUTE,
+};
+pub(crate) use bookmark::url_regex;
 
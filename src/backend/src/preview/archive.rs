@@ -0,0 +1,184 @@
+//! Local copies of bookmark media, kept because remote media URLs eventually
+//! stop resolving
+//!
+//! Files are named after the SHA-256 of their contents, so the same image
+//! bookmarked twice is stored once.
+
+use super::{read_body, request_error, LinkPreviewer};
+use crate::{Database, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// File extensions for the media types Twitter serves
+const MEDIA_EXTENSIONS: &[(&str, &str)] = &[
+    ("image/jpeg", "jpg"),
+    ("image/png", "png"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+    ("video/mp4", "mp4"),
+    ("video/quicktime", "mov"),
+];
+
+/// Outcome of `LinkPreviewer::archive_bookmark_media` and `archive_all_media`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveReport {
+    /// Media downloaded and recorded
+    pub archived: usize,
+    /// Media whose local copy was already there
+    pub skipped: usize,
+    /// Media that could not be downloaded or written
+    pub failed: usize,
+}
+
+impl LinkPreviewer {
+    /// Download each media file of bookmark `id` into `media_dir` and record
+    /// its path in `media.local_path`. Media with a local copy still on disk
+    /// is skipped; a failed download is counted and the rest carry on.
+    pub async fn archive_bookmark_media(
+        &self,
+        db: &Database,
+        id: &str,
+        media_dir: &Path,
+    ) -> Result<ArchiveReport> {
+        std::fs::create_dir_all(media_dir)?;
+        let mut report = ArchiveReport::default();
+
+        for media in db.load_bookmark_media(id)? {
+            let archived = media
+                .local_path
+                .as_deref()
+                .is_some_and(|path| Path::new(path).is_file());
+            if archived {
+                report.skipped += 1;
+                continue;
+            }
+            match self.download_media(&media.url, media_dir).await {
+                Ok(path) => {
+                    db.set_media_local_path(id, &media.url, &path.to_string_lossy())?;
+                    report.archived += 1;
+                }
+                Err(error) => {
+                    debug!("Could not archive {}: {}", media.url, error);
+                    report.failed += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// `archive_bookmark_media` for every live bookmark with media that has
+    /// not been archived yet
+    pub async fn archive_all_media(
+        &self,
+        db: &Database,
+        media_dir: &Path,
+    ) -> Result<ArchiveReport> {
+        let mut total = ArchiveReport::default();
+        for id in db.bookmarks_with_unarchived_media()? {
+            let report = self.archive_bookmark_media(db, &id, media_dir).await?;
+            total.archived += report.archived;
+            total.skipped += report.skipped;
+            total.failed += report.failed;
+        }
+        Ok(total)
+    }
+
+    /// Fetch `url` (up to `max_media_bytes`) and store it under `media_dir`
+    async fn download_media(&self, url: &str, media_dir: &Path) -> Result<PathBuf> {
+        let response = self.client.get(url).send().await.map_err(request_error)?;
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = read_body(response, self.options.max_media_bytes).await?;
+
+        let name = format!(
+            "{:x}.{}",
+            Sha256::digest(&body),
+            media_extension(url, content_type.as_deref())
+        );
+        let path = media_dir.join(name);
+        if !path.is_file() {
+            // Write beside the target and rename, so a crash never leaves a
+            // truncated file under a valid name
+            let mut file = tempfile::NamedTempFile::new_in(media_dir)?;
+            file.write_all(&body)?;
+            file.persist(&path).map_err(|error| error.error)?;
+        }
+        Ok(path)
+    }
+}
+
+/// Extension for a downloaded file: from its `Content-Type`, else from the URL
+/// path or Twitter's `?format=jpg` parameter, else `bin`
+fn media_extension(url: &str, content_type: Option<&str>) -> &'static str {
+    let mime = content_type
+        .and_then(|value| value.split(';').next())
+        .map(|mime| mime.trim().to_ascii_lowercase());
+    if let Some((_, extension)) = MEDIA_EXTENSIONS
+        .iter()
+        .find(|(known, _)| mime.as_deref() == Some(*known))
+    {
+        return extension;
+    }
+
+    let Ok(url) = url::Url::parse(url) else {
+        return "bin";
+    };
+    let from_path = url
+        .path()
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase());
+    let from_query = url
+        .query_pairs()
+        .find(|(key, _)| key == "format")
+        .map(|(_, value)| value.to_ascii_lowercase());
+    [from_path, from_query]
+        .into_iter()
+        .flatten()
+        .find_map(|candidate| {
+            let candidate = if candidate == "jpeg" {
+                "jpg".into()
+            } else {
+                candidate
+            };
+            MEDIA_EXTENSIONS
+                .iter()
+                .map(|(_, extension)| *extension)
+                .find(|extension| *extension == candidate)
+        })
+        .unwrap_or("bin")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extensions_come_from_the_content_type_then_the_url() {
+        let url = "https://pbs.twimg.com/media/abc?format=jpg&name=large";
+        assert_eq!(
+            media_extension(url, Some("image/png; charset=binary")),
+            "png"
+        );
+        assert_eq!(
+            media_extension(url, Some("application/octet-stream")),
+            "jpg"
+        );
+        assert_eq!(media_extension(url, None), "jpg");
+        assert_eq!(
+            media_extension("https://video.twimg.com/clip.MP4", None),
+            "mp4"
+        );
+        assert_eq!(
+            media_extension("https://example.com/photo.jpeg", None),
+            "jpg"
+        );
+        assert_eq!(media_extension("https://example.com/file.exe", None), "bin");
+        assert_eq!(media_extension("not a url", None), "bin");
+    }
+}
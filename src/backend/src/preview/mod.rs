@@ -0,0 +1,1095 @@
+//! Link previews for URLs found in bookmarks
+//!
+//! Fetches a page and extracts OpenGraph / Twitter card metadata. Requests are
+//! bounded by a total timeout, a small retry budget, and a body-size cap so a
+//! slow or huge page can't stall the caller.
+//!
+//! Also resolves shortened links (`t.co`, `bit.ly`, ...) to where they point,
+//! and backfills missing author profile data (`LinkPreviewer::enrich_authors`).
+//! `archive` keeps local copies of bookmark media.
+//!
+//! The user-agent and proxy come from `PreviewSettings`, stored in the database
+//! so they can be changed while the app runs.
+
+use crate::models::profile_url_for_handle;
+use crate::{Database, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Duration;
+use thiserror::Error;
+use tracing::debug;
+
+mod archive;
+
+pub use archive::ArchiveReport;
+
+/// Metadata scraped from a linked page
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LinkPreview {
+    /// URL that was requested
+    pub url: String,
+    /// URL after following redirects
+    pub final_url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub site_name: Option<String>,
+    /// Page icon from `<link rel="icon">` (or `apple-touch-icon`), else the
+    /// site's `/favicon.ico`
+    #[serde(default)]
+    pub favicon_url: Option<String>,
+    /// Preferred URL of the page from `<link rel="canonical">` or `og:url`
+    #[serde(default)]
+    pub canonical_url: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// A link as written in a tweet and the URL it resolves to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExpandedUrl {
+    pub original: String,
+    /// Same as `original` for links that aren't shortened or couldn't be resolved
+    pub expanded: String,
+}
+
+/// Progress of `LinkPreviewer::enrich_authors`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct EnrichProgress {
+    /// Authors processed so far
+    pub done: usize,
+    /// Authors that were missing a profile URL or image
+    pub total: usize,
+}
+
+/// Outcome of `LinkPreviewer::enrich_authors`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnrichReport {
+    /// Profile URLs derived from handles
+    pub profile_urls: usize,
+    /// Avatars found on profile pages
+    pub profile_images: usize,
+    /// Profile pages that could not be fetched
+    pub failed: usize,
+}
+
+/// Hosts whose links are only redirects to the real destination
+const SHORTENER_HOSTS: &[&str] = &[
+    "t.co",
+    "bit.ly",
+    "buff.ly",
+    "dlvr.it",
+    "goo.gl",
+    "ift.tt",
+    "lnkd.in",
+    "ow.ly",
+    "tinyurl.com",
+    "trib.al",
+];
+
+/// Whether `url` points at a known link shortener
+pub fn is_shortened_url(url: &str) -> bool {
+    crate::models::url_domain(url).is_some_and(|domain| SHORTENER_HOSTS.contains(&domain.as_str()))
+}
+
+/// Why a preview could not be produced
+#[derive(Error, Debug)]
+pub enum PreviewError {
+    #[error("Timed out fetching preview")]
+    Timeout,
+
+    #[error("Response exceeds the {0} byte limit")]
+    TooLarge(usize),
+
+    #[error("Preview request failed with HTTP status {0}")]
+    Status(u16),
+
+    #[error("Preview request failed: {0}")]
+    Request(String),
+
+    #[error("Could not parse preview: {0}")]
+    Parse(String),
+}
+
+/// User-agent sent when `PreviewSettings::user_agent` is unset
+pub const DEFAULT_USER_AGENT: &str = concat!("eterea/", env!("CARGO_PKG_VERSION"));
+
+/// `app_metadata` key holding the JSON-serialized `PreviewSettings`
+const PREVIEW_SETTINGS_METADATA_KEY: &str = "preview_settings";
+
+/// User-editable network settings for preview fetching
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PreviewSettings {
+    /// Sent instead of `DEFAULT_USER_AGENT`, for sites that block unknown clients
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Proxy for all preview requests (`http://`, `https://` or `socks5://`).
+    /// When unset, `HTTP_PROXY` / `HTTPS_PROXY` / `NO_PROXY` are honoured.
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+}
+
+impl PreviewSettings {
+    /// Settings saved in `db`, or the defaults if none were saved
+    pub fn load(db: &Database) -> Result<Self> {
+        match db.get_metadata(PREVIEW_SETTINGS_METADATA_KEY)? {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Validate and store the settings; previewers built afterwards use them
+    pub fn save(&self, db: &Database) -> Result<()> {
+        if let Some(proxy_url) = &self.proxy_url {
+            reqwest::Proxy::all(proxy_url).map_err(|error| {
+                PreviewError::Request(format!("invalid proxy URL {proxy_url:?}: {error}"))
+            })?;
+        }
+        db.set_metadata(PREVIEW_SETTINGS_METADATA_KEY, &serde_json::to_string(self)?)
+    }
+}
+
+/// Limits applied to preview fetching
+#[derive(Debug, Clone)]
+pub struct PreviewOptions {
+    /// Total time allowed per request attempt
+    pub timeout: Duration,
+    /// Attempts for connection errors and 5xx responses (including the first)
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each further retry
+    pub retry_backoff: Duration,
+    /// Maximum response body size to buffer
+    pub max_body_bytes: usize,
+    /// Maximum size of one archived media file
+    pub max_media_bytes: usize,
+    /// How long a cached preview stays fresh
+    pub cache_ttl: Duration,
+    /// `User-Agent` header for every request
+    pub user_agent: String,
+    /// Explicit proxy; `None` falls back to the proxy environment variables
+    pub proxy_url: Option<String>,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            max_attempts: 2,
+            retry_backoff: Duration::from_millis(250),
+            max_body_bytes: 2 * 1024 * 1024,
+            max_media_bytes: 50 * 1024 * 1024,
+            cache_ttl: Duration::from_secs(7 * 24 * 60 * 60),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy_url: None,
+        }
+    }
+}
+
+impl PreviewOptions {
+    /// Apply the user's saved user-agent and proxy
+    pub fn with_settings(mut self, settings: &PreviewSettings) -> Self {
+        if let Some(user_agent) = settings.user_agent.as_deref().map(str::trim) {
+            if !user_agent.is_empty() {
+                self.user_agent = user_agent.to_string();
+            }
+        }
+        self.proxy_url = settings.proxy_url.clone();
+        self
+    }
+}
+
+/// Reusable preview fetcher holding a configured HTTP client
+pub struct LinkPreviewer {
+    client: reqwest::Client,
+    options: PreviewOptions,
+}
+
+impl LinkPreviewer {
+    pub fn new(options: PreviewOptions) -> Result<Self> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(options.timeout)
+            .redirect(reqwest::redirect::Policy::limited(10))
+            .user_agent(options.user_agent.as_str());
+        if let Some(proxy_url) = &options.proxy_url {
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(|error| PreviewError::Request(error.to_string()))?;
+            builder = builder.proxy(proxy);
+        }
+        let client = builder
+            .build()
+            .map_err(|error| PreviewError::Request(error.to_string()))?;
+        Ok(Self { client, options })
+    }
+
+    /// Fetch `url` and extract its preview metadata
+    pub async fn fetch(&self, url: &str) -> Result<LinkPreview> {
+        let max_attempts = self.options.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            match self.fetch_once(url).await {
+                Err(error) if attempt < max_attempts && is_retryable(&error) => {
+                    let delay = self.options.retry_backoff * 2u32.pow(attempt - 1);
+                    debug!("Retrying preview for {} after {:?}: {}", url, delay, error);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result.map_err(Into::into),
+            }
+        }
+    }
+
+    /// Return a cached preview younger than `cache_ttl`, fetching and storing a
+    /// fresh one otherwise. `force_refresh` always re-fetches.
+    pub async fn fetch_cached(
+        &self,
+        url: &str,
+        db: &Database,
+        force_refresh: bool,
+    ) -> Result<LinkPreview> {
+        if !force_refresh {
+            if let Some(preview) = db.get_cached_preview(url, self.options.cache_ttl)? {
+                return Ok(preview);
+            }
+        }
+
+        let preview = self.fetch(url).await?;
+        db.upsert_preview(&preview)?;
+        Ok(preview)
+    }
+
+    /// Follow redirects from `url` and return the final destination. Tries a
+    /// `HEAD` first and falls back to `GET` for servers that reject it.
+    pub async fn expand_url(&self, url: &str) -> Result<ExpandedUrl> {
+        let response = match self.client.head(url).send().await {
+            Ok(response) if response.status().is_success() => response,
+            Err(error) if error.is_timeout() => return Err(PreviewError::Timeout.into()),
+            _ => self.client.get(url).send().await.map_err(request_error)?,
+        };
+        let status = response.status();
+        if !status.is_success() {
+            return Err(PreviewError::Status(status.as_u16()).into());
+        }
+
+        Ok(ExpandedUrl {
+            original: url.to_string(),
+            expanded: response.url().to_string(),
+        })
+    }
+
+    /// `expand_url` backed by the `links` table, so each shortened link is only
+    /// resolved once. Links that aren't shortened are returned as-is.
+    pub async fn expand_cached(&self, url: &str, db: &Database) -> Result<ExpandedUrl> {
+        if !is_shortened_url(url) {
+            return Ok(ExpandedUrl {
+                original: url.to_string(),
+                expanded: url.to_string(),
+            });
+        }
+        if let Some(expanded) = db.get_expanded_url(url)? {
+            return Ok(ExpandedUrl {
+                original: url.to_string(),
+                expanded,
+            });
+        }
+
+        let expanded = self.expand_url(url).await?;
+        db.set_expanded_url(url, &expanded.expanded)?;
+        Ok(expanded)
+    }
+
+    /// Expand every outbound link of a bookmark, in order. A link that fails to
+    /// resolve keeps its original URL (and is retried next time).
+    pub async fn expand_links(&self, bookmark_id: &str, db: &Database) -> Result<Vec<ExpandedUrl>> {
+        let mut expanded = Vec::new();
+        for url in db.get_links(bookmark_id)? {
+            match self.expand_cached(&url, db).await {
+                Ok(link) => expanded.push(link),
+                Err(error) => {
+                    debug!("Could not expand {}: {}", url, error);
+                    expanded.push(ExpandedUrl {
+                        expanded: url.clone(),
+                        original: url,
+                    });
+                }
+            }
+        }
+        Ok(expanded)
+    }
+
+    /// Backfill authors missing profile data: the profile URL is derived from
+    /// the handle, and with `fetch_avatars` the avatar is taken from the
+    /// profile page's preview image. Only empty fields are filled, so running
+    /// this again is harmless. `on_progress` is called after each author.
+    pub async fn enrich_authors<F>(
+        &self,
+        db: &Database,
+        fetch_avatars: bool,
+        mut on_progress: F,
+    ) -> Result<EnrichReport>
+    where
+        F: FnMut(EnrichProgress),
+    {
+        let authors = db.authors_missing_profile()?;
+        let total = authors.len();
+        let mut report = EnrichReport::default();
+
+        for (index, author) in authors.into_iter().enumerate() {
+            let stored_url = author.profile_url.filter(|url| !url.trim().is_empty());
+            let derived_url = match stored_url {
+                Some(_) => None,
+                None => profile_url_for_handle(&author.handle),
+            };
+
+            let mut image = None;
+            let missing_image = author
+                .profile_image
+                .as_deref()
+                .is_none_or(|image| image.trim().is_empty());
+            if let Some(url) = stored_url.as_ref().or(derived_url.as_ref()) {
+                if fetch_avatars && missing_image {
+                    match self.fetch(url).await {
+                        Ok(preview) => image = preview.image_url,
+                        Err(error) => {
+                            debug!("Could not fetch profile {}: {}", url, error);
+                            report.failed += 1;
+                        }
+                    }
+                }
+            }
+
+            if db.fill_author_profile(&author.handle, derived_url.as_deref(), image.as_deref())? {
+                report.profile_urls += derived_url.is_some() as usize;
+                report.profile_images += image.is_some() as usize;
+            }
+            on_progress(EnrichProgress {
+                done: index + 1,
+                total,
+            });
+        }
+        Ok(report)
+    }
+
+    async fn fetch_once(&self, url: &str) -> std::result::Result<LinkPreview, PreviewError> {
+        let response = self.client.get(url).send().await.map_err(request_error)?;
+        let final_url = response.url().to_string();
+        let body = read_body(response, self.options.max_body_bytes).await?;
+
+        let html = String::from_utf8_lossy(&body);
+        let mut preview = extract_meta(&html)?;
+        resolve_preview_urls(&mut preview, &final_url);
+        preview.url = url.to_string();
+        preview.final_url = final_url;
+        preview.fetched_at = Utc::now();
+        Ok(preview)
+    }
+}
+
+/// Fetch a preview with the default limits
+pub async fn fetch_link_preview(url: &str) -> Result<LinkPreview> {
+    LinkPreviewer::new(PreviewOptions::default())?
+        .fetch(url)
+        .await
+}
+
+/// Buffer the body of a successful `response`, failing past `limit` bytes
+async fn read_body(
+    mut response: reqwest::Response,
+    limit: usize,
+) -> std::result::Result<Vec<u8>, PreviewError> {
+    let status = response.status();
+    if !status.is_success() {
+        return Err(PreviewError::Status(status.as_u16()));
+    }
+    if response
+        .content_length()
+        .is_some_and(|length| length > limit as u64)
+    {
+        return Err(PreviewError::TooLarge(limit));
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(request_error)? {
+        if body.len() + chunk.len() > limit {
+            return Err(PreviewError::TooLarge(limit));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+fn request_error(error: reqwest::Error) -> PreviewError {
+    if error.is_timeout() {
+        PreviewError::Timeout
+    } else {
+        PreviewError::Request(error.to_string())
+    }
+}
+
+fn is_retryable(error: &PreviewError) -> bool {
+    match error {
+        PreviewError::Request(_) => true,
+        PreviewError::Status(status) => *status >= 500,
+        PreviewError::Timeout | PreviewError::TooLarge(_) | PreviewError::Parse(_) => false,
+    }
+}
+
+/// Extract title/description/image/site name, icon and canonical URL from
+/// page HTML. URLs are returned as written; see `resolve_preview_urls`.
+fn extract_meta(html: &str) -> std::result::Result<LinkPreview, PreviewError> {
+    static META_RE: OnceLock<regex::Regex> = OnceLock::new();
+    static LINK_RE: OnceLock<regex::Regex> = OnceLock::new();
+    static TITLE_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let meta_re = META_RE.get_or_init(|| regex::Regex::new(r"(?is)<meta\s[^>]*>").unwrap());
+    let link_re = LINK_RE.get_or_init(|| regex::Regex::new(r"(?is)<link\s[^>]*>").unwrap());
+    let title_re =
+        TITLE_RE.get_or_init(|| regex::Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap());
+
+    if !html.to_lowercase().contains("<html") && !html.to_lowercase().contains("<meta") {
+        return Err(PreviewError::Parse("response is not an HTML page".into()));
+    }
+
+    let mut meta = Vec::<(String, String)>::new();
+    for tag in meta_re.find_iter(html) {
+        let mut key = None;
+        let mut content = None;
+        for (name, value) in tag_attributes(tag.as_str()) {
+            match name.as_str() {
+                "property" | "name" => key = Some(value.to_lowercase()),
+                "content" => content = Some(value),
+                _ => {}
+            }
+        }
+        if let (Some(key), Some(content)) = (key, content) {
+            if !content.is_empty() {
+                meta.push((key, content));
+            }
+        }
+    }
+
+    // (rel tokens, href) of each <link>, in document order
+    let mut links = Vec::<(Vec<String>, String)>::new();
+    for tag in link_re.find_iter(html) {
+        let mut rel = Vec::new();
+        let mut href = None;
+        for (name, value) in tag_attributes(tag.as_str()) {
+            match name.as_str() {
+                "rel" => {
+                    rel = value
+                        .split_whitespace()
+                        .map(str::to_ascii_lowercase)
+                        .collect()
+                }
+                "href" => href = Some(value),
+                _ => {}
+            }
+        }
+        if let Some(href) = href.filter(|href| !href.is_empty()) {
+            links.push((rel, href));
+        }
+    }
+    let link = |rels: &[&str]| {
+        rels.iter().find_map(|wanted| {
+            links
+                .iter()
+                .find(|(rel, _)| rel.iter().any(|token| token == wanted))
+                .map(|(_, href)| href.clone())
+        })
+    };
+
+    let lookup = |keys: &[&str]| {
+        keys.iter().find_map(|wanted| {
+            meta.iter()
+                .find(|(key, _)| key == wanted)
+                .map(|(_, value)| value.clone())
+        })
+    };
+
+    let title = lookup(&["og:title", "twitter:title"]).or_else(|| {
+        title_re
+            .captures(html)
+            .map(|cap| decode_entities(&cap[1]))
+            .filter(|title| !title.is_empty())
+    });
+
+    Ok(LinkPreview {
+        title,
+        description: lookup(&["og:description", "twitter:description", "description"]),
+        image_url: lookup(&["og:image", "twitter:image", "twitter:image:src"]),
+        site_name: lookup(&["og:site_name"]),
+        favicon_url: link(&["icon", "apple-touch-icon", "apple-touch-icon-precomposed"]),
+        canonical_url: link(&["canonical"]).or_else(|| lookup(&["og:url"])),
+        ..LinkPreview::default()
+    })
+}
+
+/// Lowercased names and entity-decoded values of an HTML tag's attributes;
+/// values may be double-, single- or unquoted
+fn tag_attributes(tag: &str) -> Vec<(String, String)> {
+    static ATTR_RE: OnceLock<regex::Regex> = OnceLock::new();
+    let attr_re = ATTR_RE.get_or_init(|| {
+        regex::Regex::new(r#"(?is)([a-z:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap()
+    });
+    attr_re
+        .captures_iter(tag)
+        .map(|attr| {
+            let value = attr
+                .get(2)
+                .or_else(|| attr.get(3))
+                .or_else(|| attr.get(4))
+                .map(|m| decode_entities(m.as_str()))
+                .unwrap_or_default();
+            (attr[1].to_lowercase(), value)
+        })
+        .collect()
+}
+
+/// Make the URLs `extract_meta` found absolute against the page's final URL.
+/// A page without an icon link gets the site's `/favicon.ico`.
+fn resolve_preview_urls(preview: &mut LinkPreview, final_url: &str) {
+    preview.image_url = preview
+        .image_url
+        .take()
+        .and_then(|image| resolve_url(final_url, &image));
+    preview.favicon_url = preview
+        .favicon_url
+        .take()
+        .and_then(|icon| resolve_url(final_url, &icon))
+        .or_else(|| {
+            url::Url::parse(final_url)
+                .ok()
+                .filter(|url| url.scheme().starts_with("http"))
+                .and_then(|url| url.join("/favicon.ico").ok())
+                .map(String::from)
+        });
+    preview.canonical_url = preview
+        .canonical_url
+        .take()
+        .and_then(|canonical| resolve_url(final_url, &canonical));
+}
+
+/// Decode HTML entities (`&amp;`, `&#39;`, ...) and trim whitespace
+fn decode_entities(raw: &str) -> String {
+    html_escape::decode_html_entities(raw).trim().to_string()
+}
+
+/// Resolve a possibly relative (`/img.png`, `//cdn/img.png`) URL against the page URL
+fn resolve_url(base: &str, candidate: &str) -> Option<String> {
+    match url::Url::parse(base) {
+        Ok(base) => base.join(candidate).ok().map(String::from),
+        Err(_) => Some(candidate.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bookmark, Error};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serve each canned `(status, body)` response to one connection, in order.
+    /// For 3xx statuses the body is sent as the `Location` header instead.
+    fn serve(responses: Vec<(u16, String)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    return;
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 0 && line != "\r\n" {
+                    line.clear();
+                }
+                if (300..400).contains(&status) {
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 {status} X\r\nLocation: {body}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    );
+                    continue;
+                }
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status} X\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+            }
+        });
+        format!("http://{address}/page")
+    }
+
+    fn fast_options() -> PreviewOptions {
+        PreviewOptions {
+            timeout: Duration::from_millis(500),
+            retry_backoff: Duration::from_millis(10),
+            ..PreviewOptions::default()
+        }
+    }
+
+    const PAGE: &str = r#"<html><head><title>Fallback</title>
+        <meta property="og:title" content="Rust 1.80" />
+        <meta name="description" content='Release notes'>
+        <meta property="og:image" content="https://example.com/card.png">
+        </head></html>"#;
+
+    #[test]
+    fn extracts_open_graph_metadata() {
+        let preview = extract_meta(PAGE).unwrap();
+        assert_eq!(preview.title.as_deref(), Some("Rust 1.80"));
+        assert_eq!(preview.description.as_deref(), Some("Release notes"));
+        assert_eq!(
+            preview.image_url.as_deref(),
+            Some("https://example.com/card.png")
+        );
+    }
+
+    #[test]
+    fn decodes_entities_and_falls_back_to_twitter_image_src() {
+        let html = r#"<html><head><title>Tom &amp; Jerry &#39;24</title>
+            <meta name="twitter:image:src" content="/cards/tj.png?a=1&amp;b=2"></head></html>"#;
+        let preview = extract_meta(html).unwrap();
+        assert_eq!(preview.title.as_deref(), Some("Tom & Jerry '24"));
+        assert_eq!(preview.image_url.as_deref(), Some("/cards/tj.png?a=1&b=2"));
+    }
+
+    #[test]
+    fn resolves_relative_image_urls() {
+        let base = "https://example.com/posts/1";
+        assert_eq!(
+            resolve_url(base, "/img/x.png").as_deref(),
+            Some("https://example.com/img/x.png")
+        );
+        assert_eq!(
+            resolve_url(base, "//cdn.example.net/x.png").as_deref(),
+            Some("https://cdn.example.net/x.png")
+        );
+        assert_eq!(
+            resolve_url(base, "https://other.org/x.png").as_deref(),
+            Some("https://other.org/x.png")
+        );
+    }
+
+    #[test]
+    fn finds_icons_and_canonical_urls_in_their_various_forms() {
+        let base = "https://example.com/posts/1?ref=x";
+        let resolved = |html: &str| {
+            let mut preview = extract_meta(html).unwrap();
+            resolve_preview_urls(&mut preview, base);
+            (preview.favicon_url, preview.canonical_url)
+        };
+
+        let shortcut = r#"<html><head>
+            <link rel="shortcut icon" href="/static/fav.ico">
+            <link rel="canonical" href="/posts/1"></head></html>"#;
+        assert_eq!(
+            resolved(shortcut),
+            (
+                Some("https://example.com/static/fav.ico".into()),
+                Some("https://example.com/posts/1".into())
+            )
+        );
+
+        // href before rel, single quotes, path relative to the page
+        let touch = r#"<html><head><link href='icons/touch.png' rel='apple-touch-icon'>
+            <meta property="og:url" content="https://example.com/p/1"></head></html>"#;
+        assert_eq!(
+            resolved(touch),
+            (
+                Some("https://example.com/posts/icons/touch.png".into()),
+                Some("https://example.com/p/1".into())
+            )
+        );
+
+        // Unquoted attributes; a plain icon wins over an earlier touch icon
+        let unquoted = r#"<html><head>
+            <link rel=apple-touch-icon href=/touch.png>
+            <LINK REL=icon type=image/png href=//cdn.example.net/i.png /></head></html>"#;
+        assert_eq!(
+            resolved(unquoted).0.as_deref(),
+            Some("https://cdn.example.net/i.png")
+        );
+
+        let bare = "<html><head><title>No icon</title></head></html>";
+        assert_eq!(
+            resolved(bare),
+            (Some("https://example.com/favicon.ico".into()), None)
+        );
+    }
+
+    fn author_bookmark(handle: &str, profile_url: Option<&str>, image: Option<&str>) -> Bookmark {
+        let mut builder = crate::models::BookmarkBuilder::new()
+            .tweet_url(format!("https://x.com/{handle}/status/1"))
+            .content("hello")
+            .tweeted_at(Utc::now())
+            .author_handle(handle)
+            .author_name(handle);
+        if let Some(url) = profile_url {
+            builder = builder.author_profile_url(url);
+        }
+        if let Some(image) = image {
+            builder = builder.author_profile_image(image);
+        }
+        builder.build().unwrap()
+    }
+
+    #[tokio::test]
+    async fn enriching_authors_only_fills_missing_fields() {
+        let db = Database::open_memory().unwrap();
+        db.insert_bookmarks(&[
+            author_bookmark("alice", None, None),
+            author_bookmark("carol", Some("https://example.com/carol"), Some("c.png")),
+        ])
+        .unwrap();
+        let previewer = LinkPreviewer::new(fast_options()).unwrap();
+
+        let mut progress = Vec::new();
+        let report = previewer
+            .enrich_authors(&db, false, |step| progress.push(step))
+            .await
+            .unwrap();
+        assert_eq!(report.profile_urls, 1);
+        assert_eq!(progress, vec![EnrichProgress { done: 1, total: 1 }]);
+        let alice = db.get_author("alice").unwrap().unwrap();
+        assert_eq!(
+            alice.profile_url.as_deref(),
+            Some("https://twitter.com/alice")
+        );
+        let stored = db.search("hello", 10).unwrap();
+        assert!(stored
+            .iter()
+            .any(|b| b.author_profile_url.as_deref() == Some("https://twitter.com/alice")));
+        let carol = db.get_author("carol").unwrap().unwrap();
+        assert_eq!(
+            carol.profile_url.as_deref(),
+            Some("https://example.com/carol")
+        );
+
+        // Alice still has no avatar, but there's nothing left to derive
+        let again = previewer.enrich_authors(&db, false, |_| {}).await.unwrap();
+        assert_eq!(again, EnrichReport::default());
+    }
+
+    #[tokio::test]
+    async fn enriching_authors_can_fetch_avatars_from_profile_pages() {
+        let page = r#"<html><head><meta property="og:image" content="/avatar.jpg"></head></html>"#;
+        let url = serve(vec![(200, page.to_string())]);
+        let db = Database::open_memory().unwrap();
+        db.insert_bookmarks(&[author_bookmark("bob", Some(&url), None)])
+            .unwrap();
+
+        let report = LinkPreviewer::new(fast_options())
+            .unwrap()
+            .enrich_authors(&db, true, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(report.profile_images, 1);
+        let origin = url.trim_end_matches("/page");
+        let bob = db.get_author("bob").unwrap().unwrap();
+        assert_eq!(bob.profile_url.as_deref(), Some(url.as_str()));
+        assert_eq!(bob.profile_image, Some(format!("{origin}/avatar.jpg")));
+    }
+
+    #[tokio::test]
+    async fn archiving_media_stores_each_file_once_and_skips_failures() {
+        let url = serve(vec![
+            (200, "first image".to_string()),
+            (404, String::new()),
+            (200, "x".repeat(64)),
+            (200, "first image".to_string()),
+        ]);
+        let origin = url.trim_end_matches("/page");
+        let bookmark = crate::models::BookmarkBuilder::new()
+            .tweet_url("https://x.com/alice/status/1")
+            .content("photos")
+            .tweeted_at(Utc::now())
+            .author_handle("alice")
+            .author_name("Alice")
+            .add_media(format!("{origin}/a.png"))
+            .add_media(format!("{origin}/gone.png"))
+            .add_media(format!("{origin}/huge.png"))
+            .add_media(format!("{origin}/copy.png"))
+            .build()
+            .unwrap();
+        let db = Database::open_memory().unwrap();
+        db.insert_bookmarks(std::slice::from_ref(&bookmark))
+            .unwrap();
+        let media_dir = tempfile::tempdir().unwrap();
+        let previewer = LinkPreviewer::new(PreviewOptions {
+            max_media_bytes: 32,
+            ..fast_options()
+        })
+        .unwrap();
+
+        let report = previewer
+            .archive_all_media(&db, media_dir.path())
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            ArchiveReport {
+                archived: 2,
+                skipped: 0,
+                failed: 2
+            }
+        );
+        let media = db.load_bookmark_media(&bookmark.id).unwrap();
+        let paths: Vec<Option<&str>> = media.iter().map(|m| m.local_path.as_deref()).collect();
+        assert!(paths[1].is_none() && paths[2].is_none());
+        // Same bytes, same file
+        assert_eq!(paths[0], paths[3]);
+        let path = paths[0].unwrap();
+        assert!(path.ends_with(".png"));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "first image");
+        assert_eq!(std::fs::read_dir(media_dir.path()).unwrap().count(), 1);
+        assert_eq!(
+            db.bookmarks_with_unarchived_media().unwrap(),
+            vec![bookmark.id.clone()]
+        );
+
+        // Only the failures are retried (and fail again: the server is gone)
+        let again = previewer
+            .archive_bookmark_media(&db, &bookmark.id, media_dir.path())
+            .await
+            .unwrap();
+        assert_eq!(
+            again,
+            ArchiveReport {
+                archived: 0,
+                skipped: 2,
+                failed: 2
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn fetched_previews_resolve_images_against_the_final_url() {
+        let page = r#"<html><head>
+            <meta property="og:title" content="Q&amp;A">
+            <meta property="og:image" content="/static/card.png">
+            </head></html>"#;
+        let url = serve(vec![(200, page.to_string())]);
+        let preview = LinkPreviewer::new(fast_options())
+            .unwrap()
+            .fetch(&url)
+            .await
+            .unwrap();
+        let origin = url.trim_end_matches("/page");
+        assert_eq!(preview.title.as_deref(), Some("Q&A"));
+        assert_eq!(preview.image_url, Some(format!("{origin}/static/card.png")));
+    }
+
+    #[tokio::test]
+    async fn saved_settings_set_the_user_agent_and_proxy() {
+        let db = Database::open_memory().unwrap();
+        assert_eq!(
+            PreviewSettings::load(&db).unwrap(),
+            PreviewSettings::default()
+        );
+
+        let invalid = PreviewSettings {
+            proxy_url: Some("not a url".into()),
+            ..PreviewSettings::default()
+        };
+        assert!(invalid.save(&db).is_err());
+
+        // The "proxy" records what it was asked for and serves the page itself
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy_url = format!("http://{}", listener.local_addr().unwrap());
+        let request = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = String::new();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 0 && line != "\r\n" {
+                head.push_str(&line);
+                line.clear();
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{PAGE}",
+                PAGE.len()
+            );
+            head
+        });
+
+        PreviewSettings {
+            user_agent: Some("Mozilla/5.0 (eterea test)".into()),
+            proxy_url: Some(proxy_url),
+        }
+        .save(&db)
+        .unwrap();
+        let options = fast_options().with_settings(&PreviewSettings::load(&db).unwrap());
+        let preview = LinkPreviewer::new(options)
+            .unwrap()
+            .fetch("http://eterea.invalid/page")
+            .await
+            .unwrap();
+        assert_eq!(preview.title.as_deref(), Some("Rust 1.80"));
+
+        let head = request.join().unwrap().to_lowercase();
+        assert!(head.starts_with("get http://eterea.invalid/page"), "{head}");
+        assert!(
+            head.contains("user-agent: mozilla/5.0 (eterea test)"),
+            "{head}"
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors_then_succeeds() {
+        let url = serve(vec![(503, String::new()), (200, PAGE.to_string())]);
+        let preview = LinkPreviewer::new(fast_options())
+            .unwrap()
+            .fetch(&url)
+            .await
+            .unwrap();
+        assert_eq!(preview.title.as_deref(), Some("Rust 1.80"));
+        assert_eq!(preview.final_url, url);
+    }
+
+    #[tokio::test]
+    async fn cached_previews_skip_the_network_until_refreshed() {
+        let db = Database::open_memory().unwrap();
+        // Only one response is served; a second network fetch would fail.
+        let url = serve(vec![(200, PAGE.to_string())]);
+        let previewer = LinkPreviewer::new(fast_options()).unwrap();
+
+        let fetched = previewer.fetch_cached(&url, &db, false).await.unwrap();
+        let cached = previewer.fetch_cached(&url, &db, false).await.unwrap();
+        assert_eq!(cached.title, fetched.title);
+        assert_eq!(
+            cached.fetched_at.timestamp(),
+            fetched.fetched_at.timestamp()
+        );
+
+        assert!(previewer.fetch_cached(&url, &db, true).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn expands_redirects_to_the_final_url() {
+        let url = serve(vec![(301, "/landing".to_string()), (200, PAGE.to_string())]);
+        let expanded = LinkPreviewer::new(fast_options())
+            .unwrap()
+            .expand_url(&url)
+            .await
+            .unwrap();
+        assert_eq!(expanded.original, url);
+        assert_eq!(
+            expanded.expanded,
+            format!("{}/landing", url.trim_end_matches("/page"))
+        );
+    }
+
+    #[tokio::test]
+    async fn cached_expansions_and_plain_links_skip_the_network() {
+        let db = Database::open_memory().unwrap();
+        let bookmark = crate::models::BookmarkBuilder::new()
+            .tweet_url("https://x.com/user/status/1")
+            .content("New release https://t.co/abc and notes https://example.com/notes")
+            .tweeted_at(Utc::now())
+            .author_handle("user")
+            .build()
+            .unwrap();
+        db.insert_bookmarks(std::slice::from_ref(&bookmark))
+            .unwrap();
+        db.set_expanded_url(
+            "https://t.co/abc",
+            "https://github.com/rust-lang/rust/releases",
+        )
+        .unwrap();
+
+        let links = LinkPreviewer::new(fast_options())
+            .unwrap()
+            .expand_links(&bookmark.id, &db)
+            .await
+            .unwrap();
+        assert_eq!(
+            links,
+            vec![
+                ExpandedUrl {
+                    original: "https://t.co/abc".into(),
+                    expanded: "https://github.com/rust-lang/rust/releases".into(),
+                },
+                ExpandedUrl {
+                    original: "https://example.com/notes".into(),
+                    expanded: "https://example.com/notes".into(),
+                },
+            ]
+        );
+        assert!(is_shortened_url("https://t.co/abc"));
+        assert!(!is_shortened_url("https://example.com/notes"));
+        assert_eq!(
+            db.get_bookmarks_with_link_domain("github.com", 0, 10)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn stale_previews_are_ignored_and_purged() {
+        let db = Database::open_memory().unwrap();
+        let stale = LinkPreview {
+            url: "https://example.com/old".into(),
+            final_url: "https://example.com/old".into(),
+            fetched_at: Utc::now() - chrono::Duration::days(30),
+            ..LinkPreview::default()
+        };
+        let fresh = LinkPreview {
+            url: "https://example.com/new".into(),
+            final_url: "https://example.com/new".into(),
+            favicon_url: Some("https://example.com/favicon.ico".into()),
+            canonical_url: Some("https://example.com/new".into()),
+            fetched_at: Utc::now(),
+            ..LinkPreview::default()
+        };
+        db.upsert_preview(&stale).unwrap();
+        db.upsert_preview(&fresh).unwrap();
+
+        let ttl = Duration::from_secs(24 * 60 * 60);
+        assert!(db.get_cached_preview(&stale.url, ttl).unwrap().is_none());
+        let cached = db.get_cached_preview(&fresh.url, ttl).unwrap().unwrap();
+        assert_eq!(cached.favicon_url, fresh.favicon_url);
+        assert_eq!(cached.canonical_url, fresh.canonical_url);
+        assert_eq!(db.purge_stale_previews(ttl).unwrap(), 1);
+        assert!(db
+            .get_cached_preview(&stale.url, Duration::MAX)
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_pages_over_the_body_limit() {
+        let url = serve(vec![(200, PAGE.to_string())]);
+        let error = LinkPreviewer::new(PreviewOptions {
+            max_body_bytes: 32,
+            ..fast_options()
+        })
+        .unwrap()
+        .fetch(&url)
+        .await
+        .unwrap_err();
+        assert!(matches!(error, Error::Preview(PreviewError::TooLarge(32))));
+    }
+
+    #[tokio::test]
+    async fn reports_timeouts_distinctly() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/slow", listener.local_addr().unwrap());
+        // Accept but never answer
+        let _hold = std::thread::spawn(move || {
+            let _connection = listener.accept();
+            std::thread::sleep(Duration::from_secs(2));
+        });
+
+        let error = LinkPreviewer::new(PreviewOptions {
+            timeout: Duration::from_millis(100),
+            ..fast_options()
+        })
+        .unwrap()
+        .fetch(&url)
+        .await
+        .unwrap_err();
+        assert!(matches!(error, Error::Preview(PreviewError::Timeout)));
+    }
+}
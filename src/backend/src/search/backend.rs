@@ -0,0 +1,21 @@
+//! Pluggable full-text search backends
+
+use crate::models::Bookmark;
+use crate::Result;
+
+/// A full-text index over bookmarks.
+///
+/// `Database` uses its built-in SQLite FTS5 index unless another backend is set
+/// with `Database::set_search_backend`; it then forwards every inserted or
+/// edited bookmark to `index`, every deleted or trashed one to `remove`, and
+/// hydrates the ids returned by `search`.
+pub trait SearchBackend: Send {
+    /// Add or replace bookmarks in the index
+    fn index(&self, bookmarks: &[Bookmark]) -> Result<()>;
+
+    /// Drop bookmarks from the index; unknown ids are ignored
+    fn remove(&self, ids: &[&str]) -> Result<()>;
+
+    /// Ids of the bookmarks matching `query`, best matches first
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<String>>;
+}
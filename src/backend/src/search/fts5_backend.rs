@@ -0,0 +1,156 @@
+//! Standalone SQLite FTS5 search backend
+
+use super::SearchBackend;
+use crate::models::Bookmark;
+use crate::storage::{Database, FtsTokenizer};
+use crate::Result;
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+/// An FTS5 index over bookmark content, notes, author and tags, kept in its own
+/// SQLite file (or in memory) instead of inside the library database.
+///
+/// Queries are read like `Database::search` reads them: every term is a
+/// prefix match, `#tag` searches content and tags, `@handle` content and
+/// authors.
+pub struct Fts5Backend {
+    conn: Connection,
+}
+
+impl Fts5Backend {
+    /// Open the index in the SQLite file `path`, creating it if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// An index that lives only in memory
+    pub fn in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(&format!(
+            r#"CREATE VIRTUAL TABLE IF NOT EXISTS backend_fts USING fts5(
+                   bookmark_id UNINDEXED,
+                   content,
+                   note_text,
+                   author_handle,
+                   author_name,
+                   tags_text,
+                   tokenize='{}'
+               )"#,
+            FtsTokenizer::default().fts5_spec()
+        ))?;
+        Ok(Self { conn })
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM backend_fts WHERE bookmark_id = ?1",
+            params![id],
+        )?;
+        Ok(())
+    }
+}
+
+impl SearchBackend for Fts5Backend {
+    fn index(&self, bookmarks: &[Bookmark]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for bookmark in bookmarks {
+            // Comments hold the latest annotation; search them as notes
+            let notes = [&bookmark.note_text, &bookmark.comments]
+                .into_iter()
+                .flatten()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join("\n");
+            self.delete(&bookmark.id)?;
+            self.conn.execute(
+                r#"INSERT INTO backend_fts
+                       (bookmark_id, content, note_text, author_handle, author_name, tags_text)
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+                params![
+                    bookmark.id,
+                    bookmark.content,
+                    notes,
+                    bookmark.author_handle,
+                    bookmark.author_name,
+                    bookmark.tags.join(" "),
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn remove(&self, ids: &[&str]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for id in ids {
+            self.delete(id)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<String>> {
+        let query = Database::prepare_fts_query(query, true);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut stmt = self.conn.prepare(
+            "SELECT bookmark_id FROM backend_fts WHERE backend_fts MATCH ?1 ORDER BY rank LIMIT ?2",
+        )?;
+        let ids = stmt
+            .query_map(params![query, limit as i64], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::BookmarkBuilder;
+    use chrono::Utc;
+
+    #[test]
+    fn indexes_replaces_and_removes_bookmarks() {
+        let backend = Fts5Backend::in_memory().unwrap();
+        let mut bookmark = BookmarkBuilder::new()
+            .tweet_url("https://x.com/alice/status/1")
+            .content("Borrow checker tips")
+            .tweeted_at(Utc::now())
+            .author_handle("alice")
+            .author_name("Alice")
+            .add_tag("rust")
+            .build()
+            .unwrap();
+        backend.index(std::slice::from_ref(&bookmark)).unwrap();
+        assert_eq!(
+            backend.search("borrow", 10).unwrap(),
+            vec![bookmark.id.clone()]
+        );
+        assert_eq!(
+            backend.search("#rust", 10).unwrap(),
+            vec![bookmark.id.clone()]
+        );
+        assert_eq!(
+            backend.search("@ali", 10).unwrap(),
+            vec![bookmark.id.clone()]
+        );
+
+        bookmark.content = "Lifetimes explained".to_string();
+        backend.index(std::slice::from_ref(&bookmark)).unwrap();
+        assert!(backend.search("borrow", 10).unwrap().is_empty());
+        assert_eq!(
+            backend.search("lifetimes", 10).unwrap(),
+            vec![bookmark.id.clone()]
+        );
+
+        backend.remove(&[bookmark.id.as_str()]).unwrap();
+        assert!(backend.search("lifetimes", 10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+//! In-memory inverted index for search-as-you-type
+
+use crate::models::Bookmark;
+use crate::storage::{Database, SearchFilters};
+use crate::Result;
+use std::collections::{BTreeMap, HashMap};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A compact prefix index over the live bookmarks of a library, answering
+/// queries without a round trip to SQLite.
+///
+/// Only meant for small and medium libraries: `build` gives up above
+/// `SearchIndex::MAX_BOOKMARKS`, and `Database::search_instant` falls back to
+/// FTS5 then. Content, author handle and name, and tags are indexed.
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    /// Lowercased word -> slots of the bookmarks containing it, ascending
+    terms: BTreeMap<String, Vec<u32>>,
+    /// Bookmark per slot; `None` once it has been removed or replaced
+    slots: Vec<Option<IndexedBookmark>>,
+    /// Bookmark id -> its current slot
+    by_id: HashMap<String, u32>,
+}
+
+#[derive(Debug)]
+struct IndexedBookmark {
+    id: String,
+    tweeted_at: i64,
+}
+
+impl SearchIndex {
+    /// Largest library `build` indexes; past this, memory use outweighs the
+    /// gain over FTS5
+    pub const MAX_BOOKMARKS: usize = 100_000;
+
+    /// Index every live bookmark in `db`. Returns `None` when the library has
+    /// more than `MAX_BOOKMARKS`.
+    pub fn build(db: &Database) -> Result<Option<Self>> {
+        if db.count_bookmarks()? as usize > Self::MAX_BOOKMARKS {
+            return Ok(None);
+        }
+        let mut index = Self::default();
+        for bookmark in db.bookmarks_matching(&SearchFilters::new(), None)? {
+            index.insert(&bookmark);
+        }
+        Ok(Some(index))
+    }
+
+    /// Number of bookmarks indexed
+    pub fn len(&self) -> usize {
+        self.by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_id.is_empty()
+    }
+
+    /// Add `bookmark`, replacing what was indexed under its id
+    pub fn insert(&mut self, bookmark: &Bookmark) {
+        self.remove(&bookmark.id);
+
+        let slot = self.slots.len() as u32;
+        self.slots.push(Some(IndexedBookmark {
+            id: bookmark.id.clone(),
+            tweeted_at: bookmark.tweeted_at.timestamp(),
+        }));
+        self.by_id.insert(bookmark.id.clone(), slot);
+
+        let fields = [
+            bookmark.content.as_str(),
+            bookmark.author_handle.as_str(),
+            bookmark.author_name.as_str(),
+        ];
+        let tags = bookmark.tags.iter().map(String::as_str);
+        for text in fields.into_iter().chain(tags) {
+            for word in text.unicode_words() {
+                let postings = self.terms.entry(word.to_lowercase()).or_default();
+                // Slots only grow, so a repeated word is always the last entry
+                if postings.last() != Some(&slot) {
+                    postings.push(slot);
+                }
+            }
+        }
+    }
+
+    /// Drop the bookmark `id`; returns whether it was indexed
+    pub fn remove(&mut self, id: &str) -> bool {
+        let Some(slot) = self.by_id.remove(id) else {
+            return false;
+        };
+        self.slots[slot as usize] = None;
+        if self.slots.len() > 1024 && self.slots.len() > 2 * self.by_id.len() {
+            self.compact();
+        }
+        true
+    }
+
+    /// Ids of the bookmarks containing a word starting with each word of
+    /// `query`, newest first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<String> {
+        let mut matches: Option<Vec<u32>> = None;
+        for word in query.unicode_words() {
+            let word = word.to_lowercase();
+            let mut slots: Vec<u32> = self
+                .terms
+                .range(word.clone()..)
+                .take_while(|(term, _)| term.starts_with(&word))
+                .flat_map(|(_, postings)| postings.iter().copied())
+                .collect();
+            slots.sort_unstable();
+            slots.dedup();
+            matches = Some(match matches {
+                None => slots,
+                Some(previous) => previous
+                    .into_iter()
+                    .filter(|slot| slots.binary_search(slot).is_ok())
+                    .collect(),
+            });
+        }
+
+        let mut hits: Vec<&IndexedBookmark> = matches
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|slot| self.slots[slot as usize].as_ref())
+            .collect();
+        hits.sort_by(|a, b| b.tweeted_at.cmp(&a.tweeted_at).then(b.id.cmp(&a.id)));
+        hits.into_iter()
+            .take(limit)
+            .map(|hit| hit.id.clone())
+            .collect()
+    }
+
+    /// Renumber the live slots so removed bookmarks stop taking up memory
+    fn compact(&mut self) {
+        let mut renumbered = vec![None; self.slots.len()];
+        let mut slots = Vec::with_capacity(self.by_id.len());
+        for (old, bookmark) in std::mem::take(&mut self.slots).into_iter().enumerate() {
+            if let Some(bookmark) = bookmark {
+                renumbered[old] = Some(slots.len() as u32);
+                self.by_id.insert(bookmark.id.clone(), slots.len() as u32);
+                slots.push(Some(bookmark));
+            }
+        }
+        self.slots = slots;
+        self.terms.retain(|_, postings| {
+            postings.retain_mut(|slot| match renumbered[*slot as usize] {
+                Some(new) => {
+                    *slot = new;
+                    true
+                }
+                None => false,
+            });
+            !postings.is_empty()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::BookmarkBuilder;
+    use chrono::{TimeZone, Utc};
+
+    fn bookmark(id: u32, content: &str, tags: &[&str]) -> Bookmark {
+        let mut builder = BookmarkBuilder::new()
+            .tweet_url(format!("https://x.com/alice/status/{id}"))
+            .content(content)
+            .tweeted_at(Utc.timestamp_opt(1_700_000_000 + id as i64, 0).unwrap())
+            .author_handle("alice")
+            .author_name("Alice");
+        for tag in tags {
+            builder = builder.add_tag(*tag);
+        }
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn matches_every_word_by_prefix_newest_first() {
+        let mut index = SearchIndex::default();
+        let older = bookmark(1, "Rust async runtimes compared", &["programming"]);
+        let newer = bookmark(2, "Rusty old bikes", &["cycling"]);
+        index.insert(&older);
+        index.insert(&newer);
+
+        assert_eq!(
+            index.search("rus", 10),
+            vec![newer.id.clone(), older.id.clone()]
+        );
+        assert_eq!(index.search("RUST prog", 10), vec![older.id.clone()]);
+        assert_eq!(index.search("rust", 1), vec![newer.id.clone()]);
+        assert!(index.search("python", 10).is_empty());
+        assert!(index.search("", 10).is_empty());
+    }
+
+    #[test]
+    fn replaced_and_removed_bookmarks_stop_matching() {
+        let mut index = SearchIndex::default();
+        let mut first = bookmark(1, "tokio internals", &[]);
+        index.insert(&first);
+        first.tags.push("async".into());
+        first.content = "runtime internals".into();
+        index.insert(&first);
+        assert_eq!(index.len(), 1);
+        assert!(index.search("tokio", 10).is_empty());
+        assert_eq!(index.search("async runtime", 10), vec![first.id.clone()]);
+
+        // Enough churn to trigger compaction
+        for id in 2..2000 {
+            let other = bookmark(id, "filler words", &[]);
+            index.insert(&other);
+            assert!(index.remove(&other.id));
+        }
+        assert!(!index.remove("missing"));
+        assert_eq!(index.search("internals", 10), vec![first.id.clone()]);
+        assert!(index.search("filler", 10).is_empty());
+        assert!(index.slots.len() < 1024);
+    }
+}
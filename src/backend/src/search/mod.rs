@@ -1,40 +1,98 @@
 //! Search module
 //!
-//! Provides additional search utilities beyond FTS5.
-//! Can be extended with Tantivy for advanced features.
+//! Provides additional search utilities beyond FTS5, the `SearchBackend`
+//! trait for swapping the full-text index (a standalone FTS5 index, or Tantivy
+//! with the `tantivy` feature), and `SearchIndex` for search-as-you-type.
 
+mod backend;
+mod fts5_backend;
 mod highlighter;
+mod instant;
+#[cfg(feature = "tantivy")]
+mod tantivy_backend;
+mod terms;
 
+pub use backend::SearchBackend;
+pub use fts5_backend::Fts5Backend;
 pub use highlighter::highlight_matches;
+pub use instant::SearchIndex;
+#[cfg(feature = "tantivy")]
+pub use tantivy_backend::TantivyBackend;
+pub use terms::{is_stopword, proximity_bonus, salient_terms, ENGLISH_STOPWORDS};
 
-/// Extract search snippets with context
+/// Extract a search snippet: the first match with `context_chars` of context
+/// either side (see `extract_snippets` for more than one)
 pub fn extract_snippet(text: &str, query: &str, context_chars: usize) -> String {
+    extract_snippets(text, query, context_chars, 1)
+}
+
+/// Extract up to `max_snippets` snippets, one per region of `text` where
+/// `query` appears, each with `context_chars` of context either side.
+/// Windows that overlap or touch merge into one; the snippets are joined with
+/// `...`. Without a match, returns the beginning of the text.
+pub fn extract_snippets(
+    text: &str,
+    query: &str,
+    context_chars: usize,
+    max_snippets: usize,
+) -> String {
     let text_lower = text.to_lowercase();
     let query_lower = query.to_lowercase();
-    
-    // Find first match
-    if let Some(pos) = text_lower.find(&query_lower) {
-        let start = pos.saturating_sub(context_chars);
-        let end = (pos + query.len() + context_chars).min(text.len());
-        
-        let mut snippet = String::new();
-        if start > 0 {
-            snippet.push_str("...");
+
+    let mut windows: Vec<(usize, usize)> = Vec::new();
+    let positions: Vec<usize> = if query_lower.is_empty() {
+        vec![0]
+    } else {
+        text_lower
+            .match_indices(&query_lower)
+            .map(|(pos, _)| pos)
+            .collect()
+    };
+    for pos in positions {
+        // Offsets are bytes; snap them to char boundaries so multi-byte text
+        // (emoji, CJK) can't split a character
+        let mut start = pos.saturating_sub(context_chars).min(text.len());
+        while !text.is_char_boundary(start) {
+            start -= 1;
         }
-        snippet.push_str(&text[start..end]);
-        if end < text.len() {
-            snippet.push_str("...");
+        let mut end = (pos + query.len() + context_chars).min(text.len());
+        while !text.is_char_boundary(end) {
+            end += 1;
         }
-        
-        snippet
-    } else {
+
+        let full = windows.len() == max_snippets.max(1);
+        match windows.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+            _ if full => break,
+            _ => windows.push((start, end)),
+        }
+    }
+
+    if windows.is_empty() {
         // No match, return beginning of text
-        if text.len() > context_chars * 2 {
-            format!("{}...", &text[..context_chars * 2])
+        return if text.chars().count() > context_chars * 2 {
+            let prefix: String = text.chars().take(context_chars * 2).collect();
+            format!("{}...", prefix)
         } else {
             text.to_string()
+        };
+    }
+
+    let mut snippet = String::new();
+    if windows[0].0 > 0 {
+        snippet.push_str("...");
+    }
+    for (index, &(start, end)) in windows.iter().enumerate() {
+        if index > 0 {
+            snippet.push_str(" ... ");
         }
+        snippet.push_str(&text[start..end]);
     }
+    if windows[windows.len() - 1].1 < text.len() {
+        snippet.push_str("...");
+    }
+
+    snippet
 }
 
 #[cfg(test)]
@@ -48,5 +106,31 @@ mod tests {
         assert!(snippet.contains("Rust"));
         assert!(snippet.contains("..."));
     }
-}
 
+    #[test]
+    fn test_extract_snippet_multibyte() {
+        let text = "🦀🦀🦀 Rust 🦀🦀🦀 ржавчина и крабы";
+        assert!(extract_snippet(text, "rust", 3).contains("Rust"));
+        assert_eq!(extract_snippet("ржавчина", "xyz", 2), "ржав...");
+    }
+
+    #[test]
+    fn test_extract_snippets_merges_overlapping_windows() {
+        let text =
+            "rust and rust again, then a long stretch of unrelated words, then rust at the end";
+        // The first two matches are close enough to share one window
+        assert_eq!(
+            extract_snippets(text, "Rust", 5, 3),
+            "rust and rust agai ... then rust at t..."
+        );
+        assert_eq!(
+            extract_snippets(text, "rust", 5, 1),
+            "rust and rust agai..."
+        );
+        assert_eq!(
+            extract_snippets(text, "rust", 5, 1),
+            extract_snippet(text, "rust", 5)
+        );
+        assert_eq!(extract_snippets(text, "python", 5, 3), "rust and r...");
+    }
+}
@@ -0,0 +1,204 @@
+//! Tantivy search backend (`tantivy` feature)
+
+use super::SearchBackend;
+use crate::models::Bookmark;
+use crate::Result;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use tantivy::collector::TopDocs;
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, Schema, Value, STORED, STRING, TEXT};
+use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+
+/// Memory budget of the index writer
+const WRITER_HEAP_BYTES: usize = 50_000_000;
+
+/// A Tantivy index over bookmark id, content, notes, author and tags
+pub struct TantivyBackend {
+    index: Index,
+    reader: IndexReader,
+    /// Opened once: an index takes a single writer, and each one allocates
+    /// `WRITER_HEAP_BYTES` up front
+    writer: Mutex<IndexWriter>,
+    id: Field,
+    content: Field,
+    note: Field,
+    author: Field,
+    tags: Field,
+}
+
+impl TantivyBackend {
+    /// Open the index in `dir`, creating it if the directory is empty
+    pub fn open(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let directory =
+            tantivy::directory::MmapDirectory::open(dir).map_err(tantivy::TantivyError::from)?;
+        Self::from_index(Index::open_or_create(directory, Self::schema())?)
+    }
+
+    /// An index that lives only in memory
+    pub fn in_memory() -> Result<Self> {
+        Self::from_index(Index::create_in_ram(Self::schema()))
+    }
+
+    fn schema() -> Schema {
+        let mut builder = Schema::builder();
+        builder.add_text_field("id", STRING | STORED);
+        builder.add_text_field("content", TEXT);
+        builder.add_text_field("note", TEXT);
+        builder.add_text_field("author", TEXT);
+        builder.add_text_field("tags", TEXT);
+        builder.build()
+    }
+
+    fn from_index(index: Index) -> Result<Self> {
+        let schema = index.schema();
+        let field = |name| schema.get_field(name);
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let writer = index.writer::<TantivyDocument>(WRITER_HEAP_BYTES)?;
+        Ok(Self {
+            id: field("id")?,
+            content: field("content")?,
+            note: field("note")?,
+            author: field("author")?,
+            tags: field("tags")?,
+            index,
+            reader,
+            writer: Mutex::new(writer),
+        })
+    }
+
+    /// Apply `edit` with the shared writer and commit it, or roll it back if
+    /// it fails
+    fn write(&self, edit: impl FnOnce(&mut IndexWriter) -> Result<()>) -> Result<()> {
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = edit(&mut writer) {
+            writer.rollback()?;
+            return Err(e);
+        }
+        writer.commit()?;
+        self.reader.reload()?;
+        Ok(())
+    }
+}
+
+impl SearchBackend for TantivyBackend {
+    fn index(&self, bookmarks: &[Bookmark]) -> Result<()> {
+        self.write(|writer| {
+            for bookmark in bookmarks {
+                // Comments hold the latest annotation; search them as notes
+                let notes = [&bookmark.note_text, &bookmark.comments]
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                writer.delete_term(Term::from_field_text(self.id, &bookmark.id));
+                writer.add_document(doc!(
+                    self.id => bookmark.id.as_str(),
+                    self.content => bookmark.content.as_str(),
+                    self.note => notes,
+                    self.author => format!("{} {}", bookmark.author_handle, bookmark.author_name),
+                    self.tags => bookmark.tags.join(" "),
+                ))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn remove(&self, ids: &[&str]) -> Result<()> {
+        self.write(|writer| {
+            for id in ids {
+                writer.delete_term(Term::from_field_text(self.id, id));
+            }
+            Ok(())
+        })
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Result<Vec<String>> {
+        let searcher = self.reader.searcher();
+        let parser = QueryParser::for_index(
+            &self.index,
+            vec![self.content, self.note, self.author, self.tags],
+        );
+        // Lenient parsing: stray quotes or operators in user input aren't errors
+        let (query, _) = parser.parse_query_lenient(query);
+
+        let mut ids = Vec::new();
+        for (_, address) in searcher.search(&query, &TopDocs::with_limit(limit.max(1)))? {
+            let document: TantivyDocument = searcher.doc(address)?;
+            if let Some(id) = document.get_first(self.id).and_then(|v| v.as_str()) {
+                ids.push(id.to_string());
+            }
+        }
+        ids.truncate(limit);
+        Ok(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::BookmarkBuilder;
+    use chrono::Utc;
+
+    #[test]
+    fn indexes_and_replaces_bookmarks() {
+        let backend = TantivyBackend::in_memory().unwrap();
+        let mut bookmark = BookmarkBuilder::new()
+            .tweet_url("https://x.com/alice/status/1")
+            .content("Borrow checker tips")
+            .tweeted_at(Utc::now())
+            .author_handle("alice")
+            .author_name("Alice")
+            .build()
+            .unwrap();
+        backend.index(std::slice::from_ref(&bookmark)).unwrap();
+        assert_eq!(
+            backend.search("borrow", 10).unwrap(),
+            vec![bookmark.id.clone()]
+        );
+        assert_eq!(
+            backend.search("alice", 10).unwrap(),
+            vec![bookmark.id.clone()]
+        );
+
+        bookmark.content = "Lifetimes explained".to_string();
+        backend.index(std::slice::from_ref(&bookmark)).unwrap();
+        assert!(backend.search("borrow", 10).unwrap().is_empty());
+        assert_eq!(
+            backend.search("lifetimes", 10).unwrap(),
+            vec![bookmark.id.clone()]
+        );
+
+        backend.remove(&[bookmark.id.as_str()]).unwrap();
+        assert!(backend.search("lifetimes", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn searches_comments_as_notes() {
+        let backend = TantivyBackend::in_memory().unwrap();
+        let mut bookmark = BookmarkBuilder::new()
+            .tweet_url("https://x.com/alice/status/1")
+            .content("Borrow checker tips")
+            .tweeted_at(Utc::now())
+            .author_handle("alice")
+            .author_name("Alice")
+            .build()
+            .unwrap();
+        bookmark.note_text = Some("Read later".to_string());
+        bookmark.comments = Some("Shared with the team".to_string());
+        backend.index(std::slice::from_ref(&bookmark)).unwrap();
+        assert_eq!(
+            backend.search("later", 10).unwrap(),
+            vec![bookmark.id.clone()]
+        );
+        assert_eq!(
+            backend.search("team", 10).unwrap(),
+            vec![bookmark.id.clone()]
+        );
+    }
+}
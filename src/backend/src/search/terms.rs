@@ -0,0 +1,139 @@
+//! Term extraction for query building
+
+use crate::models::url_regex;
+use std::collections::HashMap;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Common English words that carry little meaning on their own
+pub const ENGLISH_STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "am", "an", "and", "any", "are", "as", "at", "be",
+    "because", "been", "before", "being", "but", "by", "can", "could", "did", "do", "does",
+    "doing", "for", "from", "had", "has", "have", "having", "he", "her", "here", "him", "his",
+    "how", "i", "if", "in", "into", "is", "it", "its", "just", "me", "more", "most", "my", "no",
+    "not", "now", "of", "on", "one", "only", "or", "other", "our", "out", "over", "she", "so",
+    "some", "such", "than", "that", "the", "their", "them", "then", "there", "these", "they",
+    "this", "those", "to", "too", "up", "very", "was", "we", "were", "what", "when", "where",
+    "which", "while", "who", "why", "will", "with", "would", "you", "your",
+];
+
+/// Whether `word` (any case) is in `ENGLISH_STOPWORDS`
+pub fn is_stopword(word: &str) -> bool {
+    ENGLISH_STOPWORDS.contains(&word.to_lowercase().as_str())
+}
+
+/// The `max_terms` most frequent meaningful words in `text`, lowercased.
+///
+/// Links, numbers, stopwords and words shorter than three characters are
+/// skipped; ties keep the order of first appearance.
+pub fn salient_terms(text: &str, max_terms: usize) -> Vec<String> {
+    let text = url_regex().replace_all(text, " ");
+
+    let mut counts = HashMap::<String, (usize, usize)>::new();
+    for (position, word) in text.unicode_words().enumerate() {
+        let word = word.to_lowercase();
+        if word.chars().count() < 3 || word.chars().all(|ch| ch.is_numeric()) || is_stopword(&word)
+        {
+            continue;
+        }
+        counts.entry(word).or_insert((0, position)).0 += 1;
+    }
+
+    let mut terms = counts.into_iter().collect::<Vec<_>>();
+    terms.sort_by(|(_, (count_a, first_a)), (_, (count_b, first_b))| {
+        count_b.cmp(count_a).then(first_a.cmp(first_b))
+    });
+    terms
+        .into_iter()
+        .take(max_terms)
+        .map(|(term, _)| term)
+        .collect()
+}
+
+/// How close together the words of `query` appear in `text`: the number of
+/// distinct query words divided by the length (in words) of the shortest stretch
+/// of `text` containing all of them. `1.0` means adjacent; `0.0` when `query`
+/// has fewer than two words or `text` lacks one of them.
+///
+/// Query words match by prefix, like the FTS5 query built by `Database::search`.
+pub fn proximity_bonus(text: &str, query: &str) -> f64 {
+    let mut terms: Vec<String> = Vec::new();
+    for word in query.unicode_words() {
+        let word = word.to_lowercase();
+        if !terms.contains(&word) {
+            terms.push(word);
+        }
+    }
+    if terms.len() < 2 {
+        return 0.0;
+    }
+
+    // (word position, index of the query term it matches)
+    let hits: Vec<(usize, usize)> = text
+        .unicode_words()
+        .enumerate()
+        .filter_map(|(position, word)| {
+            let word = word.to_lowercase();
+            terms
+                .iter()
+                .position(|term| word.starts_with(term.as_str()))
+                .map(|term| (position, term))
+        })
+        .collect();
+
+    // Shortest window of hits covering every term
+    let mut seen = vec![0usize; terms.len()];
+    let mut covered = 0;
+    let mut shortest = usize::MAX;
+    let mut left = 0;
+    for &(position, term) in &hits {
+        seen[term] += 1;
+        if seen[term] == 1 {
+            covered += 1;
+        }
+        while covered == terms.len() {
+            let (start, first_term) = hits[left];
+            shortest = shortest.min(position - start + 1);
+            seen[first_term] -= 1;
+            if seen[first_term] == 0 {
+                covered -= 1;
+            }
+            left += 1;
+        }
+    }
+
+    if shortest == usize::MAX {
+        0.0
+    } else {
+        terms.len() as f64 / shortest as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn salient_terms_prefer_frequent_meaningful_words() {
+        let text =
+            "The Rust book: learn Rust with the book at https://doc.rust-lang.org/book in 2024";
+        assert_eq!(salient_terms(text, 3), vec!["rust", "book", "learn"]);
+        assert!(salient_terms("the and of it", 5).is_empty());
+    }
+
+    #[test]
+    fn proximity_bonus_rewards_adjacent_query_words() {
+        let query = "machine learning";
+        assert_eq!(proximity_bonus("Notes on machine learning", query), 1.0);
+        assert_eq!(
+            proximity_bonus("learning to fix a machine", query),
+            2.0 / 5.0
+        );
+        // The closest pair wins, whichever order the words come in
+        assert_eq!(
+            proximity_bonus("machine shop, then learning machines", query),
+            1.0
+        );
+        assert_eq!(proximity_bonus("machine only", query), 0.0);
+        assert_eq!(proximity_bonus("machine learning", "machine"), 0.0);
+    }
+}
@@ -1,20 +1,100 @@
 //! SQLite database implementation
 
-use super::queries::BookmarkStats;
-use super::schema::{PRAGMAS, SCHEMA};
-use crate::models::{Bookmark, Media, MediaType};
+use super::options::{DatabaseOptions, FtsTokenizer};
+use super::queries::{
+    BookmarkStats, Collection, Facets, ImportReport, MergeReport, PaginatedResponse, Pagination,
+    Period, SavedSearch, SearchField, SearchFilters, SortBy, SyncExport, TagMap, TagMapEntry,
+    TagMapReport, TagMatch, Tombstone,
+};
+use super::schema::{
+    fts_table_sql, CONTENT_TRIGRAM_SCHEMA, PRAGMAS, SCHEMA, SYNC_CHANGES_TRIGGERS,
+};
+use crate::export::{self, ExportFormat};
+use crate::models::{
+    extract_urls, normalize_tweet_url, tweet_id_from_url, url_domain, Annotation, Author, Bookmark,
+    Media, MediaType,
+};
+use crate::preview::LinkPreview;
+use crate::search::{
+    proximity_bonus, salient_terms, SearchBackend, SearchIndex, ENGLISH_STOPWORDS,
+};
 use crate::{Error, Result};
+use rusqlite::backup::Progress;
+use rusqlite::functions::FunctionFlags;
 use rusqlite::types::Value;
-use rusqlite::{params, params_from_iter, Connection};
-use std::collections::HashMap;
+use rusqlite::{
+    params, params_from_iter, Connection, DatabaseName, OpenFlags, Transaction, TransactionBehavior,
+};
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use tracing::{debug, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 
 const STATS_SNAPSHOT_METADATA_KEY: &str = "stats_snapshot_v1";
 
+/// Set once `ensure_lowercase_author_handles` has run, so later opens skip its scan
+const LOWERCASE_HANDLES_METADATA_KEY: &str = "lowercase_author_handles_v1";
+
+/// How many terms `find_related` pulls from the source bookmark
+const MAX_RELATED_TERMS: usize = 8;
+
+/// How many of the best FTS matches `search_with_proximity` re-ranks
+const PROXIMITY_CANDIDATES: usize = 200;
+
+/// Weight of `proximity_bonus` against bm25 order in `search_with_proximity`:
+/// adjacent query words can lift a match past this share of the candidates
+const PROXIMITY_WEIGHT: f64 = 0.5;
+
+/// Shared bookmark projection; column order must match `row_to_bookmark`.
+/// Author fields come from `authors`, falling back to the denormalized columns.
+const BOOKMARK_COLUMNS: &str = r#"b.id, b.tweet_url, b.content, b.note_text, b.tweeted_at, b.imported_at,
+                      b.author_handle,
+                      COALESCE(a.name, b.author_name),
+                      COALESCE(a.profile_url, b.author_profile_url),
+                      COALESCE(a.profile_image, b.author_profile_image),
+                      b.comments, b.is_favorite, b.is_archived, b.date_estimated, b.tweet_id,
+                      b.content_original_len, b.last_seen_at"#;
+
+/// Environment variable that overrides `Database::default_path`
+pub const DB_PATH_ENV: &str = "ETEREA_DB_PATH";
+
+/// Source tables for `BOOKMARK_COLUMNS` (`b` = bookmarks, `a` = authors)
+const BOOKMARK_TABLES: &str = "bookmarks b LEFT JOIN authors a ON a.handle = b.author_handle";
+
+/// Most ids bound into one `IN (...)` list, well under SQLite's variable limit
+const MAX_IN_PARAMS: usize = 900;
+
+/// Newest-first ordering for listings and search ties. Snowflake status ids break
+/// ties between tweets whose dates were truncated to the same minute; rows without
+/// one fall back to import order.
+const NEWEST_FIRST: &str =
+    "b.tweeted_at DESC, CAST(b.tweet_id AS INTEGER) DESC, b.imported_at DESC, b.id DESC";
+
+/// "Recently added" ordering for `SortBy::ImportedAt`
+const NEWEST_IMPORTED_FIRST: &str = "b.imported_at DESC, b.id DESC";
+
 /// Main database handle
 pub struct Database {
     conn: Connection,
+    /// Whether the optional `bookmarks_trigram` content index exists
+    content_trigram: bool,
+    /// Lowercased words dropped by `search_with_stopword_filter`
+    stopwords: Vec<String>,
+    /// Replaces the FTS5 index for `search` when set
+    search_backend: Option<Box<dyn SearchBackend>>,
+    /// See `DatabaseOptions::skip_content_duplicates`
+    skip_content_duplicates: bool,
+    /// In-memory index for `search_instant`, once `enable_instant_search` built it
+    instant_index: RefCell<Option<SearchIndex>>,
+    /// Last `get_stats` result and the `PRAGMA data_version` it was read at
+    stats_cache: RefCell<Option<(i64, BookmarkStats)>>,
+    /// Opened with `open_read_only`: write methods fail with `Error::ReadOnly`
+    read_only: bool,
 }
 
 impl Database {
@@ -24,70 +104,259 @@ impl Database {
         Self::open(&path)
     }
 
-    /// Get the default database path
+    /// Open `path` if given, otherwise the default location
+    pub fn open_default_or(path: Option<&Path>) -> Result<Self> {
+        Self::open(&Self::resolve_path(path))
+    }
+
+    /// Get the default database path: `$ETEREA_DB_PATH` when set, otherwise
+    /// `eterea/bookmarks.db` in the platform's local data directory
     pub fn default_path() -> PathBuf {
-        dirs::data_local_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("eterea")
-            .join("bookmarks.db")
+        resolve_db_path(None, std::env::var_os(DB_PATH_ENV))
+    }
+
+    /// Where to open the database: `explicit` (e.g. a `--db` flag) wins over
+    /// `$ETEREA_DB_PATH`, which wins over the platform default
+    pub fn resolve_path(explicit: Option<&Path>) -> PathBuf {
+        resolve_db_path(explicit, std::env::var_os(DB_PATH_ENV))
     }
 
     /// Open or create database at the specified path
     pub fn open(path: &Path) -> Result<Self> {
+        Self::open_with_options(path, DatabaseOptions::default())
+    }
+
+    /// Open or create database at the specified path with explicit options
+    pub fn open_with_options(path: &Path, options: DatabaseOptions) -> Result<Self> {
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
         info!("Opening database at: {}", path.display());
-        let conn = Connection::open(path)?;
+        let open = || -> Result<Self> {
+            let conn = Connection::open(path)?;
+            let mut db = Self {
+                conn,
+                content_trigram: false,
+                stopwords: Vec::new(),
+                search_backend: None,
+                skip_content_duplicates: false,
+                instant_index: RefCell::default(),
+                stats_cache: RefCell::default(),
+                read_only: false,
+            };
+            db.initialize(&options)?;
+            Ok(db)
+        };
 
-        let db = Self { conn };
-        db.initialize()?;
+        open().map_err(|e| classify_open_error(path, e))
+    }
 
-        Ok(db)
+    /// Open an existing database for reading only, e.g. to query it from the
+    /// CLI while the app has it open. Nothing is created or migrated, and
+    /// every write method fails with `Error::ReadOnly`.
+    pub fn open_read_only(path: &Path) -> Result<Self> {
+        info!("Opening database read-only at: {}", path.display());
+        let open = || -> Result<Self> {
+            let conn = Connection::open_with_flags(
+                path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY
+                    | OpenFlags::SQLITE_OPEN_URI
+                    | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )?;
+            let mut db = Self {
+                conn,
+                content_trigram: false,
+                stopwords: Vec::new(),
+                search_backend: None,
+                skip_content_duplicates: false,
+                instant_index: RefCell::default(),
+                stats_cache: RefCell::default(),
+                read_only: true,
+            };
+            db.initialize_read_only()?;
+            Ok(db)
+        };
+
+        open().map_err(|e| classify_open_error(path, e))
+    }
+
+    /// Whether the database was opened with `open_read_only`
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
     }
 
     /// Open an in-memory database (for testing)
     pub fn open_memory() -> Result<Self> {
+        Self::open_memory_with_options(DatabaseOptions::default())
+    }
+
+    /// Open an in-memory database with explicit options (for testing)
+    pub fn open_memory_with_options(options: DatabaseOptions) -> Result<Self> {
         let conn = Connection::open_in_memory()?;
-        let db = Self { conn };
-        db.initialize()?;
+        let mut db = Self {
+            conn,
+            content_trigram: false,
+            stopwords: Vec::new(),
+            search_backend: None,
+            skip_content_duplicates: false,
+            instant_index: RefCell::default(),
+            stats_cache: RefCell::default(),
+            read_only: false,
+        };
+        db.initialize(&options)?;
         Ok(db)
     }
 
+    /// Apply the tunable pragmas from `options`, then the fixed `PRAGMAS`.
+    /// Fails if a file database can't use the requested journal mode, e.g.
+    /// WAL on a read-only or network filesystem.
+    fn apply_pragmas(&self, options: &DatabaseOptions) -> Result<()> {
+        options.validate()?;
+        let requested = options.journal_mode.as_str();
+        let mode: String =
+            self.conn
+                .query_row(&format!("PRAGMA journal_mode = {requested}"), [], |row| {
+                    row.get(0)
+                })?;
+        // In-memory databases always report "memory"
+        let file_backed = self.conn.path().is_some_and(|path| !path.is_empty());
+        if file_backed && !mode.eq_ignore_ascii_case(requested) {
+            return Err(Error::InvalidOptions(format!(
+                "journal_mode {requested} is unavailable for this database (SQLite kept {mode}); \
+                 WAL needs a local, writable filesystem, so open it with JournalMode::Delete"
+            )));
+        }
+
+        self.conn.execute_batch(&format!(
+            "PRAGMA synchronous = {}; PRAGMA cache_size = {}; PRAGMA mmap_size = {};",
+            options.synchronous.as_str(),
+            options.cache_size,
+            options.mmap_size
+        ))?;
+        self.conn.execute_batch(PRAGMAS)?;
+        Ok(())
+    }
+
     /// Initialize database schema
-    fn initialize(&self) -> Result<()> {
+    fn initialize(&mut self, options: &DatabaseOptions) -> Result<()> {
         // Set performance pragmas
-        self.conn.execute_batch(PRAGMAS)?;
+        self.conn.busy_timeout(options.busy_timeout)?;
+        self.apply_pragmas(options)?;
+        self.register_functions()?;
 
-        // Create schema
+        // Create schema; an existing FTS table keeps the tokenizer it was built with
+        self.conn
+            .execute_batch(&fts_table_sql(options.tokenizer.fts5_spec()))?;
         self.conn.execute_batch(SCHEMA)?;
+        let tokenizer = self.tokenizer()?;
+        if tokenizer != options.tokenizer {
+            info!(
+                "Full-text index uses the {:?} tokenizer (requested {:?}); call reconfigure_tokenizer to rebuild it",
+                tokenizer, options.tokenizer
+            );
+        }
 
         self.ensure_is_favorite_column()?;
+        self.ensure_is_archived_column()?;
         self.ensure_has_media_column()?;
+        self.ensure_authors_table()?;
+        self.ensure_lowercase_author_handles()?;
+        self.ensure_links_table()?;
+        self.ensure_link_preview_icon_columns()?;
+        self.ensure_annotations_table()?;
+        self.ensure_content_hash_column()?;
+        self.ensure_deleted_at_column()?;
+        self.ensure_date_estimated_column()?;
+        self.ensure_tweet_id_column()?;
+        self.ensure_content_original_len_column()?;
+        self.ensure_media_local_path_column()?;
+        self.ensure_last_seen_at_column()?;
+        self.ensure_sync_changes_log()?;
+        self.content_trigram = self.ensure_content_trigram_index(options.content_trigram_index)?;
+        self.skip_content_duplicates = options.skip_content_duplicates;
+        self.stopwords = stopword_list(options);
 
         debug!("Database initialized");
         Ok(())
     }
 
-    fn ensure_has_media_column(&self) -> Result<()> {
-        let mut stmt = self.conn.prepare("PRAGMA table_info(bookmarks)")?;
+    /// `initialize` for `open_read_only`: only settings that don't write, and
+    /// the schema is taken as it is
+    fn initialize_read_only(&mut self) -> Result<()> {
+        let options = DatabaseOptions::default();
+        self.conn.busy_timeout(options.busy_timeout)?;
+        self.conn.execute_batch(&format!(
+            "PRAGMA cache_size = {}; PRAGMA mmap_size = {};",
+            options.cache_size, options.mmap_size
+        ))?;
+        self.conn.execute_batch(PRAGMAS)?;
+        self.register_functions()?;
+        self.content_trigram = self.ensure_content_trigram_index(false)?;
+        self.stopwords = stopword_list(&options);
+        Ok(())
+    }
+
+    /// SQL functions the queries rely on
+    fn register_functions(&self) -> Result<()> {
+        self.conn.create_scalar_function(
+            "unaccent",
+            1,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |ctx| Ok(ctx.get::<Option<String>>(0)?.map(|text| unaccent(&text))),
+        )?;
+        Ok(())
+    }
+
+    fn ensure_writable(&self) -> Result<()> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+        Ok(())
+    }
+
+    /// Whether `table` has a column named `column`
+    fn has_column(&self, table: &str, column: &str) -> Result<bool> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({table})"))?;
         let mut rows = stmt.query([])?;
-        let mut has_column = false;
         while let Some(row) = rows.next()? {
             let name: String = row.get(1)?;
-            if name.eq_ignore_ascii_case("has_media") {
-                has_column = true;
-                break;
+            if name.eq_ignore_ascii_case(column) {
+                return Ok(true);
             }
         }
+        Ok(false)
+    }
+
+    /// Whether the table `name` exists
+    fn has_table(&self, name: &str) -> Result<bool> {
+        Ok(self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+            params![name],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Run `f` in a `BEGIN IMMEDIATE` transaction: committed if it succeeds,
+    /// rolled back if it (or the commit) fails
+    fn with_transaction<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.conn.execute("BEGIN IMMEDIATE", [])?;
+        let result = f().and_then(|value| {
+            self.conn.execute("COMMIT", [])?;
+            Ok(value)
+        });
+        if result.is_err() {
+            let _ = self.conn.execute("ROLLBACK", []);
+        }
+        result
+    }
 
-        if !has_column {
+    fn ensure_has_media_column(&self) -> Result<()> {
+        if !self.has_column("bookmarks", "has_media")? {
             // Wrap ALTER + backfill in a transaction so a crash between the two
             // doesn't leave every bookmark with has_media=0 permanently.
-            self.conn.execute("BEGIN IMMEDIATE", [])?;
-            let result = (|| -> Result<()> {
+            self.with_transaction(|| {
                 self.conn.execute(
                     "ALTER TABLE bookmarks ADD COLUMN has_media INTEGER DEFAULT 0",
                     [],
@@ -97,16 +366,7 @@ impl Database {
                     [],
                 )?;
                 Ok(())
-            })();
-            match result {
-                Ok(()) => {
-                    self.conn.execute("COMMIT", [])?;
-                }
-                Err(e) => {
-                    let _ = self.conn.execute("ROLLBACK", []);
-                    return Err(e);
-                }
-            }
+            })?;
         }
 
         // These DDL statements depend on has_media existing — run after the migration above.
@@ -129,1030 +389,7078 @@ END;
         Ok(())
     }
 
-    fn ensure_is_favorite_column(&self) -> Result<()> {
-        let mut stmt = self.conn.prepare("PRAGMA table_info(bookmarks)")?;
-        let mut rows = stmt.query([])?;
-        let mut has_column = false;
-        while let Some(row) = rows.next()? {
-            let name: String = row.get(1)?;
-            if name.eq_ignore_ascii_case("is_favorite") {
-                has_column = true;
-                break;
-            }
+    fn ensure_authors_table(&self) -> Result<()> {
+        if self.has_table("authors")? {
+            return Ok(());
         }
 
-        if !has_column {
+        // Create + backfill atomically so a crash can't leave a half-populated table
+        // that the existence check above would then treat as migrated.
+        self.with_transaction(|| {
+            self.conn.execute_batch(
+                r#"
+CREATE TABLE authors (
+    handle TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    profile_url TEXT,
+    profile_image TEXT
+);
+"#,
+            )?;
+            // Bare columns next to MAX() come from the max row in SQLite, so each
+            // author takes the name/profile from their most recent bookmark.
             self.conn.execute(
-                "ALTER TABLE bookmarks ADD COLUMN is_favorite INTEGER DEFAULT 0",
+                r#"INSERT INTO authors (handle, name, profile_url, profile_image)
+                   SELECT author_handle, author_name, author_profile_url, author_profile_image
+                   FROM (
+                       SELECT author_handle, author_name, author_profile_url,
+                              author_profile_image, MAX(tweeted_at)
+                       FROM bookmarks
+                       GROUP BY author_handle
+                   )"#,
+                [],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Lowercase handles stored before inserts normalized them. Authors whose
+    /// handles only differed in case collapse into one row. Inserts keep
+    /// handles lowercase since, so this scans the library only once.
+    fn ensure_lowercase_author_handles(&self) -> Result<()> {
+        if self.get_metadata(LOWERCASE_HANDLES_METADATA_KEY)?.is_some() {
+            return Ok(());
+        }
+
+        self.with_transaction(|| {
+            let has_mixed_case: bool = self.conn.query_row(
+                r#"SELECT EXISTS (SELECT 1 FROM authors WHERE handle != lower(handle))
+                       OR EXISTS (SELECT 1 FROM bookmarks WHERE author_handle != lower(author_handle))"#,
                 [],
+                |row| row.get(0),
+            )?;
+            self.set_metadata(LOWERCASE_HANDLES_METADATA_KEY, "1")?;
+            if !has_mixed_case {
+                return Ok(());
+            }
+
+            info!("Lowercasing stored author handles");
+            self.conn.execute_batch(
+                r#"
+INSERT OR IGNORE INTO authors (handle, name, profile_url, profile_image)
+    SELECT lower(handle), name, profile_url, profile_image
+    FROM authors WHERE handle != lower(handle);
+DELETE FROM authors WHERE handle != lower(handle);
+UPDATE bookmarks SET author_handle = lower(author_handle)
+    WHERE author_handle != lower(author_handle);
+UPDATE bookmarks_fts_content SET author_handle = lower(author_handle)
+    WHERE author_handle != lower(author_handle);
+"#,
+            )?;
+            Ok(())
+        })
+    }
+
+    fn ensure_annotations_table(&self) -> Result<()> {
+        if self.has_table("annotations")? {
+            return Ok(());
+        }
+
+        // Existing `comments` become each bookmark's first annotation
+        self.with_transaction(|| {
+            self.conn.execute_batch(
+                r#"
+CREATE TABLE annotations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    bookmark_id TEXT NOT NULL,
+    body TEXT NOT NULL,
+    created_at INTEGER NOT NULL,  -- Unix timestamp
+    FOREIGN KEY (bookmark_id) REFERENCES bookmarks(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_annotations_bookmark ON annotations(bookmark_id, created_at);
+
+INSERT INTO annotations (bookmark_id, body, created_at)
+    SELECT id, comments, imported_at FROM bookmarks
+    WHERE comments IS NOT NULL AND trim(comments) != '';
+"#,
             )?;
+            let ids = self
+                .conn
+                .prepare("SELECT DISTINCT bookmark_id FROM annotations")?
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for id in ids {
+                self.refresh_fts_notes(&id)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Change log behind `export_json_since`. Bookmarks, trash and deletions
+    /// already stored are logged once, oldest first, so a first export sends
+    /// them all; triggers log every later change.
+    fn ensure_sync_changes_log(&self) -> Result<()> {
+        if !self.has_table("sync_changes")? {
+            self.with_transaction(|| {
+                self.conn.execute_batch(
+                    r#"
+CREATE TABLE sync_changes (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    bookmark_id TEXT NOT NULL,
+    deleted INTEGER NOT NULL,  -- 1 when trashed or deleted for good
+    changed_at INTEGER NOT NULL  -- Unix timestamp
+);
+CREATE INDEX idx_sync_changes_bookmark ON sync_changes(bookmark_id, seq);
+
+INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    SELECT id, 0, imported_at FROM bookmarks ORDER BY imported_at, rowid;
+INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    SELECT bookmark_id, 1, deleted_at FROM (
+        SELECT id AS bookmark_id, deleted_at FROM bookmarks WHERE deleted_at IS NOT NULL
+        UNION ALL
+        SELECT bookmark_id, deleted_at FROM bookmark_deletions
+    )
+    ORDER BY deleted_at, bookmark_id;
+"#,
+                )?;
+                Ok(())
+            })?;
         }
 
+        self.conn.execute_batch(SYNC_CHANGES_TRIGGERS)?;
         Ok(())
     }
 
-    /// Insert multiple bookmarks in a transaction
-    pub fn insert_bookmarks(&self, bookmarks: &[Bookmark]) -> Result<usize> {
-        let mut count = 0;
+    fn ensure_links_table(&self) -> Result<()> {
+        if self.has_table("links")? {
+            return self.ensure_link_expansion_columns();
+        }
 
-        // Use a transaction for batch insert
-        let conn = &self.conn;
-        conn.execute("BEGIN IMMEDIATE", [])?;
+        // URLs are extracted in Rust, so backfill row by row in the same transaction
+        // that creates the table.
+        self.with_transaction(|| {
+            self.conn.execute_batch(
+                r#"
+CREATE TABLE links (
+    bookmark_id TEXT NOT NULL,
+    url TEXT NOT NULL,          -- As written in the tweet (e.g. a t.co shortener)
+    domain TEXT,                -- models::url_domain of url; NULL if it doesn't parse
+    position INTEGER NOT NULL,  -- Order of appearance within the bookmark
+    expanded_url TEXT,          -- Redirect target of a shortened url; NULL until expanded
+    expanded_domain TEXT,       -- models::url_domain of expanded_url
+    PRIMARY KEY (bookmark_id, url),
+    FOREIGN KEY (bookmark_id) REFERENCES bookmarks(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_links_domain ON links(domain);
+CREATE INDEX idx_links_expanded_domain ON links(expanded_domain);
+CREATE INDEX idx_links_url ON links(url);
+"#,
+            )?;
+            let mut select = self
+                .conn
+                .prepare("SELECT id, content, note_text FROM bookmarks")?;
+            let rows = select
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for (id, content, note_text) in rows {
+                self.insert_links(&id, &extract_urls(&content, note_text.as_deref()))?;
+            }
+            Ok(())
+        })
+    }
 
-        for bookmark in bookmarks {
-            match self.insert_bookmark_internal(bookmark) {
-                Ok(_) => count += 1,
-                Err(Error::Database(rusqlite::Error::SqliteFailure(err, _)))
-                    if err.code == rusqlite::ErrorCode::ConstraintViolation =>
-                {
-                    // Skip duplicates (same tweet_url)
-                    debug!("Skipping duplicate bookmark: {}", bookmark.tweet_url);
-                }
-                Err(e) => {
-                    conn.execute("ROLLBACK", [])?;
-                    return Err(e);
-                }
+    fn ensure_link_expansion_columns(&self) -> Result<()> {
+        self.ensure_text_columns("links", &["expanded_url", "expanded_domain"])?;
+        self.conn.execute_batch(
+            r#"
+CREATE INDEX IF NOT EXISTS idx_links_expanded_domain ON links(expanded_domain);
+CREATE INDEX IF NOT EXISTS idx_links_url ON links(url);
+"#,
+        )?;
+
+        Ok(())
+    }
+
+    fn ensure_link_preview_icon_columns(&self) -> Result<()> {
+        self.ensure_text_columns("link_previews", &["favicon_url", "canonical_url"])
+    }
+
+    /// Add whichever of the nullable TEXT `columns` `table` lacks, together.
+    /// Each is checked on its own, so a library that has only some of them
+    /// (say, from an older build that crashed between ALTERs) gets the rest.
+    fn ensure_text_columns(&self, table: &str, columns: &[&str]) -> Result<()> {
+        let mut missing = Vec::new();
+        for column in columns {
+            if !self.has_column(table, column)? {
+                missing.push(*column);
+            }
+        }
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        self.with_transaction(|| {
+            for column in missing {
+                self.conn
+                    .execute(&format!("ALTER TABLE {table} ADD COLUMN {column} TEXT"), [])?;
             }
+            Ok(())
+        })
+    }
+
+    fn ensure_content_hash_column(&self) -> Result<()> {
+        if !self.has_column("bookmarks", "content_hash")? {
+            // The hash is computed in Rust, so backfill row by row inside one transaction.
+            self.with_transaction(|| {
+                self.conn
+                    .execute("ALTER TABLE bookmarks ADD COLUMN content_hash TEXT", [])?;
+                let mut select = self.conn.prepare("SELECT id, content FROM bookmarks")?;
+                let rows = select
+                    .query_map([], |row| {
+                        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                let mut update = self
+                    .conn
+                    .prepare("UPDATE bookmarks SET content_hash = ?2 WHERE id = ?1")?;
+                for (id, content) in rows {
+                    update.execute(params![id, crate::models::content_hash(&content)])?;
+                }
+                Ok(())
+            })?;
         }
 
-        self.refresh_stats_snapshot()?;
-        conn.execute("COMMIT", [])?;
-        Ok(count)
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_bookmarks_content_hash ON bookmarks(content_hash)",
+            [],
+        )?;
+
+        Ok(())
     }
 
-    fn insert_bookmark_internal(&self, bookmark: &Bookmark) -> Result<()> {
-        // Insert main bookmark
-        let has_media_flag = if bookmark.media.is_empty() {
-            0i32
-        } else {
-            1i32
-        };
+    fn ensure_deleted_at_column(&self) -> Result<()> {
+        if !self.has_column("bookmarks", "deleted_at")? {
+            self.conn
+                .execute("ALTER TABLE bookmarks ADD COLUMN deleted_at INTEGER", [])?;
+        }
+
         self.conn.execute(
-            r#"INSERT INTO bookmarks
-               (id, tweet_url, content, note_text, tweeted_at, imported_at,
-                author_handle, author_name, author_profile_url, author_profile_image, comments, is_favorite, has_media)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)"#,
-            params![
-                bookmark.id,
-                bookmark.tweet_url,
-                bookmark.content,
-                bookmark.note_text,
-                bookmark.tweeted_at.timestamp(),
-                bookmark.imported_at.timestamp(),
-                bookmark.author_handle,
-                bookmark.author_name,
-                bookmark.author_profile_url,
-                bookmark.author_profile_image,
-                bookmark.comments,
-                bookmark.is_favorite as i32,
-                has_media_flag,
-            ],
+            "CREATE INDEX IF NOT EXISTS idx_bookmarks_deleted_at ON bookmarks(deleted_at DESC) WHERE deleted_at IS NOT NULL",
+            [],
         )?;
 
-        // Insert tags
-        for tag in &bookmark.tags {
-            // Insert tag if not exists
+        Ok(())
+    }
+
+    fn ensure_date_estimated_column(&self) -> Result<()> {
+        if !self.has_column("bookmarks", "date_estimated")? {
             self.conn.execute(
-                "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
-                params![tag],
+                "ALTER TABLE bookmarks ADD COLUMN date_estimated INTEGER DEFAULT 0",
+                [],
             )?;
+        }
 
-            // Get tag ID
-            let tag_id: i64 = self.conn.query_row(
-                "SELECT id FROM tags WHERE name = ?1",
-                params![tag],
-                |row| row.get(0),
-            )?;
+        Ok(())
+    }
 
-            // Link bookmark to tag
+    fn ensure_content_original_len_column(&self) -> Result<()> {
+        if !self.has_column("bookmarks", "content_original_len")? {
             self.conn.execute(
-                "INSERT INTO bookmark_tags (bookmark_id, tag_id) VALUES (?1, ?2)",
-                params![bookmark.id, tag_id],
+                "ALTER TABLE bookmarks ADD COLUMN content_original_len INTEGER",
+                [],
             )?;
         }
 
-        // Insert media
-        for media in &bookmark.media {
-            let media_type = match media.media_type {
-                MediaType::Image => "image",
-                MediaType::Video => "video",
-                MediaType::Gif => "gif",
-                MediaType::Unknown => "unknown",
-            };
+        Ok(())
+    }
 
-            self.conn.execute(
-                "INSERT INTO media (bookmark_id, url, media_type) VALUES (?1, ?2, ?3)",
-                params![bookmark.id, media.url, media_type],
-            )?;
+    fn ensure_last_seen_at_column(&self) -> Result<()> {
+        if !self.has_column("bookmarks", "last_seen_at")? {
+            // Every stored bookmark was last seen when it was imported
+            self.with_transaction(|| {
+                self.conn.execute_batch(
+                    r#"ALTER TABLE bookmarks ADD COLUMN last_seen_at INTEGER;
+                       UPDATE bookmarks SET last_seen_at = imported_at;"#,
+                )?;
+                Ok(())
+            })?;
         }
 
-        // Insert FTS content
-        let tags_text = bookmark.tags.join(" ");
         self.conn.execute(
-            r#"INSERT INTO bookmarks_fts_content 
-               (bookmark_id, content, note_text, author_handle, author_name, tags_text)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
-            params![
-                bookmark.id,
-                bookmark.content,
-                bookmark.note_text,
-                bookmark.author_handle,
-                bookmark.author_name,
-                tags_text,
-            ],
+            "CREATE INDEX IF NOT EXISTS idx_bookmarks_last_seen_at ON bookmarks(last_seen_at)",
+            [],
         )?;
 
         Ok(())
     }
 
-    /// Full-text search across bookmarks
-    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<Bookmark>> {
-        let query = Self::prepare_fts_query(query);
+    fn ensure_media_local_path_column(&self) -> Result<()> {
+        if !self.has_column("media", "local_path")? {
+            self.conn
+                .execute("ALTER TABLE media ADD COLUMN local_path TEXT", [])?;
+        }
 
-        let mut stmt = self.conn.prepare(
-            r#"SELECT b.id, b.tweet_url, b.content, b.note_text, b.tweeted_at, b.imported_at,
-                      b.author_handle, b.author_name, b.author_profile_url, b.author_profile_image,
-                      b.comments, b.is_favorite
-               FROM bookmarks b
-               JOIN bookmarks_fts_content fc ON fc.bookmark_id = b.id
-               JOIN bookmarks_fts fts ON fts.rowid = fc.rowid
-               WHERE bookmarks_fts MATCH ?1
-               ORDER BY bm25(bookmarks_fts), b.tweeted_at DESC, b.id DESC
-               LIMIT ?2"#,
-        )?;
+        Ok(())
+    }
 
-        let mut bookmarks: Vec<Bookmark> = stmt
-            .query_map(params![query, limit as i64], |row| {
-                self.row_to_bookmark(row)
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+    fn ensure_tweet_id_column(&self) -> Result<()> {
+        if !self.has_column("bookmarks", "tweet_id")? {
+            // Ids are parsed from the URL in Rust, so backfill row by row inside one transaction.
+            self.with_transaction(|| {
+                self.conn
+                    .execute("ALTER TABLE bookmarks ADD COLUMN tweet_id TEXT", [])?;
+                let mut select = self.conn.prepare("SELECT id, tweet_url FROM bookmarks")?;
+                let rows = select
+                    .query_map([], |row| {
+                        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                let mut update = self
+                    .conn
+                    .prepare("UPDATE bookmarks SET tweet_id = ?2 WHERE id = ?1")?;
+                for (id, tweet_url) in rows {
+                    update.execute(params![id, tweet_id_from_url(&tweet_url)])?;
+                }
+                Ok(())
+            })?;
+        }
 
-        self.hydrate_bookmarks(&mut bookmarks)?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_bookmarks_tweet_id ON bookmarks(tweet_id)",
+            [],
+        )?;
 
-        Ok(bookmarks)
+        Ok(())
     }
 
-    /// Prepare FTS5 query (add prefix matching for better UX)
-    fn prepare_fts_query(query: &str) -> String {
-        let terms: Vec<String> = query
-            .split_whitespace()
-            .map(|term| {
-                // Escape special FTS5 characters
-                let escaped = term.replace('"', "\"\"");
-                format!("\"{}\"*", escaped)
-            })
-            .collect();
-
-        terms.join(" ")
-    }
+    fn ensure_is_archived_column(&self) -> Result<()> {
+        if !self.has_column("bookmarks", "is_archived")? {
+            self.conn.execute(
+                "ALTER TABLE bookmarks ADD COLUMN is_archived INTEGER DEFAULT 0",
+                [],
+            )?;
+        }
 
-    /// Get all bookmarks with pagination
-    pub fn get_bookmarks(&self, offset: usize, limit: usize) -> Result<Vec<Bookmark>> {
-        let overall_started = std::time::Instant::now();
-        let mut stmt = self.conn.prepare(
-            r#"SELECT id, tweet_url, content, note_text, tweeted_at, imported_at,
-                      author_handle, author_name, author_profile_url, author_profile_image,
-                      comments, is_favorite
-               FROM bookmarks
-               ORDER BY tweeted_at DESC, id DESC
-               LIMIT ?1 OFFSET ?2"#,
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_bookmarks_archived_date ON bookmarks(is_archived, tweeted_at DESC) WHERE is_archived = 1",
+            [],
         )?;
 
-        let query_started = std::time::Instant::now();
-        let mut bookmarks: Vec<Bookmark> = stmt
-            .query_map(params![limit as i64, offset as i64], |row| {
-                self.row_to_bookmark(row)
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
-        let query_elapsed = query_started.elapsed();
+        Ok(())
+    }
 
-        let hydrate_started = std::time::Instant::now();
-        self.hydrate_bookmarks(&mut bookmarks)?;
-        eprintln!(
-            "[eterea][db][get_bookmarks] offset={} limit={} rows={} query={}ms hydrate={}ms total={}ms",
-            offset,
-            limit,
-            bookmarks.len(),
-            query_elapsed.as_millis(),
-            hydrate_started.elapsed().as_millis(),
-            overall_started.elapsed().as_millis()
-        );
+    /// Create (and backfill) the trigram content index when requested.
+    /// Returns whether the index exists.
+    fn ensure_content_trigram_index(&self, requested: bool) -> Result<bool> {
+        let exists = self.has_table("bookmarks_trigram")?;
+        if exists || !requested {
+            return Ok(exists);
+        }
 
-        Ok(bookmarks)
+        self.with_transaction(|| {
+            self.conn.execute_batch(CONTENT_TRIGRAM_SCHEMA)?;
+            self.conn.execute(
+                "INSERT INTO bookmarks_trigram(bookmarks_trigram) VALUES ('rebuild')",
+                [],
+            )?;
+            Ok(())
+        })?;
+
+        Ok(true)
     }
 
-    pub fn count_bookmarks(&self) -> Result<i64> {
-        self.conn
-            .query_row("SELECT COUNT(*) FROM bookmarks", [], |row| row.get(0))
-            .map_err(Into::into)
+    fn ensure_is_favorite_column(&self) -> Result<()> {
+        if !self.has_column("bookmarks", "is_favorite")? {
+            self.conn.execute(
+                "ALTER TABLE bookmarks ADD COLUMN is_favorite INTEGER DEFAULT 0",
+                [],
+            )?;
+        }
+
+        Ok(())
     }
 
-    /// Get bookmarks by tag
-    pub fn get_bookmarks_by_tag(
-        &self,
-        tag: &str,
-        offset: usize,
-        limit: usize,
-    ) -> Result<Vec<Bookmark>> {
-        let mut stmt = self.conn.prepare(
-            r#"SELECT b.id, b.tweet_url, b.content, b.note_text, b.tweeted_at, b.imported_at,
-                      b.author_handle, b.author_name, b.author_profile_url, b.author_profile_image,
-                      b.comments, b.is_favorite
-               FROM bookmarks b
-               JOIN bookmark_tags bt ON bt.bookmark_id = b.id
-               JOIN tags t ON t.id = bt.tag_id
-               WHERE t.name = ?1
-               ORDER BY b.tweeted_at DESC, b.id DESC
-               LIMIT ?2 OFFSET ?3"#,
+    /// Tokenizer the full-text index was built with
+    pub fn tokenizer(&self) -> Result<FtsTokenizer> {
+        let sql: String = self.conn.query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'bookmarks_fts'",
+            [],
+            |row| row.get(0),
         )?;
+        FtsTokenizer::from_table_sql(&sql).ok_or_else(|| {
+            Error::Other(format!("Unrecognized full-text tokenizer in schema: {sql}"))
+        })
+    }
 
-        let mut bookmarks: Vec<Bookmark> = stmt
-            .query_map(params![tag, limit as i64, offset as i64], |row| {
-                self.row_to_bookmark(row)
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+    /// Rebuild the full-text index with a different tokenizer.
+    ///
+    /// FTS5 can't change the tokenizer of an existing table, so the index is
+    /// dropped, recreated and repopulated from `bookmarks_fts_content`. This
+    /// rereads every bookmark and can take a while on large libraries.
+    pub fn reconfigure_tokenizer(&self, tokenizer: FtsTokenizer) -> Result<()> {
+        self.ensure_writable()?;
+        if self.tokenizer()? == tokenizer {
+            return Ok(());
+        }
 
-        self.hydrate_bookmarks(&mut bookmarks)?;
+        let started = std::time::Instant::now();
+        self.with_transaction(|| {
+            self.conn.execute("DROP TABLE bookmarks_fts", [])?;
+            self.conn
+                .execute_batch(&fts_table_sql(tokenizer.fts5_spec()))?;
+            self.conn.execute(
+                "INSERT INTO bookmarks_fts(bookmarks_fts) VALUES ('rebuild')",
+                [],
+            )?;
+            Ok(())
+        })?;
 
-        Ok(bookmarks)
+        info!(
+            "Rebuilt full-text index with {:?} tokenizer in {}ms",
+            tokenizer,
+            started.elapsed().as_millis()
+        );
+        Ok(())
     }
 
-    /// Get bookmarks by author
-    pub fn get_bookmarks_by_author(
-        &self,
-        handle: &str,
-        offset: usize,
-        limit: usize,
-    ) -> Result<Vec<Bookmark>> {
-        let mut stmt = self.conn.prepare(
-            r#"SELECT id, tweet_url, content, note_text, tweeted_at, imported_at,
-                      author_handle, author_name, author_profile_url, author_profile_image,
-                      comments, is_favorite
-               FROM bookmarks
-               WHERE author_handle = ?1
-               ORDER BY tweeted_at DESC, id DESC
-               LIMIT ?2 OFFSET ?3"#,
-        )?;
+    /// Problems reported by `PRAGMA integrity_check`; empty when the file is sound
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("PRAGMA integrity_check")?;
+        let problems = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(problems
+            .into_iter()
+            .filter(|problem| problem != "ok")
+            .collect())
+    }
 
-        let mut bookmarks: Vec<Bookmark> = stmt
-            .query_map(params![handle, limit as i64, offset as i64], |row| {
-                self.row_to_bookmark(row)
+    /// Rows whose foreign key points at a missing parent (`PRAGMA
+    /// foreign_key_check`), e.g. tags linked to a deleted bookmark
+    pub fn foreign_key_check(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("PRAGMA foreign_key_check")?;
+        let problems = stmt
+            .query_map([], |row| {
+                let table: String = row.get(0)?;
+                let rowid: Option<i64> = row.get(1)?;
+                let parent: String = row.get(2)?;
+                Ok(match rowid {
+                    Some(rowid) => format!("{table} row {rowid} references a missing {parent} row"),
+                    None => format!("{table} references a missing {parent} row"),
+                })
             })?
-            .filter_map(|r| r.ok())
-            .collect();
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(problems)
+    }
 
-        self.hydrate_bookmarks(&mut bookmarks)?;
+    /// Check the full-text indexes against the bookmark text they were built
+    /// from; a problem here means searches can miss or misreport bookmarks
+    /// (fix with `reconfigure_tokenizer` or by rebuilding the index)
+    pub fn fts_integrity_check(&self) -> Result<Vec<String>> {
+        let mut tables = vec!["bookmarks_fts"];
+        if self.content_trigram {
+            tables.push("bookmarks_trigram");
+        }
 
-        Ok(bookmarks)
+        let mut problems = Vec::new();
+        for table in tables {
+            let check = self.conn.execute(
+                &format!("INSERT INTO {table}({table}, rank) VALUES ('integrity-check', 1)"),
+                [],
+            );
+            match check {
+                Ok(_) => {}
+                Err(rusqlite::Error::SqliteFailure(err, message))
+                    if err.code == rusqlite::ErrorCode::DatabaseCorrupt =>
+                {
+                    problems.push(format!(
+                        "{table}: {}",
+                        message.unwrap_or_else(|| "index does not match its content".into())
+                    ));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(problems)
     }
 
-    /// Get a single bookmark by ID
-    pub fn get_bookmark(&self, id: &str) -> Result<Option<Bookmark>> {
-        let mut stmt = self.conn.prepare(
-            r#"SELECT id, tweet_url, content, note_text, tweeted_at, imported_at,
-                      author_handle, author_name, author_profile_url, author_profile_image,
-                      comments, is_favorite
-               FROM bookmarks WHERE id = ?1"#,
-        )?;
+    /// Insert multiple bookmarks in a transaction, returning how many were new.
+    /// See `import` for a breakdown of the rest.
+    pub fn insert_bookmarks(&self, bookmarks: &[Bookmark]) -> Result<usize> {
+        Ok(self.import(bookmarks)?.inserted)
+    }
 
-        let result = stmt.query_row(params![id], |row| self.row_to_bookmark(row));
+    /// Insert already-parsed bookmarks in one transaction, reporting duplicates
+    /// (already stored) separately from bookmarks that failed to insert.
+    /// A failed bookmark is skipped without affecting the others.
+    pub fn import(&self, bookmarks: &[Bookmark]) -> Result<ImportReport> {
+        self.insert_batch(bookmarks, true)
+    }
 
-        match result {
-            Ok(mut bookmark) => {
-                bookmark.tags = self.load_bookmark_tags(&bookmark.id)?;
-                bookmark.media = self.load_bookmark_media(&bookmark.id)?;
-                Ok(Some(bookmark))
+    /// Insert a large stream of bookmarks, committing every `batch_size` rows.
+    ///
+    /// Every `checkpoint_every` batches a `PRAGMA wal_checkpoint(PASSIVE)` folds the
+    /// WAL back into the main file so it stays bounded on very large imports
+    /// (`0` disables the explicit checkpoints). The stats snapshot is invalidated once
+    /// at the end instead of after every batch.
+    pub fn insert_bookmarks_streaming<I>(
+        &self,
+        bookmarks: I,
+        batch_size: usize,
+        checkpoint_every: usize,
+    ) -> Result<usize>
+    where
+        I: IntoIterator<Item = Bookmark>,
+    {
+        self.insert_bookmarks_streaming_with_progress(
+            bookmarks,
+            batch_size,
+            checkpoint_every,
+            |_, _| {},
+        )
+    }
+
+    /// `insert_bookmarks_streaming`, calling `on_batch(processed, inserted)` after
+    /// each committed batch with running totals
+    pub fn insert_bookmarks_streaming_with_progress<I, F>(
+        &self,
+        bookmarks: I,
+        batch_size: usize,
+        checkpoint_every: usize,
+        on_batch: F,
+    ) -> Result<usize>
+    where
+        I: IntoIterator<Item = Bookmark>,
+        F: FnMut(usize, usize),
+    {
+        self.insert_bookmarks_streaming_cancellable(
+            bookmarks,
+            batch_size,
+            checkpoint_every,
+            &AtomicBool::new(false),
+            on_batch,
+        )
+    }
+
+    /// `insert_bookmarks_streaming_with_progress` that stops before the next batch
+    /// once `cancel` is set, returning how many bookmarks were inserted so far.
+    ///
+    /// Each batch is its own transaction, so batches committed before the
+    /// cancellation are kept rather than rolled back.
+    pub fn insert_bookmarks_streaming_cancellable<I, F>(
+        &self,
+        bookmarks: I,
+        batch_size: usize,
+        checkpoint_every: usize,
+        cancel: &AtomicBool,
+        mut on_batch: F,
+    ) -> Result<usize>
+    where
+        I: IntoIterator<Item = Bookmark>,
+        F: FnMut(usize, usize),
+    {
+        self.ensure_writable()?;
+        let batch_size = batch_size.max(1);
+        let mut processed = 0;
+        let mut inserted = 0;
+        let mut batches = 0;
+        let mut content_duplicates = 0;
+        let mut batch = Vec::with_capacity(batch_size);
+
+        let mut bookmarks = bookmarks.into_iter().peekable();
+        while bookmarks.peek().is_some() {
+            if cancel.load(Ordering::Relaxed) {
+                info!(
+                    "Import cancelled after {} bookmarks ({} inserted)",
+                    processed, inserted
+                );
+                break;
+            }
+            batch.extend(bookmarks.by_ref().take(batch_size));
+            let report = self.insert_batch(&batch, false)?;
+            inserted += report.inserted;
+            content_duplicates += report.skipped_content_duplicate;
+            processed += batch.len();
+            batch.clear();
+            batches += 1;
+            on_batch(processed, inserted);
+
+            if checkpoint_every > 0 && batches % checkpoint_every == 0 {
+                self.checkpoint_wal()?;
             }
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e.into()),
         }
-    }
 
-    /// Delete a bookmark
-    pub fn delete_bookmark(&self, id: &str) -> Result<bool> {
-        let count = self
-            .conn
-            .execute("DELETE FROM bookmarks WHERE id = ?1", params![id])?;
-        if count > 0 {
-            self.refresh_stats_snapshot()?;
+        if content_duplicates > 0 {
+            info!(
+                "Skipped {} bookmarks whose content is already stored under another URL",
+                content_duplicates
+            );
         }
-        Ok(count > 0)
+        if batches > 0 {
+            self.invalidate_stats()?;
+        }
+        Ok(inserted)
     }
 
-    /// Toggle favorite status for a bookmark
-    pub fn toggle_favorite(&self, id: &str) -> Result<bool> {
-        self.conn.execute(
-            "UPDATE bookmarks SET is_favorite = NOT is_favorite WHERE id = ?1",
-            params![id],
-        )?;
+    /// Run a passive WAL checkpoint (never blocks readers or writers)
+    pub fn checkpoint_wal(&self) -> Result<()> {
+        self.ensure_writable()?;
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(PASSIVE)", [], |_| Ok(()))?;
+        Ok(())
+    }
 
-        // Return the new favorite status
-        let is_favorite: bool = self.conn.query_row(
-            "SELECT is_favorite FROM bookmarks WHERE id = ?1",
-            params![id],
+    fn insert_batch(&self, bookmarks: &[Bookmark], refresh_stats: bool) -> Result<ImportReport> {
+        self.ensure_writable()?;
+        let mut report = ImportReport::default();
+        let mut inserted = Vec::new();
+
+        // Use a transaction for batch insert; each bookmark gets a savepoint so a
+        // failure part-way through one can't leave half its rows behind. Any
+        // other error, savepoint statements included, rolls back the batch.
+        let seen_at = chrono::Utc::now().timestamp();
+        let conn = &self.conn;
+        self.with_transaction(|| {
+            for bookmark in bookmarks {
+                if self.skip_content_duplicates {
+                    if let Some(id) = self.content_duplicate_of(bookmark)? {
+                        debug!("Skipping content duplicate: {}", bookmark.tweet_url);
+                        self.mark_seen(&id, seen_at)?;
+                        report.skipped_content_duplicate += 1;
+                        continue;
+                    }
+                }
+                conn.execute("SAVEPOINT import_bookmark", [])?;
+                match self.insert_bookmark_internal(bookmark) {
+                    Ok(_) => {
+                        conn.execute("RELEASE import_bookmark", [])?;
+                        report.inserted += 1;
+                        if self.search_backend.is_some() || self.instant_index.borrow().is_some() {
+                            inserted.push(bookmark.clone());
+                        }
+                    }
+                    Err(e) => {
+                        conn.execute_batch(
+                            "ROLLBACK TO import_bookmark; RELEASE import_bookmark;",
+                        )?;
+                        if is_duplicate_bookmark(&e) {
+                            // Same tweet_url (or id) is already stored
+                            debug!("Skipping duplicate bookmark: {}", bookmark.tweet_url);
+                            if let Some(id) = self.stored_bookmark_id(bookmark)? {
+                                self.mark_seen(&id, seen_at)?;
+                            }
+                            report.skipped_duplicate += 1;
+                        } else {
+                            warn!("Failed to import bookmark {}: {}", bookmark.tweet_url, e);
+                            report.failed += 1;
+                        }
+                    }
+                }
+            }
+
+            if refresh_stats {
+                self.invalidate_stats()?;
+            }
+            Ok(())
+        })?;
+
+        self.add_to_search_indexes(&inserted);
+        Ok(report)
+    }
+
+    /// The live bookmark under a different URL with the same content hash and
+    /// author as `bookmark`, if there is one
+    fn content_duplicate_of(&self, bookmark: &Bookmark) -> Result<Option<String>> {
+        let Some(hash) = bookmark.content_hash() else {
+            return Ok(None);
+        };
+        let found = self.conn.query_row(
+            r#"SELECT id FROM bookmarks
+               WHERE content_hash = ?1 AND author_handle = lower(?2) AND tweet_url != ?3
+                 AND deleted_at IS NULL
+               LIMIT 1"#,
+            params![hash, bookmark.author_handle, bookmark.tweet_url],
             |row| row.get(0),
-        )?;
+        );
+        match found {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
 
-        self.refresh_stats_snapshot()?;
-        Ok(is_favorite)
+    /// Id of the stored bookmark for the same tweet as `bookmark` (same
+    /// bookmark id, URL or status id), if there is one
+    fn stored_bookmark_id(&self, bookmark: &Bookmark) -> Result<Option<String>> {
+        let found = self.conn.query_row(
+            "SELECT id FROM bookmarks WHERE tweet_url = ?1 OR id = ?2 OR tweet_id = ?3",
+            params![
+                bookmark.tweet_url,
+                bookmark.id,
+                tweet_id_from_url(&bookmark.tweet_url)
+            ],
+            |row| row.get(0),
+        );
+        match found {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
     }
 
-    /// Set favorite status for a bookmark
-    pub fn set_favorite(&self, id: &str, favorite: bool) -> Result<()> {
+    /// Record that an import contained the stored bookmark `id` again
+    fn mark_seen(&self, id: &str, seen_at: i64) -> Result<()> {
         self.conn.execute(
-            "UPDATE bookmarks SET is_favorite = ?2 WHERE id = ?1",
-            params![id, favorite as i32],
+            "UPDATE bookmarks SET last_seen_at = ?2 WHERE id = ?1",
+            params![id, seen_at],
         )?;
-        self.refresh_stats_snapshot()?;
         Ok(())
     }
 
-    /// Get all favorite bookmarks
-    pub fn get_favorites(&self, offset: usize, limit: usize) -> Result<Vec<Bookmark>> {
-        let mut stmt = self.conn.prepare(
-            r#"SELECT id, tweet_url, content, note_text, tweeted_at, imported_at,
-                      author_handle, author_name, author_profile_url, author_profile_image,
-                      comments, is_favorite
-               FROM bookmarks
-               WHERE is_favorite = 1
-               ORDER BY tweeted_at DESC, id DESC
-               LIMIT ?1 OFFSET ?2"#,
-        )?;
-
-        let mut bookmarks: Vec<Bookmark> = stmt
-            .query_map(params![limit as i64, offset as i64], |row| {
-                self.row_to_bookmark(row)
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        self.hydrate_bookmarks(&mut bookmarks)?;
+    /// Import bookmarks, merging ones already stored (same bookmark id, or the
+    /// same tweet: URL or status id) instead of skipping them.
+    ///
+    /// A merge follows `Bookmark::merge_from`: empty fields (note, comments,
+    /// author profile image/URL) are filled and tags and media are added, but
+    /// nothing stored is overwritten or removed. Favorite and archive state are
+    /// never touched.
+    pub fn merge_import(&self, bookmarks: &[Bookmark]) -> Result<MergeReport> {
+        self.ensure_writable()?;
+        let mut report = MergeReport::default();
+        let mut inserted = Vec::new();
+        let mut merged = Vec::new();
+
+        let seen_at = chrono::Utc::now().timestamp();
+        let conn = &self.conn;
+        self.with_transaction(|| {
+            for bookmark in bookmarks {
+                conn.execute("SAVEPOINT merge_bookmark", [])?;
+                let result = match self.stored_bookmark_id(bookmark) {
+                    Ok(Some(id)) => self
+                        .mark_seen(&id, seen_at)
+                        .and_then(|()| self.merge_bookmark_internal(&id, bookmark))
+                        .map(|changed| Some((id, changed))),
+                    Ok(None) => self.insert_bookmark_internal(bookmark).map(|_| None),
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(outcome) => {
+                        conn.execute("RELEASE merge_bookmark", [])?;
+                        match outcome {
+                            None => {
+                                report.inserted += 1;
+                                inserted.push(bookmark.clone());
+                            }
+                            Some((id, true)) => {
+                                report.merged += 1;
+                                merged.push(id);
+                            }
+                            Some((_, false)) => report.unchanged += 1,
+                        }
+                    }
+                    Err(e) => {
+                        conn.execute_batch("ROLLBACK TO merge_bookmark; RELEASE merge_bookmark;")?;
+                        warn!("Failed to merge bookmark {}: {}", bookmark.tweet_url, e);
+                        report.failed += 1;
+                    }
+                }
+            }
 
-        Ok(bookmarks)
+            self.invalidate_stats()?;
+            Ok(())
+        })?;
+        self.add_to_search_indexes(&inserted);
+        self.refresh_search_indexes(&merged.iter().map(String::as_str).collect::<Vec<_>>());
+        Ok(report)
     }
 
-    /// Get bookmarks within a date range
-    pub fn get_bookmarks_by_date_range(
-        &self,
-        from: Option<chrono::DateTime<chrono::Utc>>,
-        to: Option<chrono::DateTime<chrono::Utc>>,
-        offset: usize,
-        limit: usize,
-    ) -> Result<Vec<Bookmark>> {
-        let from_ts = from.map(|d| d.timestamp()).unwrap_or(0);
-        let to_ts = to.map(|d| d.timestamp()).unwrap_or(i64::MAX);
+    /// Merge `bookmark` into the stored bookmark `id` (`Bookmark::merge_from`)
+    /// and write back what changed; returns whether anything did
+    fn merge_bookmark_internal(&self, id: &str, bookmark: &Bookmark) -> Result<bool> {
+        let Some(stored) = self.get_bookmark(id)? else {
+            return Ok(false);
+        };
+        let mut merged = stored.clone();
+        if !merged.merge_from(bookmark) {
+            return Ok(false);
+        }
 
-        let mut stmt = self.conn.prepare(
-            r#"SELECT id, tweet_url, content, note_text, tweeted_at, imported_at,
-                      author_handle, author_name, author_profile_url, author_profile_image,
-                      comments, is_favorite
-               FROM bookmarks
-               WHERE tweeted_at >= ?1 AND tweeted_at <= ?2
-               ORDER BY tweeted_at DESC, id DESC
-               LIMIT ?3 OFFSET ?4"#,
-        )?;
+        if merged.note_text != stored.note_text {
+            self.conn.execute(
+                "UPDATE bookmarks SET note_text = ?2 WHERE id = ?1",
+                params![id, merged.note_text],
+            )?;
+        }
+        if merged.comments != stored.comments {
+            // Imported comments start the annotation history
+            self.conn.execute(
+                "UPDATE bookmarks SET comments = ?2 WHERE id = ?1",
+                params![id, merged.comments],
+            )?;
+            self.conn.execute(
+                "INSERT INTO annotations (bookmark_id, body, created_at) VALUES (?1, ?2, ?3)",
+                params![id, merged.comments, bookmark.imported_at.timestamp()],
+            )?;
+        }
+        if merged.note_text != stored.note_text || merged.comments != stored.comments {
+            self.refresh_fts_notes(id)?;
+        }
 
-        let mut bookmarks: Vec<Bookmark> = stmt
-            .query_map(
-                params![from_ts, to_ts, limit as i64, offset as i64],
-                |row| self.row_to_bookmark(row),
-            )?
-            .filter_map(|r| r.ok())
+        if merged.author_profile_image != stored.author_profile_image
+            || merged.author_profile_url != stored.author_profile_url
+        {
+            self.conn.execute(
+                "UPDATE bookmarks SET author_profile_image = ?2, author_profile_url = ?3 WHERE id = ?1",
+                params![id, merged.author_profile_image, merged.author_profile_url],
+            )?;
+            self.conn.execute(
+                r#"UPDATE authors SET
+                       profile_image = COALESCE(profile_image, ?2),
+                       profile_url = COALESCE(profile_url, ?3)
+                   WHERE handle = ?1"#,
+                params![
+                    stored.author_handle,
+                    merged.author_profile_image,
+                    merged.author_profile_url
+                ],
+            )?;
+        }
+
+        let new_tags: Vec<&String> = merged
+            .tags
+            .iter()
+            .filter(|tag| !stored.tags.contains(tag))
             .collect();
+        for tag in &new_tags {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+                params![tag],
+            )?;
+            self.conn.execute(
+                r#"INSERT OR IGNORE INTO bookmark_tags (bookmark_id, tag_id)
+                   SELECT ?1, id FROM tags WHERE name = ?2"#,
+                params![id, tag],
+            )?;
+        }
+        if !new_tags.is_empty() {
+            self.refresh_fts_tags(id)?;
+        }
 
-        self.hydrate_bookmarks(&mut bookmarks)?;
+        for media in &merged.media {
+            match stored.media.iter().find(|own| own.url == media.url) {
+                None => {
+                    self.conn.execute(
+                        "INSERT INTO media (bookmark_id, url, media_type, local_path) VALUES (?1, ?2, ?3, ?4)",
+                        params![id, media.url, media.media_type.as_str(), media.local_path],
+                    )?;
+                }
+                Some(own) => {
+                    if let (None, Some(path)) = (&own.local_path, &media.local_path) {
+                        self.set_media_local_path(id, &media.url, path)?;
+                    }
+                }
+            }
+        }
 
-        Ok(bookmarks)
+        Ok(true)
     }
 
-    /// Advanced search with filters
-    #[allow(clippy::too_many_arguments)]
-    pub fn search_with_filters(
-        &self,
-        query: Option<&str>,
-        tag: Option<&str>,
-        author: Option<&str>,
-        from_date: Option<chrono::DateTime<chrono::Utc>>,
-        to_date: Option<chrono::DateTime<chrono::Utc>>,
-        favorites_only: bool,
-        has_media: Option<bool>,
-        limit: usize,
-    ) -> Result<Vec<Bookmark>> {
-        let (bookmarks, _) = self.search_with_filters_page(
-            query,
-            tag,
-            author,
-            from_date,
-            to_date,
-            favorites_only,
-            has_media,
-            0,
-            limit,
+    fn insert_bookmark_internal(&self, bookmark: &Bookmark) -> Result<()> {
+        // Handles are case-insensitive on X; store one spelling so lookups match
+        let author_handle = normalize_handle(&bookmark.author_handle);
+
+        // Insert main bookmark
+        let has_media_flag = if bookmark.media.is_empty() {
+            0i32
+        } else {
+            1i32
+        };
+        self.conn.execute(
+            r#"INSERT INTO bookmarks
+               (id, tweet_url, content, note_text, tweeted_at, imported_at,
+                author_handle, author_name, author_profile_url, author_profile_image, comments, is_favorite, has_media,
+                content_hash, is_archived, date_estimated, tweet_id, content_original_len, last_seen_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)"#,
+            params![
+                bookmark.id,
+                bookmark.tweet_url,
+                bookmark.content,
+                bookmark.note_text,
+                bookmark.tweeted_at.timestamp(),
+                bookmark.imported_at.timestamp(),
+                author_handle,
+                bookmark.author_name,
+                bookmark.author_profile_url,
+                bookmark.author_profile_image,
+                bookmark.comments,
+                bookmark.is_favorite as i32,
+                has_media_flag,
+                bookmark.content_hash(),
+                bookmark.is_archived as i32,
+                bookmark.date_estimated as i32,
+                tweet_id_from_url(&bookmark.tweet_url),
+                bookmark.content_original_len.map(|len| len as i64),
+                chrono::Utc::now().timestamp(),
+            ],
         )?;
 
-        Ok(bookmarks)
-    }
+        // Register the author; keep existing names, only fill missing profile fields
+        self.conn.execute(
+            r#"INSERT INTO authors (handle, name, profile_url, profile_image)
+               VALUES (?1, ?2, ?3, ?4)
+               ON CONFLICT(handle) DO UPDATE SET
+                   profile_url = COALESCE(authors.profile_url, excluded.profile_url),
+                   profile_image = COALESCE(authors.profile_image, excluded.profile_image)"#,
+            params![
+                author_handle,
+                bookmark.author_name,
+                bookmark.author_profile_url,
+                bookmark.author_profile_image,
+            ],
+        )?;
 
-    #[allow(clippy::too_many_arguments)]
-    pub fn search_with_filters_page(
-        &self,
-        query: Option<&str>,
-        tag: Option<&str>,
-        author: Option<&str>,
-        from_date: Option<chrono::DateTime<chrono::Utc>>,
-        to_date: Option<chrono::DateTime<chrono::Utc>>,
-        favorites_only: bool,
-        has_media: Option<bool>,
-        offset: usize,
-        limit: usize,
-    ) -> Result<(Vec<Bookmark>, i64)> {
-        let overall_started = std::time::Instant::now();
-        let (where_clause, mut params) = self.build_filtered_where_clause(
-            query,
-            tag,
-            author,
-            from_date,
-            to_date,
-            favorites_only,
-            has_media,
-        );
+        // Insert tags
+        for tag in &bookmark.tags {
+            // Insert tag if not exists
+            self.conn.execute(
+                "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+                params![tag],
+            )?;
 
-        // Single query: data + total count via window function (no second COUNT query)
-        let mut sql = String::from(
-            r#"SELECT b.id, b.tweet_url, b.content, b.note_text, b.tweeted_at, b.imported_at,
-                      b.author_handle, b.author_name, b.author_profile_url, b.author_profile_image,
-                      b.comments, b.is_favorite,
-                      COUNT(*) OVER() AS total_count
-               FROM bookmarks b"#,
-        );
-        if !where_clause.is_empty() {
-            sql.push_str(" WHERE ");
-            sql.push_str(&where_clause);
+            // Get tag ID
+            let tag_id: i64 = self.conn.query_row(
+                "SELECT id FROM tags WHERE name = ?1",
+                params![tag],
+                |row| row.get(0),
+            )?;
+
+            // Link bookmark to tag
+            self.conn.execute(
+                "INSERT INTO bookmark_tags (bookmark_id, tag_id) VALUES (?1, ?2)",
+                params![bookmark.id, tag_id],
+            )?;
         }
-        sql.push_str(" ORDER BY b.tweeted_at DESC, b.id DESC LIMIT ? OFFSET ?");
 
-        params.push(Value::Integer(limit as i64));
-        params.push(Value::Integer(offset as i64));
+        // Insert media
+        for media in &bookmark.media {
+            self.conn.execute(
+                "INSERT INTO media (bookmark_id, url, media_type, local_path) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    bookmark.id,
+                    media.url,
+                    media.media_type.as_str(),
+                    media.local_path
+                ],
+            )?;
+        }
 
-        let mut stmt = self.conn.prepare(&sql)?;
-        let query_started = std::time::Instant::now();
+        self.insert_links(&bookmark.id, &bookmark.extract_urls())?;
 
-        let mut total: i64 = 0;
-        let mut bookmarks = Vec::new();
-        let mut rows = stmt.query(params_from_iter(params.iter()))?;
-        while let Some(row) = rows.next()? {
-            // Named column access — safe against projection reordering
-            total = row.get::<_, i64>("total_count")?;
-            bookmarks.push(self.row_to_bookmark(row)?);
+        // Imported comments start the annotation history
+        let comments = bookmark
+            .comments
+            .as_deref()
+            .filter(|comments| !comments.trim().is_empty());
+        if let Some(comments) = comments {
+            self.conn.execute(
+                "INSERT INTO annotations (bookmark_id, body, created_at) VALUES (?1, ?2, ?3)",
+                params![bookmark.id, comments, bookmark.imported_at.timestamp()],
+            )?;
         }
-        let query_elapsed = query_started.elapsed();
 
-        let hydrate_started = std::time::Instant::now();
-        self.hydrate_bookmarks(&mut bookmarks)?;
-        eprintln!(
-            "[eterea][db][search_with_filters_page] offset={} limit={} total={} rows={} query={}ms hydrate={}ms total={}ms",
-            offset,
-            limit,
-            total,
-            bookmarks.len(),
-            query_elapsed.as_millis(),
-            hydrate_started.elapsed().as_millis(),
-            overall_started.elapsed().as_millis()
-        );
+        // Insert FTS content
+        let tags_text = bookmark.tags.join(" ");
+        let notes_text = fts_notes_text(bookmark.note_text.as_deref(), comments);
+        self.conn.execute(
+            r#"INSERT INTO bookmarks_fts_content 
+               (bookmark_id, content, note_text, author_handle, author_name, tags_text)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+            params![
+                bookmark.id,
+                bookmark.content,
+                notes_text,
+                author_handle,
+                bookmark.author_name,
+                tags_text,
+            ],
+        )?;
 
-        Ok((bookmarks, total))
+        Ok(())
     }
 
-    /// Build a WHERE clause using subqueries — no outer JOINs means no DISTINCT needed.
-    /// FTS and tag filters use IN-subqueries; has_media uses the denormalized column.
-    #[allow(clippy::too_many_arguments)]
-    fn build_filtered_where_clause(
+    fn insert_links(&self, bookmark_id: &str, urls: &[String]) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO links (bookmark_id, url, domain, position) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (position, url) in urls.iter().enumerate() {
+            stmt.execute(params![bookmark_id, url, url_domain(url), position as i64])?;
+        }
+        Ok(())
+    }
+
+    /// Outbound links of a bookmark, in the order they appear
+    pub fn get_links(&self, bookmark_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT url FROM links WHERE bookmark_id = ?1 ORDER BY position")?;
+        let links = stmt
+            .query_map(params![bookmark_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(links)
+    }
+
+    /// Bookmarks linking to `domain` or one of its subdomains (`github.com`
+    /// also matches `gist.github.com`), newest first. Expanded shortener
+    /// targets count too.
+    pub fn get_bookmarks_with_link_domain(
         &self,
-        query: Option<&str>,
-        tag: Option<&str>,
-        author: Option<&str>,
-        from_date: Option<chrono::DateTime<chrono::Utc>>,
-        to_date: Option<chrono::DateTime<chrono::Utc>>,
-        favorites_only: bool,
-        has_media: Option<bool>,
-    ) -> (String, Vec<Value>) {
-        let mut conditions = Vec::<String>::new();
-        let mut params = Vec::<Value>::new();
+        domain: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Bookmark>> {
+        let domain = domain.trim().to_lowercase();
+        let domain = domain.strip_prefix("www.").unwrap_or(&domain);
+        let mut stmt = self.conn.prepare(&format!(
+            r#"SELECT {BOOKMARK_COLUMNS}
+               FROM {BOOKMARK_TABLES}
+               WHERE b.deleted_at IS NULL
+                 AND b.id IN (
+                     SELECT bookmark_id FROM links
+                     WHERE domain = ?1 OR substr(domain, -length(?2)) = ?2
+                        OR expanded_domain = ?1 OR substr(expanded_domain, -length(?2)) = ?2
+                 )
+               ORDER BY {NEWEST_FIRST}
+               LIMIT ?3 OFFSET ?4"#
+        ))?;
 
-        if let Some(q) = query {
-            if !q.trim().is_empty() {
-                // FTS via subquery: query the virtual table first (its optimized MATCH path),
-                // then look up bookmark_id via the rowid link to our content table.
-                // No outer JOIN → no row multiplication, no DISTINCT needed.
-                conditions.push(
-                    "b.id IN (SELECT fc.bookmark_id FROM bookmarks_fts fts \
-                     JOIN bookmarks_fts_content fc ON fc.rowid = fts.rowid \
-                     WHERE bookmarks_fts MATCH ?)"
-                        .to_string(),
-                );
-                params.push(Value::Text(Self::prepare_fts_query(q)));
-            }
-        }
+        let mut bookmarks: Vec<Bookmark> = stmt
+            .query_map(
+                params![domain, format!(".{domain}"), limit as i64, offset as i64],
+                |row| self.row_to_bookmark(row),
+            )?
+            .filter_map(|r| r.ok())
+            .collect();
 
-        if let Some(t) = tag {
-            // Tag via subquery: no outer JOIN, no row multiplication
-            conditions.push(
-                "b.id IN (SELECT bt.bookmark_id FROM bookmark_tags bt \
-                 JOIN tags t ON t.id = bt.tag_id WHERE t.name = ?)"
-                    .to_string(),
-            );
-            params.push(Value::Text(t.to_string()));
-        }
+        self.hydrate_bookmarks(&mut bookmarks)?;
 
-        if let Some(a) = author {
-            conditions.push("b.author_handle = ?".to_string());
-            params.push(Value::Text(a.to_string()));
-        }
+        Ok(bookmarks)
+    }
 
-        if let Some(from) = from_date {
-            conditions.push("b.tweeted_at >= ?".to_string());
-            params.push(Value::Integer(from.timestamp()));
+    /// Full-text search across bookmarks; each term also matches words it
+    /// starts ("go" finds "google"). An empty or whitespace-only query returns
+    /// the newest bookmarks, as `search_with_filters` does without a query.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<Bookmark>> {
+        self.search_with_prefix_match(query, limit, true)
+    }
+
+    /// Like `search`; with `prefix_match` false, terms only match whole words,
+    /// so a short word like "go" no longer finds "google" or "gopher".
+    /// A configured `SearchBackend` does its own matching and ignores the flag.
+    pub fn search_with_prefix_match(
+        &self,
+        query: &str,
+        limit: usize,
+        prefix_match: bool,
+    ) -> Result<Vec<Bookmark>> {
+        if query.trim().is_empty() {
+            return self.bookmarks_matching(&SearchFilters::new(), Some(limit));
         }
-        if let Some(to) = to_date {
-            conditions.push("b.tweeted_at <= ?".to_string());
-            params.push(Value::Integer(to.timestamp()));
+        if let Some(backend) = &self.search_backend {
+            let ids = backend.search(query, limit)?;
+            return self.get_bookmarks_by_ids(&ids.iter().map(String::as_str).collect::<Vec<_>>());
         }
-
-        if favorites_only {
-            conditions.push("b.is_favorite = 1".to_string());
+        let with_trigram = self.content_trigram && !has_field_terms(query);
+        let query = Self::prepare_fts_query(query, prefix_match);
+        if query.is_empty() {
+            // Nothing but punctuation: no bookmark can match
+            return Ok(Vec::new());
         }
+        self.search_fts(&query, limit, with_trigram)
+    }
 
-        if let Some(has) = has_media {
-            // Use denormalized column — no JOIN needed
-            conditions.push(format!("b.has_media = {}", if has { 1 } else { 0 }));
+    /// Use `backend` instead of the FTS5 index for `search`.
+    ///
+    /// Writes through this handle from now on are forwarded to it (inserts,
+    /// edits, deletes, restores); call `reindex_search_backend` to add the
+    /// bookmarks already stored. Indexing runs after each write commits, so
+    /// a backend failure is logged instead of failing the write. Field-scoped
+    /// search and `find_related` keep using FTS5.
+    pub fn set_search_backend(&mut self, backend: Box<dyn SearchBackend>) {
+        self.search_backend = Some(backend);
+    }
+
+    /// Index every live bookmark in the configured search backend. Returns how
+    /// many were indexed (0 when FTS5 is in use, which is always up to date).
+    pub fn reindex_search_backend(&self) -> Result<usize> {
+        let Some(backend) = &self.search_backend else {
+            return Ok(0);
+        };
+        let bookmarks = self.bookmarks_matching(&SearchFilters::new(), None)?;
+        backend.index(&bookmarks)?;
+        Ok(bookmarks.len())
+    }
+
+    /// Build the in-memory `SearchIndex` used by `search_instant`; later
+    /// inserts, tag edits, deletes and restores through this handle keep it in
+    /// sync. Returns `false`, leaving `search_instant` on FTS5, when the
+    /// library is too large (`SearchIndex::MAX_BOOKMARKS`).
+    ///
+    /// Writes through other connections aren't seen; call this again after
+    /// them to rebuild.
+    pub fn enable_instant_search(&self) -> Result<bool> {
+        let started = std::time::Instant::now();
+        let index = SearchIndex::build(self)?;
+        match &index {
+            Some(index) => info!(
+                "Built instant search index over {} bookmarks in {:?}",
+                index.len(),
+                started.elapsed()
+            ),
+            None => info!("Library too large for instant search; using FTS5"),
         }
+        let enabled = index.is_some();
+        *self.instant_index.borrow_mut() = index;
+        Ok(enabled)
+    }
 
-        (conditions.join(" AND "), params)
+    /// Whether `search_instant` is answered from memory
+    pub fn instant_search_enabled(&self) -> bool {
+        self.instant_index.borrow().is_some()
     }
 
-    fn hydrate_bookmarks(&self, bookmarks: &mut [Bookmark]) -> Result<()> {
+    /// Search-as-you-type: bookmarks containing a word starting with each
+    /// word of `query`, newest first. Answered from memory after
+    /// `enable_instant_search`, by `search` otherwise.
+    pub fn search_instant(&self, query: &str, limit: usize) -> Result<Vec<Bookmark>> {
+        let ids = match &*self.instant_index.borrow() {
+            Some(index) if !query.trim().is_empty() => index.search(query, limit),
+            _ => return self.search(query, limit),
+        };
+        self.get_bookmarks_by_ids(&ids.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+
+    /// Add freshly inserted bookmarks to the configured `SearchBackend` and
+    /// the instant-search index, dropping the latter (back to FTS5) if the
+    /// library outgrows `SearchIndex::MAX_BOOKMARKS`
+    fn add_to_search_indexes(&self, bookmarks: &[Bookmark]) {
         if bookmarks.is_empty() {
-            return Ok(());
+            return;
+        }
+        if let Some(backend) = &self.search_backend {
+            if let Err(error) = backend.index(bookmarks) {
+                warn!(
+                    "Failed to index {} bookmarks in the search backend: {error}",
+                    bookmarks.len()
+                );
+            }
+        }
+        let mut instant_index = self.instant_index.borrow_mut();
+        let Some(index) = instant_index.as_mut() else {
+            return;
+        };
+        for bookmark in bookmarks {
+            index.insert(bookmark);
         }
+        if index.len() > SearchIndex::MAX_BOOKMARKS {
+            info!("Library outgrew instant search; using FTS5");
+            *instant_index = None;
+        }
+    }
 
-        let overall_started = std::time::Instant::now();
-        let bookmark_ids = bookmarks
+    /// Re-read bookmarks `ids` into the instant-search index and the
+    /// configured `SearchBackend`, dropping those that are gone or in the
+    /// trash. Called after the write committed, so failures are only logged.
+    fn refresh_search_indexes(&self, ids: &[&str]) {
+        if ids.is_empty()
+            || (self.search_backend.is_none() && self.instant_index.borrow().is_none())
+        {
+            return;
+        }
+        let mut live = Vec::new();
+        for chunk in ids.chunks(MAX_IN_PARAMS) {
+            match self.get_bookmarks_by_ids(chunk) {
+                Ok(found) => live.extend(found),
+                Err(error) => {
+                    warn!(
+                        "Failed to refresh search indexes for {} bookmarks: {error}",
+                        ids.len()
+                    );
+                    return;
+                }
+            }
+        }
+        let live_ids = live.iter().map(|b| b.id.as_str()).collect::<HashSet<_>>();
+        let gone = ids
             .iter()
-            .map(|bookmark| bookmark.id.clone())
+            .copied()
+            .filter(|id| !live_ids.contains(id))
             .collect::<Vec<_>>();
-        let tags_started = std::time::Instant::now();
-        let tags_by_bookmark = self.load_tags_for_bookmarks(&bookmark_ids)?;
-        let tags_elapsed = tags_started.elapsed();
-        let media_started = std::time::Instant::now();
-        let media_by_bookmark = self.load_media_for_bookmarks(&bookmark_ids)?;
-        let media_elapsed = media_started.elapsed();
 
-        for bookmark in bookmarks {
-            bookmark.tags = tags_by_bookmark
-                .get(&bookmark.id)
-                .cloned()
-                .unwrap_or_default();
-            bookmark.media = media_by_bookmark
-                .get(&bookmark.id)
-                .cloned()
-                .unwrap_or_default();
+        if let Some(index) = self.instant_index.borrow_mut().as_mut() {
+            for bookmark in &live {
+                index.insert(bookmark);
+            }
+            for id in &gone {
+                index.remove(id);
+            }
+        }
+        if let Some(backend) = &self.search_backend {
+            let result = backend.index(&live).and_then(|()| match gone.is_empty() {
+                true => Ok(()),
+                false => backend.remove(&gone),
+            });
+            if let Err(error) = result {
+                warn!(
+                    "Failed to update {} bookmarks in the search backend: {error}",
+                    ids.len()
+                );
+            }
         }
-        eprintln!(
-            "[eterea][db][hydrate_bookmarks] bookmarks={} tags={}ms media={}ms total={}ms",
-            bookmark_ids.len(),
-            tags_elapsed.as_millis(),
-            media_elapsed.as_millis(),
-            overall_started.elapsed().as_millis()
-        );
-        Ok(())
     }
 
-    fn load_tags_for_bookmarks(
+    /// Like `search`, optionally dropping stopwords ("the", "a", "to", ...) from
+    /// `query` first. Stopwords are prefix-matched like any other term, which is
+    /// slow and skews ranking; a query made only of stopwords is kept as is.
+    ///
+    /// The list is English by default (`DatabaseOptions::with_stopwords`).
+    pub fn search_with_stopword_filter(
         &self,
-        bookmark_ids: &[String],
-    ) -> Result<HashMap<String, Vec<String>>> {
-        if bookmark_ids.is_empty() {
-            return Ok(HashMap::new());
+        query: &str,
+        limit: usize,
+        skip_stopwords: bool,
+    ) -> Result<Vec<Bookmark>> {
+        if !skip_stopwords {
+            return self.search(query, limit);
         }
-
-        let placeholders = vec!["?"; bookmark_ids.len()].join(", ");
-        let sql = format!(
-            r#"SELECT bt.bookmark_id, t.name
-               FROM bookmark_tags bt
-               JOIN tags t ON t.id = bt.tag_id
-               WHERE bt.bookmark_id IN ({placeholders})
-               ORDER BY bt.bookmark_id, t.name"#
-        );
-        let params = bookmark_ids
-            .iter()
-            .cloned()
-            .map(Value::Text)
+        let kept = query
+            .split_whitespace()
+            .filter(|term| !self.stopwords.contains(&term.to_lowercase()))
             .collect::<Vec<_>>();
-        let mut stmt = self.conn.prepare(&sql)?;
-        let mut rows = stmt.query(params_from_iter(params.iter()))?;
-        let mut tags_by_bookmark = HashMap::<String, Vec<String>>::new();
-
-        while let Some(row) = rows.next()? {
-            let bookmark_id: String = row.get(0)?;
-            let tag: String = row.get(1)?;
-            tags_by_bookmark.entry(bookmark_id).or_default().push(tag);
+        if kept.is_empty() {
+            return self.search(query, limit);
         }
-
-        Ok(tags_by_bookmark)
+        self.search(&kept.join(" "), limit)
     }
 
-    fn load_media_for_bookmarks(
+    /// Like `search`, optionally re-ranking the best matches so that ones with
+    /// the query words close together in their content come first. bm25 scores
+    /// the words as a bag, so "machine learning" otherwise ranks a tweet with the
+    /// two words far apart the same as one with them adjacent.
+    ///
+    /// Only the top `PROXIMITY_CANDIDATES` matches are re-ranked, in Rust.
+    pub fn search_with_proximity(
         &self,
-        bookmark_ids: &[String],
-    ) -> Result<HashMap<String, Vec<Media>>> {
-        if bookmark_ids.is_empty() {
-            return Ok(HashMap::new());
+        query: &str,
+        limit: usize,
+        rerank: bool,
+    ) -> Result<Vec<Bookmark>> {
+        if !rerank {
+            return self.search(query, limit);
         }
-
-        let placeholders = vec!["?"; bookmark_ids.len()].join(", ");
-        let sql = format!(
-            r#"SELECT bookmark_id, url, media_type
-               FROM media
-               WHERE bookmark_id IN ({placeholders})
-               ORDER BY bookmark_id, id"#
-        );
-        let params = bookmark_ids
-            .iter()
-            .cloned()
-            .map(Value::Text)
+        let mut bookmarks = self.search(query, limit.max(PROXIMITY_CANDIDATES))?;
+
+        let reranked = bookmarks.len().min(PROXIMITY_CANDIDATES);
+        let mut scored = bookmarks
+            .drain(..reranked)
+            .enumerate()
+            .map(|(position, bookmark)| {
+                let bm25_score = 1.0 - position as f64 / PROXIMITY_CANDIDATES as f64;
+                let score =
+                    bm25_score + PROXIMITY_WEIGHT * proximity_bonus(&bookmark.content, query);
+                (score, bookmark)
+            })
             .collect::<Vec<_>>();
-        let mut stmt = self.conn.prepare(&sql)?;
-        let mut rows = stmt.query(params_from_iter(params.iter()))?;
-        let mut media_by_bookmark = HashMap::<String, Vec<Media>>::new();
+        scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        bookmarks.splice(0..0, scored.into_iter().map(|(_, bookmark)| bookmark));
 
-        while let Some(row) = rows.next()? {
-            let bookmark_id: String = row.get(0)?;
-            let url: String = row.get(1)?;
-            let media_type = match row.get::<_, String>(2)?.as_str() {
-                "image" => MediaType::Image,
-                "video" => MediaType::Video,
-                "gif" => MediaType::Gif,
-                _ => MediaType::Unknown,
-            };
+        bookmarks.truncate(limit);
+        Ok(bookmarks)
+    }
 
-            media_by_bookmark
-                .entry(bookmark_id)
-                .or_default()
-                .push(Media { url, media_type });
-        }
+    /// Full-text search restricted to one field, e.g. only author names
+    pub fn search_in_field(
+        &self,
+        field: SearchField,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Bookmark>> {
+        if query.trim().is_empty() {
+            return self.search(query, limit);
+        }
+        // The trigram index only covers content (under the same column name)
+        let with_trigram =
+            self.content_trigram && field == SearchField::Content && !has_field_terms(query);
+        let terms = Self::prepare_fts_query(query, true);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = format!("{}: ({})", field.fts_columns(), terms);
+        self.search_fts(&query, limit, with_trigram)
+    }
 
-        Ok(media_by_bookmark)
+    /// Bookmarks similar to `id` ("more like this"), best matches first.
+    ///
+    /// Builds an OR query from the most frequent meaningful words in the
+    /// bookmark's content and note, so bm25 favours bookmarks sharing several of
+    /// them. Returns nothing if the bookmark is missing or has no usable words.
+    pub fn find_related(&self, id: &str, limit: usize) -> Result<Vec<Bookmark>> {
+        let Some(bookmark) = self.get_bookmark(id)? else {
+            return Ok(Vec::new());
+        };
+        let text = match &bookmark.note_text {
+            Some(note) => format!("{} {}", bookmark.content, note),
+            None => bookmark.content.clone(),
+        };
+        let terms = salient_terms(&text, MAX_RELATED_TERMS);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let query = terms
+            .iter()
+            .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        // The source bookmark always matches its own terms; fetch one extra
+        let mut related = self.search_fts(&query, limit.saturating_add(1), false)?;
+        related.retain(|candidate| candidate.id != bookmark.id);
+        related.truncate(limit);
+        Ok(related)
     }
 
-    /// Get all unique tags with counts
-    pub fn get_all_tags(&self) -> Result<Vec<(String, i64)>> {
-        let mut stmt = self.conn.prepare(
-            r#"SELECT t.name, COUNT(bt.bookmark_id) as count
-               FROM tags t
-               LEFT JOIN bookmark_tags bt ON bt.tag_id = t.id
-               GROUP BY t.id
-               ORDER BY count DESC"#,
-        )?;
+    /// Run an FTS5 `MATCH` expression, best matches first
+    fn search_fts(&self, query: &str, limit: usize, with_trigram: bool) -> Result<Vec<Bookmark>> {
+        let sql = if with_trigram {
+            // Union the word index with trigram content hits; trigram-only hits get
+            // rank 0, which sorts after every (negative) bm25 score.
+            format!(
+                r#"WITH ranked AS (
+                       SELECT rowid, MIN(rank) AS rank FROM (
+                           SELECT rowid, bm25(bookmarks_fts) AS rank
+                           FROM bookmarks_fts WHERE bookmarks_fts MATCH ?1
+                           UNION ALL
+                           SELECT rowid, 0.0 AS rank
+                           FROM bookmarks_trigram WHERE bookmarks_trigram MATCH ?1
+                       )
+                       GROUP BY rowid
+                   )
+                   SELECT {BOOKMARK_COLUMNS}
+                   FROM {BOOKMARK_TABLES}
+                   JOIN bookmarks_fts_content fc ON fc.bookmark_id = b.id
+                   JOIN ranked r ON r.rowid = fc.rowid
+                   WHERE b.deleted_at IS NULL
+                   ORDER BY r.rank, {NEWEST_FIRST}
+                   LIMIT ?2"#
+            )
+        } else {
+            format!(
+                r#"SELECT {BOOKMARK_COLUMNS}
+                   FROM {BOOKMARK_TABLES}
+                   JOIN bookmarks_fts_content fc ON fc.bookmark_id = b.id
+                   JOIN bookmarks_fts fts ON fts.rowid = fc.rowid
+                   WHERE bookmarks_fts MATCH ?1 AND b.deleted_at IS NULL
+                   ORDER BY bm25(bookmarks_fts), {NEWEST_FIRST}
+                   LIMIT ?2"#
+            )
+        };
+        let mut stmt = self.conn.prepare(&sql)?;
 
-        let tags = stmt
-            .query_map([], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        let mut bookmarks: Vec<Bookmark> = stmt
+            .query_map(params![query, limit as i64], |row| {
+                self.row_to_bookmark(row)
             })?
             .filter_map(|r| r.ok())
             .collect();
 
-        Ok(tags)
+        self.hydrate_bookmarks(&mut bookmarks)?;
+
+        Ok(bookmarks)
     }
 
-    /// Get database statistics
-    pub fn get_stats(&self) -> Result<BookmarkStats> {
+    /// Prepare FTS5 query: NFC-normalized, every term quoted, and made a prefix
+    /// match (`"go"*` also finds "google") unless `prefix_match` is false.
+    ///
+    /// `#tag` only searches content and tags, `@handle` content and authors.
+    /// Tokens that are all punctuation are dropped (so the result can be
+    /// empty), and a term ending in punctuation (`C++`, `F#`) matches that
+    /// word only, since the tokenizer would otherwise leave a bare prefix.
+    pub(crate) fn prepare_fts_query(query: &str, prefix_match: bool) -> String {
+        // Stored text is NFC (see `Bookmark::normalize_unicode`)
+        let query = query.nfc().collect::<String>();
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .filter_map(|term| {
+                let (columns, word) = match term.strip_prefix('#') {
+                    Some(tag) => (Some("{content tags_text}"), tag),
+                    None => match term.strip_prefix('@') {
+                        Some(handle) => (Some("{content author_handle}"), handle),
+                        None => (None, term),
+                    },
+                };
+                if !word.chars().any(char::is_alphanumeric) {
+                    return None;
+                }
+                let whole_word = !word.ends_with(char::is_alphanumeric);
+                let suffix = if prefix_match && !whole_word { "*" } else { "" };
+                // Escape special FTS5 characters
+                let phrase = format!("\"{}\"{}", word.replace('"', "\"\""), suffix);
+                Some(match columns {
+                    Some(columns) => format!("{columns} : {phrase}"),
+                    None => phrase,
+                })
+            })
+            .collect();
+
+        terms.join(" ")
+    }
+
+    /// Get bookmarks with pagination (the default listing: archived bookmarks are
+    /// left out; use `get_archived` or a filtered search to reach them)
+    pub fn get_bookmarks(&self, offset: usize, limit: usize) -> Result<Vec<Bookmark>> {
         let overall_started = std::time::Instant::now();
-        if let Some(snapshot) = self.get_metadata(STATS_SNAPSHOT_METADATA_KEY)? {
-            if let Ok(stats) = serde_json::from_str::<BookmarkStats>(&snapshot) {
-                eprintln!(
-                    "[eterea][db][get_stats] snapshot-hit total={}ms",
-                    overall_started.elapsed().as_millis()
-                );
-                return Ok(stats);
-            }
-        }
+        let mut stmt = self.conn.prepare(&format!(
+            r#"SELECT {BOOKMARK_COLUMNS}
+               FROM {BOOKMARK_TABLES}
+               WHERE b.deleted_at IS NULL AND b.is_archived = 0
+               ORDER BY {NEWEST_FIRST}
+               LIMIT ?1 OFFSET ?2"#
+        ))?;
 
-        let compute_started = std::time::Instant::now();
-        let stats = self.compute_stats()?;
-        self.persist_stats_snapshot(&stats)?;
+        let query_started = std::time::Instant::now();
+        let mut bookmarks: Vec<Bookmark> = stmt
+            .query_map(params![limit as i64, offset as i64], |row| {
+                self.row_to_bookmark(row)
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        let query_elapsed = query_started.elapsed();
+
+        let hydrate_started = std::time::Instant::now();
+        self.hydrate_bookmarks(&mut bookmarks)?;
         eprintln!(
-            "[eterea][db][get_stats] recompute compute={}ms total={}ms",
-            compute_started.elapsed().as_millis(),
+            "[eterea][db][get_bookmarks] offset={} limit={} rows={} query={}ms hydrate={}ms total={}ms",
+            offset,
+            limit,
+            bookmarks.len(),
+            query_elapsed.as_millis(),
+            hydrate_started.elapsed().as_millis(),
             overall_started.elapsed().as_millis()
         );
-        Ok(stats)
+
+        Ok(bookmarks)
     }
 
-    fn compute_stats(&self) -> Result<BookmarkStats> {
-        let total_bookmarks: i64 =
-            self.conn
-                .query_row("SELECT COUNT(*) FROM bookmarks", [], |row| row.get(0))?;
+    /// Count the bookmarks in the default `get_bookmarks` listing
+    pub fn count_bookmarks(&self) -> Result<i64> {
+        self.conn
+            .query_row(
+                "SELECT COUNT(*) FROM bookmarks WHERE deleted_at IS NULL AND is_archived = 0",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(Into::into)
+    }
 
-        let unique_authors: i64 = self.conn.query_row(
-            "SELECT COUNT(DISTINCT author_handle) FROM bookmarks",
-            [],
-            |row| row.get(0),
-        )?;
+    /// Get bookmarks by tag
+    pub fn get_bookmarks_by_tag(
+        &self,
+        tag: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Bookmark>> {
+        let filters = SearchFilters::new().with_tag(tag);
+        Ok(self
+            .query_bookmarks(&filters, Pagination::new(offset, limit))?
+            .items)
+    }
 
-        let unique_tags: i64 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0))?;
+    /// Count the live bookmarks carrying `tag`, without grouping every tag
+    pub fn count_by_tag(&self, tag: &str) -> Result<i64> {
+        self.count_matching(&SearchFilters::new().with_tag(tag))
+    }
 
-        let favorite_bookmarks: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM bookmarks WHERE is_favorite = 1",
+    /// `get_bookmarks_by_tag` plus the total number tagged `tag`
+    pub fn get_bookmarks_by_tag_page(
+        &self,
+        tag: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<PaginatedResponse<Bookmark>> {
+        let filters = SearchFilters::new().with_tag(tag);
+        self.query_bookmarks(&filters, Pagination::new(offset, limit))
+    }
+
+    /// Get bookmarks by author
+    pub fn get_bookmarks_by_author(
+        &self,
+        handle: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Bookmark>> {
+        let filters = SearchFilters::new().with_author(handle);
+        Ok(self
+            .query_bookmarks(&filters, Pagination::new(offset, limit))?
+            .items)
+    }
+
+    /// `get_bookmarks_by_author` plus the total number by `handle`
+    pub fn get_bookmarks_by_author_page(
+        &self,
+        handle: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<PaginatedResponse<Bookmark>> {
+        let filters = SearchFilters::new().with_author(handle);
+        self.query_bookmarks(&filters, Pagination::new(offset, limit))
+    }
+
+    /// Count the live bookmarks by `handle`
+    pub fn count_by_author(&self, handle: &str) -> Result<i64> {
+        self.count_matching(&SearchFilters::new().with_author(handle))
+    }
+
+    /// Up to `n` bookmarks picked at random (trash excluded), for "surprise me".
+    ///
+    /// Always returns `min(n, live bookmarks)` of them. A small sample avoids
+    /// `ORDER BY RANDOM()` over the whole table: each pick is a random rowid
+    /// between the lowest and highest, rounded up to the next live row, so
+    /// bookmarks just after a gap in the rowids come up a little more often.
+    /// Larger samples, and any picks the probing misses, come from a shuffle.
+    pub fn random_bookmarks(&self, n: usize) -> Result<Vec<Bookmark>> {
+        let (live, min_rowid, max_rowid): (usize, Option<i64>, Option<i64>) = self.conn.query_row(
+            "SELECT COUNT(*), MIN(rowid), MAX(rowid) FROM bookmarks WHERE deleted_at IS NULL",
             [],
-            |row| row.get(0),
+            |row| Ok((row.get::<_, i64>(0)? as usize, row.get(1)?, row.get(2)?)),
         )?;
+        let (Some(min_rowid), Some(max_rowid)) = (min_rowid, max_rowid) else {
+            return Ok(Vec::new());
+        };
+        let wanted = n.min(live);
+        let mut ids: Vec<String> = Vec::with_capacity(wanted);
+
+        // Past a quarter of the library, repeated picks pile up (the coupon
+        // collector needs about n·ln n draws), so only probe for small samples
+        if wanted.saturating_mul(4) < live {
+            let mut pick = self.conn.prepare(
+                r#"SELECT id FROM bookmarks
+                   WHERE rowid >= ?1 + abs(random() % (?2 - ?1 + 1)) AND deleted_at IS NULL
+                   ORDER BY rowid
+                   LIMIT 1"#,
+            )?;
+            // Give up after a generous number of tries rather than loop on an
+            // unlucky streak; the shuffle below fills in the rest
+            let mut attempts = wanted.saturating_mul(8).max(32);
+            while ids.len() < wanted && attempts > 0 {
+                attempts -= 1;
+                let id = match pick
+                    .query_row(params![min_rowid, max_rowid], |row| row.get::<_, String>(0))
+                {
+                    Ok(id) => id,
+                    Err(rusqlite::Error::QueryReturnedNoRows) => continue,
+                    Err(e) => return Err(e.into()),
+                };
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
 
-        let earliest_date =
-            self.conn
-                .query_row("SELECT MIN(tweeted_at) FROM bookmarks", [], |row| {
-                    row.get::<_, Option<i64>>(0)
-                })?;
+        let missing = wanted - ids.len();
+        if missing > 0 {
+            // Of `wanted` shuffled rows, at least `missing` aren't picked yet
+            let shuffled = self
+                .conn
+                .prepare(
+                    "SELECT id FROM bookmarks WHERE deleted_at IS NULL ORDER BY RANDOM() LIMIT ?1",
+                )?
+                .query_map(params![wanted as i64], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let picked = ids.iter().cloned().collect::<HashSet<_>>();
+            ids.extend(
+                shuffled
+                    .into_iter()
+                    .filter(|id| !picked.contains(id))
+                    .take(missing),
+            );
+        }
+
+        self.get_bookmarks_by_ids(&ids.iter().map(String::as_str).collect::<Vec<_>>())
+    }
 
-        let latest_date =
+    /// Bookmarks tweeted on `month`/`day` (UTC) in any year, newest first
+    pub fn on_this_day(&self, month: u32, day: u32) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"SELECT {BOOKMARK_COLUMNS}
+               FROM {BOOKMARK_TABLES}
+               WHERE b.deleted_at IS NULL
+                 AND strftime('%m-%d', b.tweeted_at, 'unixepoch') = ?1
+               ORDER BY {NEWEST_FIRST}"#
+        ))?;
+
+        let mut bookmarks: Vec<Bookmark> = stmt
+            .query_map(params![format!("{month:02}-{day:02}")], |row| {
+                self.row_to_bookmark(row)
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        self.hydrate_bookmarks(&mut bookmarks)?;
+
+        Ok(bookmarks)
+    }
+
+    /// Most recent `tweeted_at` across all stored bookmarks (trash included)
+    pub fn latest_tweeted_at(&self) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        use chrono::TimeZone;
+
+        let latest: Option<i64> =
             self.conn
                 .query_row("SELECT MAX(tweeted_at) FROM bookmarks", [], |row| {
-                    row.get::<_, Option<i64>>(0)
+                    row.get(0)
                 })?;
+        Ok(latest.map(|ts| chrono::Utc.timestamp_opt(ts, 0).unwrap()))
+    }
 
-        let top_tags = self.get_all_tags()?;
+    /// Get a single bookmark by ID (including bookmarks in the trash)
+    pub fn get_bookmark(&self, id: &str) -> Result<Option<Bookmark>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {BOOKMARK_COLUMNS} FROM {BOOKMARK_TABLES} WHERE b.id = ?1"
+        ))?;
 
-        use chrono::TimeZone;
+        let result = stmt.query_row(params![id], |row| self.row_to_bookmark(row));
 
-        Ok(BookmarkStats {
-            total_bookmarks,
-            unique_authors,
-            unique_tags,
-            favorite_bookmarks,
-            earliest_date: earliest_date.map(|ts| chrono::Utc.timestamp_opt(ts, 0).unwrap()),
-            latest_date: latest_date.map(|ts| chrono::Utc.timestamp_opt(ts, 0).unwrap()),
-            top_tags,
-        })
+        match result {
+            Ok(mut bookmark) => {
+                bookmark.tags = self.load_bookmark_tags(&bookmark.id)?;
+                bookmark.media = self.load_bookmark_media(&bookmark.id)?;
+                Ok(Some(bookmark))
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
     }
 
-    fn persist_stats_snapshot(&self, stats: &BookmarkStats) -> Result<()> {
-        let payload = serde_json::to_string(stats).map_err(|error| {
-            Error::Other(format!("Failed to serialize stats snapshot: {error}"))
-        })?;
-        self.set_metadata(STATS_SNAPSHOT_METADATA_KEY, &payload)
+    /// Get the live bookmark for a status id (the number in `/status/<id>`),
+    /// whichever host its URL uses
+    pub fn get_bookmark_by_tweet_id(&self, tweet_id: &str) -> Result<Option<Bookmark>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"SELECT {BOOKMARK_COLUMNS}
+               FROM {BOOKMARK_TABLES}
+               WHERE b.tweet_id = ?1 AND b.deleted_at IS NULL
+               ORDER BY b.imported_at, b.id
+               LIMIT 1"#
+        ))?;
+        let mut bookmarks: Vec<Bookmark> = stmt
+            .query_map([tweet_id.trim()], |row| self.row_to_bookmark(row))?
+            .filter_map(|r| r.ok())
+            .collect();
+        self.hydrate_bookmarks(&mut bookmarks)?;
+        Ok(bookmarks.pop())
     }
 
-    fn refresh_stats_snapshot(&self) -> Result<()> {
-        let stats = self.compute_stats()?;
-        self.persist_stats_snapshot(&stats)
+    /// Get several bookmarks by ID, in the order the ids were given.
+    ///
+    /// Unknown and trashed ids are skipped.
+    pub fn get_bookmarks_by_ids(&self, ids: &[&str]) -> Result<Vec<Bookmark>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut params = Vec::<Value>::new();
+        let placeholders = ids
+            .iter()
+            .map(|id| bind(&mut params, Value::Text(id.to_string())))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut stmt = self.conn.prepare(&format!(
+            r#"SELECT {BOOKMARK_COLUMNS}
+               FROM {BOOKMARK_TABLES}
+               WHERE b.id IN ({placeholders}) AND b.deleted_at IS NULL"#
+        ))?;
+
+        let mut found: Vec<Bookmark> = stmt
+            .query_map(params_from_iter(params.iter()), |row| {
+                self.row_to_bookmark(row)
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        self.hydrate_bookmarks(&mut found)?;
+
+        let mut by_id: HashMap<String, Bookmark> = found
+            .into_iter()
+            .map(|bookmark| (bookmark.id.clone(), bookmark))
+            .collect();
+        let bookmarks = ids.iter().filter_map(|id| by_id.remove(*id)).collect();
+
+        Ok(bookmarks)
     }
 
-    /// Convert a database row to a Bookmark
-    fn row_to_bookmark(&self, row: &rusqlite::Row) -> rusqlite::Result<Bookmark> {
-        use chrono::TimeZone;
+    /// Tag a bookmark. Returns `false` if the bookmark doesn't exist or already
+    /// has the tag (the `bookmark_tags` primary key makes this idempotent).
+    pub fn add_tag_to_bookmark(&self, id: &str, tag: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let tag = tag.trim();
+        if tag.is_empty() || !self.bookmark_exists(id)? {
+            return Ok(false);
+        }
 
-        let id: String = row.get(0)?;
-        let tweeted_at_ts: i64 = row.get(4)?;
-        let imported_at_ts: i64 = row.get(5)?;
-        let is_favorite: i32 = row.get(11).unwrap_or(0);
+        let added = self.with_transaction(|| {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+                params![tag],
+            )?;
+            let added = self.conn.execute(
+                r#"INSERT OR IGNORE INTO bookmark_tags (bookmark_id, tag_id)
+                   SELECT ?1, id FROM tags WHERE name = ?2"#,
+                params![id, tag],
+            )?;
+            if added > 0 {
+                self.refresh_fts_tags(id)?;
+            }
+            Ok(added > 0)
+        })?;
+        self.finish_tag_update(id, added)
+    }
 
-        let bookmark = Bookmark {
-            id: id.clone(),
-            tweet_url: row.get(1)?,
-            content: row.get(2)?,
-            note_text: row.get(3)?,
-            tweeted_at: chrono::Utc.timestamp_opt(tweeted_at_ts, 0).unwrap(),
-            imported_at: chrono::Utc.timestamp_opt(imported_at_ts, 0).unwrap(),
-            author_handle: row.get(6)?,
-            author_name: row.get(7)?,
-            author_profile_url: row.get(8)?,
-            author_profile_image: row.get(9)?,
-            comments: row.get(10)?,
-            tags: Vec::new(),
-            media: Vec::new(),
-            is_favorite: is_favorite != 0,
-            search_text: String::new(),
-        };
+    /// Remove a tag from a bookmark. Returns whether the bookmark had the tag.
+    pub fn remove_tag_from_bookmark(&self, id: &str, tag: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let removed = self.with_transaction(|| {
+            let removed = self.conn.execute(
+                r#"DELETE FROM bookmark_tags
+                   WHERE bookmark_id = ?1
+                     AND tag_id = (SELECT id FROM tags WHERE name = ?2)"#,
+                params![id, tag.trim()],
+            )?;
+            if removed > 0 {
+                self.refresh_fts_tags(id)?;
+            }
+            Ok(removed > 0)
+        })?;
+        self.finish_tag_update(id, removed)
+    }
 
-        // Note: tags and media are loaded separately for performance
-        // Use load_bookmark_tags() and load_bookmark_media() when needed
+    /// After a committed tag edit, refresh stats (and the bookmark's
+    /// instant-search entry) if it changed anything
+    fn finish_tag_update(&self, id: &str, changed: bool) -> Result<bool> {
+        // Even without a bookmark change the tags table may have
+        self.invalidate_stats()?;
+        if changed {
+            self.refresh_search_indexes(&[id]);
+        }
+        Ok(changed)
+    }
 
-        Ok(bookmark)
+    /// Rename tag `from` to `to` on every bookmark. If `to` already exists the
+    /// two tags are merged; a rename that only changes case keeps the tag.
+    /// `from` is remembered as an alias of `to` for `export_tag_map`. Returns
+    /// how many bookmarks changed.
+    pub fn rename_tag(&self, from: &str, to: &str) -> Result<usize> {
+        self.merge_tags(&[from], to)
     }
 
-    /// Load tags for a bookmark
-    pub fn load_bookmark_tags(&self, bookmark_id: &str) -> Result<Vec<String>> {
-        let mut stmt = self.conn.prepare(
-            r#"SELECT t.name FROM tags t
-               JOIN bookmark_tags bt ON bt.tag_id = t.id
-               WHERE bt.bookmark_id = ?1"#,
-        )?;
+    /// Merge each of `sources` into `target` (created if needed): bookmarks
+    /// carrying a source tag carry `target` instead, and the source tags are
+    /// removed and remembered as aliases of `target`. Returns how many
+    /// bookmarks changed.
+    pub fn merge_tags(&self, sources: &[&str], target: &str) -> Result<usize> {
+        self.ensure_writable()?;
+        let target = target.trim();
+        if target.is_empty() {
+            return Err(Error::Other("tag name is empty".into()));
+        }
 
-        let tags = stmt
-            .query_map(params![bookmark_id], |row| row.get::<_, String>(0))?
-            .filter_map(|r| r.ok())
-            .collect();
+        let changed = self.with_transaction(|| {
+            let mut changed = BTreeSet::new();
+            for source in sources.iter().map(|source| source.trim()) {
+                if source == target {
+                    continue;
+                }
+                if let Some(ids) = self.rename_tag_internal(source, target)? {
+                    changed.extend(ids);
+                }
+            }
+            Ok(changed)
+        })?;
+        self.finish_tag_rewrite(changed)
+    }
+
+    /// Move tag `from` onto `to` inside the caller's transaction. Returns the
+    /// bookmarks that carried `from`, or `None` if there is no such tag.
+    fn rename_tag_internal(&self, from: &str, to: &str) -> Result<Option<Vec<String>>> {
+        let Some((from_id, _)) = self.find_tag(from)? else {
+            return Ok(None);
+        };
+        let ids = self
+            .conn
+            .prepare("SELECT bookmark_id FROM bookmark_tags WHERE tag_id = ?1")?
+            .query_map(params![from_id], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        match self.find_tag(to)? {
+            Some((to_id, _)) if to_id != from_id => {
+                self.conn.execute(
+                    r#"INSERT OR IGNORE INTO bookmark_tags (bookmark_id, tag_id)
+                       SELECT bookmark_id, ?2 FROM bookmark_tags WHERE tag_id = ?1"#,
+                    params![from_id, to_id],
+                )?;
+                self.conn.execute(
+                    "DELETE FROM bookmark_tags WHERE tag_id = ?1",
+                    params![from_id],
+                )?;
+                self.conn
+                    .execute("DELETE FROM tags WHERE id = ?1", params![from_id])?;
+            }
+            _ => {
+                self.conn.execute(
+                    "UPDATE tags SET name = ?2 WHERE id = ?1",
+                    params![from_id, to],
+                )?;
+            }
+        }
+        self.record_tag_alias(from, to)?;
+        for id in &ids {
+            self.refresh_fts_tags(id)?;
+        }
+        Ok(Some(ids))
+    }
+
+    /// Remember `alias` as a former name of `tag`, repointing aliases of
+    /// `alias` at `tag`. Names that only differ in case aren't aliases.
+    fn record_tag_alias(&self, alias: &str, tag: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE tag_aliases SET tag = ?2 WHERE tag = ?1",
+            params![alias, tag],
+        )?;
+        self.conn
+            .execute("DELETE FROM tag_aliases WHERE alias = ?1", params![tag])?;
+        if !alias.eq_ignore_ascii_case(tag) {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO tag_aliases (alias, tag) VALUES (?1, ?2)",
+                params![alias, tag],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// A tag's id and stored name (tag names are case-insensitive)
+    fn find_tag(&self, name: &str) -> Result<Option<(i64, String)>> {
+        match self.conn.query_row(
+            "SELECT id, name FROM tags WHERE name = ?1",
+            params![name],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ) {
+            Ok(tag) => Ok(Some(tag)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// `finish_tag_update` for edits that retag many bookmarks; returns how
+    /// many changed
+    fn finish_tag_rewrite(&self, changed: BTreeSet<String>) -> Result<usize> {
+        // Tags can be created or dropped without any bookmark changing
+        self.invalidate_stats()?;
+        if !changed.is_empty() {
+            self.refresh_search_indexes(&changed.iter().map(String::as_str).collect::<Vec<_>>());
+        }
+        Ok(changed.len())
+    }
+
+    /// Rewrite the FTS `tags_text` for a bookmark from its current tags
+    fn refresh_fts_tags(&self, id: &str) -> Result<()> {
+        let tags_text = self.load_bookmark_tags(id)?.join(" ");
+        self.conn.execute(
+            "UPDATE bookmarks_fts_content SET tags_text = ?2 WHERE bookmark_id = ?1",
+            params![id, tags_text],
+        )?;
+        Ok(())
+    }
+
+    /// Add a timestamped note to a bookmark, keeping earlier ones. `comments`
+    /// is set to the new note. Returns `None` if the bookmark doesn't exist.
+    pub fn add_annotation(&self, id: &str, body: &str) -> Result<Option<Annotation>> {
+        self.ensure_writable()?;
+        let body = body.trim();
+        if body.is_empty() {
+            return Err(Error::Other("annotation is empty".into()));
+        }
+
+        let annotation = self.with_transaction(|| {
+            if !self.bookmark_exists(id)? {
+                return Ok(None);
+            }
+            let created_at = chrono::Utc::now().timestamp();
+            self.conn.execute(
+                "INSERT INTO annotations (bookmark_id, body, created_at) VALUES (?1, ?2, ?3)",
+                params![id, body, created_at],
+            )?;
+            let annotation_id = self.conn.last_insert_rowid();
+            self.conn.execute(
+                "UPDATE bookmarks SET comments = ?2 WHERE id = ?1",
+                params![id, body],
+            )?;
+            self.refresh_fts_notes(id)?;
+            Ok(Some(Annotation {
+                id: annotation_id,
+                bookmark_id: id.to_string(),
+                body: body.to_string(),
+                created_at: chrono::DateTime::from_timestamp(created_at, 0).unwrap_or_default(),
+            }))
+        })?;
+
+        if annotation.is_some() {
+            self.refresh_search_indexes(&[id]);
+        }
+        Ok(annotation)
+    }
+
+    /// A bookmark's annotations, oldest first
+    pub fn get_annotations(&self, id: &str) -> Result<Vec<Annotation>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT id, bookmark_id, body, created_at FROM annotations
+               WHERE bookmark_id = ?1
+               ORDER BY created_at, id"#,
+        )?;
+        let annotations = stmt
+            .query_map(params![id], |row| {
+                Ok(Annotation {
+                    id: row.get(0)?,
+                    bookmark_id: row.get(1)?,
+                    body: row.get(2)?,
+                    created_at: chrono::DateTime::from_timestamp(row.get(3)?, 0)
+                        .unwrap_or_default(),
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(annotations)
+    }
+
+    /// Rewrite the FTS `note_text` for a bookmark: its note followed by its
+    /// annotations, so both are searchable as notes
+    fn refresh_fts_notes(&self, id: &str) -> Result<()> {
+        let note_text: Option<String> = self.conn.query_row(
+            "SELECT note_text FROM bookmarks WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )?;
+        let annotations = self
+            .get_annotations(id)?
+            .into_iter()
+            .map(|annotation| annotation.body)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let annotations = Some(annotations.as_str()).filter(|text| !text.is_empty());
+        self.conn.execute(
+            "UPDATE bookmarks_fts_content SET note_text = ?2 WHERE bookmark_id = ?1",
+            params![id, fts_notes_text(note_text.as_deref(), annotations)],
+        )?;
+        Ok(())
+    }
+
+    fn bookmark_exists(&self, id: &str) -> Result<bool> {
+        self.conn
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM bookmarks WHERE id = ?1)",
+                params![id],
+                |row| row.get(0),
+            )
+            .map_err(Into::into)
+    }
+
+    /// Move a bookmark to the trash (soft delete); see `restore_bookmark`
+    pub fn delete_bookmark(&self, id: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let count = self.conn.execute(
+            "UPDATE bookmarks SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            params![id, chrono::Utc::now().timestamp()],
+        )?;
+        if count > 0 {
+            self.invalidate_stats()?;
+            self.refresh_search_indexes(&[id]);
+        }
+        Ok(count > 0)
+    }
+
+    /// Permanently delete a bookmark, whether or not it is in the trash
+    pub fn delete_bookmark_permanently(&self, id: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let count = self
+            .conn
+            .execute("DELETE FROM bookmarks WHERE id = ?1", params![id])?;
+        if count > 0 {
+            self.invalidate_stats()?;
+            self.refresh_search_indexes(&[id]);
+        }
+        Ok(count > 0)
+    }
+
+    /// Take a bookmark back out of the trash
+    pub fn restore_bookmark(&self, id: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let count = self.conn.execute(
+            "UPDATE bookmarks SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+        )?;
+        if count > 0 {
+            self.invalidate_stats()?;
+            self.refresh_search_indexes(&[id]);
+        }
+        Ok(count > 0)
+    }
+
+    /// Permanently delete bookmarks that have been in the trash longer than `older_than`
+    pub fn purge_deleted(&self, older_than: Duration) -> Result<usize> {
+        self.ensure_writable()?;
+        let cutoff = cutoff_timestamp(older_than);
+        let purged = self
+            .conn
+            .prepare("SELECT id FROM bookmarks WHERE deleted_at IS NOT NULL AND deleted_at < ?1")?
+            .query_map(params![cutoff], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let count = self.conn.execute(
+            "DELETE FROM bookmarks WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+            params![cutoff],
+        )?;
+        if count > 0 {
+            self.invalidate_stats()?;
+            self.refresh_search_indexes(&purged.iter().map(String::as_str).collect::<Vec<_>>());
+        }
+        Ok(count)
+    }
+
+    /// Get trashed bookmarks, most recently deleted first
+    pub fn get_trash(&self, offset: usize, limit: usize) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"SELECT {BOOKMARK_COLUMNS}
+               FROM {BOOKMARK_TABLES}
+               WHERE b.deleted_at IS NOT NULL
+               ORDER BY b.deleted_at DESC, b.id DESC
+               LIMIT ?1 OFFSET ?2"#
+        ))?;
+
+        let mut bookmarks: Vec<Bookmark> = stmt
+            .query_map(params![limit as i64, offset as i64], |row| {
+                self.row_to_bookmark(row)
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        self.hydrate_bookmarks(&mut bookmarks)?;
+
+        Ok(bookmarks)
+    }
+
+    /// Group bookmarks that look like the same tweet.
+    ///
+    /// Two bookmarks are duplicates when their URLs normalize to the same tweet
+    /// (twitter.com vs x.com, tracking params), or when the same author posted
+    /// content with the same hash; the same short text ("lol", "+1") from
+    /// different authors is not a duplicate.
+    /// Groups are transitive; ids within a group are ordered by import time, so
+    /// the first id is the earliest import.
+    pub fn find_duplicates(&self) -> Result<Vec<Vec<String>>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT id, tweet_url, content_hash, author_handle FROM bookmarks
+               WHERE deleted_at IS NULL
+               ORDER BY imported_at ASC, rowid ASC"#,
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        // Union-find over row positions, keyed by normalized URL and by author
+        // plus content hash (as `content_duplicate_of` matches them)
+        let mut parent: Vec<usize> = (0..rows.len()).collect();
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        let mut first_seen: HashMap<String, usize> = HashMap::new();
+        for (index, (_, url, hash, author)) in rows.iter().enumerate() {
+            let keys = std::iter::once(format!("url:{}", normalize_tweet_url(url)))
+                .chain(hash.iter().map(|hash| format!("hash:{author}:{hash}")));
+            for key in keys {
+                let other = *first_seen.entry(key).or_insert(index);
+                let (a, b) = (root(&mut parent, index), root(&mut parent, other));
+                // Keep the earlier row as the root so groups stay in import order
+                if a != b {
+                    parent[a.max(b)] = a.min(b);
+                }
+            }
+        }
+
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        let mut group_of_root: HashMap<usize, usize> = HashMap::new();
+        for (index, (id, ..)) in rows.iter().enumerate() {
+            let group = *group_of_root
+                .entry(root(&mut parent, index))
+                .or_insert_with(|| {
+                    groups.push(Vec::new());
+                    groups.len() - 1
+                });
+            groups[group].push(id.clone());
+        }
+        groups.retain(|group| group.len() > 1);
+
+        Ok(groups)
+    }
+
+    /// Group bookmarks that repeat the same content by the same author under
+    /// different URLs (reposts, re-shares).
+    ///
+    /// Narrower than `find_duplicates`: URL matches alone don't group, and the
+    /// content hash must match together with the author handle. Ids within a
+    /// group are ordered by import time.
+    pub fn find_content_duplicates(&self) -> Result<Vec<Vec<String>>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT id, content_hash, author_handle FROM bookmarks
+               WHERE deleted_at IS NULL AND content_hash IS NOT NULL
+               ORDER BY imported_at ASC, rowid ASC"#,
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        let mut group_of_key: HashMap<(String, String), usize> = HashMap::new();
+        for (id, hash, handle) in rows {
+            let group = *group_of_key.entry((hash, handle)).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].push(id);
+        }
+        groups.retain(|group| group.len() > 1);
+
+        Ok(groups)
+    }
+
+    /// Toggle favorite status for a bookmark
+    pub fn toggle_favorite(&self, id: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        self.conn.execute(
+            "UPDATE bookmarks SET is_favorite = NOT is_favorite WHERE id = ?1",
+            params![id],
+        )?;
+
+        // Return the new favorite status
+        let is_favorite: bool = self.conn.query_row(
+            "SELECT is_favorite FROM bookmarks WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )?;
+
+        self.invalidate_stats()?;
+        Ok(is_favorite)
+    }
+
+    /// Set favorite status for a bookmark
+    pub fn set_favorite(&self, id: &str, favorite: bool) -> Result<()> {
+        self.ensure_writable()?;
+        self.conn.execute(
+            "UPDATE bookmarks SET is_favorite = ?2 WHERE id = ?1",
+            params![id, favorite as i32],
+        )?;
+        self.invalidate_stats()?;
+        Ok(())
+    }
+
+    /// Set favorite status for many bookmarks at once, in one transaction.
+    /// Returns how many bookmarks changed; unknown ids and bookmarks that
+    /// already had that status aren't counted.
+    pub fn set_favorites(&self, ids: &[&str], favorite: bool) -> Result<usize> {
+        self.ensure_writable()?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let changed = self.with_transaction(|| {
+            let mut changed = 0;
+            for chunk in ids.chunks(MAX_IN_PARAMS) {
+                let placeholders = vec!["?"; chunk.len()].join(", ");
+                changed += self.conn.execute(
+                    &format!(
+                        "UPDATE bookmarks SET is_favorite = {favorite} \
+                         WHERE id IN ({placeholders}) AND is_favorite != {favorite}",
+                        favorite = favorite as i32
+                    ),
+                    params_from_iter(chunk.iter()),
+                )?;
+            }
+            Ok(changed)
+        })?;
+
+        if changed > 0 {
+            self.invalidate_stats()?;
+        }
+        Ok(changed)
+    }
+
+    /// Toggle archived status for a bookmark
+    pub fn toggle_archived(&self, id: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        self.conn.execute(
+            "UPDATE bookmarks SET is_archived = NOT is_archived WHERE id = ?1",
+            params![id],
+        )?;
+
+        // Return the new archived status
+        let is_archived: bool = self.conn.query_row(
+            "SELECT is_archived FROM bookmarks WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )?;
+
+        Ok(is_archived)
+    }
+
+    /// Set archived status for a bookmark
+    pub fn set_archived(&self, id: &str, archived: bool) -> Result<()> {
+        self.ensure_writable()?;
+        self.conn.execute(
+            "UPDATE bookmarks SET is_archived = ?2 WHERE id = ?1",
+            params![id, archived as i32],
+        )?;
+        Ok(())
+    }
+
+    /// Get archived bookmarks
+    pub fn get_archived(&self, offset: usize, limit: usize) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"SELECT {BOOKMARK_COLUMNS}
+               FROM {BOOKMARK_TABLES}
+               WHERE b.is_archived = 1 AND b.deleted_at IS NULL
+               ORDER BY {NEWEST_FIRST}
+               LIMIT ?1 OFFSET ?2"#
+        ))?;
+
+        let mut bookmarks: Vec<Bookmark> = stmt
+            .query_map(params![limit as i64, offset as i64], |row| {
+                self.row_to_bookmark(row)
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        self.hydrate_bookmarks(&mut bookmarks)?;
+
+        Ok(bookmarks)
+    }
+
+    /// Get all favorite bookmarks
+    pub fn get_favorites(&self, offset: usize, limit: usize) -> Result<Vec<Bookmark>> {
+        let filters = SearchFilters::new().with_favorites_only(true);
+        Ok(self
+            .query_bookmarks(&filters, Pagination::new(offset, limit))?
+            .items)
+    }
+
+    /// `get_favorites` plus the total number of favorites
+    pub fn get_favorites_page(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<PaginatedResponse<Bookmark>> {
+        let filters = SearchFilters::new().with_favorites_only(true);
+        self.query_bookmarks(&filters, Pagination::new(offset, limit))
+    }
+
+    /// Count the bookmarks `get_favorites` pages through
+    pub fn count_favorites(&self) -> Result<i64> {
+        self.count_matching(&SearchFilters::new().with_favorites_only(true))
+    }
+
+    /// Bookmarks with at least one attachment of `media_type`, newest first
+    pub fn get_bookmarks_with_media_type(
+        &self,
+        media_type: MediaType,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Bookmark>> {
+        let filters = SearchFilters::new().with_media_type(Some(media_type));
+        Ok(self
+            .query_bookmarks(&filters, Pagination::new(offset, limit))?
+            .items)
+    }
+
+    /// Get bookmarks within a date range
+    pub fn get_bookmarks_by_date_range(
+        &self,
+        from: Option<chrono::DateTime<chrono::Utc>>,
+        to: Option<chrono::DateTime<chrono::Utc>>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Bookmark>> {
+        let filters = SearchFilters::new().with_date_range(from, to);
+        Ok(self
+            .query_bookmarks(&filters, Pagination::new(offset, limit))?
+            .items)
+    }
+
+    /// Advanced search with filters (first page only)
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_with_filters(
+        &self,
+        query: Option<&str>,
+        tag: Option<&str>,
+        author: Option<&str>,
+        from_date: Option<chrono::DateTime<chrono::Utc>>,
+        to_date: Option<chrono::DateTime<chrono::Utc>>,
+        favorites_only: bool,
+        has_media: Option<bool>,
+        limit: usize,
+    ) -> Result<Vec<Bookmark>> {
+        let page = self.search_with_filters_page(
+            query,
+            tag.as_slice(),
+            TagMatch::All,
+            author,
+            from_date,
+            to_date,
+            favorites_only,
+            has_media,
+            None,
+            0,
+            limit,
+        )?;
+
+        Ok(page.items)
+    }
+
+    /// Advanced search with filters, returning one page plus the total match count
+    #[allow(clippy::too_many_arguments)]
+    pub fn search_with_filters_page(
+        &self,
+        query: Option<&str>,
+        tags: &[&str],
+        tag_match: TagMatch,
+        author: Option<&str>,
+        from_date: Option<chrono::DateTime<chrono::Utc>>,
+        to_date: Option<chrono::DateTime<chrono::Utc>>,
+        favorites_only: bool,
+        has_media: Option<bool>,
+        media_type: Option<MediaType>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<PaginatedResponse<Bookmark>> {
+        let (where_clause, params) = self.build_filtered_where_clause(
+            query,
+            true,
+            tags,
+            tag_match,
+            author,
+            from_date,
+            to_date,
+            favorites_only,
+            has_media,
+            media_type,
+        );
+
+        self.filtered_page(where_clause, params, NEWEST_FIRST, offset, limit)
+    }
+
+    /// One page of bookmarks matching `where_clause`, plus the total match count
+    fn filtered_page(
+        &self,
+        where_clause: String,
+        mut params: Vec<Value>,
+        order_by: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<PaginatedResponse<Bookmark>> {
+        let overall_started = std::time::Instant::now();
+        let filter_params = params.len();
+
+        // Single query: data + total count via window function (no second COUNT query)
+        let mut sql = format!(
+            r#"SELECT {BOOKMARK_COLUMNS},
+                      COUNT(*) OVER() AS total_count
+               FROM {BOOKMARK_TABLES}"#
+        );
+        sql.push_str(" WHERE ");
+        sql.push_str(&where_clause);
+        let limit_placeholder = bind(&mut params, Value::Integer(limit as i64));
+        let offset_placeholder = bind(&mut params, Value::Integer(offset as i64));
+        sql.push_str(&format!(
+            " ORDER BY {order_by} LIMIT {limit_placeholder} OFFSET {offset_placeholder}"
+        ));
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let query_started = std::time::Instant::now();
+
+        let mut total: i64 = 0;
+        let mut bookmarks = Vec::new();
+        let mut rows = stmt.query(params_from_iter(params.iter()))?;
+        while let Some(row) = rows.next()? {
+            // Named column access — safe against projection reordering
+            total = row.get::<_, i64>("total_count")?;
+            bookmarks.push(self.row_to_bookmark(row)?);
+        }
+        drop(rows);
+        // The window count is only visible on returned rows; a page past the end
+        // still needs the real total, so fall back to a COUNT query.
+        if bookmarks.is_empty() && offset > 0 {
+            total = self.conn.query_row(
+                &format!("SELECT COUNT(*) FROM bookmarks b WHERE {where_clause}"),
+                params_from_iter(params[..filter_params].iter()),
+                |row| row.get(0),
+            )?;
+        }
+        let query_elapsed = query_started.elapsed();
+
+        let hydrate_started = std::time::Instant::now();
+        self.hydrate_bookmarks(&mut bookmarks)?;
+        eprintln!(
+            "[eterea][db][search_with_filters_page] offset={} limit={} total={} rows={} query={}ms hydrate={}ms total={}ms",
+            offset,
+            limit,
+            total,
+            bookmarks.len(),
+            query_elapsed.as_millis(),
+            hydrate_started.elapsed().as_millis(),
+            overall_started.elapsed().as_millis()
+        );
+
+        Ok(PaginatedResponse::new(bookmarks, total, offset, limit))
+    }
+
+    /// One page of the bookmarks matching `filters`, newest first, with the
+    /// total match count. The single entry point the listing methods wrap, so
+    /// fetch and count always agree.
+    pub fn query_bookmarks(
+        &self,
+        filters: &SearchFilters,
+        page: Pagination,
+    ) -> Result<PaginatedResponse<Bookmark>> {
+        let (where_clause, params) = self.search_filters_clause(filters);
+        let order_by = sort_order(filters.sort_by);
+        self.filtered_page(where_clause, params, order_by, page.offset, page.limit)
+    }
+
+    /// WHERE clause and parameters for `filters`
+    fn search_filters_clause(&self, filters: &SearchFilters) -> (String, Vec<Value>) {
+        let tags = filters.tags.iter().map(String::as_str).collect::<Vec<_>>();
+        let (mut where_clause, mut params) = self.build_filtered_where_clause(
+            filters.query.as_deref(),
+            filters.prefix_match,
+            &tags,
+            filters.tag_match,
+            filters.author.as_deref(),
+            filters.from_date,
+            filters.to_date,
+            filters.favorites_only,
+            filters.has_media,
+            filters.media_type,
+        );
+
+        if let Some(from) = filters.from_imported {
+            let placeholder = bind(&mut params, Value::Integer(from.timestamp()));
+            where_clause.push_str(&format!(" AND b.imported_at >= {placeholder}"));
+        }
+        if let Some(to) = filters.to_imported {
+            let placeholder = bind(&mut params, Value::Integer(to.timestamp()));
+            where_clause.push_str(&format!(" AND b.imported_at <= {placeholder}"));
+        }
+
+        (where_clause, params)
+    }
+
+    /// Bookmarks matching `filters` in `query_bookmarks` order (all of them
+    /// when `limit` is `None`)
+    pub(crate) fn bookmarks_matching(
+        &self,
+        filters: &SearchFilters,
+        limit: Option<usize>,
+    ) -> Result<Vec<Bookmark>> {
+        let (where_clause, mut params) = self.search_filters_clause(filters);
+        // SQLite treats a negative LIMIT as no limit
+        let limit = bind(
+            &mut params,
+            Value::Integer(limit.map_or(-1, |limit| limit as i64)),
+        );
+
+        let mut stmt = self.conn.prepare(&format!(
+            r#"SELECT {BOOKMARK_COLUMNS}
+               FROM {BOOKMARK_TABLES}
+               WHERE {where_clause}
+               ORDER BY {order_by}
+               LIMIT {limit}"#,
+            order_by = sort_order(filters.sort_by),
+        ))?;
+        let mut bookmarks: Vec<Bookmark> = stmt
+            .query_map(params_from_iter(params.iter()), |row| {
+                self.row_to_bookmark(row)
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        self.hydrate_bookmarks(&mut bookmarks)?;
+
+        Ok(bookmarks)
+    }
+
+    /// Count the bookmarks `filters` would export, without fetching them
+    pub fn count_matching(&self, filters: &SearchFilters) -> Result<i64> {
+        let (where_clause, params) = self.search_filters_clause(filters);
+        self.conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM bookmarks b WHERE {where_clause}"),
+                params_from_iter(params.iter()),
+                |row| row.get(0),
+            )
+            .map_err(Into::into)
+    }
+
+    /// Tag, author, media and favorite counts over the bookmarks matching
+    /// `query` (which replaces `filters.query`) and the rest of `filters`.
+    ///
+    /// The match set is materialized once in a temp table so the full-text
+    /// query runs once rather than once per facet.
+    pub fn search_facets(&self, query: &str, filters: &SearchFilters) -> Result<Facets> {
+        let mut filters = filters.clone();
+        filters.query = Some(query.to_string()).filter(|q| !q.trim().is_empty());
+        let (where_clause, params) = self.search_filters_clause(&filters);
+
+        self.conn
+            .execute_batch("DROP TABLE IF EXISTS temp.facet_matches")?;
+        self.conn.execute(
+            &format!(
+                "CREATE TEMP TABLE facet_matches AS
+                 SELECT b.id, b.author_handle, b.has_media, b.is_favorite
+                 FROM bookmarks b WHERE {where_clause}"
+            ),
+            params_from_iter(params.iter()),
+        )?;
+
+        let facets = (|| -> Result<Facets> {
+            let counted = |sql: &str| -> Result<Vec<(String, i64)>> {
+                let mut stmt = self.conn.prepare(sql)?;
+                let rows = stmt
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .filter_map(|r| r.ok())
+                    .collect();
+                Ok(rows)
+            };
+            let tags = counted(
+                r#"SELECT t.name, COUNT(*) AS count
+                   FROM facet_matches m
+                   JOIN bookmark_tags bt ON bt.bookmark_id = m.id
+                   JOIN tags t ON t.id = bt.tag_id
+                   GROUP BY t.id
+                   ORDER BY count DESC, t.name"#,
+            )?;
+            let authors = counted(
+                r#"SELECT author_handle, COUNT(*) AS count
+                   FROM facet_matches
+                   GROUP BY author_handle
+                   ORDER BY count DESC, author_handle"#,
+            )?;
+            let (media, favorites) = self.conn.query_row(
+                "SELECT COALESCE(SUM(has_media = 1), 0), COALESCE(SUM(is_favorite = 1), 0)
+                 FROM facet_matches",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            Ok(Facets {
+                tags,
+                authors,
+                media,
+                favorites,
+            })
+        })();
+
+        self.conn
+            .execute_batch("DROP TABLE IF EXISTS temp.facet_matches")?;
+        facets
+    }
+
+    /// Write exactly what `query_bookmarks` lists for `filters` (every page,
+    /// in the same order) to `writer` in `format`, so "export these results"
+    /// matches the list on screen. HTML highlights the filter's query.
+    /// Returns how many bookmarks were written.
+    pub fn export_query(
+        &self,
+        filters: &SearchFilters,
+        format: ExportFormat,
+        mut writer: impl Write,
+    ) -> Result<usize> {
+        let bookmarks = self.bookmarks_matching(filters, None)?;
+        match format {
+            ExportFormat::Json => serde_json::to_writer_pretty(&mut writer, &bookmarks)?,
+            ExportFormat::Csv => export::write_csv(&bookmarks, writer)?,
+            ExportFormat::Markdown => export::write_markdown(&bookmarks, writer)?,
+            ExportFormat::Html => export::write_html(&bookmarks, filters.query.as_deref(), writer)?,
+        }
+        Ok(bookmarks.len())
+    }
+
+    /// `export_query` as a Markdown digest
+    pub fn export_markdown(&self, filters: &SearchFilters, writer: impl Write) -> Result<usize> {
+        self.export_query(filters, ExportFormat::Markdown, writer)
+    }
+
+    /// `export_query` as a standalone HTML page
+    pub fn export_html(&self, filters: &SearchFilters, writer: impl Write) -> Result<usize> {
+        self.export_query(filters, ExportFormat::Html, writer)
+    }
+
+    /// Live bookmarks that no import has contained since `since`, least
+    /// recently seen first. After re-importing a full export, these are the
+    /// bookmarks it no longer lists: candidates for removal.
+    pub fn bookmarks_not_seen_since(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(&format!(
+            r#"SELECT {BOOKMARK_COLUMNS}
+               FROM {BOOKMARK_TABLES}
+               WHERE b.last_seen_at < ?1 AND b.deleted_at IS NULL
+               ORDER BY b.last_seen_at, b.id"#
+        ))?;
+        let mut bookmarks: Vec<Bookmark> = stmt
+            .query_map(params![since.timestamp()], |row| self.row_to_bookmark(row))?
+            .filter_map(|r| r.ok())
+            .collect();
+        self.hydrate_bookmarks(&mut bookmarks)?;
+        Ok(bookmarks)
+    }
+
+    /// Write what changed after `since` to `writer` as a JSON `SyncExport`, for
+    /// incremental sync: bookmarks imported or edited (tags, notes,
+    /// annotations, favorite, author, ...) or taken out of the trash since
+    /// then, and tombstones for bookmarks trashed or deleted since then.
+    /// Returns how many bookmarks and tombstones were written.
+    ///
+    /// Changes are timed when they are written, not by `imported_at`. The
+    /// export runs in an IMMEDIATE transaction, so an import still writing
+    /// commits first and everything timed up to `until` is visible. The window
+    /// ends at the last whole second, so nothing written later in the current
+    /// second is missed by the next export.
+    pub fn export_json_since(
+        &self,
+        since: chrono::DateTime<chrono::Utc>,
+        writer: impl Write,
+    ) -> Result<(usize, usize)> {
+        let tx = Transaction::new_unchecked(&self.conn, TransactionBehavior::Immediate)?;
+        let until = chrono::Utc::now().timestamp() - 1;
+        // A bookmark counts once, as it was at its latest change in the window
+        let mut stmt = tx.prepare(
+            r#"SELECT c.bookmark_id, c.deleted, c.changed_at FROM sync_changes c
+               WHERE c.changed_at > ?1 AND c.changed_at <= ?2
+                 AND c.seq = (SELECT MAX(seq) FROM sync_changes
+                              WHERE bookmark_id = c.bookmark_id AND changed_at <= ?2)
+               ORDER BY c.seq"#,
+        )?;
+        let changes = stmt
+            .query_map(params![since.timestamp(), until], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, bool>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        drop(stmt);
+
+        let mut changed = Vec::new();
+        let mut deleted = Vec::new();
+        for (id, is_deleted, changed_at) in changes {
+            if is_deleted {
+                deleted.push(Tombstone {
+                    id,
+                    deleted_at: chrono::DateTime::from_timestamp(changed_at, 0).unwrap_or_default(),
+                });
+            } else {
+                changed.push(id);
+            }
+        }
+        let mut bookmarks = Vec::with_capacity(changed.len());
+        for chunk in changed.chunks(MAX_IN_PARAMS) {
+            bookmarks.extend(
+                self.get_bookmarks_by_ids(&chunk.iter().map(String::as_str).collect::<Vec<_>>())?,
+            );
+        }
+        tx.commit()?;
+
+        let export = SyncExport {
+            since,
+            until: chrono::DateTime::from_timestamp(until, 0).unwrap_or_default(),
+            bookmarks,
+            deleted,
+        };
+        serde_json::to_writer(writer, &export)?;
+        Ok((export.bookmarks.len(), export.deleted.len()))
+    }
+
+    /// Write the tag taxonomy to `writer` as a JSON `TagMap`: every tag with
+    /// its bookmark count and the former names merged or renamed into it.
+    /// Bookmarks themselves aren't included. Returns how many tags were written.
+    pub fn export_tag_map(&self, writer: impl Write) -> Result<usize> {
+        let mut aliases: HashMap<String, Vec<String>> = HashMap::new();
+        let mut stmt = self
+            .conn
+            .prepare("SELECT alias, tag FROM tag_aliases ORDER BY alias")?;
+        for (alias, tag) in stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .filter_map(|r| r.ok())
+        {
+            aliases.entry(tag.to_lowercase()).or_default().push(alias);
+        }
+
+        let mut stmt = self.conn.prepare(
+            r#"SELECT t.name, COUNT(b.id) as count
+               FROM tags t
+               LEFT JOIN bookmark_tags bt ON bt.tag_id = t.id
+               LEFT JOIN bookmarks b ON b.id = bt.bookmark_id AND b.deleted_at IS NULL
+               GROUP BY t.id
+               ORDER BY count DESC, t.name ASC"#,
+        )?;
+        let tags = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .filter_map(|r| r.ok())
+            .map(|(name, count)| TagMapEntry {
+                aliases: aliases.remove(&name.to_lowercase()).unwrap_or_default(),
+                name,
+                count,
+            })
+            .collect();
+
+        let map = TagMap { tags };
+        serde_json::to_writer_pretty(writer, &map)?;
+        Ok(map.tags.len())
+    }
+
+    /// Apply a `TagMap` from `export_tag_map` to this library, in one
+    /// transaction: local tags named like an alias are renamed to the alias's
+    /// tag, or merged into it when it already exists here; local tags that
+    /// only differ in case take the map's spelling; missing tags are created.
+    /// Aliases are recorded so the taxonomy can be exported again.
+    pub fn import_tag_map(&self, reader: impl Read) -> Result<TagMapReport> {
+        self.ensure_writable()?;
+        let map: TagMap = serde_json::from_reader(reader)?;
+        let mut report = TagMapReport::default();
+
+        let changed = self.with_transaction(|| {
+            let mut changed = BTreeSet::new();
+            for entry in &map.tags {
+                let name = entry.name.trim();
+                if name.is_empty() {
+                    continue;
+                }
+                for alias in &entry.aliases {
+                    let alias = alias.trim();
+                    if alias.is_empty() || alias.eq_ignore_ascii_case(name) {
+                        continue;
+                    }
+                    match self.rename_tag_internal(alias, name)? {
+                        Some(ids) => {
+                            report.renamed += 1;
+                            changed.extend(ids);
+                        }
+                        None => self.record_tag_alias(alias, name)?,
+                    }
+                }
+                match self.find_tag(name)? {
+                    None => {
+                        self.conn
+                            .execute("INSERT INTO tags (name) VALUES (?1)", params![name])?;
+                        report.created += 1;
+                    }
+                    Some((_, stored)) if stored != name => {
+                        if let Some(ids) = self.rename_tag_internal(&stored, name)? {
+                            report.renamed += 1;
+                            changed.extend(ids);
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
+            Ok(changed)
+        })?;
+        report.bookmarks_changed = self.finish_tag_rewrite(changed)?;
+        Ok(report)
+    }
+
+    /// Count bookmarks matching the same filters as `search_with_filters_page`
+    #[allow(clippy::too_many_arguments)]
+    pub fn count_with_filters(
+        &self,
+        query: Option<&str>,
+        tags: &[&str],
+        tag_match: TagMatch,
+        author: Option<&str>,
+        from_date: Option<chrono::DateTime<chrono::Utc>>,
+        to_date: Option<chrono::DateTime<chrono::Utc>>,
+        favorites_only: bool,
+        has_media: Option<bool>,
+        media_type: Option<MediaType>,
+    ) -> Result<i64> {
+        let (where_clause, params) = self.build_filtered_where_clause(
+            query,
+            true,
+            tags,
+            tag_match,
+            author,
+            from_date,
+            to_date,
+            favorites_only,
+            has_media,
+            media_type,
+        );
+
+        let mut sql = String::from("SELECT COUNT(*) FROM bookmarks b");
+        sql.push_str(" WHERE ");
+        sql.push_str(&where_clause);
+
+        self.conn
+            .query_row(&sql, params_from_iter(params.iter()), |row| row.get(0))
+            .map_err(Into::into)
+    }
+
+    /// Build a WHERE clause using subqueries — no outer JOINs means no DISTINCT needed.
+    /// FTS, tag and media-type filters use IN-subqueries; has_media uses the denormalized column.
+    ///
+    /// Every user-supplied value is bound through a numbered placeholder (`?1`, `?2`, …);
+    /// only structural SQL (column names, subqueries) is ever formatted into the string.
+    #[allow(clippy::too_many_arguments)]
+    fn build_filtered_where_clause(
+        &self,
+        query: Option<&str>,
+        prefix_match: bool,
+        tags: &[&str],
+        tag_match: TagMatch,
+        author: Option<&str>,
+        from_date: Option<chrono::DateTime<chrono::Utc>>,
+        to_date: Option<chrono::DateTime<chrono::Utc>>,
+        favorites_only: bool,
+        has_media: Option<bool>,
+        media_type: Option<MediaType>,
+    ) -> (String, Vec<Value>) {
+        // Trashed bookmarks never match a search
+        let mut conditions = vec!["b.deleted_at IS NULL".to_string()];
+        let mut params = Vec::<Value>::new();
+
+        if let Some(q) = query {
+            if !q.trim().is_empty() {
+                // FTS via subquery: query the virtual table first (its optimized MATCH path),
+                // then look up bookmark_id via the rowid link to our content table.
+                // No outer JOIN → no row multiplication, no DISTINCT needed.
+                let fts_query = Self::prepare_fts_query(q, prefix_match);
+                if fts_query.is_empty() {
+                    // Nothing but punctuation: no bookmark can match
+                    conditions.push("0".to_string());
+                } else {
+                    let placeholder = bind(&mut params, Value::Text(fts_query));
+                    let trigram = if self.content_trigram && !has_field_terms(q) {
+                        format!(
+                            " UNION SELECT fc.bookmark_id FROM bookmarks_trigram tg \
+                             JOIN bookmarks_fts_content fc ON fc.rowid = tg.rowid \
+                             WHERE bookmarks_trigram MATCH {placeholder}"
+                        )
+                    } else {
+                        String::new()
+                    };
+                    conditions.push(format!(
+                        "b.id IN (SELECT fc.bookmark_id FROM bookmarks_fts fts \
+                         JOIN bookmarks_fts_content fc ON fc.rowid = fts.rowid \
+                         WHERE bookmarks_fts MATCH {placeholder}{trigram})"
+                    ));
+                }
+            }
+        }
+
+        let mut wanted_tags = Vec::<&str>::new();
+        for tag in tags {
+            // tags.name is NOCASE, so dedupe case-insensitively to keep the HAVING count exact
+            if !wanted_tags
+                .iter()
+                .any(|seen| seen.eq_ignore_ascii_case(tag))
+            {
+                wanted_tags.push(tag);
+            }
+        }
+        if !wanted_tags.is_empty() {
+            // Tag via subquery: no outer JOIN, no row multiplication
+            let placeholders = wanted_tags
+                .iter()
+                .map(|tag| bind(&mut params, Value::Text(tag.to_string())))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let having = match tag_match {
+                TagMatch::All => {
+                    let count = bind(&mut params, Value::Integer(wanted_tags.len() as i64));
+                    format!(" GROUP BY bt.bookmark_id HAVING COUNT(DISTINCT t.name) = {count}")
+                }
+                TagMatch::Any => String::new(),
+            };
+            conditions.push(format!(
+                "b.id IN (SELECT bt.bookmark_id FROM bookmark_tags bt \
+                 JOIN tags t ON t.id = bt.tag_id WHERE t.name IN ({placeholders}){having})"
+            ));
+        }
+
+        if let Some(a) = author {
+            let placeholder = bind(&mut params, Value::Text(normalize_handle(a)));
+            conditions.push(format!("b.author_handle = {placeholder}"));
+        }
+
+        if let Some(from) = from_date {
+            let placeholder = bind(&mut params, Value::Integer(from.timestamp()));
+            conditions.push(format!("b.tweeted_at >= {placeholder}"));
+        }
+        if let Some(to) = to_date {
+            let placeholder = bind(&mut params, Value::Integer(to.timestamp()));
+            conditions.push(format!("b.tweeted_at <= {placeholder}"));
+        }
+
+        if favorites_only {
+            conditions.push("b.is_favorite = 1".to_string());
+        }
+
+        if let Some(media_type) = media_type {
+            // A media type implies has_media; the IN-subquery counts a bookmark
+            // with several matching media only once
+            let placeholder = bind(&mut params, Value::Text(media_type.as_str().to_string()));
+            conditions.push(format!(
+                "b.has_media = 1 AND b.id IN (SELECT m.bookmark_id FROM media m WHERE m.media_type = {placeholder})"
+            ));
+        } else if let Some(has) = has_media {
+            // Use denormalized column — no JOIN needed
+            let placeholder = bind(&mut params, Value::Integer(has as i64));
+            conditions.push(format!("b.has_media = {placeholder}"));
+        }
+
+        (conditions.join(" AND "), params)
+    }
+
+    fn hydrate_bookmarks(&self, bookmarks: &mut [Bookmark]) -> Result<()> {
+        if bookmarks.is_empty() {
+            return Ok(());
+        }
+
+        let overall_started = std::time::Instant::now();
+        let bookmark_ids = bookmarks
+            .iter()
+            .map(|bookmark| bookmark.id.as_str())
+            .collect::<Vec<_>>();
+        let tags_started = std::time::Instant::now();
+        let mut tags_by_bookmark = self.tags_for_bookmarks(&bookmark_ids)?;
+        let tags_elapsed = tags_started.elapsed();
+        let media_started = std::time::Instant::now();
+        let mut media_by_bookmark = self.media_for_bookmarks(&bookmark_ids)?;
+        let media_elapsed = media_started.elapsed();
+
+        let hydrated = bookmark_ids.len();
+        for bookmark in bookmarks {
+            bookmark.tags = tags_by_bookmark.remove(&bookmark.id).unwrap_or_default();
+            bookmark.media = media_by_bookmark.remove(&bookmark.id).unwrap_or_default();
+        }
+        eprintln!(
+            "[eterea][db][hydrate_bookmarks] bookmarks={} tags={}ms media={}ms total={}ms",
+            hydrated,
+            tags_elapsed.as_millis(),
+            media_elapsed.as_millis(),
+            overall_started.elapsed().as_millis()
+        );
+        Ok(())
+    }
+
+    /// Tags of several bookmarks in one query per `MAX_IN_PARAMS` ids, keyed by
+    /// bookmark id (bookmarks without tags are absent). Tags are sorted by name.
+    pub fn tags_for_bookmarks(
+        &self,
+        bookmark_ids: &[&str],
+    ) -> Result<HashMap<String, Vec<String>>> {
+        let mut tags_by_bookmark = HashMap::<String, Vec<String>>::new();
+
+        for chunk in bookmark_ids.chunks(MAX_IN_PARAMS) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!(
+                r#"SELECT bt.bookmark_id, t.name
+                   FROM bookmark_tags bt
+                   JOIN tags t ON t.id = bt.tag_id
+                   WHERE bt.bookmark_id IN ({placeholders})
+                   ORDER BY bt.bookmark_id, t.name"#
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let mut rows = stmt.query(params_from_iter(chunk.iter()))?;
+
+            while let Some(row) = rows.next()? {
+                let bookmark_id: String = row.get(0)?;
+                let tag: String = row.get(1)?;
+                tags_by_bookmark.entry(bookmark_id).or_default().push(tag);
+            }
+        }
+
+        Ok(tags_by_bookmark)
+    }
+
+    /// Media of several bookmarks in one query per `MAX_IN_PARAMS` ids, keyed by
+    /// bookmark id (bookmarks without media are absent), in insertion order
+    pub fn media_for_bookmarks(
+        &self,
+        bookmark_ids: &[&str],
+    ) -> Result<HashMap<String, Vec<Media>>> {
+        let mut media_by_bookmark = HashMap::<String, Vec<Media>>::new();
+
+        for chunk in bookmark_ids.chunks(MAX_IN_PARAMS) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!(
+                r#"SELECT bookmark_id, url, media_type, local_path
+                   FROM media
+                   WHERE bookmark_id IN ({placeholders})
+                   ORDER BY bookmark_id, id"#
+            );
+            let mut stmt = self.conn.prepare(&sql)?;
+            let mut rows = stmt.query(params_from_iter(chunk.iter()))?;
+
+            while let Some(row) = rows.next()? {
+                let bookmark_id: String = row.get(0)?;
+                let url: String = row.get(1)?;
+                let media_type =
+                    MediaType::from_name(&row.get::<_, String>(2)?).unwrap_or(MediaType::Unknown);
+                let local_path: Option<String> = row.get(3)?;
+
+                media_by_bookmark
+                    .entry(bookmark_id)
+                    .or_default()
+                    .push(Media {
+                        url,
+                        media_type,
+                        local_path,
+                    });
+            }
+        }
+
+        Ok(media_by_bookmark)
+    }
+
+    /// Get all unique tags with counts
+    pub fn get_all_tags(&self) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT t.name, COUNT(b.id) as count
+               FROM tags t
+               LEFT JOIN bookmark_tags bt ON bt.tag_id = t.id
+               LEFT JOIN bookmarks b ON b.id = bt.bookmark_id AND b.deleted_at IS NULL
+               GROUP BY t.id
+               ORDER BY count DESC"#,
+        )?;
+
+        let tags = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(tags)
+    }
+
+    /// Tags that appear alongside `tag` (case-insensitive), with how many live
+    /// bookmarks carry both; most frequent first, `tag` itself left out
+    pub fn tag_cooccurrence(&self, tag: &str, limit: usize) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT other.name, COUNT(*) AS count
+               FROM tags t
+               JOIN bookmark_tags bt ON bt.tag_id = t.id
+               JOIN bookmarks b ON b.id = bt.bookmark_id AND b.deleted_at IS NULL
+               JOIN bookmark_tags co ON co.bookmark_id = bt.bookmark_id AND co.tag_id != t.id
+               JOIN tags other ON other.id = co.tag_id
+               WHERE t.name = ?1
+               GROUP BY other.id
+               ORDER BY count DESC, other.name ASC
+               LIMIT ?2"#,
+        )?;
+
+        let tags = stmt
+            .query_map(params![tag, limit as i64], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(tags)
+    }
+
+    /// Tags starting with `prefix` (case-insensitive), most used first.
+    ///
+    /// The bound `LIKE 'prefix%'` pattern lets SQLite range-scan the NOCASE
+    /// index on `tags.name`, so only matching tags are counted.
+    pub fn suggest_tags(&self, prefix: &str, limit: usize) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT t.name, COUNT(b.id) as count
+               FROM tags t
+               JOIN bookmark_tags bt ON bt.tag_id = t.id
+               JOIN bookmarks b ON b.id = bt.bookmark_id AND b.deleted_at IS NULL
+               WHERE t.name LIKE ?1 ESCAPE '\'
+               GROUP BY t.id
+               ORDER BY count DESC, t.name ASC
+               LIMIT ?2"#,
+        )?;
+
+        let tags = stmt
+            .query_map(params![like_prefix(prefix), limit as i64], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(tags)
+    }
+
+    /// Authors whose handle or display name starts with `prefix` (ignoring case
+    /// and, for names, accents; a leading `@` is ignored), as `(handle, name, bookmark_count)`, most
+    /// bookmarked first.
+    ///
+    /// `authors` holds one row per handle, so the prefix scan stays small; counts
+    /// come from `idx_bookmarks_author_handle`.
+    pub fn suggest_authors(
+        &self,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT a.handle, a.name, COUNT(b.id) as count
+               FROM authors a
+               JOIN bookmarks b ON b.author_handle = a.handle AND b.deleted_at IS NULL
+               WHERE a.handle LIKE ?1 ESCAPE '\' OR unaccent(a.name) LIKE ?2 ESCAPE '\'
+               GROUP BY a.handle
+               ORDER BY count DESC, a.handle ASC
+               LIMIT ?3"#,
+        )?;
+
+        let prefix = prefix.trim().trim_start_matches('@');
+        let authors = stmt
+            .query_map(
+                params![
+                    like_prefix(prefix),
+                    like_prefix(&unaccent(prefix)),
+                    limit as i64
+                ],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                },
+            )?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(authors)
+    }
+
+    /// Tags of the most recently imported live bookmarks, for "recent" chips:
+    /// each tag once, ordered by the newest `imported_at` among its bookmarks
+    pub fn recent_tags(&self, limit: usize) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT t.name
+               FROM tags t
+               JOIN bookmark_tags bt ON bt.tag_id = t.id
+               JOIN bookmarks b ON b.id = bt.bookmark_id AND b.deleted_at IS NULL
+               GROUP BY t.id
+               ORDER BY MAX(b.imported_at) DESC, t.name ASC
+               LIMIT ?1"#,
+        )?;
+
+        let tags = stmt
+            .query_map(params![limit as i64], |row| row.get::<_, String>(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(tags)
+    }
+
+    /// Authors of the most recently imported live bookmarks, as
+    /// `(handle, name)`: each author once, ordered like `recent_tags`
+    pub fn recent_authors(&self, limit: usize) -> Result<Vec<(String, String)>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT a.handle, a.name
+               FROM authors a
+               JOIN bookmarks b ON b.author_handle = a.handle AND b.deleted_at IS NULL
+               GROUP BY a.handle
+               ORDER BY MAX(b.imported_at) DESC, a.handle ASC
+               LIMIT ?1"#,
+        )?;
+
+        let authors = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(authors)
+    }
+
+    /// Bookmark counts per `period` of `tweeted_at` (UTC), oldest first.
+    /// Buckets are labelled like `2024-05-31`, `2024-W22` (an ISO week),
+    /// `2024-05` or `2024`; periods without bookmarks are left out.
+    pub fn bookmark_counts_by_period(&self, period: Period) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT strftime(?1, tweeted_at, 'unixepoch') AS bucket, COUNT(*)
+               FROM bookmarks
+               WHERE deleted_at IS NULL
+               GROUP BY bucket
+               ORDER BY bucket"#,
+        )?;
+        let counts = stmt
+            .query_map(params![period.strftime_format()], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(counts)
+    }
+
+    /// Get database statistics.
+    ///
+    /// The result is cached in memory and as a snapshot in `app_metadata`, and
+    /// is never stale: writes through this handle drop both, and writes from
+    /// other connections are caught by `PRAGMA data_version`. The first call
+    /// after a write pays for the full recomputation; for a plain total, such as
+    /// paging `get_bookmarks`, use the much cheaper `count_bookmarks`.
+    pub fn get_stats(&self) -> Result<BookmarkStats> {
+        let overall_started = std::time::Instant::now();
+        let version: i64 = self
+            .conn
+            .query_row("PRAGMA data_version", [], |row| row.get(0))?;
+        if let Some((cached_at, stats)) = &*self.stats_cache.borrow() {
+            if *cached_at == version {
+                return Ok(stats.clone());
+            }
+        }
+
+        if let Some(snapshot) = self.get_metadata(STATS_SNAPSHOT_METADATA_KEY)? {
+            if let Ok(stats) = serde_json::from_str::<BookmarkStats>(&snapshot) {
+                eprintln!(
+                    "[eterea][db][get_stats] snapshot-hit total={}ms",
+                    overall_started.elapsed().as_millis()
+                );
+                *self.stats_cache.borrow_mut() = Some((version, stats.clone()));
+                return Ok(stats);
+            }
+        }
+
+        let compute_started = std::time::Instant::now();
+        let stats = self.compute_stats()?;
+        if !self.read_only {
+            self.persist_stats_snapshot(&stats)?;
+        }
+        eprintln!(
+            "[eterea][db][get_stats] recompute compute={}ms total={}ms",
+            compute_started.elapsed().as_millis(),
+            overall_started.elapsed().as_millis()
+        );
+        *self.stats_cache.borrow_mut() = Some((version, stats.clone()));
+        Ok(stats)
+    }
+
+    fn compute_stats(&self) -> Result<BookmarkStats> {
+        let total_bookmarks: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM bookmarks WHERE deleted_at IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+
+        // Authors outlive their bookmarks, so only count those still referenced
+        let unique_authors: i64 = self.conn.query_row(
+            r#"SELECT COUNT(*) FROM authors a
+               WHERE EXISTS (
+                   SELECT 1 FROM bookmarks b
+                   WHERE b.author_handle = a.handle AND b.deleted_at IS NULL
+               )"#,
+            [],
+            |row| row.get(0),
+        )?;
+
+        let unique_tags: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0))?;
+
+        let favorite_bookmarks: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM bookmarks WHERE is_favorite = 1 AND deleted_at IS NULL",
+            [],
+            |row| row.get(0),
+        )?;
+
+        let earliest_date = self.conn.query_row(
+            "SELECT MIN(tweeted_at) FROM bookmarks WHERE deleted_at IS NULL",
+            [],
+            |row| row.get::<_, Option<i64>>(0),
+        )?;
+
+        let latest_date = self.conn.query_row(
+            "SELECT MAX(tweeted_at) FROM bookmarks WHERE deleted_at IS NULL",
+            [],
+            |row| row.get::<_, Option<i64>>(0),
+        )?;
+
+        let top_tags = self.get_all_tags()?;
+
+        use chrono::TimeZone;
+
+        Ok(BookmarkStats {
+            total_bookmarks,
+            unique_authors,
+            unique_tags,
+            favorite_bookmarks,
+            earliest_date: earliest_date.map(|ts| chrono::Utc.timestamp_opt(ts, 0).unwrap()),
+            latest_date: latest_date.map(|ts| chrono::Utc.timestamp_opt(ts, 0).unwrap()),
+            top_tags,
+        })
+    }
+
+    fn persist_stats_snapshot(&self, stats: &BookmarkStats) -> Result<()> {
+        let payload = serde_json::to_string(stats).map_err(|error| {
+            Error::Other(format!("Failed to serialize stats snapshot: {error}"))
+        })?;
+        self.set_metadata(STATS_SNAPSHOT_METADATA_KEY, &payload)
+    }
+
+    /// Drop the cached stats after a write; the next `get_stats` recomputes them
+    fn invalidate_stats(&self) -> Result<()> {
+        self.stats_cache.take();
+        self.conn.execute(
+            "DELETE FROM app_metadata WHERE key = ?1",
+            params![STATS_SNAPSHOT_METADATA_KEY],
+        )?;
+        Ok(())
+    }
+
+    /// Convert a database row to a Bookmark
+    fn row_to_bookmark(&self, row: &rusqlite::Row) -> rusqlite::Result<Bookmark> {
+        use chrono::TimeZone;
+
+        let id: String = row.get(0)?;
+        let tweeted_at_ts: i64 = row.get(4)?;
+        let imported_at_ts: i64 = row.get(5)?;
+        let is_favorite: i32 = row.get(11).unwrap_or(0);
+        let is_archived: i32 = row.get(12).unwrap_or(0);
+        let date_estimated: i32 = row.get(13).unwrap_or(0);
+
+        let bookmark = Bookmark {
+            id: id.clone(),
+            tweet_url: row.get(1)?,
+            tweet_id: row.get(14)?,
+            content: row.get(2)?,
+            note_text: row.get(3)?,
+            tweeted_at: chrono::Utc.timestamp_opt(tweeted_at_ts, 0).unwrap(),
+            imported_at: chrono::Utc.timestamp_opt(imported_at_ts, 0).unwrap(),
+            author_handle: row.get(6)?,
+            author_name: row.get(7)?,
+            author_profile_url: row.get(8)?,
+            author_profile_image: row.get(9)?,
+            comments: row.get(10)?,
+            tags: Vec::new(),
+            media: Vec::new(),
+            is_favorite: is_favorite != 0,
+            is_archived: is_archived != 0,
+            date_estimated: date_estimated != 0,
+            content_original_len: row.get::<_, Option<i64>>(15)?.map(|len| len as usize),
+            last_seen_at: row
+                .get::<_, Option<i64>>(16)?
+                .and_then(|ts| chrono::Utc.timestamp_opt(ts, 0).single()),
+            search_text: String::new(),
+        };
+
+        // Note: tags and media are loaded separately for performance
+        // Use load_bookmark_tags() and load_bookmark_media() when needed
+
+        Ok(bookmark)
+    }
+
+    /// Load tags for a bookmark
+    pub fn load_bookmark_tags(&self, bookmark_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT t.name FROM tags t
+               JOIN bookmark_tags bt ON bt.tag_id = t.id
+               WHERE bt.bookmark_id = ?1
+               ORDER BY t.name"#,
+        )?;
+
+        let tags = stmt
+            .query_map(params![bookmark_id], |row| row.get::<_, String>(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(tags)
+    }
+
+    /// Load media for a bookmark
+    pub fn load_bookmark_media(&self, bookmark_id: &str) -> Result<Vec<Media>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT url, media_type, local_path FROM media WHERE bookmark_id = ?1 ORDER BY id",
+        )?;
+
+        let media = stmt
+            .query_map(params![bookmark_id], |row| {
+                let url: String = row.get(0)?;
+                let media_type_str: String = row.get(1)?;
+                let media_type =
+                    MediaType::from_name(&media_type_str).unwrap_or(MediaType::Unknown);
+                Ok(Media {
+                    url,
+                    media_type,
+                    local_path: row.get(2)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(media)
+    }
+
+    /// Record where the media `url` of a bookmark was archived; returns whether
+    /// the bookmark has that media
+    pub fn set_media_local_path(&self, bookmark_id: &str, url: &str, path: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let updated = self.conn.execute(
+            "UPDATE media SET local_path = ?3 WHERE bookmark_id = ?1 AND url = ?2",
+            params![bookmark_id, url, path],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Ids of live bookmarks with media not archived yet, oldest import first
+    pub fn bookmarks_with_unarchived_media(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT b.id
+               FROM bookmarks b
+               WHERE b.has_media = 1 AND b.deleted_at IS NULL
+                 AND EXISTS (SELECT 1 FROM media m
+                             WHERE m.bookmark_id = b.id AND m.local_path IS NULL)
+               ORDER BY b.imported_at, b.id"#,
+        )?;
+
+        let ids = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(ids)
+    }
+
+    /// Look up an author by handle
+    pub fn get_author(&self, handle: &str) -> Result<Option<Author>> {
+        let result = self.conn.query_row(
+            "SELECT handle, name, profile_url, profile_image FROM authors WHERE handle = ?1",
+            params![normalize_handle(handle)],
+            |row| {
+                Ok(Author {
+                    handle: row.get(0)?,
+                    name: row.get(1)?,
+                    profile_url: row.get(2)?,
+                    profile_image: row.get(3)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(author) => Ok(Some(author)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Authors missing a profile URL or image, by handle
+    pub fn authors_missing_profile(&self) -> Result<Vec<Author>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT handle, name, profile_url, profile_image FROM authors
+               WHERE COALESCE(trim(profile_url), '') = ''
+                  OR COALESCE(trim(profile_image), '') = ''
+               ORDER BY handle"#,
+        )?;
+        let authors = stmt
+            .query_map([], |row| {
+                Ok(Author {
+                    handle: row.get(0)?,
+                    name: row.get(1)?,
+                    profile_url: row.get(2)?,
+                    profile_image: row.get(3)?,
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(authors)
+    }
+
+    /// Fill an author's empty profile URL and/or image, on the `authors` row
+    /// and their bookmarks. Values already stored are never overwritten, so
+    /// this is safe to repeat. Returns whether the author row changed.
+    pub fn fill_author_profile(
+        &self,
+        handle: &str,
+        profile_url: Option<&str>,
+        profile_image: Option<&str>,
+    ) -> Result<bool> {
+        self.ensure_writable()?;
+        let handle = normalize_handle(handle);
+        let count = self.with_transaction(|| {
+            let count = self.conn.execute(
+                r#"UPDATE authors SET
+                       profile_url = CASE WHEN COALESCE(trim(profile_url), '') = ''
+                                          THEN COALESCE(?2, profile_url) ELSE profile_url END,
+                       profile_image = CASE WHEN COALESCE(trim(profile_image), '') = ''
+                                            THEN COALESCE(?3, profile_image) ELSE profile_image END
+                   WHERE handle = ?1
+                     AND ((COALESCE(trim(profile_url), '') = '' AND ?2 IS NOT NULL)
+                       OR (COALESCE(trim(profile_image), '') = '' AND ?3 IS NOT NULL))"#,
+                params![handle, profile_url, profile_image],
+            )?;
+            self.conn.execute(
+                r#"UPDATE bookmarks SET
+                       author_profile_url = CASE WHEN COALESCE(trim(author_profile_url), '') = ''
+                                                 THEN COALESCE(?2, author_profile_url)
+                                                 ELSE author_profile_url END,
+                       author_profile_image = CASE WHEN COALESCE(trim(author_profile_image), '') = ''
+                                                   THEN COALESCE(?3, author_profile_image)
+                                                   ELSE author_profile_image END
+                   WHERE author_handle = ?1"#,
+                params![handle, profile_url, profile_image],
+            )?;
+            Ok(count)
+        })?;
+        Ok(count > 0)
+    }
+
+    /// Update an author's display name and profile fields.
+    ///
+    /// Bookmarks read author details through the `authors` join, so only the
+    /// search indexes need rewriting to keep name search in sync.
+    pub fn update_author(&self, author: &Author) -> Result<bool> {
+        self.ensure_writable()?;
+        let handle = normalize_handle(&author.handle);
+        let (count, ids) = self.with_transaction(|| {
+            let count = self.conn.execute(
+                r#"UPDATE authors SET name = ?2, profile_url = ?3, profile_image = ?4
+                   WHERE handle = ?1"#,
+                params![
+                    handle,
+                    author.name,
+                    author.profile_url,
+                    author.profile_image,
+                ],
+            )?;
+            if count == 0 {
+                return Ok((0, Vec::new()));
+            }
+            self.conn.execute(
+                "UPDATE bookmarks_fts_content SET author_name = ?2 WHERE author_handle = ?1",
+                params![handle, author.name],
+            )?;
+            let mut stmt = self
+                .conn
+                .prepare("SELECT id FROM bookmarks WHERE author_handle = ?1")?;
+            let ids = stmt
+                .query_map(params![handle], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok((count, ids))
+        })?;
+
+        self.refresh_search_indexes(&ids.iter().map(String::as_str).collect::<Vec<_>>());
+        Ok(count > 0)
+    }
+
+    /// Get a cached link preview fetched within `max_age`
+    pub fn get_cached_preview(&self, url: &str, max_age: Duration) -> Result<Option<LinkPreview>> {
+        use chrono::TimeZone;
+
+        let cutoff = cutoff_timestamp(max_age);
+        let result = self.conn.query_row(
+            r#"SELECT url, final_url, title, description, image_url, site_name, fetched_at,
+                      favicon_url, canonical_url
+               FROM link_previews
+               WHERE url = ?1 AND fetched_at >= ?2"#,
+            params![url, cutoff],
+            |row| {
+                Ok(LinkPreview {
+                    url: row.get(0)?,
+                    final_url: row.get(1)?,
+                    title: row.get(2)?,
+                    description: row.get(3)?,
+                    image_url: row.get(4)?,
+                    site_name: row.get(5)?,
+                    favicon_url: row.get(7)?,
+                    canonical_url: row.get(8)?,
+                    fetched_at: chrono::Utc.timestamp_opt(row.get(6)?, 0).unwrap(),
+                })
+            },
+        );
+
+        match result {
+            Ok(preview) => Ok(Some(preview)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Previously resolved target of a shortened link, if any bookmark has it
+    pub fn get_expanded_url(&self, url: &str) -> Result<Option<String>> {
+        let result = self.conn.query_row(
+            "SELECT expanded_url FROM links WHERE url = ?1 AND expanded_url IS NOT NULL LIMIT 1",
+            params![url],
+            |row| row.get(0),
+        );
+
+        match result {
+            Ok(expanded) => Ok(Some(expanded)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Record where `url` redirects to on every link row that uses it
+    pub fn set_expanded_url(&self, url: &str, expanded_url: &str) -> Result<usize> {
+        self.ensure_writable()?;
+        let updated = self.conn.execute(
+            "UPDATE links SET expanded_url = ?2, expanded_domain = ?3 WHERE url = ?1",
+            params![url, expanded_url, url_domain(expanded_url)],
+        )?;
+        Ok(updated)
+    }
+
+    /// Store (or replace) a link preview in the cache
+    pub fn upsert_preview(&self, preview: &LinkPreview) -> Result<()> {
+        self.ensure_writable()?;
+        self.conn.execute(
+            r#"INSERT INTO link_previews
+                   (url, final_url, title, description, image_url, site_name, fetched_at,
+                    favicon_url, canonical_url)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+               ON CONFLICT(url) DO UPDATE SET
+                   final_url = excluded.final_url,
+                   title = excluded.title,
+                   description = excluded.description,
+                   image_url = excluded.image_url,
+                   site_name = excluded.site_name,
+                   fetched_at = excluded.fetched_at,
+                   favicon_url = excluded.favicon_url,
+                   canonical_url = excluded.canonical_url"#,
+            params![
+                preview.url,
+                preview.final_url,
+                preview.title,
+                preview.description,
+                preview.image_url,
+                preview.site_name,
+                preview.fetched_at.timestamp(),
+                preview.favicon_url,
+                preview.canonical_url,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Delete cached previews older than `max_age`, returning how many were removed
+    pub fn purge_stale_previews(&self, max_age: Duration) -> Result<usize> {
+        self.ensure_writable()?;
+        let cutoff = cutoff_timestamp(max_age);
+        let removed = self
+            .conn
+            .execute("DELETE FROM link_previews WHERE fetched_at < ?1", [cutoff])?;
+        Ok(removed)
+    }
+
+    /// Save `filters` under `name`, replacing any saved search with that name
+    pub fn save_search(&self, name: &str, filters: &SearchFilters) -> Result<()> {
+        self.ensure_writable()?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::Other("saved search name is empty".into()));
+        }
+
+        self.conn.execute(
+            r#"INSERT INTO saved_searches (name, filters, updated_at)
+               VALUES (?1, ?2, ?3)
+               ON CONFLICT(name) DO UPDATE SET
+                   filters = excluded.filters,
+                   updated_at = excluded.updated_at"#,
+            params![
+                name,
+                serde_json::to_string(filters)?,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// All saved searches, by name
+    pub fn list_saved_searches(&self) -> Result<Vec<SavedSearch>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, filters, updated_at FROM saved_searches ORDER BY name COLLATE NOCASE",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?
+            .filter_map(|r| r.ok())
+            .collect::<Vec<_>>();
+
+        rows.into_iter()
+            .map(|(name, filters, updated_at)| {
+                Ok(SavedSearch {
+                    name,
+                    filters: serde_json::from_str(&filters)?,
+                    updated_at: chrono::DateTime::from_timestamp(updated_at, 0).unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    /// Up to `limit` bookmarks matching the saved search `name`, newest first,
+    /// or `None` if there is no search with that name
+    pub fn run_saved_search(&self, name: &str, limit: usize) -> Result<Option<Vec<Bookmark>>> {
+        let result = self.conn.query_row(
+            "SELECT filters FROM saved_searches WHERE name = ?1",
+            [name.trim()],
+            |row| row.get::<_, String>(0),
+        );
+        let filters: SearchFilters = match result {
+            Ok(filters) => serde_json::from_str(&filters)?,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        self.bookmarks_matching(&filters, Some(limit)).map(Some)
+    }
+
+    /// Delete the saved search `name`, returning whether it existed
+    pub fn delete_saved_search(&self, name: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let removed = self
+            .conn
+            .execute("DELETE FROM saved_searches WHERE name = ?1", [name.trim()])?;
+        Ok(removed > 0)
+    }
+
+    /// Create an empty collection. Names are unique, ignoring case.
+    pub fn create_collection(&self, name: &str) -> Result<Collection> {
+        self.ensure_writable()?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(Error::Other("collection name is empty".into()));
+        }
+        if self.collection_id(name)?.is_some() {
+            return Err(Error::Other(format!(
+                "a collection named '{name}' already exists"
+            )));
+        }
+
+        let created_at = chrono::Utc::now().timestamp();
+        self.conn.execute(
+            "INSERT INTO collections (name, created_at) VALUES (?1, ?2)",
+            params![name, created_at],
+        )?;
+        Ok(Collection {
+            id: self.conn.last_insert_rowid(),
+            name: name.to_string(),
+            bookmark_count: 0,
+            created_at: chrono::DateTime::from_timestamp(created_at, 0).unwrap_or_default(),
+        })
+    }
+
+    /// Delete a collection; its bookmarks are kept. Returns whether it existed.
+    pub fn delete_collection(&self, collection_id: i64) -> Result<bool> {
+        self.ensure_writable()?;
+        // `collection_bookmarks` rows go with it (ON DELETE CASCADE)
+        let removed = self.conn.execute(
+            "DELETE FROM collections WHERE id = ?1",
+            params![collection_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// All collections by name, with how many live bookmarks each holds
+    pub fn list_collections(&self) -> Result<Vec<Collection>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT c.id, c.name, COUNT(b.id), c.created_at
+               FROM collections c
+               LEFT JOIN collection_bookmarks cb ON cb.collection_id = c.id
+               LEFT JOIN bookmarks b ON b.id = cb.bookmark_id AND b.deleted_at IS NULL
+               GROUP BY c.id
+               ORDER BY c.name COLLATE NOCASE"#,
+        )?;
+
+        let collections = stmt
+            .query_map([], |row| {
+                Ok(Collection {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    bookmark_count: row.get(2)?,
+                    created_at: chrono::DateTime::from_timestamp(row.get(3)?, 0)
+                        .unwrap_or_default(),
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(collections)
+    }
+
+    /// Put bookmark `bookmark_id` in a collection. Returns `false` if the
+    /// collection or bookmark doesn't exist or it is already there.
+    pub fn add_to_collection(&self, collection_id: i64, bookmark_id: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let added = self.conn.execute(
+            r#"INSERT OR IGNORE INTO collection_bookmarks (collection_id, bookmark_id, added_at)
+               SELECT c.id, b.id, ?3
+               FROM collections c, bookmarks b
+               WHERE c.id = ?1 AND b.id = ?2"#,
+            params![collection_id, bookmark_id, chrono::Utc::now().timestamp()],
+        )?;
+        Ok(added > 0)
+    }
+
+    /// Take a bookmark out of a collection. Returns whether it was there.
+    pub fn remove_from_collection(&self, collection_id: i64, bookmark_id: &str) -> Result<bool> {
+        self.ensure_writable()?;
+        let removed = self.conn.execute(
+            "DELETE FROM collection_bookmarks WHERE collection_id = ?1 AND bookmark_id = ?2",
+            params![collection_id, bookmark_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    /// Move a bookmark from one collection to another in one transaction.
+    /// Returns `false` (changing nothing) if it isn't in `from` or `to`
+    /// doesn't exist.
+    pub fn move_to_collection(&self, bookmark_id: &str, from: i64, to: i64) -> Result<bool> {
+        self.ensure_writable()?;
+        if from == to {
+            return Ok(false);
+        }
+
+        // Nothing is written unless the move goes ahead
+        self.with_transaction(|| {
+            let exists: bool = self.conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM collections WHERE id = ?1)",
+                params![to],
+                |row| row.get(0),
+            )?;
+            if !exists || !self.remove_from_collection(from, bookmark_id)? {
+                return Ok(false);
+            }
+            self.add_to_collection(to, bookmark_id)?;
+            Ok(true)
+        })
+    }
+
+    /// A page of the live bookmarks in a collection, most recently added first
+    pub fn get_collection_bookmarks(
+        &self,
+        collection_id: i64,
+        pagination: Pagination,
+    ) -> Result<PaginatedResponse<Bookmark>> {
+        let Pagination { offset, limit } = pagination;
+        let mut stmt = self.conn.prepare(&format!(
+            r#"SELECT {BOOKMARK_COLUMNS}
+               FROM {BOOKMARK_TABLES}
+               JOIN collection_bookmarks cb ON cb.bookmark_id = b.id
+               WHERE cb.collection_id = ?1 AND b.deleted_at IS NULL
+               ORDER BY cb.added_at DESC, {NEWEST_FIRST}
+               LIMIT ?2 OFFSET ?3"#
+        ))?;
+        let mut bookmarks: Vec<Bookmark> = stmt
+            .query_map(params![collection_id, limit as i64, offset as i64], |row| {
+                self.row_to_bookmark(row)
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        self.hydrate_bookmarks(&mut bookmarks)?;
+
+        let total: i64 = self.conn.query_row(
+            r#"SELECT COUNT(*) FROM collection_bookmarks cb
+               JOIN bookmarks b ON b.id = cb.bookmark_id
+               WHERE cb.collection_id = ?1 AND b.deleted_at IS NULL"#,
+            params![collection_id],
+            |row| row.get(0),
+        )?;
+        Ok(PaginatedResponse::new(bookmarks, total, offset, limit))
+    }
+
+    /// Id of the collection called `name` (ignoring case)
+    fn collection_id(&self, name: &str) -> Result<Option<i64>> {
+        match self.conn.query_row(
+            "SELECT id FROM collections WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        ) {
+            Ok(id) => Ok(Some(id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Copy the whole database to `dest` with SQLite's online backup API, so
+    /// it is consistent even while this (or another) connection is writing.
+    /// An existing file at `dest` is overwritten.
+    pub fn backup_to(&self, dest: &Path) -> Result<()> {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        info!("Backing up database to: {}", dest.display());
+        self.conn
+            .backup(DatabaseName::Main, dest, None::<fn(Progress)>)?;
+        Ok(())
+    }
+
+    /// Replace this database's contents with the backup at `src`.
+    ///
+    /// Everything currently stored is discarded. Settings read when the
+    /// database was opened (tokenizer, trigram index) aren't refreshed, so
+    /// reopen the database after restoring.
+    pub fn restore_from(&mut self, src: &Path) -> Result<()> {
+        self.ensure_writable()?;
+        if !src.is_file() {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("backup not found: {}", src.display()),
+            )));
+        }
+        let previous = match &self.search_backend {
+            Some(_) => self
+                .conn
+                .prepare("SELECT id FROM bookmarks")?
+                .query_map([], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        info!("Restoring database from: {}", src.display());
+        self.conn
+            .restore(DatabaseName::Main, src, None::<fn(Progress)>)?;
+        self.invalidate_stats()?;
+        if self.instant_search_enabled() {
+            self.enable_instant_search()?;
+        }
+        if let Some(backend) = &self.search_backend {
+            // The backend still holds the old library: drop it, index the restored one
+            let previous = previous.iter().map(String::as_str).collect::<Vec<_>>();
+            let result = backend
+                .remove(&previous)
+                .and_then(|()| self.reindex_search_backend());
+            if let Err(error) = result {
+                warn!("Failed to reindex the search backend after restoring: {error}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Store lightweight app metadata as JSON/text.
+    pub fn set_metadata(&self, key: &str, value: &str) -> Result<()> {
+        self.ensure_writable()?;
+        self.conn.execute(
+            r#"INSERT INTO app_metadata (key, value)
+               VALUES (?1, ?2)
+               ON CONFLICT(key) DO UPDATE SET value = excluded.value"#,
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Read lightweight app metadata.
+    pub fn get_metadata(&self, key: &str) -> Result<Option<String>> {
+        let result = self.conn.query_row(
+            "SELECT value FROM app_metadata WHERE key = ?1",
+            params![key],
+            |row| row.get::<_, String>(0),
+        );
+
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+/// Unix timestamp `max_age` before now, saturating for very large durations
+fn cutoff_timestamp(max_age: Duration) -> i64 {
+    let age = i64::try_from(max_age.as_secs()).unwrap_or(i64::MAX);
+    chrono::Utc::now().timestamp().saturating_sub(age)
+}
+
+/// Database location: the explicit path, else a non-empty `$ETEREA_DB_PATH`,
+/// else the platform default
+fn resolve_db_path(explicit: Option<&Path>, env: Option<std::ffi::OsString>) -> PathBuf {
+    if let Some(path) = explicit {
+        return path.to_path_buf();
+    }
+    if let Some(path) = env.filter(|path| !path.is_empty()) {
+        return PathBuf::from(path);
+    }
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("eterea")
+        .join("bookmarks.db")
+}
+
+/// Lowercased stopwords from `options`, or the English defaults
+fn stopword_list(options: &DatabaseOptions) -> Vec<String> {
+    match &options.stopwords {
+        Some(words) => words.iter().map(|word| word.to_lowercase()).collect(),
+        None => ENGLISH_STOPWORDS
+            .iter()
+            .map(|word| word.to_string())
+            .collect(),
+    }
+}
+
+/// Turn SQLite's busy/corrupt failures while opening `path` into errors that
+/// say what to do about them
+fn classify_open_error(path: &Path, error: Error) -> Error {
+    let Error::Database(rusqlite::Error::SqliteFailure(err, _)) = &error else {
+        return error;
+    };
+    match err.code {
+        rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked => {
+            Error::DatabaseLocked(path.to_path_buf())
+        }
+        rusqlite::ErrorCode::DatabaseCorrupt | rusqlite::ErrorCode::NotADatabase => {
+            Error::DatabaseCorrupt(path.to_path_buf())
+        }
+        _ => error,
+    }
+}
+
+/// Whether an insert failed because the bookmark (by `tweet_url` or `id`) exists
+fn is_duplicate_bookmark(error: &Error) -> bool {
+    match error {
+        Error::Database(rusqlite::Error::SqliteFailure(err, message)) => {
+            err.code == rusqlite::ErrorCode::ConstraintViolation
+                && message
+                    .as_deref()
+                    .is_some_and(|message| message.contains("bookmarks."))
+        }
+        _ => false,
+    }
+}
+
+/// FTS `note_text`: the bookmark's note plus annotation text, newline-separated
+fn fts_notes_text(note_text: Option<&str>, annotations: Option<&str>) -> Option<String> {
+    match (note_text, annotations) {
+        (Some(note), Some(annotations)) => Some(format!("{note}\n{annotations}")),
+        (note, annotations) => note.or(annotations).map(str::to_string),
+    }
+}
+
+/// Stored form of an author handle: lowercase, without a leading `@`.
+/// Handles are ASCII, matching SQLite's `lower()` used by the migration.
+fn normalize_handle(handle: &str) -> String {
+    handle.trim().trim_start_matches('@').to_ascii_lowercase()
+}
+
+/// Lowercase `text` and strip diacritics ("Zoë" -> "zoe"), for accent-insensitive
+/// name matching. Also registered as the `unaccent()` SQL function.
+fn unaccent(text: &str) -> String {
+    text.nfd()
+        .filter(|ch| !is_combining_mark(*ch))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// `LIKE` pattern matching values that start with `prefix`, with `%`, `_` and
+/// `\` escaped (pair with `ESCAPE '\'`).
+fn like_prefix(prefix: &str) -> String {
+    let mut pattern = String::with_capacity(prefix.len() + 1);
+    for ch in prefix.trim().chars() {
+        if matches!(ch, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(ch);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Whether `query` has `#tag` or `@handle` terms, which `prepare_fts_query`
+/// turns into column filters the content-only trigram index cannot run.
+fn has_field_terms(query: &str) -> bool {
+    query.split_whitespace().any(|term| {
+        term.strip_prefix(['#', '@'])
+            .is_some_and(|word| word.chars().any(char::is_alphanumeric))
+    })
+}
+
+/// ORDER BY clause for a `SortBy`
+fn sort_order(sort_by: SortBy) -> &'static str {
+    match sort_by {
+        SortBy::TweetedAt => NEWEST_FIRST,
+        SortBy::ImportedAt => NEWEST_IMPORTED_FIRST,
+    }
+}
+
+/// Push a bound value and return its numbered placeholder (`?N`).
+fn bind(params: &mut Vec<Value>, value: Value) -> String {
+    params.push(value);
+    format!("?{}", params.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::BookmarkBuilder;
+    use chrono::{TimeZone, Utc};
+
+    fn sample_bookmark(
+        tweet_id: &str,
+        handle: &str,
+        date: chrono::DateTime<Utc>,
+        tag: &str,
+        with_media: bool,
+    ) -> Bookmark {
+        let mut builder = BookmarkBuilder::new()
+            .tweet_url(format!("https://x.com/{handle}/status/{tweet_id}"))
+            .content(format!("Bookmark {tweet_id} #{tag}"))
+            .tweeted_at(date)
+            .author_handle(handle)
+            .author_name(handle)
+            .add_tag(tag);
+
+        if with_media {
+            builder = builder.add_media("https://pbs.twimg.com/media/example.jpg");
+        }
+
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn search_with_filters_is_parameterized_and_filters_correctly() {
+        let db = Database::open_memory().unwrap();
+        let first = sample_bookmark(
+            "1",
+            "alice",
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            "rust",
+            true,
+        );
+        let second = sample_bookmark(
+            "2",
+            "bob",
+            Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap(),
+            "svelte",
+            false,
+        );
+
+        db.insert_bookmarks(&[first.clone(), second.clone()])
+            .unwrap();
+        db.set_favorite(&first.id, true).unwrap();
+
+        let results = db
+            .search_with_filters(
+                Some("Bookmark"),
+                Some("rust"),
+                Some("alice"),
+                Some(Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap()),
+                Some(Utc.with_ymd_and_hms(2024, 5, 31, 23, 59, 59).unwrap()),
+                true,
+                Some(true),
+                20,
+            )
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].author_handle, "alice");
+
+        let injection_attempt = db
+            .search_with_filters(Some("' OR 1=1 --"), None, None, None, None, false, None, 20)
+            .unwrap();
+        assert!(injection_attempt.is_empty());
+    }
+
+    #[test]
+    fn search_with_filters_binds_quotes_and_fts_syntax_safely() {
+        let db = Database::open_memory().unwrap();
+        let mut quoted = sample_bookmark(
+            "1",
+            "o'brien",
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            "rust's",
+            false,
+        );
+        quoted.content = r#"Notes on "quoted" NEAR(rust) syntax"#.to_string();
+        let other = sample_bookmark(
+            "2",
+            "bob",
+            Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap(),
+            "svelte",
+            false,
+        );
+        db.insert_bookmarks(&[quoted.clone(), other]).unwrap();
+
+        let by_author = db
+            .search_with_filters(None, None, Some("o'brien"), None, None, false, None, 20)
+            .unwrap();
+        assert_eq!(by_author.len(), 1);
+        assert_eq!(by_author[0].id, quoted.id);
+
+        let by_tag = db
+            .search_with_filters(None, Some("rust's"), None, None, None, false, None, 20)
+            .unwrap();
+        assert_eq!(by_tag.len(), 1);
+
+        let fts_syntax = db
+            .search_with_filters(
+                Some(r#""quoted" NEAR(rust)"#),
+                None,
+                Some("o'brien"),
+                None,
+                None,
+                false,
+                None,
+                20,
+            )
+            .unwrap();
+        assert_eq!(fts_syntax.len(), 1);
+        assert_eq!(fts_syntax[0].id, quoted.id);
+
+        let backslash = db
+            .search_with_filters(None, None, Some(r"o\'brien"), None, None, false, None, 20)
+            .unwrap();
+        assert!(backslash.is_empty());
+    }
+
+    #[test]
+    fn search_with_filters_page_paginates_with_stable_totals() {
+        let db = Database::open_memory().unwrap();
+        let bookmarks = (1..=5)
+            .map(|day| {
+                sample_bookmark(
+                    &day.to_string(),
+                    "alice",
+                    Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap(),
+                    "rust",
+                    false,
+                )
+            })
+            .collect::<Vec<_>>();
+        db.insert_bookmarks(&bookmarks).unwrap();
+
+        let first = db
+            .search_with_filters_page(
+                None,
+                &["rust"],
+                TagMatch::All,
+                None,
+                None,
+                None,
+                false,
+                None,
+                None,
+                0,
+                2,
+            )
+            .unwrap();
+        assert_eq!(first.items.len(), 2);
+        assert_eq!(first.total, 5);
+        assert!(first.has_more);
+        assert_eq!(first.items[0].id, bookmarks[4].id);
+
+        let last = db
+            .search_with_filters_page(
+                None,
+                &["rust"],
+                TagMatch::All,
+                None,
+                None,
+                None,
+                false,
+                None,
+                None,
+                4,
+                2,
+            )
+            .unwrap();
+        assert_eq!(last.items.len(), 1);
+        assert_eq!(last.items[0].id, bookmarks[0].id);
+        assert!(!last.has_more);
+
+        let past_end = db
+            .search_with_filters_page(
+                None,
+                &["rust"],
+                TagMatch::All,
+                None,
+                None,
+                None,
+                false,
+                None,
+                None,
+                10,
+                2,
+            )
+            .unwrap();
+        assert!(past_end.items.is_empty());
+        assert_eq!(past_end.total, 5);
+        assert_eq!(
+            db.count_with_filters(
+                None,
+                &["rust"],
+                TagMatch::All,
+                None,
+                None,
+                None,
+                false,
+                None,
+                None
+            )
+            .unwrap(),
+            5
+        );
+    }
+
+    #[test]
+    fn search_with_filters_page_matches_all_or_any_tags() {
+        let db = Database::open_memory().unwrap();
+        let mut both = sample_bookmark(
+            "1",
+            "alice",
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            "rust",
+            false,
+        );
+        both.tags.push("async".to_string());
+        let rust_only = sample_bookmark(
+            "2",
+            "bob",
+            Utc.with_ymd_and_hms(2024, 5, 2, 12, 0, 0).unwrap(),
+            "rust",
+            false,
+        );
+        let async_only = sample_bookmark(
+            "3",
+            "carol",
+            Utc.with_ymd_and_hms(2024, 5, 3, 12, 0, 0).unwrap(),
+            "async",
+            false,
+        );
+        db.insert_bookmarks(&[both.clone(), rust_only, async_only])
+            .unwrap();
+
+        let all = db
+            .search_with_filters_page(
+                None,
+                &["rust", "async", "Rust"],
+                TagMatch::All,
+                None,
+                None,
+                None,
+                false,
+                None,
+                None,
+                0,
+                20,
+            )
+            .unwrap();
+        assert_eq!(all.total, 1);
+        assert_eq!(all.items[0].id, both.id);
+
+        let any = db
+            .search_with_filters_page(
+                None,
+                &["rust", "async"],
+                TagMatch::Any,
+                None,
+                None,
+                None,
+                false,
+                None,
+                None,
+                0,
+                20,
+            )
+            .unwrap();
+        assert_eq!(any.total, 3);
+    }
+
+    #[test]
+    fn get_bookmarks_batches_related_tags_and_media() {
+        let db = Database::open_memory().unwrap();
+        let first = sample_bookmark(
+            "1",
+            "alice",
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            "rust",
+            true,
+        );
+        let mut second = sample_bookmark(
+            "2",
+            "bob",
+            Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap(),
+            "svelte",
+            false,
+        );
+        second.tags.push("frontend".to_string());
+
+        db.insert_bookmarks(&[first.clone(), second.clone()])
+            .unwrap();
+
+        let bookmarks = db.get_bookmarks(0, 10).unwrap();
+
+        assert_eq!(db.count_bookmarks().unwrap(), 2);
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0].author_handle, "bob");
+        assert_eq!(
+            bookmarks[0].tags,
+            vec!["frontend".to_string(), "svelte".to_string()]
+        );
+        assert!(bookmarks[0].media.is_empty());
+        assert_eq!(bookmarks[1].author_handle, "alice");
+        assert_eq!(bookmarks[1].tags, vec!["rust".to_string()]);
+        assert_eq!(bookmarks[1].media.len(), 1);
+    }
+
+    #[test]
+    fn streaming_insert_commits_in_batches_and_refreshes_stats() {
+        let db = Database::open_memory().unwrap();
+        let bookmarks = (1..=7)
+            .map(|day| {
+                sample_bookmark(
+                    &day.to_string(),
+                    "alice",
+                    Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap(),
+                    "rust",
+                    false,
+                )
+            })
+            .collect::<Vec<_>>();
+        let duplicate = bookmarks[0].clone();
+
+        let inserted = db
+            .insert_bookmarks_streaming(bookmarks.into_iter().chain([duplicate]), 3, 2)
+            .unwrap();
+
+        assert_eq!(inserted, 7);
+        assert_eq!(db.get_stats().unwrap().total_bookmarks, 7);
+    }
+
+    #[test]
+    fn find_related_ranks_bookmarks_sharing_the_most_terms() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let texts = [
+            ("1", "The borrow checker and lifetimes in Rust explained"),
+            ("2", "Rust lifetimes and the borrow checker, part two"),
+            ("3", "Rust release notes"),
+            ("4", "Sourdough bread recipe"),
+        ];
+        let bookmarks = texts
+            .iter()
+            .map(|(id, text)| {
+                let mut bookmark = sample_bookmark(id, "rustlang", date, "news", false);
+                bookmark.content = text.to_string();
+                bookmark
+            })
+            .collect::<Vec<_>>();
+        db.insert_bookmarks(&bookmarks).unwrap();
+
+        let related = db.find_related(&bookmarks[0].id, 10).unwrap();
+        let ids = related
+            .iter()
+            .map(|bookmark| bookmark.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![bookmarks[1].id.as_str(), bookmarks[2].id.as_str()]
+        );
+        assert_eq!(db.find_related(&bookmarks[0].id, 1).unwrap().len(), 1);
+        assert!(db.find_related("missing", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn stopword_filter_drops_common_words_unless_nothing_else_is_left() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut book = sample_bookmark("1", "rustlang", date, "news", false);
+        book.content = "Free Rust book".to_string();
+        let mut other = sample_bookmark("2", "alice", date, "news", false);
+        other.content = "Then the weather turned".to_string();
+        db.insert_bookmarks(&[book.clone(), other.clone()]).unwrap();
+
+        // "the"* also prefix-matches "Then" without the filter
+        assert!(db.search("the rust book", 10).unwrap().is_empty());
+        let results = db
+            .search_with_stopword_filter("the rust book", 10, true)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, book.id);
+
+        let results = db.search_with_stopword_filter("the", 10, true).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, other.id);
+
+        let db =
+            Database::open_memory_with_options(DatabaseOptions::new().with_stopwords(["Free"]))
+                .unwrap();
+        db.insert_bookmarks(&[book.clone(), other]).unwrap();
+        let results = db
+            .search_with_stopword_filter("free weather", 10, true)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_ne!(results[0].id, book.id);
+    }
+
+    #[test]
+    fn search_in_field_only_matches_that_column() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut by_rust = sample_bookmark("1", "rustlang", date, "news", false);
+        by_rust.content = "Release notes are out".to_string();
+        let mut about_rust = sample_bookmark("2", "alice", date, "news", false);
+        about_rust.content = "Learning rustlang this week".to_string();
+        db.insert_bookmarks(&[by_rust.clone(), about_rust.clone()])
+            .unwrap();
+
+        let ids = |results: Vec<Bookmark>| {
+            results
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(db.search("rustlang", 10).unwrap().len(), 2);
+        assert_eq!(
+            ids(db
+                .search_in_field(SearchField::Author, "rustlang", 10)
+                .unwrap()),
+            vec![by_rust.id]
+        );
+        assert_eq!(
+            ids(db
+                .search_in_field(SearchField::Content, "rustlang", 10)
+                .unwrap()),
+            vec![about_rust.id]
+        );
+        assert!(db
+            .search_in_field(SearchField::Tags, "rustlang", 10)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            "authors".parse::<SearchField>().unwrap(),
+            SearchField::Author
+        );
+        assert!("comments".parse::<SearchField>().is_err());
+    }
+
+    #[test]
+    fn search_delegates_to_the_configured_backend() {
+        /// Matches bookmarks whose content contains the query verbatim
+        #[derive(Default)]
+        struct SubstringBackend(std::sync::Mutex<Vec<(String, String)>>);
+
+        impl SearchBackend for SubstringBackend {
+            fn index(&self, bookmarks: &[Bookmark]) -> Result<()> {
+                let mut docs = self.0.lock().unwrap();
+                docs.retain(|(id, _)| !bookmarks.iter().any(|b| &b.id == id));
+                docs.extend(bookmarks.iter().map(|b| (b.id.clone(), b.content.clone())));
+                Ok(())
+            }
+
+            fn remove(&self, ids: &[&str]) -> Result<()> {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .retain(|(id, _)| !ids.contains(&id.as_str()));
+                Ok(())
+            }
+
+            fn search(&self, query: &str, limit: usize) -> Result<Vec<String>> {
+                let docs = self.0.lock().unwrap();
+                Ok(docs
+                    .iter()
+                    .filter(|(_, content)| content.contains(query))
+                    .map(|(id, _)| id.clone())
+                    .take(limit)
+                    .collect())
+            }
+        }
+
+        let mut db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let stored = sample_bookmark("1", "alice", date, "rust", false);
+        db.insert_bookmarks(std::slice::from_ref(&stored)).unwrap();
+
+        db.set_search_backend(Box::<SubstringBackend>::default());
+        assert!(db.search("Bookmark 1", 10).unwrap().is_empty());
+        assert_eq!(db.reindex_search_backend().unwrap(), 1);
+
+        let added = sample_bookmark("2", "bob", date, "go", false);
+        db.insert_bookmarks(std::slice::from_ref(&added)).unwrap();
+        let ids = |query| {
+            db.search(query, 10)
+                .unwrap()
+                .into_iter()
+                .map(|b| b.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids("Bookmark 1"), vec![stored.id.clone()]);
+        assert_eq!(ids("Bookmark 2"), vec![added.id.clone()]);
+
+        // Trashed bookmarks are dropped while hydrating
+        db.delete_bookmark(&added.id).unwrap();
+        assert!(ids("Bookmark 2").is_empty());
+    }
+
+    /// Lets a test query the backend behind the database's back
+    struct SharedBackend<B>(std::sync::Arc<std::sync::Mutex<B>>);
+
+    impl<B: SearchBackend> SearchBackend for SharedBackend<B> {
+        fn index(&self, bookmarks: &[Bookmark]) -> Result<()> {
+            self.0.lock().unwrap().index(bookmarks)
+        }
+
+        fn remove(&self, ids: &[&str]) -> Result<()> {
+            self.0.lock().unwrap().remove(ids)
+        }
+
+        fn search(&self, query: &str, limit: usize) -> Result<Vec<String>> {
+            self.0.lock().unwrap().search(query, limit)
+        }
+    }
+
+    #[test]
+    fn search_backend_follows_edits_deletes_and_restores() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = std::sync::Arc::new(std::sync::Mutex::new(
+            crate::search::Fts5Backend::open(&dir.path().join("index.db")).unwrap(),
+        ));
+        let mut db = Database::open_memory().unwrap();
+        db.set_search_backend(Box::new(SharedBackend(backend.clone())));
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let bookmarks = [
+            sample_bookmark("1", "alice", date, "rust", false),
+            sample_bookmark("2", "bob", date, "go", false),
+        ];
+        db.insert_bookmarks(&bookmarks).unwrap();
+        let backup = dir.path().join("backup.db");
+        db.backup_to(&backup).unwrap();
+        let (first, second) = (bookmarks[0].id.clone(), bookmarks[1].id.clone());
+        let found = |query| backend.lock().unwrap().search(query, 10).unwrap();
+
+        db.add_tag_to_bookmark(&second, "tokio").unwrap();
+        assert_eq!(found("#tokio"), vec![second.clone()]);
+        db.rename_tag("tokio", "runtime").unwrap();
+        assert!(found("#tokio").is_empty());
+        assert_eq!(found("#runtime"), vec![second.clone()]);
+        db.add_annotation(&first, "revisit the scheduler").unwrap();
+        assert_eq!(found("scheduler"), vec![first.clone()]);
+
+        db.delete_bookmark(&second).unwrap();
+        assert!(found("#runtime").is_empty());
+        db.delete_bookmark_permanently(&first).unwrap();
+        assert!(found("scheduler").is_empty());
+
+        db.restore_from(&backup).unwrap();
+        let mut restored = found("bookmark");
+        restored.sort();
+        let mut expected = vec![first, second];
+        expected.sort();
+        assert_eq!(restored, expected);
+    }
+
+    #[test]
+    fn search_backend_failures_do_not_fail_committed_writes() {
+        struct Offline;
+
+        impl SearchBackend for Offline {
+            fn index(&self, _: &[Bookmark]) -> Result<()> {
+                Err(Error::Other("index offline".into()))
+            }
+
+            fn remove(&self, _: &[&str]) -> Result<()> {
+                Err(Error::Other("index offline".into()))
+            }
+
+            fn search(&self, _: &str, _: usize) -> Result<Vec<String>> {
+                Ok(Vec::new())
+            }
+        }
+
+        let mut db = Database::open_memory().unwrap();
+        db.set_search_backend(Box::new(Offline));
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let bookmark = sample_bookmark("1", "alice", date, "rust", false);
+        assert_eq!(
+            db.insert_bookmarks(std::slice::from_ref(&bookmark))
+                .unwrap(),
+            1
+        );
+        assert!(db.add_tag_to_bookmark(&bookmark.id, "async").unwrap());
+        assert!(db.delete_bookmark(&bookmark.id).unwrap());
+        assert_eq!(db.get_trash(0, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn search_facets_count_the_match_set() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let bookmarks = [
+            sample_bookmark("1", "alice", date, "rust", true),
+            sample_bookmark("2", "alice", date, "rust", false),
+            sample_bookmark("3", "bob", date, "rust", false),
+            sample_bookmark("4", "bob", date, "go", true),
+        ];
+        db.insert_bookmarks(&bookmarks).unwrap();
+        db.toggle_favorite(&bookmarks[1].id).unwrap();
+
+        let facets = db.search_facets("rust", &SearchFilters::new()).unwrap();
+        assert_eq!(facets.tags, vec![("rust".to_string(), 3)]);
+        assert_eq!(
+            facets.authors,
+            vec![("alice".to_string(), 2), ("bob".to_string(), 1)]
+        );
+        assert_eq!(facets.media, 1);
+        assert_eq!(facets.favorites, 1);
+
+        let everything = db
+            .search_facets("", &SearchFilters::new().with_author("bob"))
+            .unwrap();
+        assert_eq!(everything.tags.len(), 2);
+        assert_eq!(everything.authors, vec![("bob".to_string(), 2)]);
+        assert_eq!(everything.media, 1);
+
+        let none = db.search_facets("nothing", &SearchFilters::new()).unwrap();
+        assert_eq!(none, Facets::default());
+    }
+
+    #[test]
+    fn saved_searches_round_trip_and_upsert() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let bookmarks = [
+            sample_bookmark("1", "alice", date, "rust", false),
+            sample_bookmark("2", "bob", date, "rust", false),
+            sample_bookmark("3", "bob", date, "go", false),
+        ];
+        db.insert_bookmarks(&bookmarks).unwrap();
+
+        let rust = SearchFilters::new().with_tag("rust");
+        db.save_search("Rust", &rust).unwrap();
+        db.save_search("Bob", &SearchFilters::new().with_author("bob"))
+            .unwrap();
+        assert!(db.save_search("  ", &rust).is_err());
+
+        let saved = db.list_saved_searches().unwrap();
+        assert_eq!(
+            saved.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+            vec!["Bob", "Rust"]
+        );
+        assert_eq!(saved[1].filters, rust);
+
+        assert_eq!(db.run_saved_search("Rust", 10).unwrap().unwrap().len(), 2);
+        assert_eq!(db.run_saved_search("Rust", 1).unwrap().unwrap().len(), 1);
+        assert_eq!(db.run_saved_search("missing", 10).unwrap(), None);
+
+        // Saving under an existing name replaces its filters
+        db.save_search("Rust", &rust.clone().with_author("alice"))
+            .unwrap();
+        assert_eq!(db.list_saved_searches().unwrap().len(), 2);
+        let alice = db.run_saved_search("Rust", 10).unwrap().unwrap();
+        assert_eq!(alice.len(), 1);
+        assert_eq!(alice[0].id, bookmarks[0].id);
+
+        assert!(db.delete_saved_search("Bob").unwrap());
+        assert!(!db.delete_saved_search("Bob").unwrap());
+        assert_eq!(db.list_saved_searches().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn backup_restores_into_a_fresh_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("live.db")).unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let bookmarks = [
+            sample_bookmark("1", "alice", date, "rust", true),
+            sample_bookmark("2", "bob", date, "go", false),
+        ];
+        db.insert_bookmarks(&bookmarks).unwrap();
+
+        let backup = dir.path().join("backups").join("snapshot.db");
+        db.backup_to(&backup).unwrap();
+
+        let mut restored = Database::open_memory().unwrap();
+        restored
+            .insert_bookmarks(&[sample_bookmark("3", "carol", date, "go", false)])
+            .unwrap();
+        restored.restore_from(&backup).unwrap();
+
+        assert_eq!(restored.count_bookmarks().unwrap(), 2);
+        assert_eq!(restored.count_by_tag("rust").unwrap(), 1);
+        assert_eq!(restored.search("Bookmark", 10).unwrap().len(), 2);
+        assert!(restored.get_bookmark(&bookmarks[1].id).unwrap().is_some());
+
+        assert!(restored
+            .restore_from(&dir.path().join("missing.db"))
+            .is_err());
+    }
+
+    #[test]
+    fn merge_import_fills_gaps_and_keeps_user_changes() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let stored = sample_bookmark("1", "alice", date, "rust", false);
+        let untouched = sample_bookmark("2", "bob", date, "go", false);
+        db.insert_bookmarks(&[stored.clone(), untouched.clone()])
+            .unwrap();
+        db.toggle_favorite(&stored.id).unwrap();
+        db.add_tag_to_bookmark(&stored.id, "keep").unwrap();
+        db.add_annotation(&stored.id, "my thoughts").unwrap();
+
+        // A later export of the same tweet, now with a note, media and no user tags
+        let mut export = sample_bookmark("1", "alice", date, "export", true);
+        export.note_text = Some("Full long-form text".to_string());
+        export.author_profile_image = Some("https://pbs.twimg.com/alice.jpg".to_string());
+        let report = db
+            .merge_import(&[
+                export,
+                sample_bookmark("2", "bob", date, "go", false),
+                sample_bookmark("3", "carol", date, "go", false),
+            ])
+            .unwrap();
+        assert_eq!(
+            report,
+            MergeReport {
+                inserted: 1,
+                merged: 1,
+                unchanged: 1,
+                failed: 0,
+            }
+        );
+
+        let merged = db.get_bookmark(&stored.id).unwrap().unwrap();
+        assert_eq!(merged.note_text.as_deref(), Some("Full long-form text"));
+        assert_eq!(
+            merged.author_profile_image.as_deref(),
+            Some("https://pbs.twimg.com/alice.jpg")
+        );
+        assert_eq!(merged.media.len(), 1);
+        assert!(merged.is_favorite);
+        let mut tags = merged.tags.clone();
+        tags.sort();
+        assert_eq!(tags, vec!["export", "keep", "rust"]);
+        assert_eq!(merged.comments.as_deref(), Some("my thoughts"));
+        assert_eq!(db.search("long-form", 10).unwrap().len(), 1);
+        assert_eq!(
+            db.search_in_field(SearchField::Tags, "export", 10)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // The same tweet under the other host merges too, filling the comments
+        let mut moved = sample_bookmark("2", "bob", date, "go", false);
+        moved.tweet_url = "https://twitter.com/bob/status/2".to_string();
+        moved.comments = Some("read later".to_string());
+        let report = db.merge_import(&[moved]).unwrap();
+        assert_eq!((report.merged, report.inserted), (1, 0));
+        let bob = db.get_bookmark(&untouched.id).unwrap().unwrap();
+        assert_eq!(bob.tweet_url, untouched.tweet_url);
+        assert_eq!(bob.comments.as_deref(), Some("read later"));
+        assert_eq!(db.get_annotations(&untouched.id).unwrap().len(), 1);
+
+        // Merging the same export again has nothing left to fill
+        let mut again = sample_bookmark("1", "alice", date, "export", true);
+        again.note_text = Some("A different note".to_string());
+        let report = db.merge_import(&[again]).unwrap();
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(
+            db.get_bookmark(&stored.id)
+                .unwrap()
+                .unwrap()
+                .note_text
+                .as_deref(),
+            Some("Full long-form text")
+        );
+    }
+
+    #[test]
+    fn opening_a_corrupt_file_explains_the_problem() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        std::fs::write(&path, vec![0x5a; 8192]).unwrap();
+
+        let error = Database::open(&path).err().unwrap();
+        assert!(matches!(error, Error::DatabaseCorrupt(ref p) if p == &path));
+        assert!(error.to_string().contains("integrity_check"));
+    }
+
+    #[test]
+    fn pragmas_come_from_the_options() {
+        use crate::storage::{JournalMode, Synchronous, DEFAULT_CACHE_SIZE};
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        let pragma = |db: &Database, name: &str| -> rusqlite::types::Value {
+            db.conn
+                .query_row(&format!("PRAGMA {name}"), [], |row| row.get(0))
+                .unwrap()
+        };
+
+        let db = Database::open(&path).unwrap();
+        assert_eq!(
+            pragma(&db, "cache_size"),
+            Value::Integer(DEFAULT_CACHE_SIZE)
+        );
+        assert_eq!(pragma(&db, "journal_mode"), Value::Text("wal".into()));
+        drop(db);
+
+        let options = DatabaseOptions::new()
+            .with_cache_size(-2_000)
+            .with_journal_mode(JournalMode::Delete)
+            .with_synchronous(Synchronous::Full)
+            .with_mmap_size(0);
+        let db = Database::open_with_options(&path, options).unwrap();
+        assert_eq!(pragma(&db, "cache_size"), Value::Integer(-2_000));
+        assert_eq!(pragma(&db, "journal_mode"), Value::Text("delete".into()));
+        assert_eq!(pragma(&db, "synchronous"), Value::Integer(2));
+        assert_eq!(pragma(&db, "mmap_size"), Value::Integer(0));
+        assert_eq!(pragma(&db, "foreign_keys"), Value::Integer(1));
+
+        let error =
+            Database::open_memory_with_options(DatabaseOptions::new().with_mmap_size(u64::MAX))
+                .err()
+                .unwrap();
+        assert!(matches!(error, Error::InvalidOptions(_)));
+    }
+
+    #[test]
+    fn read_only_databases_answer_queries_and_reject_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let bookmark = sample_bookmark("1", "alice", date, "rust", false);
+        let writer = Database::open(&path).unwrap();
+        writer
+            .insert_bookmarks(std::slice::from_ref(&bookmark))
+            .unwrap();
+
+        // Alongside a writer, as when the CLI runs while the app is open
+        let reader = Database::open_read_only(&path).unwrap();
+        assert!(reader.is_read_only());
+        assert_eq!(reader.search("rust", 10).unwrap().len(), 1);
+        assert_eq!(reader.get_stats().unwrap().total_bookmarks, 1);
+        for result in [
+            reader
+                .insert_bookmarks(&[sample_bookmark("2", "bob", date, "go", false)])
+                .map(drop),
+            reader.add_tag_to_bookmark(&bookmark.id, "new").map(drop),
+            reader.delete_bookmark(&bookmark.id).map(drop),
+            reader.set_metadata("key", "value"),
+        ] {
+            assert!(matches!(result, Err(Error::ReadOnly)));
+        }
+
+        assert!(writer.add_tag_to_bookmark(&bookmark.id, "new").unwrap());
+        assert_eq!(
+            reader
+                .search_in_field(SearchField::Tags, "new", 10)
+                .unwrap()
+                .len(),
+            1
+        );
+        drop(writer);
+        drop(reader);
+
+        let missing = dir.path().join("missing.db");
+        assert!(Database::open_read_only(&missing).is_err());
+        assert!(!missing.exists());
+    }
+
+    #[test]
+    fn opening_a_locked_file_times_out_with_a_clear_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+
+        // Another process mid-write on a rollback-journal file blocks even readers
+        let holder = Connection::open(&path).unwrap();
+        holder
+            .execute_batch("CREATE TABLE t (x); BEGIN EXCLUSIVE; INSERT INTO t VALUES (1);")
+            .unwrap();
+
+        let options = DatabaseOptions::new().with_busy_timeout(Duration::from_millis(50));
+        let error = Database::open_with_options(&path, options).err().unwrap();
+        assert!(matches!(error, Error::DatabaseLocked(_)), "{error}");
+    }
+
+    #[test]
+    fn health_checks_pass_on_a_fresh_database_and_catch_problems() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        db.insert_bookmarks(&[sample_bookmark("1", "alice", date, "rust", true)])
+            .unwrap();
+
+        assert!(db.integrity_check().unwrap().is_empty());
+        assert!(db.foreign_key_check().unwrap().is_empty());
+        assert!(db.fts_integrity_check().unwrap().is_empty());
+
+        // Index text no bookmark has, and orphan a media row
+        db.conn
+            .execute_batch(
+                "PRAGMA foreign_keys = OFF;
+                 INSERT INTO bookmarks_fts(rowid, content) VALUES (999, 'phantom');
+                 INSERT INTO media (bookmark_id, url, media_type) VALUES ('gone', 'x', 'image');
+                 PRAGMA foreign_keys = ON;",
+            )
+            .unwrap();
+        assert_eq!(db.fts_integrity_check().unwrap().len(), 1);
+        let dangling = db.foreign_key_check().unwrap();
+        assert_eq!(dangling.len(), 1);
+        assert!(dangling[0].starts_with("media row"), "{dangling:?}");
+    }
+
+    #[test]
+    fn db_path_prefers_flag_then_env_then_platform_default() {
+        let flag = Path::new("/tmp/flag.db");
+        let env = Some(std::ffi::OsString::from("/tmp/env.db"));
+
+        assert_eq!(resolve_db_path(Some(flag), env.clone()), flag);
+        assert_eq!(resolve_db_path(None, env), PathBuf::from("/tmp/env.db"));
+
+        let platform = resolve_db_path(None, None);
+        assert!(platform.ends_with("eterea/bookmarks.db"));
+        assert_eq!(resolve_db_path(None, Some("".into())), platform);
+    }
+
+    #[test]
+    fn favorite_tag_and_author_pages_report_totals() {
+        let db = Database::open_memory().unwrap();
+        let bookmarks = (1..=5)
+            .map(|day| {
+                let date = Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap();
+                let handle = if day % 2 == 0 { "bob" } else { "alice" };
+                sample_bookmark(&day.to_string(), handle, date, "rust", false)
+            })
+            .collect::<Vec<_>>();
+        db.insert_bookmarks(&bookmarks).unwrap();
+        for bookmark in &bookmarks[..3] {
+            db.toggle_favorite(&bookmark.id).unwrap();
+        }
+        db.delete_bookmark(&bookmarks[0].id).unwrap();
+
+        let favorites = db.get_favorites_page(0, 1).unwrap();
+        assert_eq!((favorites.items.len(), favorites.total), (1, 2));
+        assert!(favorites.has_more);
+
+        let tagged = db.get_bookmarks_by_tag_page("rust", 2, 2).unwrap();
+        assert_eq!((tagged.items.len(), tagged.total), (2, 4));
+        assert!(!tagged.has_more);
+
+        let by_alice = db.get_bookmarks_by_author_page("alice", 0, 10).unwrap();
+        assert_eq!((by_alice.items.len(), by_alice.total), (2, 2));
+    }
+
+    #[test]
+    fn media_type_filter_implies_media_and_counts_each_bookmark_once() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let two_videos = BookmarkBuilder::new()
+            .tweet_url("https://x.com/alice/status/1")
+            .content("Two clips")
+            .tweeted_at(date)
+            .author_handle("alice")
+            .author_name("Alice")
+            .add_media_with_type("https://video.twimg.com/a.mp4", MediaType::Video)
+            .add_media_with_type("https://video.twimg.com/b.mp4", MediaType::Video)
+            .build()
+            .unwrap();
+        let image = sample_bookmark("2", "bob", date, "rust", true);
+        let plain = sample_bookmark("3", "bob", date, "rust", false);
+        db.insert_bookmarks(&[two_videos.clone(), image.clone(), plain])
+            .unwrap();
+
+        let videos = db
+            .search_with_filters_page(
+                None,
+                &[],
+                TagMatch::All,
+                None,
+                None,
+                None,
+                false,
+                Some(false),
+                Some(MediaType::Video),
+                0,
+                10,
+            )
+            .unwrap();
+        assert_eq!(videos.total, 1);
+        assert_eq!(videos.items[0].id, two_videos.id);
+
+        let images = db
+            .get_bookmarks_with_media_type(MediaType::Image, 0, 10)
+            .unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].id, image.id);
+        assert_eq!(
+            db.count_matching(&SearchFilters::new().with_media_type(Some(MediaType::Gif)))
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn tweet_ids_are_stored_and_backfilled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let bookmark = sample_bookmark("1234567890", "alice", date, "rust", false);
+        {
+            let db = Database::open(&path).unwrap();
+            db.insert_bookmarks(std::slice::from_ref(&bookmark))
+                .unwrap();
+            let found = db.get_bookmark_by_tweet_id("1234567890").unwrap().unwrap();
+            assert_eq!(found.id, bookmark.id);
+            assert_eq!(found.tweet_id.as_deref(), Some("1234567890"));
+            assert!(db.get_bookmark_by_tweet_id("1").unwrap().is_none());
+
+            // Simulate a database from before the column existed
+            db.conn
+                .execute_batch(
+                    "DROP INDEX idx_bookmarks_tweet_id; ALTER TABLE bookmarks DROP COLUMN tweet_id;",
+                )
+                .unwrap();
+        }
+
+        let db = Database::open(&path).unwrap();
+        let found = db.get_bookmark_by_tweet_id("1234567890").unwrap().unwrap();
+        assert_eq!(found.id, bookmark.id);
+    }
+
+    #[test]
+    fn link_columns_are_added_one_by_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        {
+            let db = Database::open(&path).unwrap();
+            // A library where only the first column of each pair made it
+            db.conn
+                .execute_batch(
+                    r#"ALTER TABLE link_previews DROP COLUMN canonical_url;
+                       DROP INDEX idx_links_expanded_domain;
+                       ALTER TABLE links DROP COLUMN expanded_domain;"#,
+                )
+                .unwrap();
+        }
+
+        let db = Database::open(&path).unwrap();
+        assert!(db.has_column("link_previews", "canonical_url").unwrap());
+        assert!(db.has_column("links", "expanded_domain").unwrap());
+    }
+
+    #[test]
+    fn same_minute_bookmarks_order_by_tweet_id() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        // "9" sorts after "10" as text, so this fails unless ids compare numerically
+        let older = sample_bookmark("9", "alice", date, "rust", false);
+        let newer = sample_bookmark("10", "alice", date, "rust", false);
+        db.insert_bookmarks(&[older.clone(), newer.clone()])
+            .unwrap();
+
+        let listed: Vec<String> = db
+            .get_bookmarks(0, 10)
+            .unwrap()
+            .into_iter()
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(listed, vec![newer.id.clone(), older.id.clone()]);
+
+        let found: Vec<String> = db
+            .search("rust", 10)
+            .unwrap()
+            .into_iter()
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(found, vec![newer.id, older.id]);
+    }
+
+    #[test]
+    fn query_bookmarks_pages_through_filters_and_backs_the_wrappers() {
+        let db = Database::open_memory().unwrap();
+        let day = |d| Utc.with_ymd_and_hms(2024, 5, d, 12, 0, 0).unwrap();
+        let bookmarks = vec![
+            sample_bookmark("1", "alice", day(1), "rust", false),
+            sample_bookmark("2", "alice", day(2), "rust", true),
+            sample_bookmark("3", "bob", day(3), "rust", false),
+            sample_bookmark("4", "bob", day(4), "svelte", false),
+        ];
+        db.insert_bookmarks(&bookmarks).unwrap();
+        db.set_favorite(&bookmarks[2].id, true).unwrap();
+
+        let filters = SearchFilters::new()
+            .with_tag("rust")
+            .with_date_range(Some(day(2)), None);
+        let first = db.query_bookmarks(&filters, Pagination::new(0, 1)).unwrap();
+        assert_eq!(first.total, 2);
+        assert!(first.has_more);
+        assert_eq!(first.items[0].id, bookmarks[2].id);
+        let second = db.query_bookmarks(&filters, Pagination::new(1, 1)).unwrap();
+        assert_eq!(second.items[0].id, bookmarks[1].id);
+        assert!(!second.has_more);
+        assert_eq!(db.count_matching(&filters).unwrap(), first.total);
+
+        let page = db.get_bookmarks_by_author_page("@Bob", 0, 10).unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(db.get_favorites(0, 10).unwrap()[0].id, bookmarks[2].id);
+        assert_eq!(db.count_by_tag("RUST").unwrap(), 3);
+        assert_eq!(
+            db.query_bookmarks(&SearchFilters::new(), Pagination::default())
+                .unwrap()
+                .total,
+            4
+        );
+    }
+
+    #[test]
+    fn imported_at_filters_and_sorts_independently_of_tweet_dates() {
+        let db = Database::open_memory().unwrap();
+        let day = |d| Utc.with_ymd_and_hms(2024, 5, d, 12, 0, 0).unwrap();
+        // An old tweet imported recently, and a newer tweet imported long ago
+        let mut old_tweet = sample_bookmark("1", "alice", day(1), "rust", false);
+        old_tweet.imported_at = day(20);
+        let mut new_tweet = sample_bookmark("2", "alice", day(10), "rust", false);
+        new_tweet.imported_at = day(11);
+        db.insert_bookmarks(&[old_tweet.clone(), new_tweet.clone()])
+            .unwrap();
+
+        let ids = |filters: &SearchFilters| -> Vec<String> {
+            db.query_bookmarks(filters, Pagination::default())
+                .unwrap()
+                .items
+                .into_iter()
+                .map(|b| b.id)
+                .collect()
+        };
+        let recently_added = SearchFilters::new().with_imported_range(Some(day(15)), None);
+        assert_eq!(ids(&recently_added), vec![old_tweet.id.clone()]);
+        assert_eq!(db.count_matching(&recently_added).unwrap(), 1);
+
+        let by_import = SearchFilters::new().with_sort_by(SortBy::ImportedAt);
+        assert_eq!(
+            ids(&by_import),
+            vec![old_tweet.id.clone(), new_tweet.id.clone()]
+        );
+        assert_eq!(ids(&SearchFilters::new()), vec![new_tweet.id, old_tweet.id]);
+    }
+
+    #[test]
+    fn content_duplicates_under_other_urls_are_grouped_and_optionally_skipped() {
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let repost = |id: &str, handle: &str| {
+            let mut bookmark = sample_bookmark(id, handle, date, "rust", false);
+            bookmark.content = "Same words,   different link https://t.co/x".to_string();
+            bookmark
+        };
+        let original = repost("1", "alice");
+        let copy = repost("2", "alice");
+        let other_author = repost("3", "bob");
+
+        let db = Database::open_memory().unwrap();
+        let report = db
+            .import(&[original.clone(), copy.clone(), other_author.clone()])
+            .unwrap();
+        assert_eq!(report.inserted, 3);
+        assert_eq!(
+            db.find_content_duplicates().unwrap(),
+            vec![vec![original.id.clone(), copy.id.clone()]]
+        );
+
+        let db = Database::open_memory_with_options(
+            DatabaseOptions::new().with_skip_content_duplicates(true),
+        )
+        .unwrap();
+        let report = db
+            .import(&[
+                original.clone(),
+                original.clone(),
+                copy.clone(),
+                other_author,
+            ])
+            .unwrap();
+        assert_eq!(report.inserted, 2);
+        assert_eq!(report.skipped_duplicate, 1);
+        assert_eq!(report.skipped_content_duplicate, 1);
+        assert!(db.get_bookmark(&copy.id).unwrap().is_none());
+        assert!(db.find_content_duplicates().unwrap().is_empty());
+    }
+
+    #[test]
+    fn proximity_rerank_lifts_matches_with_adjacent_query_words() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut apart = sample_bookmark("1", "alice", date, "ml", false);
+        apart.content = "Machine shops are learning to automate".to_string();
+        let mut adjacent = sample_bookmark("2", "bob", date, "ml", false);
+        adjacent.content = "A long thread about what machine learning can and cannot do for the \
+                            small teams that are trying it out this year"
+            .to_string();
+        db.insert_bookmarks(&[apart.clone(), adjacent.clone()])
+            .unwrap();
+
+        // bm25 prefers the shorter tweet
+        let plain = db
+            .search_with_proximity("machine learning", 10, false)
+            .unwrap();
+        assert_eq!(plain[0].id, apart.id);
+
+        let reranked = db
+            .search_with_proximity("machine learning", 10, true)
+            .unwrap();
+        assert_eq!(reranked.len(), 2);
+        assert_eq!(reranked[0].id, adjacent.id);
+        assert_eq!(
+            db.search_with_proximity("machine learning", 1, true)
+                .unwrap()[0]
+                .id,
+            adjacent.id
+        );
+    }
+
+    #[test]
+    fn random_bookmarks_are_distinct_live_and_capped_at_the_library_size() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let bookmarks = (1..=20)
+            .map(|id| sample_bookmark(&id.to_string(), "alice", date, "rust", false))
+            .collect::<Vec<_>>();
+        db.insert_bookmarks(&bookmarks).unwrap();
+        db.delete_bookmark(&bookmarks[0].id).unwrap();
+
+        let picked = db.random_bookmarks(5).unwrap();
+        let mut ids = picked.iter().map(|b| b.id.clone()).collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 5);
+        assert!(!ids.contains(&bookmarks[0].id));
+
+        assert_eq!(db.random_bookmarks(100).unwrap().len(), 19);
+        // Every size comes back whole, whichever way it is sampled
+        for n in 0..=19 {
+            assert_eq!(db.random_bookmarks(n).unwrap().len(), n);
+        }
+        let more = (21..=200)
+            .map(|id| sample_bookmark(&id.to_string(), "bob", date, "go", false))
+            .collect::<Vec<_>>();
+        db.insert_bookmarks(&more).unwrap();
+        for n in [1, 10, 49, 50, 150, 199] {
+            let picked = db.random_bookmarks(n).unwrap();
+            let distinct = picked.iter().map(|b| &b.id).collect::<HashSet<_>>();
+            assert_eq!(distinct.len(), n);
+        }
+        assert!(Database::open_memory()
+            .unwrap()
+            .random_bookmarks(3)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn on_this_day_matches_the_calendar_day_in_any_year() {
+        let db = Database::open_memory().unwrap();
+        let older = sample_bookmark(
+            "1",
+            "alice",
+            Utc.with_ymd_and_hms(2019, 3, 7, 23, 30, 0).unwrap(),
+            "rust",
+            false,
+        );
+        let newer = sample_bookmark(
+            "2",
+            "bob",
+            Utc.with_ymd_and_hms(2023, 3, 7, 1, 0, 0).unwrap(),
+            "rust",
+            false,
+        );
+        let other_day = sample_bookmark(
+            "3",
+            "carol",
+            Utc.with_ymd_and_hms(2023, 3, 8, 0, 0, 0).unwrap(),
+            "rust",
+            false,
+        );
+        db.insert_bookmarks(&[older.clone(), newer.clone(), other_day])
+            .unwrap();
+
+        let ids = db
+            .on_this_day(3, 7)
+            .unwrap()
+            .into_iter()
+            .map(|b| b.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![newer.id, older.id]);
+        assert!(db.on_this_day(2, 30).unwrap().is_empty());
+    }
+
+    #[test]
+    fn exact_search_stops_short_terms_matching_longer_words() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut exact = sample_bookmark("1", "alice", date, "lang", false);
+        exact.content = "Why we picked Go for the backend".to_string();
+        let mut longer = sample_bookmark("2", "bob", date, "search", false);
+        longer.content = "Google search tips and gopher facts".to_string();
+        db.insert_bookmarks(&[exact.clone(), longer.clone()])
+            .unwrap();
+
+        let ids = |bookmarks: Vec<Bookmark>| {
+            let mut ids = bookmarks.into_iter().map(|b| b.id).collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+        let mut both = vec![exact.id.clone(), longer.id.clone()];
+        both.sort();
+        assert_eq!(ids(db.search("go", 10).unwrap()), both);
+        assert_eq!(
+            ids(db.search_with_prefix_match("go", 10, false).unwrap()),
+            vec![exact.id.clone()]
+        );
+
+        let filters = SearchFilters::new().with_query("go");
+        assert_eq!(db.count_matching(&filters).unwrap(), 2);
+        let exact_filters = filters.with_prefix_match(false);
+        assert_eq!(
+            ids(db
+                .query_bookmarks(&exact_filters, Pagination::new(0, 10))
+                .unwrap()
+                .items),
+            vec![exact.id]
+        );
+    }
+
+    #[test]
+    fn tag_cooccurrence_counts_other_tags_on_the_same_bookmarks() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let tagged = |id: &str, extra: &[&str]| {
+            let mut bookmark = sample_bookmark(id, "alice", date, "rust", false);
+            bookmark
+                .tags
+                .extend(extra.iter().map(|tag| tag.to_string()));
+            bookmark
+        };
+        let trashed = tagged("4", &["async"]);
+        db.insert_bookmarks(&[
+            tagged("1", &["async", "tokio"]),
+            tagged("2", &["async"]),
+            tagged("3", &["wasm"]),
+            trashed.clone(),
+            sample_bookmark("5", "bob", date, "tokio", false),
+        ])
+        .unwrap();
+        db.delete_bookmark(&trashed.id).unwrap();
+
+        assert_eq!(
+            db.tag_cooccurrence("RUST", 10).unwrap(),
+            vec![
+                ("async".to_string(), 2),
+                ("tokio".to_string(), 1),
+                ("wasm".to_string(), 1)
+            ]
+        );
+        assert_eq!(db.tag_cooccurrence("rust", 1).unwrap().len(), 1);
+        assert!(db.tag_cooccurrence("missing", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn instant_search_stays_in_sync_with_writes() {
+        let db = Database::open_memory().unwrap();
+        let day = |d| Utc.with_ymd_and_hms(2024, 5, d, 8, 0, 0).unwrap();
+        let rust = sample_bookmark("1", "alice", day(1), "rust", false);
+        db.insert_bookmarks(std::slice::from_ref(&rust)).unwrap();
+        let ids = |query: &str| -> Vec<String> {
+            db.search_instant(query, 10)
+                .unwrap()
+                .into_iter()
+                .map(|bookmark| bookmark.id)
+                .collect()
+        };
+
+        // Before the index is built, FTS5 answers
+        assert_eq!(ids("rus"), vec![rust.id.clone()]);
+        assert!(db.enable_instant_search().unwrap());
+        assert_eq!(ids("rus"), vec![rust.id.clone()]);
+
+        let go = sample_bookmark("2", "bob", day(2), "golang", false);
+        db.insert_bookmarks(std::slice::from_ref(&go)).unwrap();
+        assert_eq!(ids("bookmark"), vec![go.id.clone(), rust.id.clone()]);
+
+        assert!(db.add_tag_to_bookmark(&rust.id, "systems").unwrap());
+        assert_eq!(ids("syst"), vec![rust.id.clone()]);
+        assert!(db.remove_tag_from_bookmark(&rust.id, "systems").unwrap());
+        assert!(ids("syst").is_empty());
+
+        assert!(db.delete_bookmark(&go.id).unwrap());
+        assert!(ids("golang").is_empty());
+        assert!(db.restore_bookmark(&go.id).unwrap());
+        assert_eq!(ids("golang"), vec![go.id.clone()]);
+        assert!(db.delete_bookmark_permanently(&go.id).unwrap());
+        assert!(ids("golang").is_empty());
+    }
+
+    #[test]
+    fn export_json_since_covers_imports_edits_and_deletions_after_the_boundary() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let (kept, trashed, removed, added) = (
+            sample_bookmark("1", "alice", date, "rust", false),
+            sample_bookmark("2", "alice", date, "rust", false),
+            sample_bookmark("3", "alice", date, "rust", false),
+            sample_bookmark("4", "bob", date, "rust", false),
+        );
+        // Changes are stamped with the current second, which the window leaves
+        // for the next export; move everything logged so far into the past
+        let age = |seconds: i64| {
+            db.conn
+                .execute(
+                    "UPDATE sync_changes SET changed_at = changed_at - ?1",
+                    [seconds],
+                )
+                .unwrap();
+        };
+        let export = |since: chrono::DateTime<Utc>| {
+            let mut out = Vec::new();
+            let counts = db.export_json_since(since, &mut out).unwrap();
+            let export: SyncExport = serde_json::from_slice(&out).unwrap();
+            assert_eq!(counts, (export.bookmarks.len(), export.deleted.len()));
+            export
+        };
+        fn ids(bookmarks: &[Bookmark]) -> Vec<&str> {
+            bookmarks.iter().map(|b| b.id.as_str()).collect()
+        }
+        let recent = || Utc::now() - chrono::Duration::seconds(50);
+
+        db.insert_bookmarks(&[kept.clone(), trashed.clone(), removed.clone()])
+            .unwrap();
+        age(100);
+        let first = export(chrono::DateTime::UNIX_EPOCH);
+        assert_eq!(first.since, chrono::DateTime::UNIX_EPOCH);
+        assert_eq!(
+            ids(&first.bookmarks),
+            vec![kept.id.as_str(), trashed.id.as_str(), removed.id.as_str()]
+        );
+        assert!(first.deleted.is_empty());
+
+        assert!(db.delete_bookmark(&trashed.id).unwrap());
+        assert!(db.delete_bookmark_permanently(&removed.id).unwrap());
+        db.insert_bookmarks(std::slice::from_ref(&added)).unwrap();
+        assert!(db.add_tag_to_bookmark(&kept.id, "go").unwrap());
+        age(10);
+        let second = export(recent());
+        assert_eq!(
+            ids(&second.bookmarks),
+            vec![added.id.as_str(), kept.id.as_str()]
+        );
+        assert_eq!(second.bookmarks[1].tags, vec!["go", "rust"]);
+        let deleted: Vec<&str> = second.deleted.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(deleted, vec![trashed.id.as_str(), removed.id.as_str()]);
+
+        // Nothing changed after the previous window
+        let third = export(second.until);
+        assert!(third.bookmarks.is_empty() && third.deleted.is_empty());
+
+        // Restoring from the trash or importing again sends the bookmark
+        // again, as does marking it a favorite
+        age(100);
+        assert!(db.restore_bookmark(&trashed.id).unwrap());
+        db.insert_bookmarks(std::slice::from_ref(&removed)).unwrap();
+        db.set_favorite(added.id.as_str(), true).unwrap();
+        age(10);
+        let fourth = export(recent());
+        assert_eq!(
+            ids(&fourth.bookmarks),
+            vec![trashed.id.as_str(), removed.id.as_str(), added.id.as_str()]
+        );
+        assert!(fourth.bookmarks[2].is_favorite);
+        assert!(fourth.deleted.is_empty());
+
+        // An author edit changes each of their bookmarks
+        age(100);
+        let alice = Author {
+            handle: "alice".to_string(),
+            name: "Alice Liddell".to_string(),
+            profile_url: None,
+            profile_image: None,
+        };
+        assert!(db.update_author(&alice).unwrap());
+        age(10);
+        let fifth = export(recent());
+        let mut edited = ids(&fifth.bookmarks);
+        edited.sort();
+        let mut expected = vec![kept.id.as_str(), trashed.id.as_str(), removed.id.as_str()];
+        expected.sort();
+        assert_eq!(edited, expected);
+        assert!(fifth
+            .bookmarks
+            .iter()
+            .all(|bookmark| bookmark.author_name == "Alice Liddell"));
+    }
+
+    #[test]
+    fn export_json_since_waits_for_an_import_still_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("eterea.db");
+        let writer = Database::open(&path).unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let stored = sample_bookmark("1", "alice", date, "rust", false);
+        writer
+            .insert_bookmarks(std::slice::from_ref(&stored))
+            .unwrap();
+
+        // An import batch that started a while ago and has not committed yet
+        let pending = sample_bookmark("2", "alice", date, "rust", false);
+        writer.conn.execute("BEGIN IMMEDIATE", []).unwrap();
+        writer.insert_bookmark_internal(&pending).unwrap();
+        writer
+            .conn
+            .execute("UPDATE sync_changes SET changed_at = changed_at - 100", [])
+            .unwrap();
+
+        let (opened_tx, opened_rx) = std::sync::mpsc::channel();
+        let export = std::thread::spawn(move || {
+            let reader = Database::open(&path).unwrap();
+            opened_tx.send(()).unwrap();
+            let mut out = Vec::new();
+            reader
+                .export_json_since(chrono::DateTime::UNIX_EPOCH, &mut out)
+                .unwrap();
+            serde_json::from_slice::<SyncExport>(&out).unwrap()
+        });
+        opened_rx.recv().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        writer.conn.execute("COMMIT", []).unwrap();
+
+        let export = export.join().unwrap();
+        let ids: Vec<&str> = export.bookmarks.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, vec![stored.id.as_str(), pending.id.as_str()]);
+    }
+
+    #[test]
+    fn empty_queries_list_the_newest_bookmarks_instead_of_failing() {
+        let db = Database::open_memory().unwrap();
+        let day = |d| Utc.with_ymd_and_hms(2024, 5, d, 8, 0, 0).unwrap();
+        db.insert_bookmarks(&[
+            sample_bookmark("1", "alice", day(1), "rust", false),
+            sample_bookmark("2", "bob", day(2), "svelte", false),
+        ])
+        .unwrap();
+        let newest = db
+            .search_with_filters(None, None, None, None, None, false, None, 10)
+            .unwrap();
+        assert_eq!(newest.len(), 2);
+
+        for query in ["", "   "] {
+            assert_eq!(db.search(query, 10).unwrap(), newest);
+            assert_eq!(db.search(query, 1).unwrap(), newest[..1]);
+            assert_eq!(
+                db.search_with_filters(Some(query), None, None, None, None, false, None, 10)
+                    .unwrap(),
+                newest
+            );
+            assert_eq!(
+                db.search_in_field(SearchField::Author, query, 10).unwrap(),
+                newest
+            );
+        }
+        assert!(db.enable_instant_search().unwrap());
+        assert_eq!(db.search_instant(" ", 10).unwrap(), newest);
+    }
+
+    #[test]
+    fn hashtags_mentions_and_punctuation_terms_search_sensibly() {
+        let db = Database::open_memory().unwrap();
+        let day = |d| Utc.with_ymd_and_hms(2024, 5, d, 8, 0, 0).unwrap();
+        let tagged = sample_bookmark("1", "alice", day(1), "rust", false);
+        let by_rust = sample_bookmark("2", "rust", day(2), "misc", false);
+        let mut mention = sample_bookmark("3", "bob", day(3), "misc", false);
+        mention.content = "Release notes from @ferris today".into();
+        let by_ferris = sample_bookmark("4", "ferris", day(4), "misc", false);
+        let mut cpp = sample_bookmark("5", "carol", day(5), "misc", false);
+        cpp.content = "Modern C++ idioms".into();
+        let mut compilers = sample_bookmark("6", "dave", day(6), "misc", false);
+        compilers.content = "Compilers explained".into();
+        db.insert_bookmarks(&[
+            tagged.clone(),
+            by_rust.clone(),
+            mention.clone(),
+            by_ferris.clone(),
+            cpp.clone(),
+            compilers.clone(),
+        ])
+        .unwrap();
+        let ids = |found: Vec<Bookmark>| {
+            let mut ids: Vec<String> = found.into_iter().map(|b| b.id).collect();
+            ids.sort();
+            ids
+        };
+        let sorted = |mut expected: Vec<String>| {
+            expected.sort();
+            expected
+        };
+
+        // `#` searches content and tags, not the author named "rust"
+        assert_eq!(
+            ids(db.search("#rust", 10).unwrap()),
+            vec![tagged.id.clone()]
+        );
+        // `@` searches content and authors
+        assert_eq!(
+            ids(db.search("@ferris", 10).unwrap()),
+            sorted(vec![mention.id.clone(), by_ferris.id.clone()])
+        );
+        // `C++` is the word "c", not a prefix of "compilers"
+        assert_eq!(ids(db.search("C++", 10).unwrap()), vec![cpp.id.clone()]);
+        assert_eq!(
+            ids(db
+                .search_with_filters(Some("#rust"), None, None, None, None, false, None, 10)
+                .unwrap()),
+            vec![tagged.id.clone()]
+        );
+        assert_eq!(
+            ids(db.search_in_field(SearchField::Content, "C++", 10).unwrap()),
+            vec![cpp.id.clone()]
+        );
+
+        // Nothing searchable left: no results rather than an FTS5 syntax error
+        for query in ["++", "#", "@ #"] {
+            assert!(db.search(query, 10).unwrap().is_empty());
+            assert!(db
+                .search_in_field(SearchField::Tags, query, 10)
+                .unwrap()
+                .is_empty());
+            assert!(db
+                .search_with_filters(Some(query), None, None, None, None, false, None, 10)
+                .unwrap()
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn recent_tags_and_authors_follow_the_latest_import() {
+        let db = Database::open_memory().unwrap();
+        let day = |d| Utc.with_ymd_and_hms(2024, 5, d, 8, 0, 0).unwrap();
+        let mut old_rust = sample_bookmark("1", "alice", day(1), "rust", false);
+        old_rust.imported_at = day(1);
+        let mut svelte = sample_bookmark("2", "bob", day(2), "svelte", false);
+        svelte.imported_at = day(5);
+        let mut new_rust = sample_bookmark("3", "alice", day(3), "rust", false);
+        new_rust.imported_at = day(10);
+        new_rust.tags.push("async".into());
+        let mut trashed = sample_bookmark("4", "carol", day(4), "go", false);
+        trashed.imported_at = day(20);
+        db.insert_bookmarks(&[old_rust, svelte, new_rust, trashed.clone()])
+            .unwrap();
+        db.delete_bookmark(&trashed.id).unwrap();
+
+        assert_eq!(db.recent_tags(10).unwrap(), vec!["async", "rust", "svelte"]);
+        assert_eq!(db.recent_tags(1).unwrap(), vec!["async"]);
+        assert_eq!(
+            db.recent_authors(10).unwrap(),
+            vec![
+                ("alice".to_string(), "alice".to_string()),
+                ("bob".to_string(), "bob".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn tag_maps_replay_renames_and_merges_on_another_library() {
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let curated = Database::open_memory().unwrap();
+        let bookmarks = [
+            sample_bookmark("1", "alice", date, "ml", false),
+            sample_bookmark("2", "bob", date, "ai", false),
+            sample_bookmark("3", "carol", date, "Rust", false),
+        ];
+        curated.insert_bookmarks(&bookmarks).unwrap();
+        assert_eq!(curated.rename_tag("ml", "machine-learning").unwrap(), 1);
+        assert_eq!(curated.merge_tags(&["ai"], "machine-learning").unwrap(), 1);
+        assert_eq!(curated.rename_tag("Rust", "rust").unwrap(), 1);
+        assert_eq!(curated.rename_tag("missing", "rust").unwrap(), 0);
+        assert_eq!(
+            curated
+                .get_bookmark(&bookmarks[1].id)
+                .unwrap()
+                .unwrap()
+                .tags,
+            vec!["machine-learning"]
+        );
+        // Only the renamed tags mention "machine", so this goes through tags_text
+        assert_eq!(curated.search("machine", 10).unwrap().len(), 2);
+
+        let mut exported = Vec::new();
+        assert_eq!(curated.export_tag_map(&mut exported).unwrap(), 2);
+        let map: TagMap = serde_json::from_slice(&exported).unwrap();
+        assert_eq!(
+            map.tags,
+            vec![
+                TagMapEntry {
+                    name: "machine-learning".into(),
+                    count: 2,
+                    aliases: vec!["ai".into(), "ml".into()],
+                },
+                TagMapEntry {
+                    name: "rust".into(),
+                    count: 1,
+                    aliases: Vec::new(),
+                },
+            ]
+        );
+
+        // The other library already has "machine-learning", so its "ml" and
+        // "AI" merge into it; "RUST" takes the map's spelling
+        let other = Database::open_memory().unwrap();
+        let bookmarks = [
+            sample_bookmark("1", "alice", date, "ml", false),
+            sample_bookmark("4", "dave", date, "machine-learning", false),
+            sample_bookmark("5", "erin", date, "RUST", false),
+        ];
+        other.insert_bookmarks(&bookmarks).unwrap();
+        other
+            .add_tag_to_bookmark(&bookmarks[0].id, "machine-learning")
+            .unwrap();
+        other.add_tag_to_bookmark(&bookmarks[1].id, "AI").unwrap();
+
+        let report = other.import_tag_map(exported.as_slice()).unwrap();
+        assert_eq!(
+            report,
+            TagMapReport {
+                created: 0,
+                renamed: 3,
+                bookmarks_changed: 3,
+            }
+        );
+        let mut tags = other.get_all_tags().unwrap();
+        tags.sort();
+        assert_eq!(
+            tags,
+            vec![("machine-learning".to_string(), 2), ("rust".to_string(), 1)]
+        );
+        assert_eq!(
+            other.get_bookmark(&bookmarks[1].id).unwrap().unwrap().tags,
+            vec!["machine-learning"]
+        );
+
+        // Exporting again carries the same taxonomy, and importing it twice
+        // changes nothing
+        let mut again = Vec::new();
+        other.export_tag_map(&mut again).unwrap();
+        let again: TagMap = serde_json::from_slice(&again).unwrap();
+        assert_eq!(again, map);
+        assert_eq!(
+            other.import_tag_map(exported.as_slice()).unwrap(),
+            TagMapReport::default()
+        );
+
+        // Tags the other library lacks are created
+        let empty = Database::open_memory().unwrap();
+        let report = empty.import_tag_map(exported.as_slice()).unwrap();
+        assert_eq!(report.created, 2);
+        assert_eq!(empty.get_all_tags().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn reimports_advance_last_seen_at_even_for_skipped_duplicates() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let bookmarks = [
+            sample_bookmark("1", "alice", date, "rust", false),
+            sample_bookmark("2", "bob", date, "rust", false),
+            sample_bookmark("3", "carol", date, "rust", false),
+        ];
+        db.insert_bookmarks(&bookmarks).unwrap();
+        let first_seen = db.get_bookmark(&bookmarks[0].id).unwrap().unwrap();
+        assert!(first_seen.last_seen_at.is_some());
+
+        // Pretend the first import happened a day ago
+        let day_ago = chrono::Utc::now() - chrono::Duration::days(1);
+        db.conn
+            .execute(
+                "UPDATE bookmarks SET last_seen_at = ?1",
+                params![day_ago.timestamp()],
+            )
+            .unwrap();
+        let reimport_started = chrono::Utc::now() - chrono::Duration::seconds(1);
+
+        // The next export still lists the first two bookmarks (parsed afresh,
+        // so only the URL matches); the third is gone
+        let report = db
+            .import(&[
+                sample_bookmark("1", "alice", date, "rust", false),
+                bookmarks[1].clone(),
+            ])
+            .unwrap();
+        assert_eq!(report.skipped_duplicate, 2);
+
+        for bookmark in &bookmarks[..2] {
+            let seen = db.get_bookmark(&bookmark.id).unwrap().unwrap();
+            assert!(seen.last_seen_at.unwrap() >= reimport_started);
+        }
+        let stale = db.bookmarks_not_seen_since(reimport_started).unwrap();
+        assert_eq!(
+            stale.iter().map(|b| b.id.as_str()).collect::<Vec<_>>(),
+            vec![bookmarks[2].id.as_str()]
+        );
+
+        // Merging counts as seeing it too; trashed bookmarks are left out
+        db.merge_import(std::slice::from_ref(&bookmarks[2]))
+            .unwrap();
+        assert!(db
+            .bookmarks_not_seen_since(reimport_started)
+            .unwrap()
+            .is_empty());
+        db.delete_bookmark(&bookmarks[0].id).unwrap();
+        assert!(db
+            .bookmarks_not_seen_since(chrono::Utc::now() + chrono::Duration::days(1))
+            .unwrap()
+            .iter()
+            .all(|b| b.id != bookmarks[0].id));
+    }
+
+    #[test]
+    fn set_favorites_updates_a_selection_at_once() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let bookmarks = [
+            sample_bookmark("1", "alice", date, "rust", false),
+            sample_bookmark("2", "bob", date, "rust", false),
+            sample_bookmark("3", "carol", date, "rust", false),
+        ];
+        db.insert_bookmarks(&bookmarks).unwrap();
+        db.set_favorite(&bookmarks[1].id, true).unwrap();
+
+        let selection = [
+            bookmarks[0].id.as_str(),
+            bookmarks[1].id.as_str(),
+            "no-such-bookmark",
+        ];
+        // The second was already a favorite and the last doesn't exist
+        assert_eq!(db.set_favorites(&selection, true).unwrap(), 1);
+        assert_eq!(db.get_stats().unwrap().favorite_bookmarks, 2);
+        assert!(
+            !db.get_bookmark(&bookmarks[2].id)
+                .unwrap()
+                .unwrap()
+                .is_favorite
+        );
+
+        assert_eq!(db.set_favorites(&selection, false).unwrap(), 2);
+        assert_eq!(db.get_stats().unwrap().favorite_bookmarks, 0);
+        assert_eq!(db.set_favorites(&[], true).unwrap(), 0);
+    }
+
+    #[test]
+    fn bookmarks_can_live_in_several_collections() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let bookmarks = [
+            sample_bookmark("1", "alice", date, "rust", false),
+            sample_bookmark("2", "bob", date, "rust", false),
+            sample_bookmark("3", "carol", date, "go", false),
+        ];
+        db.insert_bookmarks(&bookmarks).unwrap();
+
+        let thesis = db.create_collection("Thesis").unwrap();
+        let reading = db.create_collection(" reading list ").unwrap();
+        assert_eq!(reading.name, "reading list");
+        assert!(db.create_collection("THESIS").is_err());
+        assert!(db.create_collection("  ").is_err());
+
+        for bookmark in &bookmarks[..2] {
+            assert!(db.add_to_collection(thesis.id, &bookmark.id).unwrap());
+        }
+        assert!(!db.add_to_collection(thesis.id, &bookmarks[0].id).unwrap());
+        assert!(!db.add_to_collection(thesis.id, "no-such-bookmark").unwrap());
+        assert!(!db.add_to_collection(9999, &bookmarks[0].id).unwrap());
+        assert!(db.add_to_collection(reading.id, &bookmarks[0].id).unwrap());
+
+        let page = db
+            .get_collection_bookmarks(thesis.id, Pagination::new(0, 1))
+            .unwrap();
+        assert_eq!(page.total, 2);
+        assert!(page.has_more);
+        assert_eq!(page.items[0].tags, vec!["rust"]);
+
+        // Moving keeps the bookmark's other collections
+        assert!(db
+            .move_to_collection(&bookmarks[1].id, thesis.id, reading.id)
+            .unwrap());
+        assert!(!db
+            .move_to_collection(&bookmarks[2].id, thesis.id, reading.id)
+            .unwrap());
+        assert!(!db
+            .move_to_collection(&bookmarks[0].id, thesis.id, 9999)
+            .unwrap());
+        let counts = |db: &Database| {
+            db.list_collections()
+                .unwrap()
+                .into_iter()
+                .map(|c| (c.name, c.bookmark_count))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            counts(&db),
+            vec![("reading list".to_string(), 2), ("Thesis".to_string(), 1)]
+        );
+
+        // Trashed bookmarks drop out of counts and pages
+        db.delete_bookmark(&bookmarks[0].id).unwrap();
+        assert_eq!(
+            counts(&db),
+            vec![("reading list".to_string(), 1), ("Thesis".to_string(), 0)]
+        );
+        assert!(db
+            .remove_from_collection(reading.id, &bookmarks[1].id)
+            .unwrap());
+        assert!(!db
+            .remove_from_collection(reading.id, &bookmarks[1].id)
+            .unwrap());
+
+        assert!(db.delete_collection(thesis.id).unwrap());
+        assert!(!db.delete_collection(thesis.id).unwrap());
+        let members: i64 = db
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM collection_bookmarks WHERE collection_id = ?1",
+                params![thesis.id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(members, 0);
+        assert!(db.get_bookmark(&bookmarks[0].id).unwrap().is_some());
+        assert_eq!(db.list_collections().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn counts_agree_with_filtered_fetches() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let bookmarks = [
+            sample_bookmark("1", "alice", date, "rust", true),
+            sample_bookmark("2", "alice", date, "go", false),
+            sample_bookmark("3", "bob", date, "rust", false),
+            sample_bookmark("4", "bob", date, "rust", true),
+        ];
+        db.insert_bookmarks(&bookmarks).unwrap();
+        db.delete_bookmark(&bookmarks[3].id).unwrap();
+
+        assert_eq!(db.count_by_tag("rust").unwrap(), 2);
+        assert_eq!(db.count_by_tag("RUST").unwrap(), 2);
+        assert_eq!(db.count_by_tag("missing").unwrap(), 0);
+        assert_eq!(db.count_by_author("@Alice").unwrap(), 2);
+        assert_eq!(db.count_by_author("bob").unwrap(), 1);
+
+        for filters in [
+            SearchFilters::new(),
+            SearchFilters::new().with_tag("rust"),
+            SearchFilters::new()
+                .with_author("alice")
+                .with_has_media(Some(true)),
+            SearchFilters::new().with_query("rust"),
+        ] {
+            assert_eq!(
+                db.count_matching(&filters).unwrap(),
+                db.bookmarks_matching(&filters, None).unwrap().len() as i64,
+                "{filters:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn counts_bookmarks_by_period() {
+        let db = Database::open_memory().unwrap();
+        let bookmarks = [
+            ("1", Utc.with_ymd_and_hms(2023, 12, 31, 23, 0, 0).unwrap()),
+            ("2", Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()),
+            ("3", Utc.with_ymd_and_hms(2024, 5, 20, 12, 0, 0).unwrap()),
+            ("4", Utc.with_ymd_and_hms(2024, 7, 2, 12, 0, 0).unwrap()),
+        ]
+        .map(|(id, date)| sample_bookmark(id, "alice", date, "rust", false));
+        db.insert_bookmarks(&bookmarks).unwrap();
+        db.delete_bookmark(&bookmarks[3].id).unwrap();
+
+        assert_eq!(
+            db.bookmark_counts_by_period(Period::Month).unwrap(),
+            vec![("2023-12".to_string(), 1), ("2024-05".to_string(), 2)]
+        );
+        assert_eq!(
+            db.bookmark_counts_by_period(Period::Year).unwrap(),
+            vec![("2023".to_string(), 1), ("2024".to_string(), 2)]
+        );
+        assert_eq!(db.bookmark_counts_by_period(Period::Week).unwrap().len(), 3);
+        assert_eq!(
+            db.bookmark_counts_by_period(Period::Day).unwrap()[1],
+            ("2024-05-01".to_string(), 1)
+        );
+    }
+
+    #[test]
+    fn weekly_counts_use_iso_weeks_across_new_year() {
+        let db = Database::open_memory().unwrap();
+        let bookmarks = [
+            // Friday: the last ISO week of 2020, not week 0 of 2021
+            ("1", Utc.with_ymd_and_hms(2021, 1, 1, 12, 0, 0).unwrap()),
+            ("2", Utc.with_ymd_and_hms(2021, 1, 3, 12, 0, 0).unwrap()),
+            ("3", Utc.with_ymd_and_hms(2021, 1, 4, 12, 0, 0).unwrap()),
+            // Monday and Wednesday: one week, which ISO counts in 2025
+            ("4", Utc.with_ymd_and_hms(2024, 12, 30, 12, 0, 0).unwrap()),
+            ("5", Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap()),
+        ]
+        .map(|(id, date)| sample_bookmark(id, "alice", date, "rust", false));
+        db.insert_bookmarks(&bookmarks).unwrap();
+
+        assert_eq!(
+            db.bookmark_counts_by_period(Period::Week).unwrap(),
+            vec![
+                ("2020-W53".to_string(), 2),
+                ("2021-W01".to_string(), 1),
+                ("2025-W01".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn import_separates_duplicates_from_failures() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let first = sample_bookmark("1", "alice", date, "rust", false);
+        let mut broken = sample_bookmark("2", "bob", date, "rust", true);
+        // The second link to the same tag violates bookmark_tags' primary key
+        broken.tags.push("RUST".to_string());
+        let third = sample_bookmark("3", "carol", date, "svelte", false);
+
+        let report = db
+            .import(&[first.clone(), first.clone(), broken.clone(), third])
+            .unwrap();
+
+        assert_eq!(
+            report,
+            ImportReport {
+                inserted: 2,
+                skipped_duplicate: 1,
+                skipped_content_duplicate: 0,
+                failed: 1
+            }
+        );
+        assert!(db.get_bookmark(&broken.id).unwrap().is_none());
+        assert_eq!(db.get_stats().unwrap().total_bookmarks, 2);
+        assert_eq!(
+            db.insert_bookmarks(std::slice::from_ref(&first)).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn failing_media_insert_rolls_back_only_that_bookmark() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let good = sample_bookmark("1", "alice", date, "rust", true);
+        let mut bad = sample_bookmark("2", "bob", date, "bad-media", true);
+        bad.media[0].url = "https://pbs.twimg.com/media/poison.jpg".into();
+        let after = sample_bookmark("3", "carol", date, "svelte", false);
+        // Fails after the bookmark row, its tags and FTS content are written
+        db.conn
+            .execute_batch(
+                r#"CREATE TEMP TRIGGER poison_media BEFORE INSERT ON media
+                   WHEN NEW.url LIKE '%poison%'
+                   BEGIN SELECT RAISE(ABORT, 'poisoned media'); END;"#,
+            )
+            .unwrap();
+
+        let report = db
+            .import(&[good.clone(), bad.clone(), after.clone()])
+            .unwrap();
+        assert_eq!((report.inserted, report.failed), (2, 1));
+
+        assert!(db.get_bookmark(&bad.id).unwrap().is_none());
+        let orphans: i64 = db
+            .conn
+            .query_row(
+                r#"SELECT (SELECT COUNT(*) FROM bookmark_tags WHERE bookmark_id = ?1)
+                        + (SELECT COUNT(*) FROM bookmarks_fts_content WHERE bookmark_id = ?1)
+                        + (SELECT COUNT(*) FROM authors WHERE handle = 'bob')"#,
+                params![bad.id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(orphans, 0);
+        assert!(db
+            .get_all_tags()
+            .unwrap()
+            .iter()
+            .all(|(tag, _)| tag != "bad-media"));
+        assert_eq!(db.search("bookmark", 10).unwrap().len(), 2);
+        assert_eq!(db.get_bookmark(&good.id).unwrap().unwrap().media.len(), 1);
+        assert!(db.get_bookmark(&after.id).unwrap().is_some());
+    }
+
+    #[test]
+    fn errors_outside_a_bookmark_savepoint_roll_back_the_batch() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let stored = sample_bookmark("1", "alice", date, "rust", false);
+        db.insert_bookmarks(std::slice::from_ref(&stored)).unwrap();
+        // Recording that the duplicate was seen again runs outside its savepoint
+        db.conn
+            .execute_batch(
+                r#"CREATE TEMP TRIGGER poison_seen BEFORE UPDATE OF last_seen_at ON bookmarks
+                   BEGIN SELECT RAISE(ABORT, 'poisoned last_seen_at'); END;"#,
+            )
+            .unwrap();
+
+        let new = sample_bookmark("2", "bob", date, "svelte", false);
+        assert!(db.import(&[new.clone(), stored.clone()]).is_err());
+        assert!(db.conn.is_autocommit());
+        assert!(db.get_bookmark(&new.id).unwrap().is_none());
+        // A merge records it inside the savepoint, failing only that bookmark
+        let report = db.merge_import(&[new.clone(), stored]).unwrap();
+        assert_eq!((report.inserted, report.failed), (1, 1));
+        assert!(db.conn.is_autocommit());
+    }
+
+    #[test]
+    fn cancelled_streaming_insert_keeps_committed_batches() {
+        let db = Database::open_memory().unwrap();
+        let bookmarks = (1..=7).map(|day| {
+            sample_bookmark(
+                &day.to_string(),
+                "alice",
+                Utc.with_ymd_and_hms(2024, 5, day, 12, 0, 0).unwrap(),
+                "rust",
+                false,
+            )
+        });
+        let cancel = AtomicBool::new(false);
+
+        let inserted = db
+            .insert_bookmarks_streaming_cancellable(bookmarks, 3, 0, &cancel, |processed, _| {
+                if processed >= 3 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            })
+            .unwrap();
+
+        assert_eq!(inserted, 3);
+        assert_eq!(db.count_bookmarks().unwrap(), 3);
+        assert_eq!(db.get_stats().unwrap().total_bookmarks, 3);
+    }
+
+    #[test]
+    fn authors_table_backs_bookmark_author_fields() {
+        let db = Database::open_memory().unwrap();
+        let mut first = sample_bookmark(
+            "1",
+            "alice",
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            "rust",
+            false,
+        );
+        first.author_name = "Alice".to_string();
+        let mut second = sample_bookmark(
+            "2",
+            "alice",
+            Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap(),
+            "rust",
+            false,
+        );
+        second.author_profile_image = Some("https://pbs.twimg.com/alice.jpg".to_string());
+        db.insert_bookmarks(&[first.clone(), second.clone()])
+            .unwrap();
+
+        let author = db.get_author("alice").unwrap().unwrap();
+        assert_eq!(author.name, "Alice");
+        assert_eq!(
+            author.profile_image.as_deref(),
+            Some("https://pbs.twimg.com/alice.jpg")
+        );
+        assert_eq!(db.get_stats().unwrap().unique_authors, 1);
+
+        assert!(db.enable_instant_search().unwrap());
+        let renamed = Author {
+            name: "Alice Liddell".to_string(),
+            ..author
+        };
+        assert!(db.update_author(&renamed).unwrap());
+        assert!(!db
+            .update_author(&Author {
+                handle: "nobody".to_string(),
+                ..renamed.clone()
+            })
+            .unwrap());
+
+        let reloaded = db.get_bookmark(&first.id).unwrap().unwrap();
+        assert_eq!(reloaded.author_name, "Alice Liddell");
+        assert_eq!(
+            reloaded.author_profile_image.as_deref(),
+            Some("https://pbs.twimg.com/alice.jpg")
+        );
+        assert_eq!(db.search("Liddell", 10).unwrap().len(), 2);
+        assert_eq!(db.search_instant("Liddell", 10).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn authors_backfill_from_existing_bookmarks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        let bookmark = sample_bookmark(
+            "1",
+            "alice",
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            "rust",
+            false,
+        );
+        {
+            let db = Database::open(&path).unwrap();
+            db.insert_bookmarks(&[bookmark]).unwrap();
+            // Simulate a database created before the authors table existed
+            db.conn.execute("DROP TABLE authors", []).unwrap();
+        }
+
+        let db = Database::open(&path).unwrap();
+        let author = db.get_author("alice").unwrap().unwrap();
+        assert_eq!(author.name, "alice");
+    }
+
+    #[test]
+    fn links_are_stored_and_searchable_by_domain() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        let mut repo = sample_bookmark(
+            "1",
+            "alice",
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            "rust",
+            false,
+        );
+        repo.content = "See https://github.com/rust-lang/rust and https://t.co/xyz".to_string();
+        let mut gist = sample_bookmark(
+            "2",
+            "bob",
+            Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap(),
+            "rust",
+            false,
+        );
+        gist.content = "Snippet: https://gist.github.com/bob/1".to_string();
+        let mut lookalike = sample_bookmark(
+            "3",
+            "carol",
+            Utc.with_ymd_and_hms(2024, 7, 1, 12, 0, 0).unwrap(),
+            "rust",
+            false,
+        );
+        lookalike.content = "Not it: https://notgithub.com/".to_string();
+        {
+            let db = Database::open(&path).unwrap();
+            db.insert_bookmarks(&[repo.clone(), gist.clone(), lookalike])
+                .unwrap();
+            assert_eq!(
+                db.get_links(&repo.id).unwrap(),
+                vec!["https://github.com/rust-lang/rust", "https://t.co/xyz"]
+            );
+            // Simulate a database created before the links table existed
+            db.conn.execute("DROP TABLE links", []).unwrap();
+        }
+
+        let db = Database::open(&path).unwrap();
+        let ids = db
+            .get_bookmarks_with_link_domain("www.GitHub.com", 0, 10)
+            .unwrap()
+            .into_iter()
+            .map(|bookmark| bookmark.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![gist.id.clone(), repo.id.clone()]);
+        assert_eq!(
+            db.get_bookmarks_with_link_domain("t.co", 0, 10)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        db.delete_bookmark_permanently(&repo.id).unwrap();
+        assert!(db.get_links(&repo.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn stats_snapshot_stays_fresh_after_writes() {
+        let db = Database::open_memory().unwrap();
+        let first = sample_bookmark(
+            "1",
+            "alice",
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            "rust",
+            false,
+        );
+        let second = sample_bookmark(
+            "2",
+            "bob",
+            Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap(),
+            "svelte",
+            true,
+        );
 
-        Ok(tags)
-    }
+        db.insert_bookmarks(&[first.clone(), second.clone()])
+            .unwrap();
 
-    /// Load media for a bookmark
-    pub fn load_bookmark_media(&self, bookmark_id: &str) -> Result<Vec<Media>> {
-        let mut stmt = self
-            .conn
-            .prepare("SELECT url, media_type FROM media WHERE bookmark_id = ?1")?;
+        let initial = db.get_stats().unwrap();
+        assert_eq!(initial.total_bookmarks, 2);
+        assert_eq!(initial.favorite_bookmarks, 0);
 
-        let media = stmt
-            .query_map(params![bookmark_id], |row| {
-                let url: String = row.get(0)?;
-                let media_type_str: String = row.get(1)?;
-                let media_type = match media_type_str.as_str() {
-                    "image" => MediaType::Image,
-                    "video" => MediaType::Video,
-                    "gif" => MediaType::Gif,
-                    _ => MediaType::Unknown,
-                };
-                Ok(Media { url, media_type })
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+        db.toggle_favorite(&second.id).unwrap();
+        let after_favorite = db.get_stats().unwrap();
+        assert_eq!(after_favorite.favorite_bookmarks, 1);
 
-        Ok(media)
+        db.delete_bookmark(&first.id).unwrap();
+        let after_delete = db.get_stats().unwrap();
+        assert_eq!(after_delete.total_bookmarks, 1);
+        assert_eq!(after_delete.unique_authors, 1);
+        assert_eq!(after_delete.favorite_bookmarks, 1);
     }
 
-    /// Store lightweight app metadata as JSON/text.
-    pub fn set_metadata(&self, key: &str, value: &str) -> Result<()> {
-        self.conn.execute(
-            r#"INSERT INTO app_metadata (key, value)
-               VALUES (?1, ?2)
-               ON CONFLICT(key) DO UPDATE SET value = excluded.value"#,
-            params![key, value],
-        )?;
-        Ok(())
+    #[test]
+    fn stats_follow_restores_and_tag_only_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut db = Database::open_memory().unwrap();
+        db.insert_bookmarks(&[sample_bookmark("1", "alice", date, "rust", false)])
+            .unwrap();
+        let backup = dir.path().join("backup.db");
+        db.backup_to(&backup).unwrap();
+        db.insert_bookmarks(&[
+            sample_bookmark("2", "bob", date, "go", false),
+            sample_bookmark("3", "carol", date, "go", false),
+        ])
+        .unwrap();
+        assert_eq!(db.get_stats().unwrap().total_bookmarks, 3);
+
+        db.restore_from(&backup).unwrap();
+        assert_eq!(db.count_bookmarks().unwrap(), 1);
+        assert_eq!(db.get_stats().unwrap().total_bookmarks, 1);
+        assert_eq!(db.get_stats().unwrap().unique_tags, 1);
+
+        // A tag map can create tags no bookmark carries yet
+        let map = r#"{"tags":[{"name":"later","count":0,"aliases":[]}]}"#;
+        assert_eq!(db.import_tag_map(map.as_bytes()).unwrap().created, 1);
+        assert_eq!(db.get_stats().unwrap().unique_tags, 2);
+        // ...and merging that empty tag away drops it without touching bookmarks
+        assert_eq!(db.merge_tags(&["later"], "rust").unwrap(), 0);
+        assert_eq!(db.get_stats().unwrap().unique_tags, 1);
     }
 
-    /// Read lightweight app metadata.
-    pub fn get_metadata(&self, key: &str) -> Result<Option<String>> {
-        let result = self.conn.query_row(
-            "SELECT value FROM app_metadata WHERE key = ?1",
-            params![key],
-            |row| row.get::<_, String>(0),
-        );
+    #[test]
+    fn cached_stats_notice_writes_from_other_connections() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.db");
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let writer = Database::open(&path).unwrap();
+        writer
+            .insert_bookmarks(&[sample_bookmark("1", "alice", date, "rust", false)])
+            .unwrap();
+        let reader = Database::open_read_only(&path).unwrap();
+        assert_eq!(reader.get_stats().unwrap().total_bookmarks, 1);
+        assert_eq!(reader.get_stats().unwrap().total_bookmarks, 1);
 
-        match result {
-            Ok(value) => Ok(Some(value)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(error) => Err(error.into()),
-        }
+        writer
+            .insert_bookmarks(&[sample_bookmark("2", "bob", date, "go", false)])
+            .unwrap();
+        let stats = reader.get_stats().unwrap();
+        assert_eq!(stats.total_bookmarks, 2);
+        assert_eq!(stats.unique_tags, 2);
+        assert_eq!(writer.get_stats().unwrap(), stats);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::models::BookmarkBuilder;
-    use chrono::{TimeZone, Utc};
 
-    fn sample_bookmark(
-        tweet_id: &str,
-        handle: &str,
-        date: chrono::DateTime<Utc>,
-        tag: &str,
-        with_media: bool,
-    ) -> Bookmark {
-        let mut builder = BookmarkBuilder::new()
-            .tweet_url(format!("https://x.com/{handle}/status/{tweet_id}"))
-            .content(format!("Bookmark {tweet_id} #{tag}"))
-            .tweeted_at(date)
-            .author_handle(handle)
-            .author_name(handle)
-            .add_tag(tag);
+    #[test]
+    fn find_duplicates_groups_url_variants_and_reposted_content() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut original = sample_bookmark("100", "alice", date, "rust", false);
+        let mut twitter_copy = sample_bookmark("100", "alice", date, "rust", false);
+        twitter_copy.tweet_url = "https://twitter.com/alice/status/100?s=20".into();
+        twitter_copy.content = "Different text, same tweet".into();
+        let mut repost = sample_bookmark("200", "alice", date, "rust", false);
+        repost.content = format!("{}  https://t.co/abc", original.content);
+        let unrelated = sample_bookmark("300", "carol", date, "svelte", false);
+
+        original.imported_at = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        twitter_copy.imported_at = Utc.with_ymd_and_hms(2024, 6, 2, 0, 0, 0).unwrap();
+        repost.imported_at = Utc.with_ymd_and_hms(2024, 5, 31, 0, 0, 0).unwrap();
+        db.insert_bookmarks(&[
+            original.clone(),
+            twitter_copy.clone(),
+            repost.clone(),
+            unrelated,
+        ])
+        .unwrap();
+
+        let groups = db.find_duplicates().unwrap();
+        assert_eq!(groups, vec![vec![repost.id, original.id, twitter_copy.id]]);
+    }
 
-        if with_media {
-            builder = builder.add_media("https://pbs.twimg.com/media/example.jpg");
+    #[test]
+    fn find_duplicates_keeps_the_same_text_from_different_authors_apart() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut alice = sample_bookmark("1", "alice", date, "rust", false);
+        let mut bob = sample_bookmark("2", "bob", date, "rust", false);
+        let mut alice_again = sample_bookmark("3", "alice", date, "rust", false);
+        for bookmark in [&mut alice, &mut bob, &mut alice_again] {
+            bookmark.content = "lol".into();
         }
+        db.insert_bookmarks(&[alice.clone(), bob.clone(), alice_again.clone()])
+            .unwrap();
 
-        builder.build().unwrap()
+        let groups = db.find_duplicates().unwrap();
+        assert_eq!(groups, vec![vec![alice.id, alice_again.id]]);
     }
 
     #[test]
-    fn search_with_filters_is_parameterized_and_filters_correctly() {
+    fn deleted_bookmarks_go_to_trash_until_restored_or_purged() {
         let db = Database::open_memory().unwrap();
-        let first = sample_bookmark(
+        let kept = sample_bookmark(
             "1",
             "alice",
             Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
             "rust",
-            true,
+            false,
         );
-        let second = sample_bookmark(
+        let trashed = sample_bookmark(
             "2",
             "bob",
             Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap(),
-            "svelte",
+            "rust",
             false,
         );
-
-        db.insert_bookmarks(&[first.clone(), second.clone()])
+        db.insert_bookmarks(&[kept.clone(), trashed.clone()])
             .unwrap();
-        db.set_favorite(&first.id, true).unwrap();
 
-        let results = db
-            .search_with_filters(
-                Some("Bookmark"),
-                Some("rust"),
-                Some("alice"),
-                Some(Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap()),
-                Some(Utc.with_ymd_and_hms(2024, 5, 31, 23, 59, 59).unwrap()),
-                true,
-                Some(true),
-                20,
+        assert!(db.delete_bookmark(&trashed.id).unwrap());
+        assert!(!db.delete_bookmark(&trashed.id).unwrap());
+
+        let live: Vec<_> = db
+            .get_bookmarks(0, 10)
+            .unwrap()
+            .into_iter()
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(live, vec![kept.id.clone()]);
+        assert_eq!(db.count_bookmarks().unwrap(), 1);
+        assert_eq!(db.search("Bookmark", 10).unwrap().len(), 1);
+        assert_eq!(db.get_bookmarks_by_tag("rust", 0, 10).unwrap().len(), 1);
+        let stats = db.get_stats().unwrap();
+        assert_eq!(stats.total_bookmarks, 1);
+        assert_eq!(stats.unique_authors, 1);
+        assert_eq!(stats.top_tags, vec![("rust".to_string(), 1)]);
+
+        let trash = db.get_trash(0, 10).unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, trashed.id);
+        assert_eq!(trash[0].tags, vec!["rust"]);
+
+        assert!(db.restore_bookmark(&trashed.id).unwrap());
+        assert_eq!(db.count_bookmarks().unwrap(), 2);
+        assert!(db.get_trash(0, 10).unwrap().is_empty());
+
+        db.delete_bookmark(&trashed.id).unwrap();
+        assert_eq!(db.purge_deleted(Duration::from_secs(3600)).unwrap(), 0);
+        assert_eq!(db.purge_deleted(Duration::ZERO).unwrap(), 0);
+        db.conn
+            .execute(
+                "UPDATE bookmarks SET deleted_at = deleted_at - 7200 WHERE id = ?1",
+                params![trashed.id],
             )
             .unwrap();
+        assert_eq!(db.purge_deleted(Duration::from_secs(3600)).unwrap(), 1);
+        assert!(db.get_bookmark(&trashed.id).unwrap().is_none());
 
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].author_handle, "alice");
-
-        let injection_attempt = db
-            .search_with_filters(Some("' OR 1=1 --"), None, None, None, None, false, None, 20)
-            .unwrap();
-        assert!(injection_attempt.is_empty());
+        assert!(db.delete_bookmark_permanently(&kept.id).unwrap());
+        assert_eq!(db.count_bookmarks().unwrap(), 0);
     }
 
     #[test]
-    fn get_bookmarks_batches_related_tags_and_media() {
+    fn archived_bookmarks_leave_the_default_listing_but_stay_searchable() {
         let db = Database::open_memory().unwrap();
-        let first = sample_bookmark(
+        let unread = sample_bookmark(
             "1",
             "alice",
             Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
             "rust",
-            true,
+            false,
         );
-        let mut second = sample_bookmark(
+        let done = sample_bookmark(
             "2",
             "bob",
             Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap(),
-            "svelte",
+            "rust",
             false,
         );
-        second.tags.push("frontend".to_string());
-
-        db.insert_bookmarks(&[first.clone(), second.clone()])
+        db.insert_bookmarks(&[unread.clone(), done.clone()])
             .unwrap();
 
-        let bookmarks = db.get_bookmarks(0, 10).unwrap();
+        assert!(db.toggle_archived(&done.id).unwrap());
+
+        let listing = db.get_bookmarks(0, 10).unwrap();
+        assert_eq!(listing.len(), 1);
+        assert_eq!(listing[0].id, unread.id);
+        assert!(!listing[0].is_archived);
+        assert_eq!(db.count_bookmarks().unwrap(), 1);
+        assert_eq!(db.get_stats().unwrap().total_bookmarks, 2);
+
+        let archived = db.get_archived(0, 10).unwrap();
+        assert_eq!(archived.len(), 1);
+        assert!(archived[0].is_archived);
+
+        let searched = db
+            .search_with_filters(None, Some("rust"), None, None, None, false, None, 10)
+            .unwrap();
+        assert_eq!(searched.len(), 2);
 
+        db.set_archived(&done.id, false).unwrap();
+        assert!(db.get_archived(0, 10).unwrap().is_empty());
         assert_eq!(db.count_bookmarks().unwrap(), 2);
-        assert_eq!(bookmarks.len(), 2);
-        assert_eq!(bookmarks[0].author_handle, "bob");
+    }
+
+    #[test]
+    fn suggest_tags_matches_prefixes_by_popularity() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        db.insert_bookmarks(&[
+            sample_bookmark("1", "alice", date, "rust", false),
+            sample_bookmark("2", "alice", date, "rust", false),
+            sample_bookmark("3", "bob", date, "rustc", false),
+            sample_bookmark("4", "bob", date, "ruby", false),
+            sample_bookmark("5", "bob", date, "100%_done", false),
+        ])
+        .unwrap();
+
         assert_eq!(
-            bookmarks[0].tags,
-            vec!["frontend".to_string(), "svelte".to_string()]
+            db.suggest_tags("RU", 10).unwrap(),
+            vec![
+                ("rust".to_string(), 2),
+                ("ruby".to_string(), 1),
+                ("rustc".to_string(), 1)
+            ]
+        );
+        assert_eq!(db.suggest_tags("rus", 1).unwrap().len(), 1);
+        assert_eq!(
+            db.suggest_tags("100%_", 10).unwrap(),
+            vec![("100%_done".to_string(), 1)]
+        );
+        assert!(db.suggest_tags("1000", 10).unwrap().is_empty());
+
+        let plan: Vec<String> = db
+            .conn
+            .prepare("EXPLAIN QUERY PLAN SELECT id FROM tags WHERE name LIKE ?1 ESCAPE '\\'")
+            .unwrap()
+            .query_map(["ru%"], |row| row.get::<_, String>(3))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert!(
+            plan.iter().any(|step| step.starts_with("SEARCH")),
+            "prefix lookup should use the tags.name index: {plan:?}"
         );
-        assert!(bookmarks[0].media.is_empty());
-        assert_eq!(bookmarks[1].author_handle, "alice");
-        assert_eq!(bookmarks[1].tags, vec!["rust".to_string()]);
-        assert_eq!(bookmarks[1].media.len(), 1);
     }
 
     #[test]
-    fn stats_snapshot_stays_fresh_after_writes() {
+    fn suggest_authors_matches_handles_and_names() {
         let db = Database::open_memory().unwrap();
-        let first = sample_bookmark(
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut named = sample_bookmark("3", "rustlang", date, "rust", false);
+        named.author_name = "Rust Language".into();
+        let mut other = sample_bookmark("4", "bob", date, "rust", false);
+        other.author_name = "Robert".into();
+        db.insert_bookmarks(&[
+            sample_bookmark("1", "Ralf", date, "rust", false),
+            sample_bookmark("2", "Ralf", date, "rust", false),
+            named,
+            other,
+        ])
+        .unwrap();
+
+        assert_eq!(
+            db.suggest_authors("@r", 10).unwrap(),
+            vec![
+                ("ralf".to_string(), "Ralf".to_string(), 2),
+                ("bob".to_string(), "Robert".to_string(), 1),
+                ("rustlang".to_string(), "Rust Language".to_string(), 1),
+            ]
+        );
+        assert_eq!(db.suggest_authors("RUST L", 10).unwrap().len(), 1);
+        assert!(db.suggest_authors("z", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn author_handles_are_matched_case_insensitively() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut first = sample_bookmark("1", "RustLang", date, "rust", false);
+        first.author_name = "Zoë Rust".into();
+        let second = sample_bookmark("2", "rustlang", date, "rust", false);
+        db.insert_bookmarks(&[first, second]).unwrap();
+
+        for handle in ["rustlang", "RUSTLANG", "@RustLang"] {
+            assert_eq!(
+                db.get_bookmarks_by_author(handle, 0, 10).unwrap().len(),
+                2,
+                "{handle}"
+            );
+        }
+        let page = db
+            .search_with_filters(None, None, Some("RustLang"), None, None, false, None, 10)
+            .unwrap();
+        assert_eq!(page.len(), 2);
+        assert!(page
+            .iter()
+            .all(|bookmark| bookmark.author_handle == "rustlang"));
+        assert_eq!(
+            db.get_author("RUSTLANG").unwrap().unwrap().handle,
+            "rustlang"
+        );
+        assert_eq!(db.suggest_authors("zoe", 10).unwrap().len(), 1);
+        assert_eq!(db.suggest_authors("ZOË", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn migration_lowercases_existing_author_handles() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("eterea.db");
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        {
+            let db = Database::open(&path).unwrap();
+            db.insert_bookmarks(&[
+                sample_bookmark("1", "alice", date, "rust", false),
+                sample_bookmark("2", "bob", date, "rust", false),
+            ])
+            .unwrap();
+            // Simulate rows written before handles were normalized
+            db.conn
+                .execute_batch(
+                    r#"UPDATE bookmarks SET author_handle = 'Alice' WHERE author_handle = 'alice';
+                       UPDATE bookmarks_fts_content SET author_handle = 'Alice' WHERE author_handle = 'alice';
+                       INSERT INTO authors (handle, name) VALUES ('Alice', 'Alice');
+                       UPDATE authors SET handle = 'BOB' WHERE handle = 'bob';
+                       DELETE FROM app_metadata WHERE key = 'lowercase_author_handles_v1';"#,
+                )
+                .unwrap();
+        }
+
+        let db = Database::open(&path).unwrap();
+        assert!(db
+            .get_metadata(LOWERCASE_HANDLES_METADATA_KEY)
+            .unwrap()
+            .is_some());
+        assert_eq!(db.get_bookmarks_by_author("alice", 0, 10).unwrap().len(), 1);
+        assert_eq!(db.get_author("Bob").unwrap().unwrap().handle, "bob");
+        let handles: Vec<String> = db
+            .conn
+            .prepare("SELECT handle FROM authors ORDER BY handle")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(handles, vec!["alice", "bob"]);
+        assert_eq!(
+            db.search_in_field(SearchField::Author, "alice", 10)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn annotations_keep_history_and_are_searchable() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut bookmark = sample_bookmark("1", "alice", date, "rust", false);
+        bookmark.comments = Some("imported remark".to_string());
+        db.insert_bookmarks(std::slice::from_ref(&bookmark))
+            .unwrap();
+
+        let first = db
+            .add_annotation(&bookmark.id, "  compare with tokio  ")
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.body, "compare with tokio");
+        db.add_annotation(&bookmark.id, "cited in chapter three")
+            .unwrap()
+            .unwrap();
+
+        let bodies = db
+            .get_annotations(&bookmark.id)
+            .unwrap()
+            .into_iter()
+            .map(|annotation| annotation.body)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            bodies,
+            vec![
+                "imported remark",
+                "compare with tokio",
+                "cited in chapter three"
+            ]
+        );
+        let stored = db.get_bookmark(&bookmark.id).unwrap().unwrap();
+        assert_eq!(stored.comments.as_deref(), Some("cited in chapter three"));
+        for query in ["tokio", "chapter", "remark"] {
+            assert_eq!(
+                db.search_in_field(SearchField::Notes, query, 10)
+                    .unwrap()
+                    .len(),
+                1,
+                "{query}"
+            );
+        }
+
+        assert!(db.add_annotation("missing", "note").unwrap().is_none());
+        assert!(db.add_annotation(&bookmark.id, "   ").is_err());
+    }
+
+    #[test]
+    fn export_markdown_writes_only_matching_bookmarks() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        db.insert_bookmarks(&[
+            sample_bookmark("1", "alice", date, "rust", false),
+            sample_bookmark("2", "bob", date + chrono::Duration::days(1), "rust", false),
+            sample_bookmark("3", "carol", date, "svelte", false),
+        ])
+        .unwrap();
+
+        let mut out = Vec::new();
+        let written = db
+            .export_markdown(&SearchFilters::new().with_tag("rust"), &mut out)
+            .unwrap();
+        let markdown = String::from_utf8(out).unwrap();
+
+        assert_eq!(written, 2);
+        assert_eq!(markdown.matches("\n---\n").count(), 2);
+        let bob = markdown.find("(@bob)").unwrap();
+        let alice = markdown.find("(@alice)").unwrap();
+        assert!(bob < alice, "newest first");
+        assert!(!markdown.contains("@carol"));
+    }
+
+    #[test]
+    fn export_query_writes_exactly_the_listed_results() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut bookmarks = [
+            sample_bookmark("1", "alice", date, "rust", false),
+            sample_bookmark("2", "bob", date + chrono::Duration::days(1), "rust", true),
+            sample_bookmark("3", "carol", date, "svelte", false),
+            sample_bookmark("4", "dave", date - chrono::Duration::days(1), "rust", false),
+        ];
+        // Imported oldest tweet first, so the two sort orders differ
+        for (i, bookmark) in bookmarks.iter_mut().enumerate() {
+            bookmark.imported_at = date - chrono::Duration::hours(i as i64);
+        }
+        db.insert_bookmarks(&bookmarks).unwrap();
+
+        for filters in [
+            SearchFilters::new().with_tag("rust"),
+            SearchFilters::new()
+                .with_tag("rust")
+                .with_sort_by(SortBy::ImportedAt),
+            SearchFilters::new().with_query("Bookmark"),
+        ] {
+            let listed = db
+                .query_bookmarks(&filters, Pagination::new(0, 100))
+                .unwrap();
+            let listed_ids = listed
+                .items
+                .iter()
+                .map(|b| b.id.clone())
+                .collect::<Vec<_>>();
+
+            let mut json = Vec::new();
+            let written = db
+                .export_query(&filters, ExportFormat::Json, &mut json)
+                .unwrap();
+            assert_eq!(written as i64, listed.total);
+            assert_eq!(written as i64, db.count_matching(&filters).unwrap());
+            let exported: Vec<Bookmark> = serde_json::from_slice(&json).unwrap();
+            assert_eq!(
+                exported.into_iter().map(|b| b.id).collect::<Vec<_>>(),
+                listed_ids
+            );
+
+            let mut csv = Vec::new();
+            let written = db
+                .export_query(&filters, ExportFormat::Csv, &mut csv)
+                .unwrap();
+            assert_eq!(written, listed_ids.len());
+            // Header plus one line per bookmark (sample content is one line)
+            assert_eq!(String::from_utf8(csv).unwrap().lines().count(), written + 1);
+        }
+    }
+
+    #[test]
+    fn date_estimated_flag_round_trips() {
+        let db = Database::open_memory().unwrap();
+        let mut bookmark =
+            sample_bookmark("1", "alice", chrono::DateTime::UNIX_EPOCH, "rust", false);
+        bookmark.date_estimated = true;
+        db.insert_bookmarks(std::slice::from_ref(&bookmark))
+            .unwrap();
+
+        let stored = db.get_bookmark(&bookmark.id).unwrap().unwrap();
+        assert!(stored.date_estimated);
+        assert_eq!(stored.tweeted_at, chrono::DateTime::UNIX_EPOCH);
+    }
+
+    #[test]
+    fn get_bookmarks_by_ids_preserves_input_order() {
+        let db = Database::open_memory().unwrap();
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let first = sample_bookmark("1", "alice", date, "rust", true);
+        let second = sample_bookmark("2", "bob", date, "svelte", false);
+        let third = sample_bookmark("3", "carol", date, "rust", false);
+        db.insert_bookmarks(&[first.clone(), second.clone(), third.clone()])
+            .unwrap();
+        db.delete_bookmark(&second.id).unwrap();
+
+        assert!(db.get_bookmarks_by_ids(&[]).unwrap().is_empty());
+
+        let fetched = db
+            .get_bookmarks_by_ids(&[&third.id, "missing", &second.id, &first.id])
+            .unwrap();
+        let ids: Vec<_> = fetched.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, vec![third.id.as_str(), first.id.as_str()]);
+        assert_eq!(fetched[1].tags, vec!["rust"]);
+        assert_eq!(fetched[1].media.len(), 1);
+    }
+
+    #[test]
+    fn tokenizer_is_chosen_at_creation_and_can_be_rebuilt() {
+        let db = Database::open_memory().unwrap();
+        assert_eq!(db.tokenizer().unwrap(), FtsTokenizer::Porter);
+
+        let mut bookmark = sample_bookmark(
             "1",
             "alice",
             Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
             "rust",
             false,
         );
-        let second = sample_bookmark(
-            "2",
-            "bob",
-            Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap(),
-            "svelte",
-            true,
-        );
+        bookmark.content = "Running benchmarks with HashMap::entry".into();
+        db.insert_bookmarks(&[bookmark]).unwrap();
+
+        // Porter stems words but can't match inside them
+        assert_eq!(db.search("run", 10).unwrap().len(), 1);
+        assert!(db.search("Map::entry", 10).unwrap().is_empty());
+
+        db.reconfigure_tokenizer(FtsTokenizer::Trigram).unwrap();
+        assert_eq!(db.tokenizer().unwrap(), FtsTokenizer::Trigram);
+        assert_eq!(db.search("Map::entry", 10).unwrap().len(), 1);
+        assert_eq!(db.search("unning", 10).unwrap().len(), 1);
+
+        let unicode = Database::open_memory_with_options(
+            DatabaseOptions::new().with_tokenizer(FtsTokenizer::Unicode61),
+        )
+        .unwrap();
+        assert_eq!(unicode.tokenizer().unwrap(), FtsTokenizer::Unicode61);
+    }
 
-        db.insert_bookmarks(&[first.clone(), second.clone()])
+    #[test]
+    fn content_trigram_index_finds_cjk_substrings() {
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let mut japanese = sample_bookmark("1", "tanaka", date, "travel", false);
+        japanese.content = "週末に東京タワーへ行きました".into();
+        let english = sample_bookmark("2", "alice", date, "rust", false);
+
+        let plain = Database::open_memory().unwrap();
+        plain
+            .insert_bookmarks(&[japanese.clone(), english.clone()])
             .unwrap();
+        assert!(plain.search("東京タワー", 10).unwrap().is_empty());
 
-        let initial = db.get_stats().unwrap();
-        assert_eq!(initial.total_bookmarks, 2);
-        assert_eq!(initial.favorite_bookmarks, 0);
+        let db = Database::open_memory_with_options(
+            DatabaseOptions::new().with_content_trigram_index(true),
+        )
+        .unwrap();
+        db.insert_bookmarks(&[japanese.clone(), english]).unwrap();
 
-        db.toggle_favorite(&second.id).unwrap();
-        let after_favorite = db.get_stats().unwrap();
-        assert_eq!(after_favorite.favorite_bookmarks, 1);
+        let hits = db.search("東京タワー", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, japanese.id);
+        // Word-index matches still work alongside the trigram index
+        assert_eq!(db.search("Bookmark", 10).unwrap().len(), 1);
 
-        db.delete_bookmark(&first.id).unwrap();
-        let after_delete = db.get_stats().unwrap();
-        assert_eq!(after_delete.total_bookmarks, 1);
-        assert_eq!(after_delete.unique_authors, 1);
-        assert_eq!(after_delete.favorite_bookmarks, 1);
+        let filtered = db
+            .search_with_filters(
+                Some("タワー"),
+                Some("travel"),
+                None,
+                None,
+                None,
+                false,
+                None,
+                10,
+            )
+            .unwrap();
+        assert_eq!(filtered.len(), 1);
+    }
+
+    #[test]
+    fn tags_can_be_added_and_removed_with_search_kept_in_sync() {
+        let db = Database::open_memory().unwrap();
+        let bookmark = sample_bookmark(
+            "1",
+            "alice",
+            Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            "rust",
+            false,
+        );
+        db.insert_bookmarks(std::slice::from_ref(&bookmark))
+            .unwrap();
+
+        assert!(db.add_tag_to_bookmark(&bookmark.id, "databases").unwrap());
+        assert!(!db.add_tag_to_bookmark(&bookmark.id, "databases").unwrap());
+        assert!(!db.add_tag_to_bookmark("missing", "databases").unwrap());
+
+        let mut tags = db.load_bookmark_tags(&bookmark.id).unwrap();
+        tags.sort();
+        assert_eq!(tags, vec!["databases", "rust"]);
+        assert_eq!(db.search("databases", 10).unwrap().len(), 1);
+        assert_eq!(db.get_stats().unwrap().unique_tags, 2);
+
+        assert!(db
+            .remove_tag_from_bookmark(&bookmark.id, "databases")
+            .unwrap());
+        assert!(!db
+            .remove_tag_from_bookmark(&bookmark.id, "databases")
+            .unwrap());
+        assert_eq!(db.load_bookmark_tags(&bookmark.id).unwrap(), vec!["rust"]);
+        assert!(db.search("databases", 10).unwrap().is_empty());
     }
 }
@@ -3,9 +3,17 @@
 //! Uses SQLite with FTS5 for lightning-fast full-text search.
 
 mod database;
-mod schema;
+mod options;
 mod queries;
+mod schema;
 
-pub use database::Database;
-pub use queries::BookmarkStats;
-
+pub use database::{Database, DB_PATH_ENV};
+pub use options::{
+    DatabaseOptions, FtsTokenizer, JournalMode, Synchronous, DEFAULT_BUSY_TIMEOUT,
+    DEFAULT_CACHE_SIZE, DEFAULT_MMAP_SIZE,
+};
+pub use queries::{
+    BookmarkStats, Collection, Facets, ImportReport, MergeReport, PaginatedResponse, Pagination, Period,
+    SavedSearch, SearchField, SearchFilters, SortBy, SyncExport, TagMap, TagMapEntry, TagMapReport,
+    TagMatch, Tombstone, DEFAULT_PAGE_LIMIT,
+};
@@ -0,0 +1,247 @@
+//! Options for opening a database
+
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long opening and writing wait on a lock held by another connection
+pub const DEFAULT_BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default page cache: 64 MB (negative `cache_size` values are KiB)
+pub const DEFAULT_CACHE_SIZE: i64 = -64_000;
+
+/// Default memory-mapped I/O window: 256 MiB
+pub const DEFAULT_MMAP_SIZE: u64 = 256 * 1024 * 1024;
+
+/// SQLite journal mode (`journal_mode` pragma).
+///
+/// WAL is the fastest and lets readers run during writes, but its shared-memory
+/// file needs a local, writable filesystem. On network filesystems (NFS, SMB)
+/// or synced folders (Dropbox) use `Delete`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalMode {
+    #[default]
+    Wal,
+    Delete,
+    Truncate,
+    Persist,
+}
+
+impl JournalMode {
+    /// Value for `PRAGMA journal_mode`, as SQLite reports it back
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JournalMode::Wal => "wal",
+            JournalMode::Delete => "delete",
+            JournalMode::Truncate => "truncate",
+            JournalMode::Persist => "persist",
+        }
+    }
+}
+
+/// How hard SQLite makes sure writes reached the disk (`synchronous` pragma).
+/// `Normal` is safe with WAL; use `Full` with the other journal modes to
+/// survive power loss.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Synchronous {
+    Off,
+    #[default]
+    Normal,
+    Full,
+}
+
+impl Synchronous {
+    /// Value for `PRAGMA synchronous`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Synchronous::Off => "OFF",
+            Synchronous::Normal => "NORMAL",
+            Synchronous::Full => "FULL",
+        }
+    }
+}
+
+/// FTS5 tokenizer used by the full-text index.
+///
+/// The tokenizer is fixed when the FTS table is created; switching an existing
+/// database requires `Database::reconfigure_tokenizer`, which rebuilds the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FtsTokenizer {
+    /// English stemming on top of `unicode61` ("running" finds "run").
+    /// Best for English prose; no substring matching.
+    #[default]
+    Porter,
+    /// Plain Unicode word tokenizer: whole-word and prefix matches, no stemming.
+    Unicode61,
+    /// Indexes every three-character sequence, so any substring of 3+ characters
+    /// matches — works for code snippets and scripts without spaces (CJK, Thai).
+    /// The index is several times larger and shorter query terms match nothing.
+    Trigram,
+}
+
+impl FtsTokenizer {
+    /// Value for the FTS5 `tokenize=` option
+    pub fn fts5_spec(self) -> &'static str {
+        match self {
+            FtsTokenizer::Porter => "porter unicode61",
+            FtsTokenizer::Unicode61 => "unicode61",
+            FtsTokenizer::Trigram => "trigram",
+        }
+    }
+
+    /// Recover the tokenizer from a `CREATE VIRTUAL TABLE` statement
+    pub(crate) fn from_table_sql(sql: &str) -> Option<Self> {
+        let lower = sql.to_lowercase();
+        let start = lower.find("tokenize")?;
+        let spec = lower[start..]
+            .split(['\'', '"'])
+            .nth(1)?
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        [
+            FtsTokenizer::Porter,
+            FtsTokenizer::Unicode61,
+            FtsTokenizer::Trigram,
+        ]
+        .into_iter()
+        .find(|tokenizer| tokenizer.fts5_spec() == spec)
+    }
+}
+
+/// Settings applied when opening a database
+#[derive(Debug, Clone)]
+pub struct DatabaseOptions {
+    /// Tokenizer for a newly created full-text index (ignored if one exists)
+    pub tokenizer: FtsTokenizer,
+    /// Maintain an extra trigram index over tweet content so substrings match in
+    /// scripts without word boundaries (CJK, Thai). Once built it stays in use,
+    /// even when later opened without this option.
+    pub content_trigram_index: bool,
+    /// Words `Database::search_with_stopword_filter` drops from queries
+    /// (`None` uses `search::ENGLISH_STOPWORDS`)
+    pub stopwords: Option<Vec<String>>,
+    /// How long to wait for another connection's lock before failing with
+    /// `Error::DatabaseLocked` (`busy_timeout` pragma)
+    pub busy_timeout: Duration,
+    /// Skip imported bookmarks whose content (by `content_hash`) and author
+    /// match a bookmark already stored under a different URL, such as reposts.
+    /// Counted in `ImportReport::skipped_content_duplicate`.
+    pub skip_content_duplicates: bool,
+    /// `journal_mode` pragma
+    pub journal_mode: JournalMode,
+    /// `synchronous` pragma
+    pub synchronous: Synchronous,
+    /// `cache_size` pragma: pages if positive, KiB if negative
+    pub cache_size: i64,
+    /// `mmap_size` pragma: bytes of the file to memory-map; 0 turns it off
+    pub mmap_size: u64,
+}
+
+impl Default for DatabaseOptions {
+    fn default() -> Self {
+        Self {
+            tokenizer: FtsTokenizer::default(),
+            content_trigram_index: false,
+            stopwords: None,
+            busy_timeout: DEFAULT_BUSY_TIMEOUT,
+            skip_content_duplicates: false,
+            journal_mode: JournalMode::default(),
+            synchronous: Synchronous::default(),
+            cache_size: DEFAULT_CACHE_SIZE,
+            mmap_size: DEFAULT_MMAP_SIZE,
+        }
+    }
+}
+
+impl DatabaseOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tokenizer(mut self, tokenizer: FtsTokenizer) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    pub fn with_content_trigram_index(mut self, enabled: bool) -> Self {
+        self.content_trigram_index = enabled;
+        self
+    }
+
+    pub fn with_busy_timeout(mut self, timeout: Duration) -> Self {
+        self.busy_timeout = timeout;
+        self
+    }
+
+    pub fn with_skip_content_duplicates(mut self, enabled: bool) -> Self {
+        self.skip_content_duplicates = enabled;
+        self
+    }
+
+    pub fn with_journal_mode(mut self, mode: JournalMode) -> Self {
+        self.journal_mode = mode;
+        self
+    }
+
+    pub fn with_synchronous(mut self, synchronous: Synchronous) -> Self {
+        self.synchronous = synchronous;
+        self
+    }
+
+    pub fn with_cache_size(mut self, cache_size: i64) -> Self {
+        self.cache_size = cache_size;
+        self
+    }
+
+    pub fn with_mmap_size(mut self, bytes: u64) -> Self {
+        self.mmap_size = bytes;
+        self
+    }
+
+    /// Reject pragma values SQLite would misread. Settings that depend on the
+    /// filesystem (WAL) are checked when the database is opened.
+    pub fn validate(&self) -> Result<()> {
+        if self.mmap_size > i64::MAX as u64 {
+            return Err(Error::InvalidOptions(format!(
+                "mmap_size {} is out of range",
+                self.mmap_size
+            )));
+        }
+        Ok(())
+    }
+
+    /// Replace the default English stopword list
+    pub fn with_stopwords<I, S>(mut self, words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.stopwords = Some(words.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizer_round_trips_through_table_sql() {
+        for tokenizer in [
+            FtsTokenizer::Porter,
+            FtsTokenizer::Unicode61,
+            FtsTokenizer::Trigram,
+        ] {
+            let sql = format!(
+                "CREATE VIRTUAL TABLE bookmarks_fts USING fts5(content, tokenize='{}')",
+                tokenizer.fts5_spec()
+            );
+            assert_eq!(FtsTokenizer::from_table_sql(&sql), Some(tokenizer));
+        }
+        assert_eq!(FtsTokenizer::from_table_sql("CREATE TABLE x (a)"), None);
+    }
+}
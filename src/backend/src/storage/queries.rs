@@ -1,8 +1,252 @@
 //! Query result types and helpers
 
+use crate::models::{Bookmark, MediaType};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// How multiple tag filters combine
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TagMatch {
+    /// Bookmark must carry every requested tag
+    #[default]
+    All,
+    /// Bookmark must carry at least one requested tag
+    Any,
+}
+
+/// Which date `Database::query_bookmarks` orders by, newest first
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    /// When the tweet was posted
+    #[default]
+    TweetedAt,
+    /// When the bookmark was imported ("recently added")
+    ImportedAt,
+}
+
+/// Which bookmarks an export (or other filtered query) covers. Unset fields
+/// don't filter; the default matches every live bookmark.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchFilters {
+    /// Full-text query
+    pub query: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub tag_match: TagMatch,
+    /// Author handle
+    pub author: Option<String>,
+    pub from_date: Option<DateTime<Utc>>,
+    pub to_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub favorites_only: bool,
+    pub has_media: Option<bool>,
+    /// Only bookmarks with media of this type (implies `has_media`)
+    #[serde(default)]
+    pub media_type: Option<MediaType>,
+    /// Imported at or after this time (as opposed to `from_date`, which is
+    /// when the tweet was posted)
+    #[serde(default)]
+    pub from_imported: Option<DateTime<Utc>>,
+    /// Imported at or before this time
+    #[serde(default)]
+    pub to_imported: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub sort_by: SortBy,
+    /// Let query terms match the start of longer words ("go" finds "google").
+    /// On by default; off gives the UI's "exact match".
+    #[serde(default = "default_prefix_match")]
+    pub prefix_match: bool,
+}
+
+fn default_prefix_match() -> bool {
+    true
+}
+
+impl Default for SearchFilters {
+    fn default() -> Self {
+        Self {
+            query: None,
+            tags: Vec::new(),
+            tag_match: TagMatch::default(),
+            author: None,
+            from_date: None,
+            to_date: None,
+            favorites_only: false,
+            has_media: None,
+            media_type: None,
+            from_imported: None,
+            to_imported: None,
+            sort_by: SortBy::default(),
+            prefix_match: true,
+        }
+    }
+}
+
+impl SearchFilters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_query(mut self, query: impl Into<String>) -> Self {
+        self.query = Some(query.into());
+        self
+    }
+
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    pub fn with_tag_match(mut self, tag_match: TagMatch) -> Self {
+        self.tag_match = tag_match;
+        self
+    }
+
+    pub fn with_author(mut self, handle: impl Into<String>) -> Self {
+        self.author = Some(handle.into());
+        self
+    }
+
+    pub fn with_date_range(
+        mut self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.from_date = from;
+        self.to_date = to;
+        self
+    }
+
+    pub fn with_favorites_only(mut self, favorites_only: bool) -> Self {
+        self.favorites_only = favorites_only;
+        self
+    }
+
+    pub fn with_has_media(mut self, has_media: Option<bool>) -> Self {
+        self.has_media = has_media;
+        self
+    }
+
+    pub fn with_media_type(mut self, media_type: Option<MediaType>) -> Self {
+        self.media_type = media_type;
+        self
+    }
+
+    pub fn with_imported_range(
+        mut self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.from_imported = from;
+        self.to_imported = to;
+        self
+    }
+
+    pub fn with_sort_by(mut self, sort_by: SortBy) -> Self {
+        self.sort_by = sort_by;
+        self
+    }
+
+    pub fn with_prefix_match(mut self, prefix_match: bool) -> Self {
+        self.prefix_match = prefix_match;
+        self
+    }
+}
+
+/// Page size used when a `Pagination` leaves `limit` out
+pub const DEFAULT_PAGE_LIMIT: usize = 50;
+
+/// Which slice of a result list to return (`Database::query_bookmarks`)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Pagination {
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_page_limit")]
+    pub limit: usize,
+}
+
+fn default_page_limit() -> usize {
+    DEFAULT_PAGE_LIMIT
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self::new(0, DEFAULT_PAGE_LIMIT)
+    }
+}
+
+impl Pagination {
+    pub fn new(offset: usize, limit: usize) -> Self {
+        Self { offset, limit }
+    }
+}
+
+/// Columns `Database::search_in_field` can restrict a search to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchField {
+    /// Tweet text
+    Content,
+    /// Extended note-tweet text and annotations
+    Notes,
+    /// Author handle and display name
+    Author,
+    Tags,
+}
+
+impl SearchField {
+    /// FTS5 column filter for this field (a `{...}` column set)
+    pub(crate) fn fts_columns(self) -> &'static str {
+        match self {
+            SearchField::Content => "{content}",
+            SearchField::Notes => "{note_text}",
+            SearchField::Author => "{author_handle author_name}",
+            SearchField::Tags => "{tags_text}",
+        }
+    }
+}
+
+impl std::str::FromStr for SearchField {
+    type Err = crate::Error;
+
+    fn from_str(name: &str) -> crate::Result<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "content" => Ok(SearchField::Content),
+            "notes" | "note" | "note_text" => Ok(SearchField::Notes),
+            "author" | "authors" => Ok(SearchField::Author),
+            "tags" | "tag" => Ok(SearchField::Tags),
+            other => Err(crate::Error::Other(format!(
+                "Unknown search field: {other}"
+            ))),
+        }
+    }
+}
+
+/// Time bucket for `Database::bookmark_counts_by_period`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Period {
+    /// SQLite `strftime` format producing the bucket label. Weeks are ISO 8601
+    /// weeks, labelled with their ISO year: 2024-12-30 falls in `2025-W01`.
+    pub(crate) fn strftime_format(self) -> &'static str {
+        match self {
+            Period::Day => "%Y-%m-%d",
+            Period::Week => "%G-W%V",
+            Period::Month => "%Y-%m",
+            Period::Year => "%Y",
+        }
+    }
+}
+
 /// Database statistics
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct BookmarkStats {
@@ -14,3 +258,147 @@ pub struct BookmarkStats {
     pub latest_date: Option<DateTime<Utc>>,
     pub top_tags: Vec<(String, i64)>,
 }
+
+/// Filters stored under a name (`Database::save_search`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SavedSearch {
+    pub name: String,
+    pub filters: SearchFilters,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A named folder of bookmarks (`Database::create_collection`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Collection {
+    pub id: i64,
+    pub name: String,
+    /// Live bookmarks in the collection
+    pub bookmark_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A bookmark deleted (trashed or removed for good) within a
+/// `Database::export_json_since` window
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Tombstone {
+    pub id: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// Document written by `Database::export_json_since`: what changed after
+/// `since` up to and including `until`. Pass `until` as the next `since`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyncExport {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    /// Live bookmarks imported or edited in the window, in order of their
+    /// latest change
+    pub bookmarks: Vec<Bookmark>,
+    /// Bookmarks deleted in the window, oldest first
+    pub deleted: Vec<Tombstone>,
+}
+
+/// Document written by `Database::export_tag_map`: the tag taxonomy without
+/// the bookmarks, so renames and merges can be replayed on another library
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TagMap {
+    /// Most used first
+    pub tags: Vec<TagMapEntry>,
+}
+
+/// A tag in a `TagMap`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TagMapEntry {
+    pub name: String,
+    /// Live bookmarks carrying the tag in the exporting library
+    #[serde(default)]
+    pub count: i64,
+    /// Former names renamed or merged into this tag
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+/// Outcome of `Database::import_tag_map`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TagMapReport {
+    /// Tags from the map that did not exist locally
+    pub created: usize,
+    /// Local tags renamed or merged into a tag from the map
+    pub renamed: usize,
+    /// Bookmarks whose tags changed
+    pub bookmarks_changed: usize,
+}
+
+/// How a query's matches spread across tags, authors, media and favorites,
+/// for a filter sidebar. Tag and author counts are most common first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Facets {
+    pub tags: Vec<(String, i64)>,
+    /// Author handles
+    pub authors: Vec<(String, i64)>,
+    /// Matches with media attached
+    pub media: i64,
+    /// Matches marked favorite
+    pub favorites: i64,
+}
+
+/// Outcome of `Database::import`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Bookmarks newly stored
+    pub inserted: usize,
+    /// Bookmarks skipped because the same tweet is already stored
+    pub skipped_duplicate: usize,
+    /// Bookmarks skipped because their content and author are already stored
+    /// under another URL (`DatabaseOptions::skip_content_duplicates`)
+    pub skipped_content_duplicate: usize,
+    /// Bookmarks that could not be stored for any other reason
+    pub failed: usize,
+}
+
+/// Outcome of `Database::merge_import`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Bookmarks newly stored
+    pub inserted: usize,
+    /// Already stored bookmarks that had empty fields filled in
+    pub merged: usize,
+    /// Already stored bookmarks the import had nothing to add to
+    pub unchanged: usize,
+    /// Bookmarks that could not be stored or merged
+    pub failed: usize,
+}
+
+/// A page of results plus the total number of matching rows
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PaginatedResponse<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub offset: usize,
+    pub limit: usize,
+    pub has_more: bool,
+}
+
+impl<T> PaginatedResponse<T> {
+    pub fn new(items: Vec<T>, total: i64, offset: usize, limit: usize) -> Self {
+        let has_more = offset + items.len() < total as usize;
+        Self {
+            items,
+            total,
+            offset,
+            limit,
+            has_more,
+        }
+    }
+
+    /// Convert each item, keeping the paging metadata
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> PaginatedResponse<U> {
+        PaginatedResponse {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            offset: self.offset,
+            limit: self.limit,
+            has_more: self.has_more,
+        }
+    }
+}
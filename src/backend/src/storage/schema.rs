@@ -9,13 +9,22 @@ CREATE TABLE IF NOT EXISTS bookmarks (
     note_text TEXT,
     tweeted_at INTEGER NOT NULL,  -- Unix timestamp for fast sorting
     imported_at INTEGER NOT NULL,
-    author_handle TEXT NOT NULL,
-    author_name TEXT NOT NULL,
+    author_handle TEXT NOT NULL,  -- Key into authors(handle) (Database::ensure_authors_table). Not a
+                                  -- declared FOREIGN KEY: SQLite can't add one to an existing table,
+                                  -- so inserts upsert the author alongside each bookmark instead
+    author_name TEXT NOT NULL,    -- Denormalized fallback; reads prefer authors.name
     author_profile_url TEXT,
     author_profile_image TEXT,
     comments TEXT,
     is_favorite INTEGER DEFAULT 0,  -- Boolean as integer (0/1)
-    has_media INTEGER DEFAULT 0     -- Denormalized: 1 if any media rows exist (avoids JOIN on filter)
+    is_archived INTEGER DEFAULT 0,  -- Boolean as integer (0/1); hidden from the default listing
+    has_media INTEGER DEFAULT 0,    -- Denormalized: 1 if any media rows exist (avoids JOIN on filter)
+    content_hash TEXT,              -- models::content_hash of the content; NULL if nothing to compare
+    deleted_at INTEGER,             -- Soft-delete (trash) timestamp; NULL for live bookmarks
+    date_estimated INTEGER DEFAULT 0, -- Boolean: tweeted_at is a stand-in for an unparseable date
+    tweet_id TEXT,                  -- Status id parsed from tweet_url; NULL if the URL has none
+    content_original_len INTEGER,   -- Bytes of content before ingest truncated it; NULL if it wasn't
+    last_seen_at INTEGER            -- Unix timestamp of the last import that contained it, even as a duplicate
 );
 
 -- Tags table (normalized for efficient filtering)
@@ -42,24 +51,79 @@ CREATE TABLE IF NOT EXISTS media (
     FOREIGN KEY (bookmark_id) REFERENCES bookmarks(id) ON DELETE CASCADE
 );
 
+-- Outbound links live in `links`, created in Database::ensure_links_table
+-- Timestamped notes live in `annotations`, created in Database::ensure_annotations_table
+
 -- App metadata (sync status, feature state, lightweight preferences)
 CREATE TABLE IF NOT EXISTS app_metadata (
     key TEXT PRIMARY KEY,
     value TEXT NOT NULL
 );
 
--- FTS5 virtual table for full-text search
-CREATE VIRTUAL TABLE IF NOT EXISTS bookmarks_fts USING fts5(
-    content,
-    note_text,
-    author_handle,
-    author_name,
-    tags_text,
-    content='bookmarks_fts_content',
-    content_rowid='rowid',
-    tokenize='porter unicode61'
+-- Cached link previews (see Database::get_cached_preview)
+CREATE TABLE IF NOT EXISTS link_previews (
+    url TEXT PRIMARY KEY,
+    final_url TEXT NOT NULL,
+    title TEXT,
+    description TEXT,
+    image_url TEXT,
+    site_name TEXT,
+    fetched_at INTEGER NOT NULL,  -- Unix timestamp
+    favicon_url TEXT,
+    canonical_url TEXT
+);
+
+-- Named filter combinations; `filters` is a JSON-serialized SearchFilters
+CREATE TABLE IF NOT EXISTS saved_searches (
+    name TEXT PRIMARY KEY,
+    filters TEXT NOT NULL,
+    updated_at INTEGER NOT NULL  -- Unix timestamp
+);
+
+-- Permanently deleted bookmarks; re-inserting a bookmark clears its entry.
+-- Seeds the tombstones of the sync_changes log (Database::export_json_since)
+-- for libraries that predate it
+CREATE TABLE IF NOT EXISTS bookmark_deletions (
+    bookmark_id TEXT PRIMARY KEY,
+    deleted_at INTEGER NOT NULL  -- Unix timestamp
+);
+
+-- Named folders of bookmarks (Database::create_collection). Unlike tags,
+-- which label content, these group bookmarks for a purpose; a bookmark can
+-- be in several.
+CREATE TABLE IF NOT EXISTS collections (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT UNIQUE NOT NULL COLLATE NOCASE,
+    created_at INTEGER NOT NULL  -- Unix timestamp
+);
+
+CREATE TABLE IF NOT EXISTS collection_bookmarks (
+    collection_id INTEGER NOT NULL,
+    bookmark_id TEXT NOT NULL,
+    added_at INTEGER NOT NULL,  -- Unix timestamp
+    PRIMARY KEY (collection_id, bookmark_id),
+    FOREIGN KEY (collection_id) REFERENCES collections(id) ON DELETE CASCADE,
+    FOREIGN KEY (bookmark_id) REFERENCES bookmarks(id) ON DELETE CASCADE
+);
+
+-- Former tag names, recorded by Database::rename_tag/merge_tags and carried
+-- to other libraries by Database::export_tag_map
+CREATE TABLE IF NOT EXISTS tag_aliases (
+    alias TEXT PRIMARY KEY COLLATE NOCASE,
+    tag TEXT NOT NULL COLLATE NOCASE
 );
 
+CREATE TRIGGER IF NOT EXISTS bookmarks_log_deletion AFTER DELETE ON bookmarks BEGIN
+    INSERT OR REPLACE INTO bookmark_deletions (bookmark_id, deleted_at)
+    VALUES (OLD.id, CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+CREATE TRIGGER IF NOT EXISTS bookmarks_clear_deletion AFTER INSERT ON bookmarks BEGIN
+    DELETE FROM bookmark_deletions WHERE bookmark_id = NEW.id;
+END;
+
+-- FTS5 virtual table for full-text search: see fts_table_sql (tokenizer is configurable)
+
 -- Content table for FTS5
 CREATE TABLE IF NOT EXISTS bookmarks_fts_content (
     rowid INTEGER PRIMARY KEY,
@@ -82,6 +146,8 @@ CREATE INDEX IF NOT EXISTS idx_bookmarks_fav_date ON bookmarks(is_favorite, twee
 CREATE INDEX IF NOT EXISTS idx_bookmark_tags_bookmark ON bookmark_tags(bookmark_id);
 CREATE INDEX IF NOT EXISTS idx_bookmark_tags_tag ON bookmark_tags(tag_id);
 CREATE INDEX IF NOT EXISTS idx_media_bookmark ON media(bookmark_id);
+CREATE INDEX IF NOT EXISTS idx_collection_bookmarks_bookmark ON collection_bookmarks(bookmark_id);
+CREATE INDEX IF NOT EXISTS idx_link_previews_fetched_at ON link_previews(fetched_at);
 CREATE INDEX IF NOT EXISTS idx_fts_content_bookmark ON bookmarks_fts_content(bookmark_id);
 
 -- Triggers to keep FTS index in sync
@@ -104,11 +170,177 @@ END;
 
 "#;
 
+/// Pragmas every connection needs; the tunable ones (journal mode, cache and
+/// mmap size, ...) come from `DatabaseOptions`
 pub const PRAGMAS: &str = r#"
-PRAGMA journal_mode = WAL;
-PRAGMA synchronous = NORMAL;
-PRAGMA cache_size = -64000;
 PRAGMA temp_store = MEMORY;
-PRAGMA mmap_size = 268435456;
 PRAGMA foreign_keys = ON;
 "#;
+
+/// FTS5 index over `bookmarks_fts_content`, using the given tokenizer spec
+pub fn fts_table_sql(tokenizer: &str) -> String {
+    format!(
+        r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS bookmarks_fts USING fts5(
+    content,
+    note_text,
+    author_handle,
+    author_name,
+    tags_text,
+    content='bookmarks_fts_content',
+    content_rowid='rowid',
+    tokenize='{tokenizer}'
+);
+"#
+    )
+}
+
+/// Optional trigram index over tweet content (see `DatabaseOptions::content_trigram_index`)
+pub const CONTENT_TRIGRAM_SCHEMA: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS bookmarks_trigram USING fts5(
+    content,
+    content='bookmarks_fts_content',
+    content_rowid='rowid',
+    tokenize='trigram'
+);
+
+CREATE TRIGGER IF NOT EXISTS bookmarks_trigram_insert AFTER INSERT ON bookmarks_fts_content BEGIN
+    INSERT INTO bookmarks_trigram(rowid, content) VALUES (NEW.rowid, NEW.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS bookmarks_trigram_delete AFTER DELETE ON bookmarks_fts_content BEGIN
+    INSERT INTO bookmarks_trigram(bookmarks_trigram, rowid, content) VALUES ('delete', OLD.rowid, OLD.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS bookmarks_trigram_update AFTER UPDATE ON bookmarks_fts_content BEGIN
+    INSERT INTO bookmarks_trigram(bookmarks_trigram, rowid, content) VALUES ('delete', OLD.rowid, OLD.content);
+    INSERT INTO bookmarks_trigram(rowid, content) VALUES (NEW.rowid, NEW.content);
+END;
+"#;
+
+/// Triggers that feed the `sync_changes` log (`Database::export_json_since`).
+/// Edits log the bookmark once per second: a change already at the end of the
+/// log isn't repeated, so an import adding a bookmark with its tags and media
+/// logs it once.
+pub const SYNC_CHANGES_TRIGGERS: &str = r#"
+CREATE INDEX IF NOT EXISTS idx_sync_changes_changed_at ON sync_changes(changed_at);
+
+CREATE TRIGGER IF NOT EXISTS bookmarks_sync_insert AFTER INSERT ON bookmarks BEGIN
+    INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    VALUES (NEW.id, NEW.deleted_at IS NOT NULL, CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+-- Moving into or out of the trash
+CREATE TRIGGER IF NOT EXISTS bookmarks_sync_trash AFTER UPDATE OF deleted_at ON bookmarks
+WHEN (OLD.deleted_at IS NULL) != (NEW.deleted_at IS NULL) BEGIN
+    INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    VALUES (NEW.id, NEW.deleted_at IS NOT NULL,
+            COALESCE(NEW.deleted_at, CAST(strftime('%s', 'now') AS INTEGER)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS bookmarks_sync_delete AFTER DELETE ON bookmarks BEGIN
+    INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    VALUES (OLD.id, 1, CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+-- Edits to a live bookmark. Columns derived from these (tweet_id, has_media,
+-- content_hash, ...) and last_seen_at, which every re-import touches, don't count
+CREATE TRIGGER IF NOT EXISTS bookmarks_sync_update AFTER UPDATE OF
+    tweet_url, content, note_text, tweeted_at, author_handle, author_name,
+    author_profile_url, author_profile_image, comments, is_favorite, is_archived ON bookmarks
+WHEN OLD.deleted_at IS NULL AND NEW.deleted_at IS NULL
+    AND (OLD.tweet_url IS NOT NEW.tweet_url OR OLD.content IS NOT NEW.content
+         OR OLD.note_text IS NOT NEW.note_text OR OLD.tweeted_at IS NOT NEW.tweeted_at
+         OR OLD.author_handle IS NOT NEW.author_handle OR OLD.author_name IS NOT NEW.author_name
+         OR OLD.author_profile_url IS NOT NEW.author_profile_url
+         OR OLD.author_profile_image IS NOT NEW.author_profile_image
+         OR OLD.comments IS NOT NEW.comments OR OLD.is_favorite IS NOT NEW.is_favorite
+         OR OLD.is_archived IS NOT NEW.is_archived) BEGIN
+    INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    SELECT NEW.id, 0, CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE NOT EXISTS (
+        SELECT 1 FROM sync_changes WHERE seq = (SELECT MAX(seq) FROM sync_changes)
+            AND bookmark_id = NEW.id AND deleted = 0
+            AND changed_at = CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+CREATE TRIGGER IF NOT EXISTS bookmark_tags_sync_insert AFTER INSERT ON bookmark_tags BEGIN
+    INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    SELECT NEW.bookmark_id, 0, CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE EXISTS (SELECT 1 FROM bookmarks WHERE id = NEW.bookmark_id AND deleted_at IS NULL)
+      AND NOT EXISTS (
+        SELECT 1 FROM sync_changes WHERE seq = (SELECT MAX(seq) FROM sync_changes)
+            AND bookmark_id = NEW.bookmark_id AND deleted = 0
+            AND changed_at = CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+CREATE TRIGGER IF NOT EXISTS bookmark_tags_sync_delete AFTER DELETE ON bookmark_tags BEGIN
+    INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    SELECT OLD.bookmark_id, 0, CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE EXISTS (SELECT 1 FROM bookmarks WHERE id = OLD.bookmark_id AND deleted_at IS NULL)
+      AND NOT EXISTS (
+        SELECT 1 FROM sync_changes WHERE seq = (SELECT MAX(seq) FROM sync_changes)
+            AND bookmark_id = OLD.bookmark_id AND deleted = 0
+            AND changed_at = CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+CREATE TRIGGER IF NOT EXISTS media_sync_insert AFTER INSERT ON media BEGIN
+    INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    SELECT NEW.bookmark_id, 0, CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE EXISTS (SELECT 1 FROM bookmarks WHERE id = NEW.bookmark_id AND deleted_at IS NULL)
+      AND NOT EXISTS (
+        SELECT 1 FROM sync_changes WHERE seq = (SELECT MAX(seq) FROM sync_changes)
+            AND bookmark_id = NEW.bookmark_id AND deleted = 0
+            AND changed_at = CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+CREATE TRIGGER IF NOT EXISTS media_sync_delete AFTER DELETE ON media BEGIN
+    INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    SELECT OLD.bookmark_id, 0, CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE EXISTS (SELECT 1 FROM bookmarks WHERE id = OLD.bookmark_id AND deleted_at IS NULL)
+      AND NOT EXISTS (
+        SELECT 1 FROM sync_changes WHERE seq = (SELECT MAX(seq) FROM sync_changes)
+            AND bookmark_id = OLD.bookmark_id AND deleted = 0
+            AND changed_at = CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+CREATE TRIGGER IF NOT EXISTS annotations_sync_insert AFTER INSERT ON annotations BEGIN
+    INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    SELECT NEW.bookmark_id, 0, CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE EXISTS (SELECT 1 FROM bookmarks WHERE id = NEW.bookmark_id AND deleted_at IS NULL)
+      AND NOT EXISTS (
+        SELECT 1 FROM sync_changes WHERE seq = (SELECT MAX(seq) FROM sync_changes)
+            AND bookmark_id = NEW.bookmark_id AND deleted = 0
+            AND changed_at = CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+CREATE TRIGGER IF NOT EXISTS annotations_sync_update AFTER UPDATE ON annotations BEGIN
+    INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    SELECT NEW.bookmark_id, 0, CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE EXISTS (SELECT 1 FROM bookmarks WHERE id = NEW.bookmark_id AND deleted_at IS NULL)
+      AND NOT EXISTS (
+        SELECT 1 FROM sync_changes WHERE seq = (SELECT MAX(seq) FROM sync_changes)
+            AND bookmark_id = NEW.bookmark_id AND deleted = 0
+            AND changed_at = CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+CREATE TRIGGER IF NOT EXISTS annotations_sync_delete AFTER DELETE ON annotations BEGIN
+    INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    SELECT OLD.bookmark_id, 0, CAST(strftime('%s', 'now') AS INTEGER)
+    WHERE EXISTS (SELECT 1 FROM bookmarks WHERE id = OLD.bookmark_id AND deleted_at IS NULL)
+      AND NOT EXISTS (
+        SELECT 1 FROM sync_changes WHERE seq = (SELECT MAX(seq) FROM sync_changes)
+            AND bookmark_id = OLD.bookmark_id AND deleted = 0
+            AND changed_at = CAST(strftime('%s', 'now') AS INTEGER));
+END;
+
+-- Bookmarks read their author's name and profile from `authors`, so an
+-- author edit changes every live bookmark of theirs
+CREATE TRIGGER IF NOT EXISTS authors_sync_update AFTER UPDATE ON authors
+WHEN OLD.name IS NOT NEW.name OR OLD.profile_url IS NOT NEW.profile_url
+    OR OLD.profile_image IS NOT NEW.profile_image BEGIN
+    INSERT INTO sync_changes (bookmark_id, deleted, changed_at)
+    SELECT id, 0, CAST(strftime('%s', 'now') AS INTEGER) FROM bookmarks
+    WHERE author_handle = NEW.handle AND deleted_at IS NULL;
+END;
+"#;
@@ -1,3 +1,7 @@
+//! Paging, hydration and stats on the storage layer. The `#[ignore]`d
+//! benchmarks time them on a large library; run those with
+//! `cargo test --release --test db_load_performance -- --ignored --nocapture`.
+
 use chrono::{TimeZone, Utc};
 use eterea_core::models::BookmarkBuilder;
 use eterea_core::Database;
@@ -27,6 +31,29 @@ fn sample_bookmark(
     builder.build().expect("sample bookmark should build")
 }
 
+/// A database of `size` bookmarks, a minute apart, spread over four authors
+/// and 500 tags (each also tagged `archive`); every third has an image
+fn large_library(size: usize) -> (Database, Vec<eterea_core::Bookmark>) {
+    let db = Database::open_memory().expect("in-memory db should open");
+    let handles = ["alice", "bob", "carol", "dave"];
+    let media_url = "https://pbs.twimg.com/media/a.jpg";
+    let bookmarks: Vec<_> = (0..size)
+        .map(|n| {
+            let media: &[&str] = if n % 3 == 0 { &[media_url] } else { &[] };
+            sample_bookmark(
+                &n.to_string(),
+                handles[n % handles.len()],
+                Utc.timestamp_opt(1_700_000_000 + n as i64 * 60, 0).unwrap(),
+                &[&format!("tag{}", n % 500), "archive"],
+                media,
+            )
+        })
+        .collect();
+    db.insert_bookmarks(&bookmarks)
+        .expect("bookmarks should insert");
+    (db, bookmarks)
+}
+
 #[test]
 fn paginated_reads_preserve_order_and_hydrate_related_data() {
     let db = Database::open_memory().expect("in-memory db should open");
@@ -177,3 +204,180 @@ fn favorites_and_tag_filters_stay_correct_after_primary_reads() {
     assert_eq!(rust_bookmarks[0].id, second.id);
     assert_eq!(rust_bookmarks[1].id, first.id);
 }
+
+#[test]
+fn batch_loaders_match_single_bookmark_loaders_for_a_full_page() {
+    let db = Database::open_memory().expect("in-memory db should open");
+    let bookmarks = (0..100)
+        .map(|i| {
+            let media_url = format!("https://pbs.twimg.com/media/{i}.jpg");
+            let media: &[&str] = if i % 2 == 0 {
+                &[]
+            } else {
+                &[media_url.as_str()]
+            };
+            sample_bookmark(
+                &(1000 + i).to_string(),
+                ["alice", "bob", "carol"][i % 3],
+                Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap()
+                    + chrono::Duration::minutes(i as i64),
+                &[["rust", "perf", "svelte"][i % 3], "archive"],
+                media,
+            )
+        })
+        .collect::<Vec<_>>();
+    db.insert_bookmarks(&bookmarks)
+        .expect("bookmarks should insert");
+
+    let page = db.get_bookmarks(0, 100).expect("page should load");
+    let ids = page.iter().map(|b| b.id.as_str()).collect::<Vec<_>>();
+    let tags = db.tags_for_bookmarks(&ids).expect("tags should batch-load");
+    let media = db
+        .media_for_bookmarks(&ids)
+        .expect("media should batch-load");
+
+    assert_eq!(page.len(), 100);
+    assert_eq!(tags.len(), 100);
+    assert_eq!(media.len(), 50);
+    for bookmark in &page {
+        let single_tags = db
+            .load_bookmark_tags(&bookmark.id)
+            .expect("tags should load");
+        let single_media = db
+            .load_bookmark_media(&bookmark.id)
+            .expect("media should load");
+        assert_eq!(tags[&bookmark.id], single_tags);
+        assert_eq!(bookmark.tags, single_tags);
+        assert_eq!(
+            media.get(&bookmark.id).cloned().unwrap_or_default(),
+            single_media
+        );
+        assert_eq!(bookmark.media, single_media);
+    }
+}
+
+/// Latency of `search_instant` against FTS5 on a mid-sized library
+#[test]
+#[ignore = "benchmark"]
+fn instant_search_latency_compared_to_fts() {
+    const LIBRARY_SIZE: usize = 20_000;
+    const QUERIES: &[&str] = &["b", "boo", "bookmark 19", "alice", "tag42 archive", "zzz"];
+
+    let (db, _) = large_library(LIBRARY_SIZE);
+
+    let time = |search: &dyn Fn(&str) -> usize| {
+        let started = std::time::Instant::now();
+        let mut hits = 0;
+        for _ in 0..20 {
+            for query in QUERIES {
+                hits += search(query);
+            }
+        }
+        (started.elapsed() / (20 * QUERIES.len() as u32), hits)
+    };
+
+    let (fts, fts_hits) = time(&|query| db.search(query, 50).unwrap().len());
+    let started = std::time::Instant::now();
+    assert!(db.enable_instant_search().unwrap());
+    let build = started.elapsed();
+    let (instant, instant_hits) = time(&|query| db.search_instant(query, 50).unwrap().len());
+
+    println!("{LIBRARY_SIZE} bookmarks: index built in {build:?}");
+    println!(
+        "per query: FTS5 {fts:?} ({fts_hits} hits), instant {instant:?} ({instant_hits} hits)"
+    );
+    assert!(instant_hits > 0 && fts_hits > 0);
+}
+
+/// Cost of paging `get_bookmarks` with its total from `count_bookmarks`, from
+/// cached stats, and from stats recomputed for every page as they used to be
+#[test]
+#[ignore = "benchmark"]
+fn paging_latency_with_cached_and_counted_totals() {
+    const LIBRARY_SIZE: usize = 20_000;
+    const PAGES: usize = 50;
+    const PAGE_SIZE: usize = 50;
+
+    let (db, bookmarks) = large_library(LIBRARY_SIZE);
+    let favorite = &bookmarks[0].id;
+
+    let time = |total: &dyn Fn() -> i64| {
+        let started = std::time::Instant::now();
+        for page in 0..PAGES {
+            let rows = db
+                .get_bookmarks(page * PAGE_SIZE, PAGE_SIZE)
+                .expect("page should load");
+            assert_eq!(rows.len(), PAGE_SIZE);
+            assert!(total() >= LIBRARY_SIZE as i64 - 1);
+        }
+        started.elapsed() / PAGES as u32
+    };
+
+    let counted = time(&|| db.count_bookmarks().unwrap());
+    let cached = time(&|| db.get_stats().unwrap().total_bookmarks);
+    // A write before every read drops the cache, as each page once recomputed
+    let recomputed = time(&|| {
+        db.toggle_favorite(favorite).unwrap();
+        db.get_stats().unwrap().total_bookmarks
+    });
+    let toggles = {
+        let started = std::time::Instant::now();
+        for _ in 0..PAGES {
+            db.toggle_favorite(favorite).unwrap();
+        }
+        started.elapsed() / PAGES as u32
+    };
+
+    println!("{LIBRARY_SIZE} bookmarks, {PAGE_SIZE} per page, per page:");
+    println!(
+        "count_bookmarks {counted:?}, cached stats {cached:?}, recomputed stats {:?}",
+        recomputed.saturating_sub(toggles)
+    );
+    assert!(cached < recomputed);
+}
+
+/// Hydrating a 100-row page: one tag and one media query per bookmark, as
+/// `hydrate_bookmarks` used to, against the batch loaders it uses now
+#[test]
+#[ignore = "benchmark"]
+fn page_hydration_latency_per_bookmark_and_batched() {
+    const LIBRARY_SIZE: usize = 20_000;
+    const PAGE_SIZE: usize = 100;
+    const ROUNDS: u32 = 50;
+
+    let (db, _) = large_library(LIBRARY_SIZE);
+
+    let page = db.get_bookmarks(0, PAGE_SIZE).expect("page should load");
+    let ids = page.iter().map(|b| b.id.as_str()).collect::<Vec<_>>();
+    assert_eq!(ids.len(), PAGE_SIZE);
+
+    let time = |hydrate: &dyn Fn() -> usize| {
+        let started = std::time::Instant::now();
+        let mut loaded = 0;
+        for _ in 0..ROUNDS {
+            loaded += hydrate();
+        }
+        (started.elapsed() / ROUNDS, loaded)
+    };
+
+    let (per_bookmark, per_bookmark_loaded) = time(&|| {
+        ids.iter()
+            .map(|id| {
+                db.load_bookmark_tags(id).unwrap().len() + db.load_bookmark_media(id).unwrap().len()
+            })
+            .sum()
+    });
+    let (batched, batched_loaded) = time(&|| {
+        let tags = db.tags_for_bookmarks(&ids).unwrap();
+        let media = db.media_for_bookmarks(&ids).unwrap();
+        tags.values().map(Vec::len).sum::<usize>() + media.values().map(Vec::len).sum::<usize>()
+    });
+    let (whole_page, _) = time(&|| db.get_bookmarks(0, PAGE_SIZE).unwrap().len());
+
+    println!("{LIBRARY_SIZE} bookmarks, {PAGE_SIZE} per page, per page:");
+    println!(
+        "per-bookmark hydration {per_bookmark:?}, batched {batched:?}, get_bookmarks {whole_page:?}"
+    );
+    assert_eq!(per_bookmark_loaded, batched_loaded);
+    assert!(batched < per_bookmark);
+}
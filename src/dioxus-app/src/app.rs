@@ -92,11 +92,27 @@ type Services = Rc<RefCell<AppServices>>;
 
 #[component]
 pub fn App() -> Element {
-    let services = use_hook(|| {
-        Rc::new(RefCell::new(
-            AppServices::open_default().expect("failed to open Eterea services"),
-        ))
+    let opened = use_hook(|| {
+        AppServices::open_default()
+            .map(|services| Rc::new(RefCell::new(services)))
+            .map_err(|error| format!("{error:#}"))
     });
+    // The open result never changes for this component, so returning early
+    // here keeps the hook order below stable across renders
+    let services = match opened {
+        Ok(services) => services,
+        Err(error) => {
+            return rsx! {
+                document::Title { "Eterea" }
+                style { "{APP_CSS}" }
+                div {
+                    class: "error-card",
+                    strong { "Couldn’t open the archive." }
+                    p { "{error}" }
+                }
+            };
+        }
+    };
     let mut state = use_signal(|| load_initial_state(&services));
     let home_services = services.clone();
     let favorites_services = services.clone();